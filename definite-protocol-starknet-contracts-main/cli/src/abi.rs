@@ -0,0 +1,449 @@
+use anyhow::{Result, Context};
+use serde::Deserialize;
+use starknet::core::types::{BlockId, BlockTag, ContractClass, FieldElement};
+use starknet::core::utils::get_selector_from_name;
+use starknet::providers::Provider;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+
+/// On-disk cache of contract ABIs keyed by class hash
+///
+/// A class hash fully determines its ABI, so entries never go stale; `--refresh-abi`
+/// only exists to recover from a corrupted or hand-edited file.
+pub struct AbiCache {
+    dir: PathBuf,
+}
+
+impl AbiCache {
+    pub fn new(dir: impl Into<PathBuf>) -> AbiCache {
+        AbiCache { dir: dir.into() }
+    }
+    
+    /// The cache under `~/.definite/abi/`
+    pub fn open_default() -> Result<AbiCache> {
+        let home = dirs::home_dir()
+            .context("Could not find home directory")?;
+        
+        Ok(AbiCache::new(home.join(".definite").join("abi")))
+    }
+    
+    fn path(&self, class_hash: FieldElement) -> PathBuf {
+        self.dir.join(format!("{:#x}.json", class_hash))
+    }
+    
+    /// Cached ABI JSON for a class, if present
+    pub fn get(&self, class_hash: FieldElement) -> Option<String> {
+        fs::read_to_string(self.path(class_hash)).ok()
+    }
+    
+    /// Store the ABI JSON for a class
+    pub fn put(&self, class_hash: FieldElement, abi: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create ABI cache directory {}", self.dir.display()))?;
+        
+        let path = self.path(class_hash);
+        fs::write(&path, abi)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+    
+    /// Return the cached ABI, or run `fetch` and cache its result on a miss or when `refresh` is set
+    pub async fn get_or_fetch<F, Fut>(&self, class_hash: FieldElement, refresh: bool, fetch: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        if !refresh {
+            if let Some(abi) = self.get(class_hash) {
+                return Ok(abi);
+            }
+        }
+        
+        let abi = fetch().await?;
+        self.put(class_hash, &abi)?;
+        Ok(abi)
+    }
+}
+
+/// Fetch a class from the node and return its ABI as JSON
+pub async fn fetch_abi<P: Provider + Sync>(provider: &P, class_hash: FieldElement) -> Result<String> {
+    let class = provider.get_class(BlockId::Tag(BlockTag::Latest), class_hash).await
+        .with_context(|| format!("Failed to fetch class {:#x}", class_hash))?;
+    
+    match class {
+        ContractClass::Sierra(class) => Ok(class.abi),
+        ContractClass::Legacy(class) => serde_json::to_string(&class.abi.unwrap_or_default())
+            .context("Failed to serialize legacy ABI"),
+    }
+}
+
+/// Resolve the class deployed at `address` and return its class hash and ABI, using the cache
+pub async fn contract_abi<P: Provider + Sync>(
+    provider: &P,
+    cache: &AbiCache,
+    address: FieldElement,
+    refresh: bool,
+) -> Result<(FieldElement, String)> {
+    let class_hash = provider.get_class_hash_at(BlockId::Tag(BlockTag::Latest), address).await
+        .with_context(|| format!("Failed to fetch class hash of {:#x}", address))?;
+    
+    let abi = cache.get_or_fetch(class_hash, refresh, || fetch_abi(provider, class_hash)).await?;
+    Ok((class_hash, abi))
+}
+
+/// A struct event declared in a Sierra ABI
+#[derive(Debug, Clone)]
+pub struct EventAbi {
+    /// Short name, whose selector is the event's first key
+    pub name: String,
+    pub selector: FieldElement,
+    pub keys: Vec<AbiMember>,
+    pub data: Vec<AbiMember>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AbiMember {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub kind: String,
+}
+
+#[derive(Deserialize)]
+struct AbiEntry {
+    #[serde(rename = "type")]
+    entry_type: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    members: Vec<AbiMember>,
+    #[serde(default)]
+    inputs: Vec<AbiMember>,
+    #[serde(default)]
+    outputs: Vec<AbiOutput>,
+    #[serde(default, alias = "stateMutability")]
+    state_mutability: String,
+    /// Functions nested in a Sierra `interface` entry
+    #[serde(default)]
+    items: Vec<AbiEntry>,
+}
+
+#[derive(Deserialize)]
+struct AbiOutput {
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+/// A function declared in an ABI, with its Cairo types
+#[derive(Debug, Clone)]
+pub struct AbiFunction {
+    pub name: String,
+    pub inputs: Vec<AbiMember>,
+    pub outputs: Vec<String>,
+    /// Declared `view`; everything else is treated as external
+    pub view: bool,
+}
+
+impl AbiMember {
+    /// Number of felts the member occupies
+    fn width(&self) -> usize {
+        type_width(&self.ty)
+    }
+}
+
+/// A decoded event field
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedField {
+    pub name: String,
+    pub ty: String,
+    pub felts: Vec<FieldElement>,
+}
+
+impl DecodedField {
+    /// Whether the member is a Cairo signed integer, which is encoded as a felt modulo P
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self.ty.as_str(),
+            "core::integer::i8" | "core::integer::i16" | "core::integer::i32" | "core::integer::i64" | "core::integer::i128"
+        )
+    }
+}
+
+impl EventAbi {
+    /// Label an event's keys (after the selector) and data with the ABI member names
+    ///
+    /// Returns `None` when the felt counts don't line up with the declaration.
+    pub fn decode(&self, keys: &[FieldElement], data: &[FieldElement]) -> Option<Vec<DecodedField>> {
+        let mut fields = split_members(&self.keys, keys.get(1..)?)?;
+        fields.extend(split_members(&self.data, data)?);
+        Some(fields)
+    }
+}
+
+fn split_members(members: &[AbiMember], felts: &[FieldElement]) -> Option<Vec<DecodedField>> {
+    let mut offset = 0;
+    let mut fields = Vec::new();
+    
+    for member in members {
+        let end = offset + member.width();
+        fields.push(DecodedField { name: member.name.clone(), ty: member.ty.clone(), felts: felts.get(offset..end)?.to_vec() });
+        offset = end;
+    }
+    
+    (offset == felts.len()).then_some(fields)
+}
+
+/// Label a function's calldata with its input names
+///
+/// `u256` takes two felts, Cairo 1 arrays and spans a length followed by their
+/// elements, and a Cairo 0 `felt*` the number of felts given by the member before it.
+/// Returns `None` when the felts don't line up with the declaration, which is also
+/// what happens for struct arguments.
+pub fn decode_calldata(inputs: &[AbiMember], calldata: &[FieldElement]) -> Option<Vec<DecodedField>> {
+    let mut offset = 0;
+    let mut fields: Vec<DecodedField> = Vec::new();
+    
+    for member in inputs {
+        let end = match array_element(&member.ty) {
+            Some(element) => {
+                let len = u32::try_from(*calldata.get(offset)?).ok()? as usize;
+                offset + 1 + len * type_width(element)
+            }
+            None if member.ty.ends_with('*') => {
+                let len = u32::try_from(*fields.last()?.felts.first()?).ok()? as usize;
+                offset + len
+            }
+            None => offset + member.width(),
+        };
+        fields.push(DecodedField { name: member.name.clone(), ty: member.ty.clone(), felts: calldata.get(offset..end)?.to_vec() });
+        offset = end;
+    }
+    
+    (offset == calldata.len()).then_some(fields)
+}
+
+/// Element type of a Cairo 1 `Array` or `Span`
+fn array_element(ty: &str) -> Option<&str> {
+    ty.strip_prefix("core::array::Array::<")
+        .or_else(|| ty.strip_prefix("core::array::Span::<"))?
+        .strip_suffix('>')
+}
+
+fn type_width(ty: &str) -> usize {
+    if ty == "core::integer::u256" { 2 } else { 1 }
+}
+
+/// The function in an ABI whose selector is `selector`
+pub fn function_by_selector(abi: &str, selector: FieldElement) -> Option<AbiFunction> {
+    functions(abi)
+        .ok()?
+        .into_iter()
+        .find(|function| get_selector_from_name(&function.name).map(|known| known == selector).unwrap_or(false))
+}
+
+/// Extract the struct events from a Sierra ABI; legacy ABIs and enum wrappers are skipped
+pub fn event_abis(abi: &str) -> Vec<EventAbi> {
+    let entries: Vec<AbiEntry> = serde_json::from_str(abi).unwrap_or_default();
+    
+    entries
+        .into_iter()
+        .filter(|entry| entry.entry_type == "event" && entry.kind == "struct")
+        .filter_map(|entry| {
+            let name = entry.name.rsplit("::").next()?.to_string();
+            let selector = get_selector_from_name(&name).ok()?;
+            let (keys, data) = entry.members.into_iter().partition(|member| member.kind == "key");
+            Some(EventAbi { name, selector, keys, data })
+        })
+        .collect()
+}
+
+/// Names of the view functions that take no arguments, in ABI order
+///
+/// Sierra ABIs declare functions inside `interface` entries; legacy ABIs list them
+/// at the top level with `stateMutability`.
+pub fn view_getters(abi: &str) -> Vec<String> {
+    let entries: Vec<AbiEntry> = serde_json::from_str(abi).unwrap_or_default();
+    
+    entries
+        .iter()
+        .flat_map(|entry| if entry.entry_type == "interface" { entry.items.iter().collect() } else { vec![entry] })
+        .filter(|entry| entry.entry_type == "function" && entry.state_mutability == "view" && entry.inputs.is_empty())
+        .map(|entry| entry.name.clone())
+        .collect()
+}
+
+/// Every function in an ABI, in order, with interface functions flattened
+///
+/// Unlike the other readers this fails on malformed JSON, since code generated
+/// from a half-read ABI would silently miss functions.
+pub fn functions(abi: &str) -> Result<Vec<AbiFunction>> {
+    let entries: Vec<AbiEntry> = serde_json::from_str(abi)
+        .context("ABI is not a JSON array of entries")?;
+    
+    let mut functions: Vec<AbiFunction> = Vec::new();
+    let flattened = entries
+        .iter()
+        .flat_map(|entry| if entry.entry_type == "interface" { entry.items.iter().collect() } else { vec![entry] })
+        .filter(|entry| entry.entry_type == "function");
+    for entry in flattened {
+        if functions.iter().any(|function| function.name == entry.name) {
+            continue;
+        }
+        functions.push(AbiFunction {
+            name: entry.name.clone(),
+            inputs: entry.inputs.clone(),
+            outputs: entry.outputs.iter().map(|output| output.ty.clone()).collect(),
+            view: entry.state_mutability == "view",
+        });
+    }
+    Ok(functions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn temp_cache() -> AbiCache {
+        AbiCache::new(std::env::temp_dir().join(format!("definite-abi-{}", uuid::Uuid::new_v4())))
+    }
+    
+    #[tokio::test]
+    async fn miss_fetches_and_hit_reuses() {
+        let cache = temp_cache();
+        let class_hash = FieldElement::from(0x1234u32);
+        assert_eq!(cache.get(class_hash), None);
+        
+        let abi = cache.get_or_fetch(class_hash, false, || async { Ok("[1]".to_string()) }).await.unwrap();
+        assert_eq!(abi, "[1]");
+        
+        let abi = cache.get_or_fetch(class_hash, false, || async { panic!("cache hit should not fetch") }).await.unwrap();
+        assert_eq!(abi, "[1]");
+        
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn refresh_bypasses_and_overwrites_entry() {
+        let cache = temp_cache();
+        let class_hash = FieldElement::from(0x1234u32);
+        cache.put(class_hash, "[1]").unwrap();
+        
+        let abi = cache.get_or_fetch(class_hash, true, || async { Ok("[2]".to_string()) }).await.unwrap();
+        assert_eq!(abi, "[2]");
+        assert_eq!(cache.get(class_hash).as_deref(), Some("[2]"));
+        
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn failed_fetch_is_not_cached() {
+        let cache = temp_cache();
+        let class_hash = FieldElement::from(0x1234u32);
+        
+        assert!(cache.get_or_fetch(class_hash, false, || async { Err(anyhow::anyhow!("rpc down")) }).await.is_err());
+        assert_eq!(cache.get(class_hash), None);
+    }
+    
+    const VAULT_ABI: &str = r#"[
+        {"type": "function", "name": "deposit", "inputs": [], "outputs": [], "state_mutability": "external"},
+        {"type": "event", "name": "vault::ProtocolVault::Deposited", "kind": "struct", "members": [
+            {"name": "user", "type": "core::starknet::contract_address::ContractAddress", "kind": "key"},
+            {"name": "assets", "type": "core::integer::u256", "kind": "data"},
+            {"name": "shares", "type": "core::integer::u256", "kind": "data"}
+        ]},
+        {"type": "event", "name": "vault::ProtocolVault::Event", "kind": "enum", "variants": []}
+    ]"#;
+    
+    #[test]
+    fn view_getters_reads_interfaces_and_legacy_functions() {
+        let sierra = r#"[
+            {"type": "interface", "name": "vault::IVault", "items": [
+                {"type": "function", "name": "total_assets", "inputs": [], "outputs": [], "state_mutability": "view"},
+                {"type": "function", "name": "balance_of", "inputs": [{"name": "account", "type": "felt252"}], "outputs": [], "state_mutability": "view"},
+                {"type": "function", "name": "deposit", "inputs": [], "outputs": [], "state_mutability": "external"}
+            ]},
+            {"type": "function", "name": "get_vault_config", "inputs": [], "outputs": [], "state_mutability": "view"}
+        ]"#;
+        assert_eq!(view_getters(sierra), vec!["total_assets", "get_vault_config"]);
+        
+        let legacy = r#"[{"type": "function", "name": "get_owner", "inputs": [], "outputs": [], "stateMutability": "view"}]"#;
+        assert_eq!(view_getters(legacy), vec!["get_owner"]);
+    }
+    
+    #[test]
+    fn functions_flattens_interfaces_and_keeps_types() {
+        let abi = r#"[
+            {"type": "interface", "name": "token::IToken", "items": [
+                {"type": "function", "name": "balance_of", "inputs": [{"name": "account", "type": "core::starknet::contract_address::ContractAddress"}], "outputs": [{"type": "core::integer::u256"}], "state_mutability": "view"},
+                {"type": "function", "name": "pause", "inputs": [], "outputs": [], "state_mutability": "external"}
+            ]},
+            {"type": "function", "name": "balance_of", "inputs": [], "outputs": [], "state_mutability": "view"}
+        ]"#;
+        let functions = functions(abi).unwrap();
+        
+        assert_eq!(functions.len(), 2);
+        assert!(functions[0].view);
+        assert_eq!(functions[0].inputs[0].name, "account");
+        assert_eq!(functions[0].outputs, vec!["core::integer::u256"]);
+        assert!(!functions[1].view);
+        
+        assert!(super::functions("{not json").is_err());
+    }
+    
+    #[test]
+    fn event_abis_reads_struct_events() {
+        let events = event_abis(VAULT_ABI);
+        
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "Deposited");
+        assert_eq!(events[0].selector, get_selector_from_name("Deposited").unwrap());
+        assert_eq!(events[0].keys.len(), 1);
+        assert_eq!(events[0].data.len(), 2);
+    }
+    
+    #[test]
+    fn decode_splits_u256_members() {
+        let event = &event_abis(VAULT_ABI)[0];
+        let keys = [event.selector, FieldElement::from(0xabcu32)];
+        let data = [FieldElement::from(5u32), FieldElement::ZERO, FieldElement::from(4u32), FieldElement::ZERO];
+        
+        let fields = event.decode(&keys, &data).unwrap();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].name, "user");
+        assert_eq!(fields[1].felts, vec![FieldElement::from(5u32), FieldElement::ZERO]);
+        
+        assert!(event.decode(&keys, &data[..3]).is_none());
+    }
+    
+    fn member(name: &str, ty: &str) -> AbiMember {
+        AbiMember { name: name.to_string(), ty: ty.to_string(), kind: String::new() }
+    }
+    
+    #[test]
+    fn decode_calldata_reads_u256_and_length_prefixed_arrays() {
+        let felts = |values: &[u64]| values.iter().map(|value| FieldElement::from(*value)).collect::<Vec<_>>();
+        let inputs = [
+            member("recipient", "core::starknet::contract_address::ContractAddress"),
+            member("amount", "core::integer::u256"),
+            member("memo", "core::array::Span::<core::felt252>"),
+        ];
+        
+        let fields = decode_calldata(&inputs, &felts(&[0xabc, 5, 0, 2, 7, 8])).unwrap();
+        assert_eq!(fields[1].felts, felts(&[5, 0]));
+        assert_eq!(fields[2].felts, felts(&[2, 7, 8]));
+        assert!(decode_calldata(&inputs, &felts(&[0xabc, 5, 0, 2, 7])).is_none());
+        
+        let legacy = [member("calldata_len", "felt"), member("calldata", "felt*")];
+        assert_eq!(decode_calldata(&legacy, &felts(&[2, 9, 9])).unwrap()[1].felts, felts(&[9, 9]));
+    }
+    
+    #[test]
+    fn function_by_selector_finds_interface_functions() {
+        let deposit = get_selector_from_name("deposit").unwrap();
+        assert_eq!(function_by_selector(VAULT_ABI, deposit).unwrap().name, "deposit");
+        assert!(function_by_selector(VAULT_ABI, FieldElement::ONE).is_none());
+    }
+}
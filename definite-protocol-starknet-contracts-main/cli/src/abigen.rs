@@ -0,0 +1,411 @@
+//! Typed Rust bindings generated from a Sierra ABI by `dev abi-gen`
+
+use anyhow::{Result, Context};
+use starknet::core::utils::get_selector_from_name;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::abi::{self, AbiFunction};
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become",
+    "box", "do", "final", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Rust side of a Cairo type the generator can encode and decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RustType {
+    Felt,
+    Bool,
+    Uint(&'static str),
+    U256,
+}
+
+impl RustType {
+    /// Map a Cairo core type; structs, enums, arrays and `ByteArray` are not supported yet
+    fn from_cairo(ty: &str) -> Option<RustType> {
+        if ty.contains("::") && !ty.starts_with("core::") {
+            return None;
+        }
+        
+        match ty.rsplit("::").next()? {
+            "felt252" | "ContractAddress" | "ClassHash" => Some(RustType::Felt),
+            "bool" => Some(RustType::Bool),
+            "u8" => Some(RustType::Uint("u8")),
+            "u16" => Some(RustType::Uint("u16")),
+            "u32" => Some(RustType::Uint("u32")),
+            "u64" => Some(RustType::Uint("u64")),
+            "u128" => Some(RustType::Uint("u128")),
+            "u256" => Some(RustType::U256),
+            _ => None,
+        }
+    }
+    
+    fn argument(self) -> &'static str {
+        match self {
+            RustType::Felt => "FieldElement",
+            RustType::Bool => "bool",
+            RustType::Uint(ty) => ty,
+            RustType::U256 => "&BigUint",
+        }
+    }
+    
+    fn output(self) -> &'static str {
+        match self {
+            RustType::U256 => "BigUint",
+            other => other.argument(),
+        }
+    }
+    
+    /// `CalldataBuilder` method appending the argument `name`
+    fn push(self, name: &str) -> String {
+        match self {
+            RustType::Felt => format!(".push_felt({})", name),
+            RustType::Bool => format!(".push_bool({})", name),
+            RustType::Uint(_) => format!(".push_felt(FieldElement::from({}))", name),
+            RustType::U256 => format!(".push_u256({})?", name),
+        }
+    }
+    
+    /// Expression decoding `result`, the response of `function`
+    fn decode(self, function: &str) -> String {
+        match self {
+            RustType::Felt => format!("utils::decode_felt_result(&result, \"{}\")", function),
+            RustType::Bool => format!("Ok(utils::decode_felt_result(&result, \"{}\")? != FieldElement::ZERO)", function),
+            RustType::Uint(_) => format!("utils::decode_uint_result(&result, \"{}\")", function),
+            RustType::U256 => format!("utils::decode_u256_result(&result, \"{}\")", function),
+        }
+    }
+}
+
+/// A function the generator can bind
+struct Binding {
+    name: String,
+    method: String,
+    inputs: Vec<(String, RustType)>,
+    /// Decoded return type of a getter; `None` for call builders
+    output: Option<RustType>,
+}
+
+impl Binding {
+    /// Plan a binding for `function`, or say why it is skipped
+    fn plan(function: &AbiFunction) -> std::result::Result<Binding, String> {
+        let mut inputs = Vec::new();
+        for input in &function.inputs {
+            let ty = RustType::from_cairo(&input.ty)
+                .ok_or_else(|| format!("argument `{}` has unsupported type {}", input.name, input.ty))?;
+            inputs.push((identifier(&input.name), ty));
+        }
+        
+        let output = if function.view {
+            match function.outputs.as_slice() {
+                [ty] => Some(RustType::from_cairo(ty).ok_or_else(|| format!("returns unsupported type {}", ty))?),
+                [] => return Err("view returns nothing".to_string()),
+                _ => return Err("returns several values".to_string()),
+            }
+        } else {
+            None
+        };
+        
+        let method = match output {
+            Some(_) => identifier(&function.name),
+            None => format!("{}_call", function.name),
+        };
+        
+        Ok(Binding { name: function.name.clone(), method, inputs, output })
+    }
+    
+    fn parameters(&self) -> String {
+        self.inputs.iter().map(|(name, ty)| format!(", {}: {}", name, ty.argument())).collect()
+    }
+    
+    fn calldata(&self) -> String {
+        if self.inputs.is_empty() {
+            return "Vec::new()".to_string();
+        }
+        
+        let pushes: String = self.inputs.iter().map(|(name, ty)| ty.push(name)).collect();
+        format!("CalldataBuilder::new(){}.build()", pushes)
+    }
+    
+    fn selector_const(&self) -> String {
+        format!("{}_SELECTOR", self.name.to_uppercase())
+    }
+}
+
+/// Source of a bindings module, with what went into it
+#[derive(Debug)]
+pub struct Bindings {
+    /// File stem of the module, e.g. `protocol_vault`
+    pub module: String,
+    pub source: String,
+    pub getters: Vec<String>,
+    pub calls: Vec<String>,
+    /// Functions left out, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Read an ABI from a file holding either the bare ABI array or a compiled Sierra class
+pub fn read_abi(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    
+    match value {
+        serde_json::Value::Array(_) => Ok(content),
+        serde_json::Value::Object(mut class) => match class.remove("abi") {
+            Some(serde_json::Value::String(abi)) => Ok(abi),
+            Some(abi @ serde_json::Value::Array(_)) => Ok(abi.to_string()),
+            _ => Err(anyhow::anyhow!("{} has no \"abi\" field", path.display())),
+        },
+        _ => Err(anyhow::anyhow!("{} is neither an ABI nor a contract class", path.display())),
+    }
+}
+
+/// Default contract name for an ABI file: its stem, without a Scarb `.contract_class` suffix
+pub fn contract_name(path: &Path) -> String {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    stem.trim_end_matches(".contract_class").to_string()
+}
+
+/// Generate bindings for the contract `name` from the ABI JSON read from `source`
+pub fn generate(abi: &str, name: &str, source: &str) -> Result<Bindings> {
+    let functions = abi::functions(abi)?;
+    let type_name = pascal_case(name);
+    if type_name.is_empty() {
+        return Err(anyhow::anyhow!("'{}' does not make a valid Rust type name", name));
+    }
+    
+    let mut bindings = Vec::new();
+    let mut skipped = Vec::new();
+    for function in &functions {
+        match Binding::plan(function) {
+            Ok(binding) => bindings.push(binding),
+            Err(reason) => skipped.push((function.name.clone(), reason)),
+        }
+    }
+    if bindings.is_empty() {
+        return Err(anyhow::anyhow!("No function in the ABI can be bound yet ({} skipped)", skipped.len()));
+    }
+    
+    let (getters, calls): (Vec<&Binding>, Vec<&Binding>) = bindings.iter().partition(|binding| binding.output.is_some());
+    let uses_u256 = bindings.iter().any(|binding| {
+        binding.output == Some(RustType::U256) || binding.inputs.iter().any(|(_, ty)| *ty == RustType::U256)
+    });
+    let uses_builder = bindings.iter().any(|binding| !binding.inputs.is_empty());
+    
+    let mut out = String::new();
+    writeln!(out, "//! Bindings for `{}`, generated by `definite dev abi-gen` from {}", name, source)?;
+    writeln!(out, "//!")?;
+    writeln!(out, "//! Do not edit by hand; regenerate when the contract's ABI changes.")?;
+    writeln!(out)?;
+    writeln!(out, "#![allow(dead_code)]")?;
+    writeln!(out)?;
+    writeln!(out, "use anyhow::Result;")?;
+    if uses_u256 {
+        writeln!(out, "use num_bigint::BigUint;")?;
+    }
+    if !calls.is_empty() {
+        writeln!(out, "use starknet::accounts::Call;")?;
+    }
+    writeln!(out, "use starknet::core::types::FieldElement;")?;
+    writeln!(out)?;
+    let mut imports = Vec::new();
+    if !getters.is_empty() {
+        imports.push("ContractReader");
+        imports.push("utils");
+    }
+    if uses_builder {
+        imports.push("calldata::CalldataBuilder");
+    }
+    match imports.as_slice() {
+        [] => {}
+        [single] => writeln!(out, "use crate::contracts::{};", single)?,
+        _ => writeln!(out, "use crate::contracts::{{{}}};", imports.join(", "))?,
+    }
+    writeln!(out)?;
+    
+    for binding in &calls {
+        let selector = get_selector_from_name(&binding.name)
+            .with_context(|| format!("Invalid function name '{}'", binding.name))?;
+        let limbs = selector.into_mont();
+        writeln!(out, "/// Selector of `{}`, {:#x}", binding.name, selector)?;
+        writeln!(
+            out,
+            "pub const {}: FieldElement = FieldElement::from_mont([{}, {}, {}, {}]);",
+            binding.selector_const(),
+            limbs[0],
+            limbs[1],
+            limbs[2],
+            limbs[3]
+        )?;
+    }
+    if !calls.is_empty() {
+        writeln!(out)?;
+    }
+    
+    writeln!(out, "pub struct {}<A> {{", type_name)?;
+    writeln!(out, "    address: FieldElement,")?;
+    writeln!(out, "    account: A,")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "impl<A> {}<A> {{", type_name)?;
+    writeln!(out, "    pub fn new(account: A, address: FieldElement) -> Self {{")?;
+    writeln!(out, "        {} {{ address, account }}", type_name)?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "    ")?;
+    writeln!(out, "    pub fn address(&self) -> FieldElement {{")?;
+    writeln!(out, "        self.address")?;
+    writeln!(out, "    }}")?;
+    for binding in &calls {
+        writeln!(out, "    ")?;
+        writeln!(out, "    /// Build a `{}` call without sending it", binding.name)?;
+        writeln!(out, "    pub fn {}(&self{}) -> Result<Call> {{", binding.method, binding.parameters())?;
+        writeln!(out, "        Ok(Call {{")?;
+        writeln!(out, "            to: self.address,")?;
+        writeln!(out, "            selector: {},", binding.selector_const())?;
+        writeln!(out, "            calldata: {},", binding.calldata())?;
+        writeln!(out, "        }})")?;
+        writeln!(out, "    }}")?;
+    }
+    writeln!(out, "}}")?;
+    
+    if !getters.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "impl<A: ContractReader> {}<A> {{", type_name)?;
+        for (index, binding) in getters.iter().enumerate() {
+            let output = binding.output.expect("getters have an output");
+            if index > 0 {
+                writeln!(out, "    ")?;
+            }
+            writeln!(out, "    /// Call the `{}` view", binding.name)?;
+            writeln!(out, "    pub async fn {}(&self{}) -> Result<{}> {{", binding.method, binding.parameters(), output.output())?;
+            writeln!(
+                out,
+                "        let result = self.account.call_contract(self.address, \"{}\", {}).await?;",
+                binding.name,
+                binding.calldata()
+            )?;
+            writeln!(out, "        ")?;
+            writeln!(out, "        {}", output.decode(&binding.name))?;
+            writeln!(out, "    }}")?;
+        }
+        writeln!(out, "}}")?;
+    }
+    
+    Ok(Bindings {
+        module: snake_case(name),
+        source: out,
+        getters: getters.iter().map(|binding| binding.name.clone()).collect(),
+        calls: calls.iter().map(|binding| binding.name.clone()).collect(),
+        skipped,
+    })
+}
+
+/// A Rust identifier for a Cairo name, suffixing `_` to keywords
+fn identifier(name: &str) -> String {
+    if RUST_KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// `protocol_vault` and `protocol-vault` become `ProtocolVault`; existing capitals are kept
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().expect("words are not empty");
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        })
+        .collect::<String>()
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .to_string()
+}
+
+fn snake_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet::core::types::FieldElement;
+    
+    const TOKEN_ABI: &str = r#"[
+        {"type": "interface", "name": "token::IToken", "items": [
+            {"type": "function", "name": "name", "inputs": [], "outputs": [{"type": "core::byte_array::ByteArray"}], "state_mutability": "view"},
+            {"type": "function", "name": "decimals", "inputs": [], "outputs": [{"type": "core::integer::u8"}], "state_mutability": "view"},
+            {"type": "function", "name": "balance_of", "inputs": [{"name": "account", "type": "core::starknet::contract_address::ContractAddress"}], "outputs": [{"type": "core::integer::u256"}], "state_mutability": "view"},
+            {"type": "function", "name": "is_paused", "inputs": [], "outputs": [{"type": "core::bool"}], "state_mutability": "view"},
+            {"type": "function", "name": "set_protocol_vault", "inputs": [{"name": "vault", "type": "core::starknet::contract_address::ContractAddress"}], "outputs": [], "state_mutability": "external"},
+            {"type": "function", "name": "mint", "inputs": [{"name": "to", "type": "core::starknet::contract_address::ContractAddress"}, {"name": "amount", "type": "core::integer::u256"}], "outputs": [], "state_mutability": "external"},
+            {"type": "function", "name": "update_config", "inputs": [{"name": "config", "type": "vault::VaultConfig"}], "outputs": [], "state_mutability": "external"}
+        ]}
+    ]"#;
+    
+    #[test]
+    fn maps_core_types_and_rejects_the_rest() {
+        assert_eq!(RustType::from_cairo("core::starknet::contract_address::ContractAddress"), Some(RustType::Felt));
+        assert_eq!(RustType::from_cairo("felt252"), Some(RustType::Felt));
+        assert_eq!(RustType::from_cairo("core::integer::u64"), Some(RustType::Uint("u64")));
+        assert_eq!(RustType::from_cairo("core::integer::u256"), Some(RustType::U256));
+        assert_eq!(RustType::from_cairo("core::byte_array::ByteArray"), None);
+        assert_eq!(RustType::from_cairo("vault::u256"), None);
+    }
+    
+    #[test]
+    fn generates_getters_and_call_builders() {
+        let bindings = generate(TOKEN_ABI, "hstrk_token", "hstrk_token.json").unwrap();
+        
+        assert_eq!(bindings.module, "hstrk_token");
+        assert_eq!(bindings.getters, vec!["decimals", "balance_of", "is_paused"]);
+        assert_eq!(bindings.calls, vec!["set_protocol_vault", "mint"]);
+        assert_eq!(
+            bindings.skipped,
+            vec![
+                ("name".to_string(), "returns unsupported type core::byte_array::ByteArray".to_string()),
+                ("update_config".to_string(), "argument `config` has unsupported type vault::VaultConfig".to_string()),
+            ]
+        );
+        
+        let source = &bindings.source;
+        assert!(source.contains("pub struct HstrkToken<A> {"));
+        assert!(source.contains("pub async fn balance_of(&self, account: FieldElement) -> Result<BigUint> {"));
+        assert!(source.contains("call_contract(self.address, \"balance_of\", CalldataBuilder::new().push_felt(account).build())"));
+        assert!(source.contains("utils::decode_uint_result(&result, \"decimals\")"));
+        assert!(source.contains("pub fn mint_call(&self, to: FieldElement, amount: &BigUint) -> Result<Call> {"));
+        assert!(source.contains("calldata: CalldataBuilder::new().push_felt(to).push_u256(amount)?.build(),"));
+        assert!(source.contains("use crate::contracts::{ContractReader, utils, calldata::CalldataBuilder};"));
+    }
+    
+    #[test]
+    fn selector_constants_encode_the_real_selector() {
+        let bindings = generate(TOKEN_ABI, "hstrk_token", "hstrk_token.json").unwrap();
+        let selector = get_selector_from_name("set_protocol_vault").unwrap();
+        let limbs = selector.into_mont();
+        
+        let expected = format!("FieldElement::from_mont([{}, {}, {}, {}])", limbs[0], limbs[1], limbs[2], limbs[3]);
+        assert!(bindings.source.contains(&format!("pub const SET_PROTOCOL_VAULT_SELECTOR: FieldElement = {};", expected)));
+        assert_eq!(FieldElement::from_mont(limbs), selector);
+    }
+    
+    #[test]
+    fn names_are_made_rust_safe() {
+        assert_eq!(pascal_case("protocol-vault"), "ProtocolVault");
+        assert_eq!(pascal_case("definite_protocol_ProtocolVault"), "DefiniteProtocolProtocolVault");
+        assert_eq!(snake_case("Protocol-Vault"), "protocol_vault");
+        assert_eq!(identifier("type"), "type_");
+        assert_eq!(contract_name(Path::new("target/dev/definite_ProtocolVault.contract_class.json")), "definite_ProtocolVault");
+    }
+}
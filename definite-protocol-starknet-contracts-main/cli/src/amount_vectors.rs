@@ -0,0 +1,143 @@
+//! Test vectors for amount parsing and formatting
+//!
+//! Each vector is `(input, decimals, expected base units rounded down, rounded up)`,
+//! where `None` means the input is refused. `parse_amount` is checked against the
+//! 18-decimal rows and `parse_units` against all of them, so a change to either
+//! shows up here as a failing row.
+
+use num_bigint::BigUint;
+use std::str::FromStr;
+
+use crate::utils::{format_units, parse_amount, parse_amount_smart, parse_units, parse_units_smart, RoundingMode, WEI_DECIMALS};
+
+/// 2^256 - 1, the largest amount a `u256` holds
+const MAX_U256: &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+type Vector = (&'static str, u8, Option<(&'static str, &'static str)>);
+
+const VECTORS: &[Vector] = &[
+    // Zero in every spelling
+    ("0", 18, Some(("0", "0"))),
+    ("0.0", 18, Some(("0", "0"))),
+    ("0.000000000000000000000", 18, Some(("0", "0"))),
+    ("0", 6, Some(("0", "0"))),
+    // Whole numbers and the smallest unit
+    ("1", 18, Some(("1000000000000000000", "1000000000000000000"))),
+    ("1", 6, Some(("1000000", "1000000"))),
+    ("1", 0, Some(("1", "1"))),
+    ("0.000000000000000001", 18, Some(("1", "1"))),
+    ("0.000001", 6, Some(("1", "1"))),
+    // 18 vs 6 decimals for the same input
+    ("1.5", 18, Some(("1500000000000000000", "1500000000000000000"))),
+    ("1.5", 6, Some(("1500000", "1500000"))),
+    ("1234.567890123456789", 18, Some(("1234567890123456789000", "1234567890123456789000"))),
+    ("1234.567890123456789", 6, Some(("1234567890", "1234567891"))),
+    // Sub-unit precision truncates down and rounds up to the next unit
+    ("0.0000000000000000001", 18, Some(("0", "1"))),
+    ("0.0000000000000000019", 18, Some(("1", "2"))),
+    ("0.0000015", 6, Some(("1", "2"))),
+    ("0.5", 0, Some(("0", "1"))),
+    // Trailing and leading zeros change nothing, even past the last decimal
+    ("1.500000000000000000000000", 18, Some(("1500000000000000000", "1500000000000000000"))),
+    ("1.50000000", 6, Some(("1500000", "1500000"))),
+    ("0001.0", 18, Some(("1000000000000000000", "1000000000000000000"))),
+    ("5.", 18, Some(("5000000000000000000", "5000000000000000000"))),
+    (".5", 18, Some(("500000000000000000", "500000000000000000"))),
+    ("  2  ", 6, Some(("2000000", "2000000"))),
+    // The u256 boundary
+    (MAX_U256, 0, Some((MAX_U256, MAX_U256))),
+    ("115792089237316195423570985008687907853269984665640564039457.584007913129639935", 18, Some((MAX_U256, MAX_U256))),
+    ("115792089237316195423570985008687907853269984665640564039457584007913129639936", 0, None),
+    ("115792089237316195423570985008687907853269984665640564039458", 18, None),
+    // Rounding up past the largest u256 is refused rather than wrapped
+    ("115792089237316195423570985008687907853269984665640564039457584007913129639935.1", 0, Some((MAX_U256, ""))),
+    // Scientific-looking and otherwise ambiguous input is refused, never guessed at
+    ("1e18", 18, None),
+    ("1E-6", 6, None),
+    ("1.5e3", 18, None),
+    ("0x10", 18, None),
+    ("1,000", 18, None),
+    ("1_000", 18, None),
+    ("1.2.3", 18, None),
+    ("-1", 18, None),
+    ("+1", 18, None),
+    ("", 18, None),
+    (".", 18, None),
+    ("inf", 18, None),
+    ("NaN", 18, None),
+];
+
+fn units(value: &str) -> BigUint {
+    BigUint::from_str(value).unwrap()
+}
+
+fn check(vector: &Vector, parse: impl Fn(&str, u8, RoundingMode) -> anyhow::Result<BigUint>) {
+    let (input, decimals, expected) = *vector;
+    let (down, up) = (parse(input, decimals, RoundingMode::Down), parse(input, decimals, RoundingMode::Up));
+    
+    match expected {
+        Some((expected_down, expected_up)) => {
+            assert_eq!(down.unwrap(), units(expected_down), "{:?} at {} decimals, rounded down", input, decimals);
+            if expected_up.is_empty() {
+                assert!(up.is_err(), "{:?} at {} decimals should not round up", input, decimals);
+            } else {
+                assert_eq!(up.unwrap(), units(expected_up), "{:?} at {} decimals, rounded up", input, decimals);
+            }
+        }
+        None => {
+            assert!(down.is_err(), "{:?} at {} decimals should be refused, got {:?}", input, decimals, down);
+            assert!(up.is_err(), "{:?} at {} decimals should be refused, got {:?}", input, decimals, up);
+        }
+    }
+}
+
+#[test]
+fn parse_units_matches_every_vector() {
+    for vector in VECTORS {
+        check(vector, parse_units);
+    }
+}
+
+#[test]
+fn parse_amount_matches_the_18_decimal_vectors() {
+    for vector in VECTORS.iter().filter(|(_, decimals, _)| *decimals == WEI_DECIMALS) {
+        check(vector, |input, _, rounding| parse_amount(input, rounding));
+    }
+}
+
+#[test]
+fn smart_parsing_agrees_on_plain_numbers() {
+    for vector in VECTORS {
+        check(vector, parse_units_smart);
+    }
+    for vector in VECTORS.iter().filter(|(_, decimals, _)| *decimals == WEI_DECIMALS) {
+        check(vector, |input, _, rounding| parse_amount_smart(input, rounding));
+    }
+}
+
+#[test]
+fn smart_suffixes_scale_in_the_token_decimals() {
+    let parse = |input, decimals| parse_units_smart(input, decimals, RoundingMode::Down).unwrap();
+    
+    assert_eq!(parse("1.5K", 6), units("1500000000"));
+    assert_eq!(parse("2M", 6), units("2000000000000"));
+    assert_eq!(parse("0.0000000001K", 6), units("0"));
+    assert_eq!(parse_units_smart("0.0000000001K", 6, RoundingMode::Up).unwrap(), units("1"));
+    assert!(parse_units_smart("115792089237316195423570985008687907853269984665640564039458K", 15, RoundingMode::Down).is_err());
+}
+
+#[test]
+fn format_units_round_trips_exactly() {
+    for (input, decimals, expected) in VECTORS {
+        let Some((down, _)) = expected else { continue };
+        let formatted = format_units(&units(down), *decimals);
+        assert_eq!(parse_units(&formatted, *decimals, RoundingMode::Up).unwrap(), units(down), "{:?} formatted as {}", input, formatted);
+    }
+    
+    assert_eq!(format_units(&units("1500000"), 6), "1.5");
+    assert_eq!(format_units(&units("1"), 18), "0.000000000000000001");
+    assert_eq!(format_units(&units("0"), 6), "0");
+    assert_eq!(format_units(&units("1000000"), 6), "1");
+    assert_eq!(format_units(&units("42"), 0), "42");
+    assert_eq!(format_units(&units(MAX_U256), 18), "115792089237316195423570985008687907853269984665640564039457.584007913129639935");
+}
@@ -0,0 +1,105 @@
+//! The audit envelope `--with-metadata` wraps JSON results in
+
+use anyhow::{Result, Context};
+use serde::Serialize;
+use starknet::providers::Provider;
+
+use crate::Cli;
+use crate::config::{is_configured, Config};
+use crate::utils::canonical_address;
+
+/// The context a result was produced in, for downstream systems to verify
+#[derive(Debug, Serialize)]
+pub struct AuditEnvelope<T> {
+    pub network: String,
+    pub chain_id: String,
+    /// Block every read of the result was made at
+    pub block_number: Option<u64>,
+    pub account: Option<String>,
+    /// Unix timestamp of when the result was produced
+    pub timestamp: u64,
+    pub cli_version: &'static str,
+    pub result: T,
+}
+
+impl<T> AuditEnvelope<T> {
+    pub fn new(config: &Config, block_number: Option<u64>, result: T) -> Self {
+        let account = is_configured(&config.account_address)
+            .then(|| canonical_address(&config.account_address).ok())
+            .flatten();
+        
+        AuditEnvelope {
+            network: config.network.clone(),
+            chain_id: config.chain_id.clone(),
+            block_number,
+            account,
+            timestamp: chrono::Utc::now().timestamp().max(0) as u64,
+            cli_version: env!("CARGO_PKG_VERSION"),
+            result,
+        }
+    }
+}
+
+/// The block a JSON report's reads should be pinned to
+///
+/// Only fetched with `--with-metadata`; otherwise reads stay on the latest block.
+pub async fn report_block<P: Provider + Sync>(cli: &Cli, provider: &P) -> Result<Option<u64>> {
+    if !cli.with_metadata {
+        return Ok(None);
+    }
+    
+    let block = provider.block_number().await
+        .context("Failed to fetch the current block")?;
+    Ok(Some(block))
+}
+
+/// Serialize a JSON result, wrapped in an `AuditEnvelope` when `--with-metadata` is set
+pub fn to_json<T: Serialize>(cli: &Cli, config: &Config, block_number: Option<u64>, result: &T, pretty: bool) -> Result<String> {
+    let json = if cli.with_metadata {
+        let mut envelope = AuditEnvelope::new(config, block_number, result);
+        // Reads were made as the `--account` override, not the configured account
+        if let Some(account) = &cli.account {
+            envelope.account = Some(canonical_address(account)?);
+        }
+        serde_json::to_value(envelope)?
+    } else {
+        serde_json::to_value(result)?
+    };
+    
+    let text = if pretty { serde_json::to_string_pretty(&json)? } else { serde_json::to_string(&json)? };
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn envelope_records_context_around_the_result() {
+        let mut config = Config::default();
+        config.network = "sepolia".to_string();
+        config.chain_id = "SN_SEPOLIA".to_string();
+        config.account_address = "0x123".to_string();
+        
+        let envelope = AuditEnvelope::new(&config, Some(812_345), serde_json::json!({"net_delta": 0.01}));
+        let json = serde_json::to_value(&envelope).unwrap();
+        
+        assert_eq!(json["network"], "sepolia");
+        assert_eq!(json["chain_id"], "SN_SEPOLIA");
+        assert_eq!(json["block_number"], 812_345);
+        assert_eq!(json["account"], format!("0x{:064x}", 0x123));
+        assert_eq!(json["cli_version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["timestamp"].as_u64().unwrap() > 0);
+        assert_eq!(json["result"]["net_delta"], 0.01);
+    }
+    
+    #[test]
+    fn unconfigured_account_is_null() {
+        let mut config = Config::default();
+        config.account_address = String::new();
+        
+        let json = serde_json::to_value(AuditEnvelope::new(&config, None, 1)).unwrap();
+        assert!(json["account"].is_null());
+        assert!(json["block_number"].is_null());
+    }
+}
@@ -1,13 +1,32 @@
-use anyhow::Result;
+use anyhow::{Result, Context};
+use comfy_table::Cell;
+use indicatif::ProgressBar;
+use num_bigint::BigUint;
 use owo_colors::OwoColorize;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use starknet::accounts::ConnectedAccount;
+use starknet::core::types::{BlockId, BlockTag, EventFilter, FieldElement};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::{Cli, theme};
+use crate::{Cli, table, theme};
+use crate::config::Config;
+use crate::contracts::events::{self, decode_flow, FlowKind, VaultFlow};
+use crate::contracts::selectors::selector;
+use crate::contracts::token::TokenContract;
+use crate::contracts::oracle::{OracleContract, PriceData};
+use crate::contracts::vault::{assets_for_shares, VaultContract};
+use crate::export::{ExportTarget, ExportWriter};
+use crate::progress::OutputFormat;
+use crate::utils::{format_signed_percentage, get_account, normalize_address, resolve_target_address, validate_address, wei_to_decimal, AmountFormat};
+use crate::watch::{self, run_watched};
 use super::AnalyticsCommands;
 
 pub async fn handle_analytics_command(command: AnalyticsCommands, cli: &Cli) -> Result<()> {
     match command {
-        AnalyticsCommands::Performance { period, format } => {
-            performance(period, format, cli).await
+        AnalyticsCommands::Performance { period, format, out } => {
+            performance(period, format, out, cli).await
         }
         AnalyticsCommands::Portfolio { history, risk } => {
             portfolio(history, risk, cli).await
@@ -15,19 +34,579 @@ pub async fn handle_analytics_command(command: AnalyticsCommands, cli: &Cli) ->
         AnalyticsCommands::Yield { period, benchmark } => {
             yield_analysis(period, benchmark, cli).await
         }
-        AnalyticsCommands::Metrics { metric, live } => {
-            metrics(metric, live, cli).await
+        AnalyticsCommands::Metrics { metric, live, watch } => {
+            metrics(metric, live, watch, cli).await
         }
+        AnalyticsCommands::Pnl { address, format, out } => {
+            pnl(address, format, out, cli).await
+        }
+        AnalyticsCommands::Export { format, out } => {
+            let manifest = export_all(&format, Path::new(&out))?;
+            println!("{}", format!("Manifest written to {}", manifest.display()).color(theme::SUCCESS));
+            Ok(())
+        }
+    }
+}
+
+/// Export formats shared by every report
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    fn parse(format: &str) -> Result<ExportFormat> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            other => Err(anyhow::anyhow!("Unsupported export format '{}' (expected json or csv)", other)),
+        }
+    }
+    
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+/// A flat analytics report: each row is a metric within a section
+#[derive(Debug, Serialize)]
+struct Report {
+    name: &'static str,
+    rows: Vec<ReportRow>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportRow {
+    section: &'static str,
+    metric: &'static str,
+    value: String,
+}
+
+impl Report {
+    fn new(name: &'static str) -> Report {
+        Report { name, rows: Vec::new() }
+    }
+    
+    fn row(mut self, section: &'static str, metric: &'static str, value: impl ToString) -> Report {
+        self.rows.push(ReportRow { section, metric, value: value.to_string() });
+        self
+    }
+    
+    fn render(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self).context("Failed to serialize report"),
+            ExportFormat::Csv => {
+                let mut out = String::from("section,metric,value\n");
+                for row in &self.rows {
+                    out.push_str(&format!("{},{},{}\n", csv_field(row.section), csv_field(row.metric), csv_field(&row.value)));
+                }
+                Ok(out)
+            }
+        }
+    }
+    
+    /// Conventional file name for the report, `<name>.<ext>`
+    fn default_path(&self, format: ExportFormat) -> PathBuf {
+        PathBuf::from(format!("{}.{}", self.name, format.extension()))
+    }
+    
+    /// Write the report as `<dir>/<name>.<ext>` and return the path
+    fn export(&self, dir: &Path, format: ExportFormat) -> Result<PathBuf> {
+        let path = dir.join(self.default_path(format));
+        fs::write(&path, self.render(format)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn performance_report(days: u32) -> Report {
+    Report::new("performance")
+        .row("Summary", "Period (days)", days)
+        .row("Summary", "Total Return", "12.45%")
+        .row("Summary", "Annualized APY", "15.23%")
+        .row("Summary", "Sharpe Ratio", "2.34")
+        .row("Summary", "Max Drawdown", "2.1%")
+        .row("Summary", "Volatility", "3.8%")
+        .row("Yield Sources", "Funding Rate Arbitrage", "65.8%")
+        .row("Yield Sources", "Volatility Premium", "24.2%")
+        .row("Yield Sources", "Liquidity Provision", "10.0%")
+}
+
+fn portfolio_report() -> Report {
+    Report::new("portfolio")
+        .row("Allocation", "STRK Holdings", "45.2%")
+        .row("Allocation", "Short Perpetuals", "43.8%")
+        .row("Allocation", "Options Positions", "8.5%")
+        .row("Allocation", "Cash/Reserves", "2.5%")
+        .row("Greeks", "Net Delta", "0.02")
+        .row("Greeks", "Total Gamma", "0.15")
+        .row("Greeks", "Total Vega", "-0.08")
+        .row("Greeks", "Total Theta", "0.12")
+        .row("Risk", "Value at Risk (95%)", "1.8%")
+        .row("Risk", "Expected Shortfall", "2.3%")
+        .row("Risk", "Beta to STRK", "0.05")
+        .row("Risk", "Correlation to Market", "0.12")
+}
+
+fn yield_report(days: u32) -> Report {
+    Report::new("yield")
+        .row("Breakdown", "Period (days)", days)
+        .row("Breakdown", "Total Yield", "12.45%")
+        .row("Breakdown", "Daily Average", "0.041%")
+        .row("Breakdown", "Annualized", "15.23%")
+        .row("Sources", "Funding Payments", "8.20%")
+        .row("Sources", "Options Premium", "3.01%")
+        .row("Sources", "Liquidity Rewards", "1.24%")
+        .row("Risk-Adjusted", "Sharpe Ratio", "2.34")
+        .row("Risk-Adjusted", "Sortino Ratio", "3.12")
+        .row("Risk-Adjusted", "Calmar Ratio", "7.25")
+}
+
+fn metrics_report() -> Report {
+    Report::new("metrics")
+        .row("Protocol", "TVL", "12.5M STRK")
+        .row("Protocol", "24h Volume", "1.2M STRK")
+        .row("Protocol", "Current APY", "15.23%")
+        .row("Protocol", "Active Users", "1,247")
+        .row("Protocol", "Risk Score", "23/100")
+}
+
+/// Contents of an export bundle
+///
+/// The reports hold fixed sample figures rather than chain reads, so the manifest
+/// says so and records no network or block.
+#[derive(Debug, Serialize)]
+struct ExportManifest {
+    generated_at: String,
+    sample_data: bool,
+    format: &'static str,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    report: &'static str,
+    file: String,
+}
+
+/// Write every report into `dir` plus a `manifest.json`, returning the manifest path
+fn export_all(format: &str, dir: &Path) -> Result<PathBuf> {
+    let format = ExportFormat::parse(format)?;
+    println!("{}", "Reports contain sample data, not on-chain figures".color(theme::WARNING));
+    
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    
+    let reports = [performance_report(30), portfolio_report(), yield_report(30), metrics_report()];
+    let mut files = Vec::with_capacity(reports.len());
+    for report in &reports {
+        let path = report.export(dir, format)?;
+        println!("  {} {}", "✓".color(theme::SUCCESS), path.display());
+        files.push(ManifestEntry {
+            report: report.name,
+            file: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+        });
+    }
+    
+    let manifest = ExportManifest {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        sample_data: true,
+        format: format.extension(),
+        files,
+    };
+    let manifest_path = dir.join("manifest.json");
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    
+    Ok(manifest_path)
+}
+
+/// Weighted-average cost basis of an hSTRK position, in STRK
+#[derive(Debug, Default, Clone, PartialEq)]
+struct CostBasis {
+    shares: Decimal,
+    cost: Decimal,
+    deposited: Decimal,
+    withdrawn: Decimal,
+    realized: Decimal,
+}
+
+impl CostBasis {
+    /// Apply `flow` and return the cost basis it added or released and the PnL it realized
+    ///
+    /// Withdrawals release basis at the average cost per hSTRK. Shares beyond the
+    /// tracked position (e.g. received by transfer) carry no basis.
+    fn apply(&mut self, flow: &VaultFlow) -> (Decimal, Decimal) {
+        match flow.kind {
+            FlowKind::Deposit => {
+                self.shares += flow.shares;
+                self.cost += flow.assets;
+                self.deposited += flow.assets;
+                (flow.assets, Decimal::ZERO)
+            }
+            FlowKind::Withdraw => {
+                let released = if flow.shares >= self.shares {
+                    self.cost
+                } else {
+                    self.cost * flow.shares / self.shares
+                };
+                let realized = flow.assets - released;
+                
+                self.shares = (self.shares - flow.shares).max(Decimal::ZERO);
+                self.cost -= released;
+                self.withdrawn += flow.assets;
+                self.realized += realized;
+                (released, realized)
+            }
+        }
+    }
+    
+    /// STRK paid per hSTRK still held, if any
+    fn average_cost(&self) -> Option<Decimal> {
+        (!self.shares.is_zero()).then(|| self.cost / self.shares)
+    }
+}
+
+/// One ledger line: a flow and its effect on the cost basis
+#[derive(Debug, Clone, PartialEq)]
+struct PnlEntry {
+    flow: VaultFlow,
+    cost_basis: Decimal,
+    realized: Decimal,
+}
+
+/// Replayed flows plus the live position they are measured against
+#[derive(Debug, Clone, PartialEq)]
+struct PnlStatement {
+    entries: Vec<PnlEntry>,
+    basis: CostBasis,
+    position_shares: Decimal,
+    position_value: Decimal,
+}
+
+impl PnlStatement {
+    /// Replay `flows` in memory; `pnl` applies them page by page as they are fetched
+    #[cfg(test)]
+    fn replay(flows: &[VaultFlow], position_shares: Decimal, position_value: Decimal) -> PnlStatement {
+        let mut basis = CostBasis::default();
+        let entries = flows
+            .iter()
+            .map(|flow| {
+                let (cost_basis, realized) = basis.apply(flow);
+                PnlEntry { flow: flow.clone(), cost_basis, realized }
+            })
+            .collect();
+        
+        PnlStatement { entries, basis, position_shares, position_value }
+    }
+    
+    fn unrealized(&self) -> Decimal {
+        self.position_value - self.basis.cost
+    }
+    
+    fn total(&self) -> Decimal {
+        self.basis.realized + self.unrealized()
+    }
+    
+    /// Total PnL as a percentage of everything deposited
+    fn return_percent(&self) -> Option<Decimal> {
+        (!self.basis.deposited.is_zero()).then(|| self.total() / self.basis.deposited * Decimal::ONE_HUNDRED)
+    }
+    
+    /// Whether hSTRK moved in or out other than through the vault
+    fn has_untracked_shares(&self) -> bool {
+        self.position_shares != self.basis.shares
+    }
+    
+    fn render(&self, address: FieldElement, format: ExportFormat) -> Result<String> {
+        let amount = csv_amount;
+        
+        match format {
+            ExportFormat::Json => {
+                let entries: Vec<_> = self.entries.iter().map(|entry| serde_json::json!({
+                    "block": entry.flow.block,
+                    "transaction": format!("{:#x}", entry.flow.transaction),
+                    "type": entry.flow.kind.label(),
+                    "strk": amount(entry.flow.assets),
+                    "hstrk": amount(entry.flow.shares),
+                    "cost_basis": amount(entry.cost_basis),
+                    "realized_pnl": amount(entry.realized),
+                })).collect();
+                
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "address": normalize_address(address),
+                    "entries": entries,
+                    "summary": {
+                        "deposited": amount(self.basis.deposited),
+                        "withdrawn": amount(self.basis.withdrawn),
+                        "cost_basis": amount(self.basis.cost),
+                        "position_hstrk": amount(self.position_shares),
+                        "position_value": amount(self.position_value),
+                        "realized_pnl": amount(self.basis.realized),
+                        "unrealized_pnl": amount(self.unrealized()),
+                        "total_pnl": amount(self.total()),
+                        "return_percent": self.return_percent().map(|percent| percent.round_dp(4).to_string()),
+                    },
+                })).context("Failed to serialize PnL statement")
+            }
+            ExportFormat::Csv => {
+                let mut out = String::from(PNL_CSV_HEADER);
+                for entry in &self.entries {
+                    out.push_str(&entry.csv_line());
+                }
+                out.push_str(&self.csv_position_line());
+                Ok(out)
+            }
+        }
+    }
+    
+    /// Closing CSV row with the open position, after every ledger entry
+    fn csv_position_line(&self) -> String {
+        format!(
+            ",,Open Position,{},{},{},,{}\n",
+            csv_amount(self.position_value),
+            csv_amount(self.position_shares),
+            csv_amount(self.basis.cost),
+            csv_amount(self.unrealized()),
+        )
+    }
+}
+
+const PNL_CSV_HEADER: &str = "block,transaction,type,strk,hstrk,cost_basis,realized_pnl,unrealized_pnl\n";
+
+fn csv_amount(value: Decimal) -> String {
+    value.round_dp(18).normalize().to_string()
+}
+
+impl PnlEntry {
+    fn csv_line(&self) -> String {
+        format!(
+            "{},{:#x},{},{},{},{},{},\n",
+            self.flow.block.map(|block| block.to_string()).unwrap_or_default(),
+            self.flow.transaction,
+            self.flow.kind.label(),
+            csv_amount(self.flow.assets),
+            csv_amount(self.flow.shares),
+            csv_amount(self.cost_basis),
+            csv_amount(self.realized),
+        )
+    }
+}
+
+/// PnL ledger CSV written row by row as events are replayed
+///
+/// Rows are flushed after every page. A file export stays at `<path>.partial` until
+/// the open position is written, so a failed export leaves nothing behind.
+struct CsvLedgerWriter {
+    out: ExportWriter,
+    rows: usize,
+}
+
+impl CsvLedgerWriter {
+    fn create(target: &ExportTarget) -> Result<CsvLedgerWriter> {
+        let mut out = target.open()?;
+        out.write(PNL_CSV_HEADER)?;
+        Ok(CsvLedgerWriter { out, rows: 0 })
+    }
+    
+    fn write_entry(&mut self, entry: &PnlEntry) -> Result<()> {
+        self.out.write(&entry.csv_line())?;
+        self.rows += 1;
+        Ok(())
+    }
+    
+    fn flush(&mut self) -> Result<()> {
+        self.out.flush()
+    }
+    
+    /// Write the open position and move the file into place
+    fn finish(mut self, statement: &PnlStatement) -> Result<ExportTarget> {
+        self.out.write(&statement.csv_position_line())?;
+        self.out.finish()
+    }
+}
+
+/// Green for gains, red for losses
+fn pnl_cell(value: Decimal) -> Cell {
+    let text = format!("{} STRK", value.round_dp(6));
+    if value.is_sign_negative() && !value.is_zero() {
+        table::cell(text, theme::ERROR)
+    } else {
+        table::cell(format!("+{}", text), theme::SUCCESS)
+    }
+}
+
+async fn pnl(address: Option<String>, format: Option<String>, out: Option<String>, cli: &Cli) -> Result<()> {
+    let export_format = format.as_deref().map(ExportFormat::parse).transpose()?;
+    let export_target = export_format.map(|format| ExportTarget::from_arg(out.as_deref(), || PathBuf::from(format!("pnl.{}", format.extension()))));
+    // With `--out -` stdout carries only the export, so it can be piped
+    let quiet = export_target.as_ref().is_some_and(ExportTarget::is_stdout);
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let account = get_account(cli).await?;
+    let target = resolve_target_address(address, &account, &config)?;
+    
+    if !quiet {
+        println!("{}", format!("Profit and Loss for {}", normalize_address(target)).color(theme::PRIMARY));
+    }
+    
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(theme::spinner_style());
+    pb.set_message("Replaying vault deposits and withdrawals...");
+    
+    let vault_address = validate_address(&config.contracts.vault)?;
+    let vault = VaultContract::with_address(&account, vault_address);
+    let filter = EventFilter {
+        from_block: Some(BlockId::Number(0)),
+        to_block: Some(BlockId::Tag(BlockTag::Latest)),
+        address: Some(vault_address),
+        keys: Some(vec![
+            vec![selector("Deposited")?, selector("Withdrawn")?],
+            vec![target],
+        ]),
+    };
+    // A CSV ledger is streamed out page by page instead of being held in memory
+    let mut csv = match (export_format, &export_target) {
+        (Some(ExportFormat::Csv), Some(target)) => Some(CsvLedgerWriter::create(target)?),
+        _ => None,
+    };
+    let mut basis = CostBasis::default();
+    let mut entries = Vec::new();
+    events::for_each_page(account.provider(), filter, 100, |page| {
+        for event in &page {
+            let Some(flow) = decode_flow(event)? else { continue };
+            let (cost_basis, realized) = basis.apply(&flow);
+            let entry = PnlEntry { flow, cost_basis, realized };
+            match csv.as_mut() {
+                Some(writer) => writer.write_entry(&entry)?,
+                None => entries.push(entry),
+            }
+        }
+        match csv.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }).await
+        .context("Failed to fetch vault events")?;
+    
+    let hstrk_address = config.contracts.require("hstrk_token")?;
+    let hstrk = TokenContract::with_address(&account, hstrk_address);
+    let (balance, total_assets, total_shares) = tokio::try_join!(
+        hstrk.balance_of(target),
+        vault.total_assets(),
+        vault.total_shares(),
+    )?;
+    let position_value = assets_for_shares(&balance, &total_assets, &total_shares);
+    
+    pb.finish_and_clear();
+    
+    let statement = PnlStatement {
+        entries,
+        basis,
+        position_shares: wei_to_decimal(&balance)?,
+        position_value: wei_to_decimal(&position_value)?,
+    };
+    
+    if quiet {
+        if statement.has_untracked_shares() {
+            eprintln!("{}", "Warning: the hSTRK balance differs from what deposits and withdrawals account for; transferred hSTRK carries no cost basis".color(theme::WARNING));
+        }
+    } else {
+        print_pnl(&statement, csv.as_ref().map(|writer| writer.rows), &config);
+    }
+    
+    let exported = match (csv, export_format, export_target) {
+        (Some(writer), _, _) => Some(writer.finish(&statement)?),
+        (None, Some(format), Some(export_target)) => {
+            export_target.write(&statement.render(target, format)?)?;
+            Some(export_target)
+        }
+        _ => None,
+    };
+    if let Some(export_target) = exported.filter(|export_target| !export_target.is_stdout()) {
+        println!();
+        println!("{}", format!("Ledger exported to {}", export_target).color(theme::SUCCESS));
+    }
+    
+    Ok(())
+}
+
+/// Console ledger and summary; `streamed` is the row count when the ledger went to a CSV export instead
+fn print_pnl(statement: &PnlStatement, streamed: Option<usize>, config: &Config) {
+    println!();
+    println!("{}", "Ledger:".color(theme::ACCENT));
+    if let Some(rows) = streamed {
+        println!("  {}", format!("{} entries streamed to the CSV export", rows).color(theme::MUTED));
+    } else if statement.entries.is_empty() {
+        println!("  {}", "No deposits or withdrawals found".color(theme::MUTED));
+    } else {
+        let mut ledger = table::new_table(&["Block", "Type", "STRK", "hSTRK", "Cost Basis", "Realized"], &config.display);
+        for entry in &statement.entries {
+            ledger.add_row(vec![
+                Cell::new(entry.flow.block.map(|block| block.to_string()).unwrap_or_else(|| "pending".to_string())),
+                table::cell(entry.flow.kind.label(), theme::INFO),
+                table::cell(entry.flow.assets.round_dp(6), theme::PRIMARY),
+                table::cell(entry.flow.shares.round_dp(6), theme::SECONDARY),
+                table::cell(entry.cost_basis.round_dp(6), theme::MUTED),
+                match entry.flow.kind {
+                    FlowKind::Deposit => Cell::new(""),
+                    FlowKind::Withdraw => pnl_cell(entry.realized),
+                },
+            ]);
+        }
+        println!("{}", ledger);
+    }
+    
+    println!();
+    println!("{}", "Summary:".color(theme::ACCENT));
+    let mut summary = table::metrics_table(&config.display);
+    summary.add_row(vec![Cell::new("Total Deposited"), table::cell(format!("{} STRK", statement.basis.deposited.round_dp(6)), theme::PRIMARY)]);
+    summary.add_row(vec![Cell::new("Total Withdrawn"), table::cell(format!("{} STRK", statement.basis.withdrawn.round_dp(6)), theme::PRIMARY)]);
+    summary.add_row(vec![Cell::new("Open Cost Basis"), table::cell(format!("{} STRK", statement.basis.cost.round_dp(6)), theme::MUTED)]);
+    if let Some(average) = statement.basis.average_cost() {
+        summary.add_row(vec![Cell::new("Avg Cost per hSTRK"), table::cell(format!("{} STRK", average.round_dp(6)), theme::MUTED)]);
+    }
+    summary.add_row(vec![Cell::new("Position Value"), table::cell(format!("{} STRK ({} hSTRK)", statement.position_value.round_dp(6), statement.position_shares.round_dp(6)), theme::SECONDARY)]);
+    summary.add_row(vec![Cell::new("Realized PnL"), pnl_cell(statement.basis.realized)]);
+    summary.add_row(vec![Cell::new("Unrealized PnL"), pnl_cell(statement.unrealized())]);
+    summary.add_row(vec![Cell::new("Total PnL"), pnl_cell(statement.total())]);
+    if let Some(percent) = statement.return_percent() {
+        summary.add_row(vec![Cell::new("Return"), table::cell(format_signed_percentage(percent), theme::INFO)]);
+    }
+    println!("{}", summary);
+    
+    if statement.has_untracked_shares() {
+        println!();
+        println!("{}", "Warning: the hSTRK balance differs from what deposits and withdrawals account for; transferred hSTRK carries no cost basis".color(theme::WARNING));
     }
 }
 
 async fn performance(
     period: Option<u32>,
     format: Option<String>,
+    out: Option<String>,
     cli: &Cli,
 ) -> Result<()> {
     let days = period.unwrap_or(30);
-    let output_format = format.unwrap_or("console".to_string());
+    let export_format = format.as_deref().filter(|format| *format != "console").map(ExportFormat::parse).transpose()?;
+    let report = performance_report(days);
+    let export = export_format.map(|format| (format, ExportTarget::from_arg(out.as_deref(), || report.default_path(format))));
+    if let Some((format, ExportTarget::Stdout)) = &export {
+        return ExportTarget::Stdout.write(&report.render(*format)?);
+    }
     
     println!("{}", format!("Performance Report ({} days)", days).color(theme::PRIMARY));
     println!();
@@ -46,49 +625,119 @@ async fn performance(
     println!("  Volatility Premium: {}%", "24.2".color(theme::SUCCESS));
     println!("  Liquidity Provision: {}%", "10.0".color(theme::SUCCESS));
     
-    if output_format != "console" {
+    if let Some((format, export_target)) = export {
+        export_target.write(&report.render(format)?)?;
         println!();
-        println!("{}", format!("Exporting report in {} format...", output_format).color(theme::INFO));
-        println!("{}", "Export feature coming soon!".color(theme::WARNING));
+        println!("{}", format!("Report exported to {}", export_target).color(theme::SUCCESS));
     }
     
     Ok(())
 }
 
+/// A holding valued at its oracle price, or `None` when the oracle has no price for it
+struct Holding {
+    asset: &'static str,
+    balance: BigUint,
+    price: Option<PriceData>,
+}
+
+/// USD total, with 18 decimals, of the priced holdings, and the assets left out of it
+fn usd_total(holdings: &[Holding]) -> (BigUint, Vec<&'static str>) {
+    let mut total = BigUint::from(0u32);
+    let mut unpriced = Vec::new();
+    for holding in holdings {
+        match &holding.price {
+            Some(price) => total += price.value_of(&holding.balance),
+            None => unpriced.push(holding.asset),
+        }
+    }
+    (total, unpriced)
+}
+
+/// The account's STRK and hSTRK with their oracle prices
+async fn read_holdings(cli: &Cli, config: &Config) -> Result<Vec<Holding>> {
+    let account = get_account(cli).await?;
+    let oracle = OracleContract::with_address(&account, config.contracts.require("price_oracle")?);
+    let strk = config.contracts.require("strk_token")?;
+    let hstrk = config.contracts.require("hstrk_token")?;
+    
+    let mut holdings = Vec::new();
+    for (asset, token) in [("STRK", strk), ("hSTRK", hstrk)] {
+        let balance = TokenContract::with_address(&account, token).balance_of(account.address()).await
+            .with_context(|| format!("Failed to read the {} balance", asset))?;
+        let price = oracle.get_price(token).await
+            .with_context(|| format!("Failed to read the {} oracle price", asset))?;
+        holdings.push(Holding { asset, balance, price: (!price.is_unpriced()).then_some(price) });
+    }
+    Ok(holdings)
+}
+
 async fn portfolio(history: bool, risk: bool, cli: &Cli) -> Result<()> {
     println!("{}", "Portfolio Analysis".color(theme::PRIMARY));
     println!();
     
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    
+    let holdings = read_holdings(cli, &config).await?;
+    println!("{}", "Holdings:".color(theme::ACCENT));
+    let mut valued = table::new_table(&["Asset", "Balance", "Price (USD)", "Value (USD)"], &config.display);
+    for holding in &holdings {
+        let (price, value) = match &holding.price {
+            Some(price) => (
+                table::cell(format!("${:.4}", price.usd()), theme::INFO),
+                table::cell(format!("${}", amounts.format(&price.value_of(&holding.balance))), theme::PRIMARY),
+            ),
+            None => (table::cell("unpriced", theme::WARNING), table::cell("-", theme::MUTED)),
+        };
+        valued.add_row(vec![Cell::new(holding.asset), table::cell(amounts.format(&holding.balance), theme::PRIMARY), price, value]);
+    }
+    let (total, unpriced) = usd_total(&holdings);
+    valued.add_row(vec![Cell::new("Total"), Cell::new(""), Cell::new(""), table::cell(format!("${}", amounts.format(&total)), theme::SUCCESS)]);
+    println!("{}", valued);
+    for asset in unpriced {
+        println!("{}", format!("Warning: the oracle has no {} price yet; it is left out of the USD total", asset).color(theme::WARNING));
+    }
+    println!();
+    
     // Simulated portfolio data
     println!("{}", "Current Allocation:".color(theme::ACCENT));
-    println!("  STRK Holdings: {}%", "45.2".color(theme::PRIMARY));
-    println!("  Short Perpetuals: {}%", "43.8".color(theme::SECONDARY));
-    println!("  Options Positions: {}%", "8.5".color(theme::INFO));
-    println!("  Cash/Reserves: {}%", "2.5".color(theme::MUTED));
+    let mut allocation = table::new_table(&["Component", "Allocation"], &config.display);
+    allocation.add_row(vec![Cell::new("STRK Holdings"), table::cell("45.2%", theme::PRIMARY)]);
+    allocation.add_row(vec![Cell::new("Short Perpetuals"), table::cell("43.8%", theme::SECONDARY)]);
+    allocation.add_row(vec![Cell::new("Options Positions"), table::cell("8.5%", theme::INFO)]);
+    allocation.add_row(vec![Cell::new("Cash/Reserves"), table::cell("2.5%", theme::MUTED)]);
+    println!("{}", allocation);
     
     println!();
     println!("{}", "Position Details:".color(theme::ACCENT));
-    println!("  Net Delta: {}", "0.02".color(theme::SUCCESS));
-    println!("  Total Gamma: {}", "0.15".color(theme::INFO));
-    println!("  Total Vega: {}", "-0.08".color(theme::WARNING));
-    println!("  Total Theta: {}", "0.12".color(theme::SUCCESS));
+    let mut greeks = table::new_table(&["Greek", "Value"], &config.display);
+    greeks.add_row(vec![Cell::new("Net Delta"), table::cell("0.02", theme::SUCCESS)]);
+    greeks.add_row(vec![Cell::new("Total Gamma"), table::cell("0.15", theme::INFO)]);
+    greeks.add_row(vec![Cell::new("Total Vega"), table::cell("-0.08", theme::WARNING)]);
+    greeks.add_row(vec![Cell::new("Total Theta"), table::cell("0.12", theme::SUCCESS)]);
+    println!("{}", greeks);
     
     if risk {
         println!();
         println!("{}", "Risk Metrics:".color(theme::ACCENT));
-        println!("  Value at Risk (95%): {}%", "1.8".color(theme::WARNING));
-        println!("  Expected Shortfall: {}%", "2.3".color(theme::WARNING));
-        println!("  Beta to STRK: {}", "0.05".color(theme::SUCCESS));
-        println!("  Correlation to Market: {}", "0.12".color(theme::INFO));
+        let mut metrics = table::metrics_table(&config.display);
+        metrics.add_row(vec![Cell::new("Value at Risk (95%)"), table::cell("1.8%", theme::WARNING)]);
+        metrics.add_row(vec![Cell::new("Expected Shortfall"), table::cell("2.3%", theme::WARNING)]);
+        metrics.add_row(vec![Cell::new("Beta to STRK"), table::cell("0.05", theme::SUCCESS)]);
+        metrics.add_row(vec![Cell::new("Correlation to Market"), table::cell("0.12", theme::INFO)]);
+        println!("{}", metrics);
     }
     
     if history {
         println!();
         println!("{}", "Historical Performance:".color(theme::ACCENT));
-        println!("  1 Day: {}%", "+0.12".color(theme::SUCCESS));
-        println!("  7 Days: {}%", "+0.89".color(theme::SUCCESS));
-        println!("  30 Days: {}%", "+3.45".color(theme::SUCCESS));
-        println!("  90 Days: {}%", "+10.23".color(theme::SUCCESS));
+        let mut performance = table::new_table(&["Period", "Return"], &config.display);
+        performance.add_row(vec![Cell::new("1 Day"), table::cell("+0.12%", theme::SUCCESS)]);
+        performance.add_row(vec![Cell::new("7 Days"), table::cell("+0.89%", theme::SUCCESS)]);
+        performance.add_row(vec![Cell::new("30 Days"), table::cell("+3.45%", theme::SUCCESS)]);
+        performance.add_row(vec![Cell::new("90 Days"), table::cell("+10.23%", theme::SUCCESS)]);
+        println!("{}", performance);
     }
     
     Ok(())
@@ -130,18 +779,24 @@ async fn yield_analysis(period: Option<u32>, benchmark: bool, cli: &Cli) -> Resu
     Ok(())
 }
 
-async fn metrics(metric: Option<String>, live: bool, cli: &Cli) -> Result<()> {
-    if live {
-        println!("{}", "Live Protocol Metrics Dashboard".color(theme::PRIMARY));
-        println!("{}", "Press Ctrl+C to exit".color(theme::MUTED));
-    } else {
-        println!("{}", "Protocol Metrics".color(theme::PRIMARY));
-    }
-    println!();
-    
+/// Refresh interval for `analytics metrics --live`
+const LIVE_METRICS_INTERVAL: u64 = 5;
+
+async fn metrics(metric: Option<String>, live: bool, watch: Option<u64>, cli: &Cli) -> Result<()> {
+    let watch = watch.or(live.then_some(LIVE_METRICS_INTERVAL));
     let specific_metric = metric.unwrap_or("all".to_string());
     
-    match specific_metric.as_str() {
+    run_watched(watch, OutputFormat::Human, |_| {
+        print_metrics(&specific_metric, watch);
+        async { Ok::<_, anyhow::Error>(()) }
+    }).await
+}
+
+fn print_metrics(specific_metric: &str, watch: Option<u64>) {
+    println!("{}", watch::title("Protocol Metrics", watch).color(theme::PRIMARY));
+    println!();
+    
+    match specific_metric {
         "tvl" => {
             println!("{}", "Total Value Locked (TVL):".color(theme::ACCENT));
             println!("  Current TVL: {}", "12.5M STRK".color(theme::SUCCESS));
@@ -177,12 +832,132 @@ async fn metrics(metric: Option<String>, live: bool, cli: &Cli) -> Result<()> {
             println!("  Risk Score: {}/100", "23".color(theme::SUCCESS));
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     
-    if live {
-        println!();
-        println!("{}", "Live monitoring feature coming soon!".color(theme::WARNING));
-        println!("This will provide real-time updates of protocol metrics.");
+    #[test]
+    fn unpriced_holdings_are_left_out_of_the_usd_total() {
+        let strk = |amount: u32| BigUint::from(amount) * BigUint::from(10u32).pow(18);
+        let price = |cents: u32| PriceData { price: BigUint::from(cents) * 1_000_000u32, timestamp: 0, sources_count: 1, confidence: 100 };
+        let holdings = [
+            Holding { asset: "STRK", balance: strk(10), price: Some(price(50)) },
+            Holding { asset: "hSTRK", balance: strk(4), price: None },
+        ];
+        
+        let (total, unpriced) = usd_total(&holdings);
+        assert_eq!(total, strk(5));
+        assert_eq!(unpriced, vec!["hSTRK"]);
     }
     
-    Ok(())
+    #[test]
+    fn csv_quotes_fields_with_delimiters() {
+        let report = Report::new("metrics")
+            .row("Protocol", "Active Users", "1,247")
+            .row("Protocol", "TVL", "12.5M STRK");
+        
+        assert_eq!(
+            report.render(ExportFormat::Csv).unwrap(),
+            "section,metric,value\nProtocol,Active Users,\"1,247\"\nProtocol,TVL,12.5M STRK\n"
+        );
+    }
+    
+    fn flow(kind: FlowKind, assets: i64, shares: i64) -> VaultFlow {
+        VaultFlow {
+            block: Some(1),
+            transaction: FieldElement::ONE,
+            kind,
+            assets: Decimal::from(assets),
+            shares: Decimal::from(shares),
+            assets_wei: BigUint::from(assets as u64) * BigUint::from(10u64.pow(18)),
+            shares_wei: BigUint::from(shares as u64) * BigUint::from(10u64.pow(18)),
+        }
+    }
+    
+    #[test]
+    fn cost_basis_uses_weighted_average_across_deposits() {
+        // 100 STRK for 100 hSTRK, then 110 STRK for 100 hSTRK: average 1.05 per hSTRK
+        let flows = [
+            flow(FlowKind::Deposit, 100, 100),
+            flow(FlowKind::Deposit, 110, 100),
+            flow(FlowKind::Withdraw, 120, 100),
+        ];
+        let statement = PnlStatement::replay(&flows, Decimal::from(100), Decimal::from(125));
+        
+        assert_eq!(statement.entries[2].cost_basis, Decimal::from(105));
+        assert_eq!(statement.basis.realized, Decimal::from(15));
+        assert_eq!(statement.basis.cost, Decimal::from(105));
+        assert_eq!(statement.unrealized(), Decimal::from(20));
+        assert_eq!(statement.total(), Decimal::from(35));
+        assert_eq!(statement.return_percent().map(|percent| percent.round_dp(4)), Some(Decimal::new(166667, 4)));
+        assert!(!statement.has_untracked_shares());
+    }
+    
+    #[test]
+    fn withdrawing_untracked_shares_releases_only_tracked_basis() {
+        let flows = [flow(FlowKind::Deposit, 100, 100), flow(FlowKind::Withdraw, 150, 150)];
+        let statement = PnlStatement::replay(&flows, Decimal::ZERO, Decimal::ZERO);
+        
+        assert_eq!(statement.basis.realized, Decimal::from(50));
+        assert_eq!(statement.basis.cost, Decimal::ZERO);
+        assert_eq!(statement.basis.average_cost(), None);
+    }
+    
+    #[test]
+    fn pnl_csv_lists_flows_then_open_position() {
+        let flows = [flow(FlowKind::Deposit, 100, 100)];
+        let statement = PnlStatement::replay(&flows, Decimal::from(100), Decimal::from(102));
+        
+        assert_eq!(
+            statement.render(FieldElement::from(0xabcu32), ExportFormat::Csv).unwrap(),
+            "block,transaction,type,strk,hstrk,cost_basis,realized_pnl,unrealized_pnl\n\
+             1,0x1,Deposit,100,100,100,0,\n\
+             ,,Open Position,102,100,100,,2\n"
+        );
+    }
+    
+    #[test]
+    fn streamed_csv_matches_rendered_csv() {
+        let dir = std::env::temp_dir().join(format!("definite-pnl-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pnl.csv");
+        let flows = [flow(FlowKind::Deposit, 100, 100), flow(FlowKind::Withdraw, 60, 50)];
+        let statement = PnlStatement::replay(&flows, Decimal::from(50), Decimal::from(55));
+        
+        let mut writer = CsvLedgerWriter::create(&ExportTarget::File(path.clone())).unwrap();
+        for entry in &statement.entries {
+            writer.write_entry(entry).unwrap();
+        }
+        writer.flush().unwrap();
+        assert!(!path.exists(), "the export is only moved into place once complete");
+        assert_eq!(writer.finish(&statement).unwrap(), ExportTarget::File(path.clone()));
+        
+        assert_eq!(fs::read_to_string(&path).unwrap(), statement.render(FieldElement::ONE, ExportFormat::Csv).unwrap());
+        assert!(!dir.join("pnl.csv.partial").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+    
+    #[test]
+    fn unfinished_csv_export_is_removed() {
+        let dir = std::env::temp_dir().join(format!("definite-pnl-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        
+        let mut writer = CsvLedgerWriter::create(&ExportTarget::File(dir.join("pnl.csv"))).unwrap();
+        writer.write_entry(&PnlStatement::replay(&[flow(FlowKind::Deposit, 1, 1)], Decimal::ZERO, Decimal::ZERO).entries[0]).unwrap();
+        writer.flush().unwrap();
+        assert!(dir.join("pnl.csv.partial").exists());
+        
+        drop(writer);
+        assert!(!dir.join("pnl.csv.partial").exists());
+        assert!(!dir.join("pnl.csv").exists());
+        fs::remove_dir_all(dir).ok();
+    }
+    
+    #[test]
+    fn export_format_rejects_pdf() {
+        assert_eq!(ExportFormat::parse("CSV").unwrap(), ExportFormat::Csv);
+        assert!(ExportFormat::parse("pdf").is_err());
+    }
 }
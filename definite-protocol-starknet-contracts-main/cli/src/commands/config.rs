@@ -1,9 +1,17 @@
-use anyhow::Result;
+use anyhow::{Result, Context};
 use owo_colors::OwoColorize;
-use dialoguer::{Input, Select, Confirm};
+use dialoguer::{Input, Password, Select};
+use starknet::accounts::Account;
+use starknet::core::types::FieldElement;
+use starknet::signers::SigningKey;
 
-use crate::{Cli, theme};
+use crate::{Cli, table, theme};
 use crate::config::Config;
+use crate::doctor::{self, Severity};
+use crate::rpc_probe::{self, MethodSupport, ProbeCache};
+use crate::prompt::{self, Prompter};
+use crate::contracts::account::AccountContract;
+use crate::utils::{canonical_address, connect_account, display_address, felt_to_hex, format_timestamp, Bps};
 use super::ConfigCommands;
 
 pub async fn handle_config_command(command: ConfigCommands, cli: &Cli) -> Result<()> {
@@ -23,6 +31,15 @@ pub async fn handle_config_command(command: ConfigCommands, cli: &Cli) -> Result
         ConfigCommands::Validate => {
             validate(cli).await
         }
+        ConfigCommands::Doctor { json } => {
+            doctor(json, cli).await
+        }
+        ConfigCommands::RotateKey => {
+            rotate_key(cli.keystore.clone(), cli).await
+        }
+        ConfigCommands::ProbeRpc { refresh, json } => {
+            probe_rpc(refresh, json, cli).await
+        }
     }
 }
 
@@ -30,9 +47,11 @@ async fn init(template: Option<String>, cli: &Cli) -> Result<()> {
     println!("{}", "Initializing Definite Protocol CLI Configuration".color(theme::PRIMARY));
     println!();
     
+    let prompter = Prompter::new(cli, &Config::default().prompts);
     let template_type = if let Some(t) = template {
         t
     } else {
+        prompt::require_tty("a configuration template", "pass --template mainnet, testnet, devnet or custom")?;
         let templates = vec!["mainnet", "testnet", "devnet", "custom"];
         let selection = Select::new()
             .with_prompt("Select configuration template")
@@ -65,47 +84,33 @@ async fn init(template: Option<String>, cli: &Cli) -> Result<()> {
         }
         _ => {
             // Custom configuration - prompt for values
-            config.network = Input::new()
-                .with_prompt("Network name")
-                .default("custom".to_string())
-                .interact_text()?;
-            
-            config.rpc_url = Input::new()
-                .with_prompt("RPC URL")
-                .default("http://localhost:5050".to_string())
-                .interact_text()?;
-            
-            config.chain_id = Input::new()
-                .with_prompt("Chain ID")
-                .default("SN_GOERLI".to_string())
-                .interact_text()?;
+            config.network = prompter.text("Network name", "custom")?;
+            config.rpc_url = prompter.text("RPC URL", "http://localhost:5050")?;
+            config.chain_id = prompter.text("Chain ID", "SN_GOERLI")?;
         }
     }
     
-    // Prompt for required values
-    config.account_address = Input::new()
-        .with_prompt("Account address")
-        .interact_text()?;
-    
-    config.private_key = Input::new()
-        .with_prompt("Private key")
-        .interact_text()?;
-    
-    // Optional contract addresses
-    if Confirm::new()
-        .with_prompt("Configure contract addresses now?")
-        .default(false)
-        .interact()?
-    {
-        config.contracts.vault = Input::new()
-            .with_prompt("Vault contract address")
-            .default("0x0".to_string())
+    // Prompt for required values; with --yes and no TTY they are left for `config set`
+    if prompter.is_interactive() {
+        let account_address: String = Input::new()
+            .with_prompt("Account address")
+            .validate_with(|input: &String| canonical_address(input).map(|_| ()).map_err(|e| e.to_string()))
             .interact_text()?;
+        config.account_address = canonical_address(&account_address)?;
         
-        config.contracts.hstrk_token = Input::new()
-            .with_prompt("hSTRK token address")
-            .default("0x0".to_string())
+        config.private_key = Input::new()
+            .with_prompt("Private key")
             .interact_text()?;
+    } else if cli.yes {
+        eprintln!("{}", "No TTY: account address and private key left empty; set them with `config set`".color(theme::WARNING));
+    } else {
+        prompt::require_tty("the account address and private key", "pass --yes to leave them empty and set them later with `config set`")?;
+    }
+    
+    // Optional contract addresses
+    if prompter.confirm("Configure contract addresses now?", false)? {
+        config.contracts.vault = prompter.text("Vault contract address", "0x0")?;
+        config.contracts.hstrk_token = prompter.text("hSTRK token address", "0x0")?;
         
         // Add other contract addresses as needed
     }
@@ -130,11 +135,22 @@ async fn show(show_secrets: bool, cli: &Cli) -> Result<()> {
     println!("{}", "Network Settings:".color(theme::ACCENT));
     println!("  Network: {}", config.network.color(theme::INFO));
     println!("  RPC URL: {}", config.rpc_url.color(theme::INFO));
-    println!("  Chain ID: {}", config.chain_id.color(theme::INFO));
+    if config.chain_id.is_empty() {
+        println!("  Chain ID: {}", "auto (fetched from the node)".color(theme::MUTED));
+    } else {
+        println!("  Chain ID: {}", config.chain_id.color(theme::INFO));
+    }
+    for (name, value) in &config.rpc.headers {
+        if show_secrets {
+            println!("  RPC Header {}: {}", name, value.color(theme::WARNING));
+        } else {
+            println!("  RPC Header {}: {}", name, "***HIDDEN***".color(theme::MUTED));
+        }
+    }
     
     println!();
     println!("{}", "Account Settings:".color(theme::ACCENT));
-    println!("  Address: {}", config.account_address.color(theme::INFO));
+    println!("  Address: {}", display_address(&config.account_address).color(theme::INFO));
     if show_secrets {
         println!("  Private Key: {}", config.private_key.color(theme::WARNING));
     } else {
@@ -143,21 +159,35 @@ async fn show(show_secrets: bool, cli: &Cli) -> Result<()> {
     
     println!();
     println!("{}", "Contract Addresses:".color(theme::ACCENT));
-    println!("  Vault: {}", config.contracts.vault.color(theme::INFO));
-    println!("  hSTRK Token: {}", config.contracts.hstrk_token.color(theme::INFO));
-    println!("  STRK Token: {}", config.contracts.strk_token.color(theme::INFO));
-    println!("  Price Oracle: {}", config.contracts.price_oracle.color(theme::INFO));
-    println!("  Risk Manager: {}", config.contracts.risk_manager.color(theme::INFO));
-    println!("  Perpetual Hedge: {}", config.contracts.perpetual_hedge.color(theme::INFO));
-    println!("  Options Strategy: {}", config.contracts.options_strategy.color(theme::INFO));
-    println!("  Rebalancing Engine: {}", config.contracts.rebalancing_engine.color(theme::INFO));
+    println!("  Vault: {}", display_address(&config.contracts.vault).color(theme::INFO));
+    println!("  hSTRK Token: {}", display_address(&config.contracts.hstrk_token).color(theme::INFO));
+    println!("  STRK Token: {}", display_address(&config.contracts.strk_token).color(theme::INFO));
+    println!("  Price Oracle: {}", display_address(&config.contracts.price_oracle).color(theme::INFO));
+    println!("  Risk Manager: {}", display_address(&config.contracts.risk_manager).color(theme::INFO));
+    println!("  Perpetual Hedge: {}", display_address(&config.contracts.perpetual_hedge).color(theme::INFO));
+    println!("  Options Strategy: {}", display_address(&config.contracts.options_strategy).color(theme::INFO));
+    println!("  Rebalancing Engine: {}", display_address(&config.contracts.rebalancing_engine).color(theme::INFO));
+    
+    if !config.address_book.is_empty() {
+        println!();
+        println!("{}", "Address Book:".color(theme::ACCENT));
+        for (name, address) in &config.address_book {
+            println!("  {}: {}", name, config.display.address_text(address).color(theme::INFO));
+        }
+    }
     
     println!();
     println!("{}", "Transaction Settings:".color(theme::ACCENT));
     println!("  Gas Limit: {}", config.transaction.gas_limit.color(theme::INFO));
     println!("  Max Fee Per Gas: {}", config.transaction.max_fee_per_gas.color(theme::INFO));
+    println!("  Gas Price Multiplier: {}", format!("{}x", config.transaction.gas_price_multiplier).color(theme::INFO));
     println!("  Timeout: {} seconds", config.transaction.timeout.color(theme::INFO));
     println!("  Confirmations: {}", config.transaction.confirmations.color(theme::INFO));
+    println!("  Fee Token: {}", config.transaction.fee_token.color(theme::INFO));
+    println!("  Max Fee: {}", config.transaction.max_fee.as_deref().unwrap_or("unset").color(theme::INFO));
+    println!("  Max Transaction Amount: {}", config.transaction.max_transaction_amount.as_deref().unwrap_or("unset").color(theme::INFO));
+    println!("  Max Oracle Deviation: {}", config.transaction.max_oracle_deviation_bps.map_or("off".to_string(), |bps| Bps(bps).to_string()).color(theme::INFO));
+    println!("  Log Operations: {}", config.transaction.log_operations.color(theme::INFO));
     
     println!();
     println!("{}", "Display Settings:".color(theme::ACCENT));
@@ -229,3 +259,158 @@ async fn validate(cli: &Cli) -> Result<()> {
     
     Ok(())
 }
+
+async fn doctor(json: bool, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    if !json {
+        println!("{}", "Checking configuration against the network...".color(theme::PRIMARY));
+    }
+    
+    let findings = doctor::diagnose(&config).await;
+    let blocking = findings.iter().filter(|finding| finding.severity <= Severity::Error).count();
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else if findings.is_empty() {
+        println!();
+        println!("{}", "No problems found".color(theme::SUCCESS));
+    } else {
+        println!();
+        for (index, finding) in findings.iter().enumerate() {
+            let color = match finding.severity {
+                Severity::Critical | Severity::Error => theme::ERROR,
+                Severity::Warning => theme::WARNING,
+            };
+            println!("{}. {} {}", index + 1, format!("[{}]", finding.severity).color(color), finding.problem);
+            println!("   {} {}", "Fix:".color(theme::MUTED), finding.fix);
+        }
+    }
+    
+    if blocking > 0 {
+        return Err(anyhow::anyhow!("config doctor found {} problem(s) that will make commands fail", blocking));
+    }
+    Ok(())
+}
+
+async fn probe_rpc(refresh: bool, json: bool, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let cache = ProbeCache::open_default()?;
+    
+    let cached = if refresh { None } else { cache.get(&config.rpc_url) };
+    let from_cache = cached.is_some();
+    let report = match cached {
+        Some(report) => report,
+        None => {
+            let report = rpc_probe::probe(&config).await?;
+            cache.put(&config.rpc_url, &report)?;
+            report
+        }
+    };
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
+    println!("{}", "RPC Compatibility".color(theme::PRIMARY));
+    println!("  Endpoint: {}", config.rpc_url.color(theme::INFO));
+    println!("  Spec Version: {}", report.spec_version.as_deref().unwrap_or("unknown").color(theme::INFO));
+    if from_cache {
+        println!("  Probed: {} {}", format_timestamp(report.probed_at), "(cached; use --refresh to probe again)".color(theme::MUTED));
+    } else {
+        println!("  Probed: {}", format_timestamp(report.probed_at));
+    }
+    println!();
+    
+    let mut methods = table::new_table(&["Method", "Status"], &config.display);
+    for method in rpc_probe::probed_methods() {
+        let support = report.support(method);
+        let color = match support {
+            MethodSupport::Supported => theme::SUCCESS,
+            MethodSupport::Missing => theme::ERROR,
+            MethodSupport::Unknown => theme::WARNING,
+        };
+        methods.add_row(vec![table::cell(method, theme::ACCENT), table::cell(support, color)]);
+    }
+    println!("{}", methods);
+    println!();
+    
+    let unavailable = report.unavailable_features();
+    if unavailable.is_empty() {
+        println!("{}", "All CLI features are available on this endpoint".color(theme::SUCCESS));
+    } else {
+        for (feature, missing) in &unavailable {
+            println!(
+                "{}",
+                format!("{} unavailable: node lacks {}; {}", feature.name, missing.join(", "), feature.unavailable).color(theme::WARNING)
+            );
+        }
+    }
+    
+    let unknown = report.unknown_methods();
+    if !unknown.is_empty() {
+        println!("{}", format!("Could not check {}; the endpoint did not give a JSON-RPC reply", unknown.join(", ")).color(theme::MUTED));
+    }
+    
+    Ok(())
+}
+
+async fn rotate_key(keystore: Option<String>, cli: &Cli) -> Result<()> {
+    println!("{}", "Rotating account private key".color(theme::PRIMARY));
+    println!();
+    
+    if cli.account.is_some() || cli.private_key.is_some() {
+        return Err(anyhow::anyhow!("config rotate-key replaces the configured account's key; it does not take --account or --private-key"));
+    }
+    let config = Config::load(cli.config.as_deref())?;
+    if config.account_address.is_empty() {
+        return Err(anyhow::anyhow!("No account address configured; run `config init` first"));
+    }
+    
+    prompt::require_tty("the new key", "run `config rotate-key` from a terminal")?;
+    let signing_key = match keystore {
+        Some(path) => {
+            let password = Password::new()
+                .with_prompt(format!("Password for {}", path))
+                .interact()?;
+            SigningKey::from_keystore(&path, &password)
+                .map_err(|e| anyhow::anyhow!("Failed to decrypt keystore {}: {}", path, e))?
+        }
+        None => {
+            let key = Password::new()
+                .with_prompt("New private key")
+                .interact()?;
+            let scalar = FieldElement::from_hex_be(key.trim())
+                .context("Private key must be a hex felt")?;
+            SigningKey::from_secret_scalar(scalar)
+        }
+    };
+    
+    // Only the key is swapped, so the account read below targets the configured address
+    let mut rotated = config.clone();
+    rotated.private_key = felt_to_hex(signing_key.secret_scalar());
+    
+    println!("{}", "Checking the key against the account contract...".color(theme::INFO));
+    let account = connect_account(&rotated).await?;
+    let on_chain_key = AccountContract::with_address(&account, account.address()).public_key().await?;
+    let new_key = signing_key.verifying_key().scalar();
+    
+    if on_chain_key != new_key {
+        return Err(anyhow::anyhow!(
+            "Key does not control account {}: it derives public key {:#x}, but the account expects {:#x}. The config was not changed.",
+            config.account_address,
+            new_key,
+            on_chain_key
+        ));
+    }
+    println!("  Public key: {}", format!("{:#x}", new_key).color(theme::SUCCESS));
+    
+    let backup = Config::backup(cli.config.as_deref())?;
+    rotated.save(cli.config.as_deref())?;
+    
+    println!();
+    println!("{}", "Private key updated".color(theme::SUCCESS));
+    println!("Previous config backed up to {}", backup.display().color(theme::ACCENT));
+    
+    Ok(())
+}
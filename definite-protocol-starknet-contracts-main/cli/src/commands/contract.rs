@@ -1,13 +1,33 @@
-use anyhow::Result;
+use anyhow::{Result, Context};
+use comfy_table::Cell;
+use num_bigint::BigUint;
 use owo_colors::OwoColorize;
+use starknet::accounts::{Account, ConnectedAccount};
+use starknet::core::types::{BlockId, BlockTag, EmittedEvent, EventFilter, FieldElement, FunctionCall, InvokeTransaction, MaybePendingTransactionReceipt, Transaction, TransactionReceipt};
+use starknet::core::utils::get_selector_from_name;
+use starknet::providers::Provider;
+use std::collections::HashMap;
 
-use crate::{Cli, theme};
+use crate::{Cli, table, theme};
+use crate::abi::{self, AbiCache, DecodedField, EventAbi};
+use crate::contracts::{events, utils as contract_utils};
+use crate::config::Config;
+use crate::deployment::{random_salt, udc_deploy, DeployedContract, DeploymentManifest, DeploymentPlan, DEFAULT_MANIFEST};
+use crate::oplog::{self, OperationRecord};
+use crate::spend;
+use crate::prompt::Prompter;
+use crate::shutdown::shutdown_signal;
+use crate::contracts::utils::felt_to_bigint;
+use crate::transaction::{self, CallSummary, SignedTransaction};
+use crate::tx_decode;
+use crate::validated::Address;
+use crate::utils::{get_account, get_offline_account, get_provider, felt_to_signed_i128, load_calldata_file, parse_calldata, parse_felt, truncate_string, validate_address, AmountFormat};
 use super::ContractCommands;
 
 pub async fn handle_contract_command(command: ContractCommands, cli: &Cli) -> Result<()> {
     match command {
-        ContractCommands::Deploy { config, network, dry_run } => {
-            deploy(config, network, dry_run, cli).await
+        ContractCommands::Deploy { config, network, manifest, from_manifest } => {
+            deploy(config, network, manifest, from_manifest, cli).await
         }
         ContractCommands::Verify { address, name } => {
             verify(address, name, cli).await
@@ -15,40 +35,190 @@ pub async fn handle_contract_command(command: ContractCommands, cli: &Cli) -> Re
         ContractCommands::Call { address, function, args } => {
             call(address, function, args, cli).await
         }
-        ContractCommands::Send { address, function, args, gas_limit } => {
-            send(address, function, args, gas_limit, cli).await
+        ContractCommands::Abi { address } => {
+            show_abi(address, cli).await
+        }
+        ContractCommands::Watch { address, event, interval, since, until } => {
+            watch(address, event, interval, since, until, cli).await
+        }
+        ContractCommands::Send { address, function, args, calldata_file, gas_limit } => {
+            send(address, function, args, calldata_file, gas_limit, cli).await
+        }
+        ContractCommands::BuildTx { address, function, args, nonce, max_fee_wei, output } => {
+            build_tx(address, function, args, nonce, max_fee_wei, output, cli).await
+        }
+        ContractCommands::Broadcast { file } => {
+            broadcast(file, cli).await
+        }
+        ContractCommands::CancelTx { nonce, tx_hash, fee_bump } => {
+            cancel_tx(nonce, tx_hash, fee_bump, cli).await
+        }
+        ContractCommands::DecodeTx { tx_hash } => {
+            decode_tx(tx_hash, cli).await
+        }
+        ContractCommands::Diff { address, at_block, getters } => {
+            diff(address, at_block, getters, cli).await
         }
     }
 }
-
 async fn deploy(
-    config: Option<String>,
+    plan: Option<String>,
     network: Option<String>,
-    dry_run: bool,
+    manifest_path: Option<String>,
+    from_manifest: Option<String>,
     cli: &Cli,
 ) -> Result<()> {
-    if dry_run {
+    if cli.dry_run {
         println!("{}", "Dry Run: Contract Deployment Simulation".color(theme::WARNING));
     } else {
         println!("{}", "Deploying Definite Protocol Contracts".color(theme::PRIMARY));
     }
     
+    let Some(plan) = plan else {
+        println!();
+        println!("{}", "Deployment Plan:".color(theme::ACCENT));
+        println!("  1. hSTRK Token Contract");
+        println!("  2. Price Oracle Contract");
+        println!("  3. Risk Manager Contract");
+        println!("  4. Perpetual Hedge Contract");
+        println!("  5. Options Strategy Contract");
+        println!("  6. Rebalancing Engine Contract");
+        println!("  7. Protocol Vault Contract");
+        println!();
+        println!("{}", "Pass --config <plan.toml> with each contract's class hash and constructor arguments to deploy them".color(theme::WARNING));
+        return Ok(());
+    };
+    
+    let plan = DeploymentPlan::load(&plan)?;
+    let config = Config::load(cli.config.as_deref())?;
+    if let Some(network) = &network {
+        if *network != config.network {
+            return Err(anyhow::anyhow!(
+                "Asked to deploy to {} but the loaded config is for {}",
+                network,
+                config.network
+            ));
+        }
+    }
+    let deployer = validate_address(&config.account_address)?;
+    
+    let (mut manifest, path) = match from_manifest {
+        Some(path) => {
+            let manifest = DeploymentManifest::load(&path)?;
+            if manifest.chain_id != config.chain_id {
+                return Err(anyhow::anyhow!(
+                    "{} records a deployment on chain {} but the config is for {}",
+                    path,
+                    manifest.chain_id,
+                    config.chain_id
+                ));
+            }
+            if parse_felt(&manifest.deployer)? != deployer {
+                return Err(anyhow::anyhow!("{} was deployed by {}, not the configured account", path, manifest.deployer));
+            }
+            (manifest, path)
+        }
+        None => {
+            let path = manifest_path.unwrap_or_else(|| DEFAULT_MANIFEST.to_string());
+            if std::path::Path::new(&path).exists() {
+                return Err(anyhow::anyhow!("{} already exists; pass --from-manifest {} to resume that deployment", path, path));
+            }
+            (DeploymentManifest::new(&config.network, &config.chain_id, deployer), path)
+        }
+    };
+    
+    let pending = manifest.pending(&plan)?;
+    
     println!();
     println!("{}", "Deployment Plan:".color(theme::ACCENT));
-    println!("  1. hSTRK Token Contract");
-    println!("  2. Price Oracle Contract");
-    println!("  3. Risk Manager Contract");
-    println!("  4. Perpetual Hedge Contract");
-    println!("  5. Options Strategy Contract");
-    println!("  6. Rebalancing Engine Contract");
-    println!("  7. Protocol Vault Contract");
-    
-    if !dry_run {
+    let mut contracts = table::new_table(&["Contract", "Class Hash", "Status"], &config.display);
+    for planned in &plan.contracts {
+        let status = match manifest.get(&planned.name) {
+            Some(deployed) => table::cell(format!("deployed at {}", config.display.address_text(&deployed.address)), theme::SUCCESS),
+            None => table::cell("pending", theme::WARNING),
+        };
+        contracts.add_row(vec![
+            table::cell(&planned.name, theme::PRIMARY),
+            table::cell(truncate_string(&planned.class_hash, 20), theme::MUTED),
+            status,
+        ]);
+    }
+    println!("{}", contracts);
+    println!("  Manifest: {}", path.color(theme::INFO));
+    
+    if pending.is_empty() {
+        println!();
+        println!("{}", "Every contract in the plan is already deployed".color(theme::SUCCESS));
+        return Ok(());
+    }
+    if cli.dry_run {
         println!();
-        println!("{}", "Deployment feature coming soon!".color(theme::WARNING));
-        println!("This will deploy all protocol contracts in the correct order.");
+        println!("{}", format!("{} contract(s) would be deployed", pending.len()).color(theme::WARNING));
+        transaction::print_dry_run_banner();
+        return Ok(());
     }
     
+    println!();
+    if !Prompter::new(cli, &config.prompts).confirm(&format!("Deploy {} contract(s)?", pending.len()), false)? {
+        println!("{}", "Deployment cancelled".color(theme::WARNING));
+        return Ok(());
+    }
+    
+    let account = get_account(cli).await?;
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+    manifest.save(&path)?;
+    
+    for planned in pending {
+        println!("{}", format!("Deploying {}...", planned.name).color(theme::INFO));
+        
+        let class_hash = parse_felt(&planned.class_hash)?;
+        let args = manifest.resolve_args(planned)?;
+        let salt = random_salt();
+        let (call, address) = udc_deploy(deployer, class_hash, salt, &args)?;
+        
+        let record = OperationRecord::new("contract deploy", &config, [&call]);
+        let result = transaction::send_capped(&account, &config, vec![call], fee_cap, gas_pricing, cli.skip_fee_check).await
+            .map_err(transaction::explain_error);
+        let tx_hash = match result {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                oplog::record(cli, &config, record.failed(&e));
+                return Err(e.context(format!("Failed to deploy {}; rerun with --from-manifest {} to resume", planned.name, path)));
+            }
+        };
+        let record = record.sent(tx_hash);
+        spend::note_sent(&config, tx_hash);
+        if let Err(e) = transaction::wait_for_execution(account.provider(), tx_hash, config.transaction.timeout).await {
+            oplog::record(cli, &config, record.failed(&e));
+            return Err(e.context(format!("Failed to deploy {}; rerun with --from-manifest {} to resume", planned.name, path)));
+        }
+        oplog::record(cli, &config, record.succeeded());
+        
+        let block = match account.provider().get_transaction_receipt(tx_hash).await {
+            Ok(MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(receipt))) => Some(receipt.block_number),
+            _ => None,
+        };
+        manifest.contracts.push(DeployedContract {
+            name: planned.name.clone(),
+            class_hash: format!("{:#x}", class_hash),
+            address: format!("{:#x}", address),
+            constructor_args: args.iter().map(|arg| format!("{:#x}", arg)).collect(),
+            salt: format!("{:#x}", salt),
+            transaction_hash: format!("{:#x}", tx_hash),
+            block,
+            timestamp: chrono::Utc::now().timestamp().max(0) as u64,
+        });
+        manifest.save(&path)?;
+        
+        println!("  {} {}", "Address:".color(theme::MUTED), format!("{:#x}", address).color(theme::SUCCESS));
+        println!("  {} {}", "Transaction:".color(theme::MUTED), format!("{:#x}", tx_hash).color(theme::MUTED));
+    }
+    
+    println!();
+    println!("{}", "Deployment complete".color(theme::SUCCESS));
+    println!("Manifest: {}", path.color(theme::ACCENT));
+    
     Ok(())
 }
 
@@ -67,7 +237,7 @@ async fn verify(address: String, name: Option<String>, cli: &Cli) -> Result<()>
 }
 
 async fn call(
-    address: String,
+    address: Address,
     function: String,
     args: Vec<String>,
     cli: &Cli,
@@ -78,23 +248,242 @@ async fn call(
         println!("Arguments: {:?}", args);
     }
     
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    let request = FunctionCall {
+        contract_address: address.0,
+        entry_point_selector: get_selector_from_name(&function)
+            .with_context(|| format!("Invalid function name '{}'", function))?,
+        calldata: parse_calldata(&args)?,
+    };
+    
+    let result = provider.call(request, BlockId::Tag(BlockTag::Latest)).await
+        .map_err(|e| transaction::explain_error(e.into()))
+        .with_context(|| format!("Call to '{}' failed", function))?;
+    
     println!();
-    println!("{}", "Contract call feature coming soon!".color(theme::WARNING));
-    println!("This will call view functions on deployed contracts.");
+    println!("{}", "Result:".color(theme::ACCENT));
+    if result.is_empty() {
+        println!("  {}", "(no return values)".color(theme::MUTED));
+    }
+    for (index, felt) in result.iter().enumerate() {
+        println!("  [{}] {} ({})", index, format!("{:#x}", felt).color(theme::INFO), felt_to_bigint(*felt));
+    }
+    
+    Ok(())
+}
+
+async fn show_abi(address: String, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    let address = validate_address(&address)?;
+    
+    let cache = AbiCache::open_default()?;
+    let (class_hash, abi) = abi::contract_abi(&provider, &cache, address, cli.refresh_abi).await?;
+    
+    println!("{}", format!("ABI for {:#x}", address).color(theme::PRIMARY));
+    println!("  Class Hash: {}", format!("{:#x}", class_hash).color(theme::INFO));
+    println!();
+    
+    match serde_json::from_str::<serde_json::Value>(&abi) {
+        Ok(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+        Err(_) => println!("{}", abi),
+    }
+    
+    Ok(())
+}
+
+async fn watch(address: String, event: Option<String>, interval: Option<u64>, since: Option<u64>, until: Option<u64>, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    let address = validate_address(&address)?;
+    let interval = interval.unwrap_or(5);
+    
+    // Without a cached or fetchable ABI, events are shown as raw felts
+    let event_abis = match AbiCache::open_default() {
+        Ok(cache) => abi::contract_abi(&provider, &cache, address, cli.refresh_abi).await
+            .map(|(_, abi)| abi::event_abis(&abi))
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    
+    let keys = match &event {
+        Some(name) => Some(vec![vec![get_selector_from_name(name)
+            .with_context(|| format!("Invalid event name '{}'", name))?]]),
+        None => None,
+    };
+    
+    // A bounded window is scanned once; otherwise follow the chain from `--since` or the next block
+    if until.is_some() {
+        let (from, to) = contract_utils::blocks_between(&provider, since, until).await?;
+        println!("{}", format!("Events from {:#x} in blocks {}-{}", address, from, to).color(theme::PRIMARY));
+        if let Some(name) = &event {
+            println!("  Filter: {}", name.color(theme::INFO));
+        }
+        println!();
+        
+        let filter = EventFilter {
+            from_block: Some(BlockId::Number(from)),
+            to_block: Some(BlockId::Number(to)),
+            address: Some(address),
+            keys,
+        };
+        for emitted in events::fetch_events(&provider, filter, 100).await? {
+            print_event(&emitted, &event_abis);
+        }
+        return Ok(());
+    }
+    
+    let mut next_block = match since {
+        Some(_) => contract_utils::blocks_between(&provider, since, None).await?.0,
+        None => contract_utils::get_current_block(&provider).await? + 1,
+    };
+    
+    println!("{}", format!("Watching events from {:#x} (Ctrl+C to stop)", address).color(theme::PRIMARY));
+    if let Some(name) = &event {
+        println!("  Filter: {}", name.color(theme::INFO));
+    }
+    println!("  Starting at block: {}", next_block.color(theme::MUTED));
+    println!();
+    
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        let latest = contract_utils::get_current_block(&provider).await?;
+        
+        if latest >= next_block {
+            let filter = EventFilter {
+                from_block: Some(BlockId::Number(next_block)),
+                to_block: Some(BlockId::Number(latest)),
+                address: Some(address),
+                keys: keys.clone(),
+            };
+            
+            for emitted in events::fetch_events(&provider, filter, 100).await? {
+                print_event(&emitted, &event_abis);
+            }
+            next_block = latest + 1;
+        }
+        
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = tokio::time::sleep(tokio::time::Duration::from_secs(interval)) => {}
+        }
+    }
+    
+    println!("{}", "Stopped watching".color(theme::MUTED));
+    Ok(())
+}
+
+fn print_event(emitted: &EmittedEvent, event_abis: &[EventAbi]) {
+    let selector = emitted.keys.first().copied();
+    let declared = selector.and_then(|key| event_abis.iter().find(|abi| abi.selector == key));
+    let name = declared
+        .map(|abi| abi.name.as_str())
+        .or_else(|| selector.and_then(events::event_name))
+        .unwrap_or("Unknown");
+    
+    println!("{} {} {}",
+        format!("[{}]", emitted.block_number.map(|n| n.to_string()).unwrap_or_else(|| "pending".to_string())).color(theme::MUTED),
+        name.color(theme::ACCENT),
+        format!("{:#x}", emitted.transaction_hash).color(theme::INFO));
+    
+    match declared.and_then(|abi| abi.decode(&emitted.keys, &emitted.data)) {
+        Some(fields) => {
+            for field in fields {
+                println!("  {}: {}", field.name, field_value(&field).color(theme::PRIMARY));
+            }
+        }
+        None => {
+            let felts = |values: &[starknet::core::types::FieldElement]| {
+                values.iter().map(|felt| format!("{:#x}", felt)).collect::<Vec<_>>().join(", ")
+            };
+            println!("  keys: {}", felts(&emitted.keys).color(theme::MUTED));
+            println!("  data: {}", felts(&emitted.data).color(theme::MUTED));
+        }
+    }
+}
+
+/// A decoded field as text: signed integers in decimal, everything else as hex felts
+fn field_value(field: &DecodedField) -> String {
+    match field.felts.as_slice() {
+        [felt] if field.is_signed() => felt_to_signed_i128(*felt).to_string(),
+        felts => felts.iter().map(|felt| format!("{:#x}", felt)).collect::<Vec<_>>().join(", "),
+    }
+}
+
+async fn decode_tx(tx_hash: String, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    let hash = parse_felt(&tx_hash).context("Invalid transaction hash")?;
+    
+    let transaction = provider.get_transaction_by_hash(hash).await
+        .with_context(|| format!("Failed to fetch transaction {:#x}", hash))?;
+    let (sender, calls) = tx_decode::transaction_calls(&transaction)?;
+    
+    println!("{}", format!("Transaction {:#x}", hash).color(theme::PRIMARY));
+    if let Some(sender) = sender {
+        println!("  Sender: {}", format!("{:#x}", sender).color(theme::INFO));
+    }
+    println!("  Calls: {}", calls.len().color(theme::INFO));
+    
+    // Without a cached or fetchable ABI, a call falls back to the known selectors and raw felts
+    let cache = AbiCache::open_default().ok();
+    let mut abis: HashMap<FieldElement, Option<String>> = HashMap::new();
+    for (index, call) in calls.iter().enumerate() {
+        if !abis.contains_key(&call.to) {
+            let abi = match &cache {
+                Some(cache) => abi::contract_abi(&provider, cache, call.to, cli.refresh_abi).await.ok().map(|(_, abi)| abi),
+                None => None,
+            };
+            abis.insert(call.to, abi);
+        }
+        let function = abis[&call.to].as_deref().and_then(|abi| abi::function_by_selector(abi, call.selector));
+        let name = function
+            .as_ref()
+            .map(|function| function.name.as_str())
+            .or_else(|| tx_decode::known_function(call.selector))
+            .unwrap_or("unknown");
+        
+        println!();
+        println!("{}", format!("Call {}: {}", index + 1, name).color(theme::ACCENT));
+        println!("  To: {}", format!("{:#x}", call.to).color(theme::INFO));
+        println!("  Selector: {}", format!("{:#x}", call.selector).color(theme::MUTED));
+        
+        match function.as_ref().and_then(|function| abi::decode_calldata(&function.inputs, &call.calldata)) {
+            Some(fields) if fields.is_empty() => println!("  {}", "No arguments".color(theme::MUTED)),
+            Some(fields) => {
+                for field in fields {
+                    println!("  {}: {}", field.name, field_value(&field).color(theme::PRIMARY));
+                }
+            }
+            None => {
+                if function.is_some() {
+                    println!("  {}", "Calldata does not match the current ABI; showing raw felts".color(theme::WARNING));
+                }
+                for (position, felt) in call.calldata.iter().enumerate() {
+                    println!("  [{}] {}", position, format!("{:#x}", felt).color(theme::PRIMARY));
+                }
+            }
+        }
+    }
     
     Ok(())
 }
 
 async fn send(
-    address: String,
+    address: Address,
     function: String,
     args: Vec<String>,
+    calldata_file: Option<String>,
     gas_limit: Option<u64>,
     cli: &Cli,
 ) -> Result<()> {
     println!("{}", format!("Sending transaction to function '{}' on contract {}", function, address).color(theme::PRIMARY));
     
-    if !args.is_empty() {
+    if let Some(path) = &calldata_file {
+        println!("Calldata file: {}", path);
+    } else if !args.is_empty() {
         println!("Arguments: {:?}", args);
     }
     
@@ -102,9 +491,357 @@ async fn send(
         println!("Gas limit: {}", gas);
     }
     
+    let to = address.0;
+    let calldata = match &calldata_file {
+        Some(path) => load_calldata_file(path)?,
+        None => parse_calldata(&args)?,
+    };
+    let (call, summary) = transaction::build_call(to, &function, calldata)?;
+    
+    let account = get_account(cli).await?;
+    let nonce = account.get_nonce().await
+        .context("Failed to fetch account nonce")?;
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+    let fee = transaction::prepare_max_fee(&account, &config, vec![call.clone()], fee_cap, gas_pricing, cli.skip_fee_check).await?;
+    
+    println!();
+    println!("{}", "Transaction Summary:".color(theme::ACCENT));
+    print_call_summary(&summary);
+    fee.print(&amounts);
+    println!();
+    
+    if cli.dry_run {
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    let max_fee = fee.sendable()?;
+    if !Prompter::new(cli, &config.prompts).confirm("Send transaction?", true)? {
+        println!("{}", "Transaction cancelled".color(theme::WARNING));
+        return Ok(());
+    }
+    
+    let record = OperationRecord::new("contract send", &config, [&summary]);
+    let signed = transaction::sign_calls(&account, vec![call], vec![summary], nonce, max_fee).await?;
+    let result = transaction::broadcast(account.provider(), &signed).await;
+    spend::note_outcome(&config, &result);
+    oplog::record(cli, &config, record.outcome(&result));
+    let tx_hash = result?;
+    
     println!();
-    println!("{}", "Contract transaction feature coming soon!".color(theme::WARNING));
-    println!("This will send transactions to deployed contracts.");
+    println!("{}", "Transaction Sent!".color(theme::SUCCESS));
+    println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+    
+    Ok(())
+}
+
+async fn build_tx(
+    address: String,
+    function: String,
+    args: Vec<String>,
+    nonce: String,
+    max_fee_wei: Option<String>,
+    output: Option<String>,
+    cli: &Cli,
+) -> Result<()> {
+    let to = validate_address(&address)?;
+    let calldata = parse_calldata(&args)?;
+    let nonce = parse_felt(&nonce).context("Invalid nonce")?;
+    
+    // Offline signing cannot estimate, so the cap is signed as the max fee
+    let config = Config::load(cli.config.as_deref())?;
+    let max_fee = match max_fee_wei {
+        Some(wei) => parse_felt(&wei).context("Invalid --max-fee-wei")?,
+        None => transaction::fee_cap(cli.max_fee.as_deref(), &config)?
+            .context("build-tx needs --max-fee-wei, --max-fee (or transaction.max_fee in the config)")?,
+    };
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    
+    // Signing only needs the key and chain ID, so this works on an air-gapped machine
+    let account = get_offline_account(cli)?;
+    let (call, summary) = transaction::build_call(to, &function, calldata)?;
+    let signed = transaction::sign_calls(&account, vec![call], vec![summary], nonce, max_fee).await?;
+    let json = signed.to_json()?;
+    
+    match output {
+        Some(path) => {
+            std::fs::write(&path, json)
+                .with_context(|| format!("Failed to write {}", path))?;
+            
+            println!("{}", "Signed transaction built (not broadcast)".color(theme::SUCCESS));
+            println!("  File: {}", path.color(theme::ACCENT));
+            println!("  Transaction Hash: {}", signed.transaction_hash.color(theme::ACCENT));
+            println!("  Nonce: {}", signed.nonce.color(theme::INFO));
+            println!("  Max Fee: {}", amounts.format(&felt_to_bigint(max_fee)).color(theme::INFO));
+        }
+        None => println!("{}", json),
+    }
     
     Ok(())
 }
+
+async fn broadcast(file: String, cli: &Cli) -> Result<()> {
+    let signed = SignedTransaction::load(&file)?;
+    
+    println!("{}", format!("Broadcasting signed transaction from {}", file).color(theme::PRIMARY));
+    println!();
+    println!("{}", "Transaction Summary:".color(theme::ACCENT));
+    println!("  Sender: {}", signed.sender_address.color(theme::INFO));
+    println!("  Nonce: {}", signed.nonce.color(theme::INFO));
+    println!("  Max Fee: {}", signed.max_fee.color(theme::INFO));
+    for summary in &signed.calls {
+        print_call_summary(summary);
+    }
+    println!("  Expected Hash: {}", signed.transaction_hash.color(theme::ACCENT));
+    println!();
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    
+    let chain_id = provider.chain_id().await
+        .context("Failed to fetch chain ID from provider")?;
+    if format!("{:#x}", chain_id) != signed.chain_id {
+        return Err(anyhow::anyhow!(
+            "Transaction was signed for chain {} but the RPC node is on {:#x}",
+            signed.chain_id,
+            chain_id
+        ));
+    }
+    
+    if cli.dry_run {
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    if !Prompter::new(cli, &config.prompts).confirm("Broadcast transaction?", true)? {
+        println!("{}", "Broadcast cancelled".color(theme::WARNING));
+        return Ok(());
+    }
+    
+    let result = transaction::broadcast(&provider, &signed).await;
+    spend::note_outcome(&config, &result);
+    oplog::record(cli, &config, OperationRecord::new("contract broadcast", &config, &signed.calls).outcome(&result));
+    let tx_hash = format!("{:#x}", result?);
+    
+    println!("{}", "Transaction Broadcast!".color(theme::SUCCESS));
+    println!("Transaction Hash: {}", tx_hash.color(theme::ACCENT));
+    
+    if tx_hash != signed.transaction_hash {
+        println!("{}", format!("Warning: node reported a different hash than the signed blob ({})", signed.transaction_hash).color(theme::WARNING));
+    }
+    
+    Ok(())
+}
+
+/// The max fee and nonce of a stuck transaction sent from `sender`, read from the node
+async fn stuck_transaction<P: Provider>(provider: &P, hash: FieldElement, sender: FieldElement) -> Result<(FieldElement, Option<BigUint>)> {
+    let transaction = provider.get_transaction_by_hash(hash).await
+        .with_context(|| format!("Failed to fetch transaction {:#x}; the node may not know about it", hash))?;
+    
+    match transaction {
+        Transaction::Invoke(InvokeTransaction::V1(tx)) if tx.sender_address == sender => Ok((tx.nonce, Some(felt_to_bigint(tx.max_fee)))),
+        // A v3 transaction pays in STRK by resource bounds, which a v1 max fee cannot be compared against
+        Transaction::Invoke(InvokeTransaction::V3(tx)) if tx.sender_address == sender => Ok((tx.nonce, None)),
+        Transaction::Invoke(InvokeTransaction::V1(_)) | Transaction::Invoke(InvokeTransaction::V3(_)) => {
+            Err(anyhow::anyhow!("Transaction {:#x} was not sent by this account", hash))
+        }
+        _ => Err(anyhow::anyhow!("Transaction {:#x} is not an account invoke transaction", hash)),
+    }
+}
+
+async fn cancel_tx(nonce: String, tx_hash: Option<String>, fee_bump: u32, cli: &Cli) -> Result<()> {
+    let nonce = parse_felt(&nonce).context("Invalid nonce")?;
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let account = get_account(cli).await?;
+    let provider = account.provider();
+    let sender = account.address();
+    
+    println!("{}", format!("Cancelling transaction at nonce {:#x}", nonce).color(theme::PRIMARY));
+    
+    let stuck_fee = match &tx_hash {
+        Some(hash) => {
+            let hash = parse_felt(hash).context("Invalid transaction hash")?;
+            // Only executed transactions have receipts, pending block included
+            if provider.get_transaction_receipt(hash).await.is_ok() {
+                return Err(anyhow::anyhow!("Transaction {:#x} has already been executed; it can no longer be replaced", hash));
+            }
+            let (stuck_nonce, stuck_fee) = stuck_transaction(provider, hash, sender).await?;
+            if stuck_nonce != nonce {
+                return Err(anyhow::anyhow!("Transaction {:#x} has nonce {:#x}, not {:#x}", hash, stuck_nonce, nonce));
+            }
+            if stuck_fee.is_none() {
+                println!("{}", "The stuck transaction pays its fee in STRK; the replacement is priced from a fresh estimate only".color(theme::WARNING));
+            }
+            stuck_fee
+        }
+        None => None,
+    };
+    
+    let (latest, pending) = tokio::try_join!(
+        provider.get_nonce(BlockId::Tag(BlockTag::Latest), sender),
+        provider.get_nonce(BlockId::Tag(BlockTag::Pending), sender),
+    ).context("Failed to fetch account nonce")?;
+    if let Some(note) = transaction::check_replaceable_nonce(nonce, latest, pending)? {
+        println!("{}", note.color(theme::WARNING));
+    }
+    
+    // A zero-value transfer to itself changes nothing but takes the nonce
+    let fee_token = transaction::fee_payment_token()?;
+    let (call, summary) = transaction::build_call(fee_token, "transfer", vec![sender, FieldElement::ZERO, FieldElement::ZERO])?;
+    let estimate = transaction::prepare_max_fee(&account, &config, vec![call.clone()], None, transaction::GasPricing::Estimate, cli.skip_fee_check).await?;
+    let max_fee = transaction::replacement_fee(&felt_to_bigint(estimate.sendable()?), stuck_fee.as_ref(), fee_bump);
+    if let Some(cap) = transaction::fee_cap(cli.max_fee.as_deref(), &config)? {
+        let cap = felt_to_bigint(cap);
+        if max_fee > cap {
+            return Err(anyhow::anyhow!(
+                "replacement fee {} exceeds the --max-fee cap of {}; not sending",
+                amounts.format(&max_fee),
+                amounts.format(&cap)
+            ));
+        }
+    }
+    
+    println!();
+    println!("{}", "Replacement Transaction:".color(theme::ACCENT));
+    print_call_summary(&summary);
+    println!("  Nonce: {}", format!("{:#x}", nonce).color(theme::INFO));
+    if let Some(stuck_fee) = &stuck_fee {
+        println!("  Stuck Max Fee: {}", amounts.format(stuck_fee).color(theme::MUTED));
+    }
+    println!("  Max Fee: {} {}", amounts.format(&max_fee).color(theme::SECONDARY), format!("(+{}%)", fee_bump).color(theme::MUTED));
+    println!();
+    
+    if cli.dry_run {
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    if !Prompter::new(cli, &config.prompts).confirm("Send the replacement transaction?", true)? {
+        println!("{}", "Cancellation aborted".color(theme::WARNING));
+        return Ok(());
+    }
+    
+    let record = OperationRecord::new("contract cancel-tx", &config, [&summary]);
+    let signed = transaction::sign_calls(&account, vec![call], vec![summary], nonce, contract_utils::bigint_to_felt(&max_fee)?).await?;
+    let result = transaction::broadcast(provider, &signed).await;
+    spend::note_outcome(&config, &result);
+    oplog::record(cli, &config, record.outcome(&result));
+    let tx_hash = result.map_err(|err| match transaction::replacement_rejection(&err) {
+        Some(transaction::ReplacementRejection::Unsupported) => err.context(
+            "The node does not replace a transaction at an occupied nonce; wait for the stuck transaction to be rejected or expire, then resend"
+        ),
+        Some(transaction::ReplacementRejection::Underpriced) => err.context(format!(
+            "The node replaces by fee but the replacement was underpriced; retry with a larger --fee-bump than {}%",
+            fee_bump
+        )),
+        None => err,
+    })?;
+    
+    println!("{}", "Replacement Sent!".color(theme::SUCCESS));
+    println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+    println!("{}", "The stuck transaction is cancelled once the replacement is included".color(theme::MUTED));
+    
+    Ok(())
+}
+
+fn print_call_summary(summary: &CallSummary) {
+    println!("  Call: {} on {}", summary.function.color(theme::PRIMARY), summary.to.color(theme::INFO));
+    if !summary.calldata.is_empty() {
+        println!("  Calldata: {}", summary.calldata.join(", ").color(theme::MUTED));
+    }
+}
+/// How a getter's result compares between two blocks
+#[derive(Debug, PartialEq)]
+enum DiffStatus {
+    Unchanged,
+    Changed,
+    Failed,
+}
+
+impl DiffStatus {
+    fn of(before: &Result<Vec<FieldElement>>, after: &Result<Vec<FieldElement>>) -> DiffStatus {
+        match (before, after) {
+            (Ok(before), Ok(after)) if before == after => DiffStatus::Unchanged,
+            (Ok(_), Ok(_)) => DiffStatus::Changed,
+            _ => DiffStatus::Failed,
+        }
+    }
+}
+
+/// Render a getter result for the diff table
+fn diff_value(result: &Result<Vec<FieldElement>>) -> String {
+    match result {
+        Ok(felts) => {
+            let values: Vec<String> = felts.iter().map(|felt| format!("{:#x}", felt)).collect();
+            truncate_string(&format!("[{}]", values.join(", ")), 48)
+        }
+        Err(e) => truncate_string(&format!("error: {}", e), 48),
+    }
+}
+
+async fn diff(address: String, at_block: Vec<u64>, getters: Vec<String>, cli: &Cli) -> Result<()> {
+    let [block_a, block_b] = at_block[..] else {
+        return Err(anyhow::anyhow!("Pass --at-block exactly twice, e.g. --at-block 100 --at-block 200"));
+    };
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    let address = validate_address(&address)?;
+    
+    let getters = if getters.is_empty() {
+        let cache = AbiCache::open_default()?;
+        let (_, abi) = abi::contract_abi(&provider, &cache, address, cli.refresh_abi).await?;
+        abi::view_getters(&abi)
+    } else {
+        getters
+    };
+    if getters.is_empty() {
+        return Err(anyhow::anyhow!("The ABI has no argument-free view functions; pass --getter <name>"));
+    }
+    
+    println!("{}", format!("Comparing {} getters on {:#x} at blocks {} and {}", getters.len(), address, block_a, block_b).color(theme::PRIMARY));
+    for block in [block_a, block_b] {
+        match provider.get_class_hash_at(BlockId::Number(block), address).await {
+            Ok(class_hash) => println!("  Class at block {}: {}", block, format!("{:#x}", class_hash).color(theme::INFO)),
+            Err(e) => println!("  Class at block {}: {}", block, format!("unavailable ({})", e).color(theme::WARNING)),
+        }
+    }
+    println!();
+    
+    let read = |function: &str, block: u64| {
+        let request = get_selector_from_name(function).map(|entry_point_selector| FunctionCall {
+            contract_address: address,
+            entry_point_selector,
+            calldata: vec![],
+        });
+        let provider = &provider;
+        async move {
+            let result = provider.call(request?, BlockId::Number(block)).await?;
+            Ok::<_, anyhow::Error>(result)
+        }
+    };
+    
+    let (label_a, label_b) = (format!("Block {}", block_a), format!("Block {}", block_b));
+    let mut table = table::new_table(&["Getter", label_a.as_str(), label_b.as_str(), "Status"], &config.display);
+    let mut changed = 0;
+    for getter in &getters {
+        let (before, after) = tokio::join!(read(getter, block_a), read(getter, block_b));
+        let status = match DiffStatus::of(&before, &after) {
+            DiffStatus::Unchanged => table::cell("unchanged", theme::SUCCESS),
+            DiffStatus::Changed => {
+                changed += 1;
+                table::cell("changed", theme::WARNING)
+            }
+            DiffStatus::Failed => table::cell("error", theme::ERROR),
+        };
+        table.add_row(vec![Cell::new(getter), Cell::new(diff_value(&before)), Cell::new(diff_value(&after)), status]);
+    }
+    println!("{}", table);
+    
+    println!();
+    println!("{}", format!("{} of {} getters changed", changed, getters.len()).color(if changed == 0 { theme::SUCCESS } else { theme::WARNING }));
+    
+    Ok(())
+}
+
@@ -1,7 +1,23 @@
-use anyhow::Result;
+use anyhow::{Result, Context};
+use comfy_table::Cell;
+use indicatif::ProgressBar;
 use owo_colors::OwoColorize;
+use starknet::accounts::ConnectedAccount;
+use starknet::core::types::{
+    BlockId, BlockTag, ExecuteInvocation, ExecutionResources, FieldElement, FunctionCall, FunctionInvocation,
+    OrderedEvent, StateDiff, TransactionTrace,
+};
+use starknet::providers::Provider;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::{Cli, theme};
+use crate::{Cli, abigen, table, theme};
+use crate::config::Config;
+use crate::contracts::events::event_name;
+use crate::contracts::utils::felt_to_bigint;
+use crate::transaction;
+use crate::utils::{connect_account, parse_calldata, validate_address, AmountFormat};
 use super::DevCommands;
 
 pub async fn handle_dev_command(command: DevCommands, cli: &Cli) -> Result<()> {
@@ -12,12 +28,21 @@ pub async fn handle_dev_command(command: DevCommands, cli: &Cli) -> Result<()> {
         DevCommands::Build { mode, target } => {
             build(mode, target, cli).await
         }
-        DevCommands::Docs { format, private } => {
-            docs(format, private, cli).await
+        DevCommands::Docs { format, private, open } => {
+            docs(format, private, open, cli).await
         }
         DevCommands::Lint { fix } => {
             lint(fix, cli).await
         }
+        DevCommands::Benchmark { rpc, iterations } => {
+            benchmark(rpc, iterations, cli).await
+        }
+        DevCommands::SimulateTx { address, function, args, fork, skip_validate } => {
+            simulate_tx(address, function, args, fork, skip_validate, cli).await
+        }
+        DevCommands::AbiGen { abi, out, name } => {
+            abi_gen(&abi, &out, name)
+        }
     }
 }
 
@@ -91,37 +116,175 @@ async fn build(mode: Option<String>, target: Option<String>, cli: &Cli) -> Resul
     Ok(())
 }
 
-async fn docs(format: Option<String>, private: bool, cli: &Cli) -> Result<()> {
-    let doc_format = format.unwrap_or("html".to_string());
+/// Root of the crate `dev docs` documents: the nearest directory with a `Cargo.toml`
+fn crate_root() -> Result<PathBuf> {
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+    cwd.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| anyhow::anyhow!("No Cargo.toml found in {} or its parents; run `dev docs` inside the CLI crate", cwd.display()))
+}
+
+/// Name rustdoc uses for the crate's directory: the first binary, else the package
+fn doc_crate_name(manifest: &str) -> Result<String> {
+    let manifest: toml::Table = toml::from_str(manifest).context("Failed to parse Cargo.toml")?;
+    let bin_name = manifest.get("bin")
+        .and_then(|bins| bins.as_array())
+        .and_then(|bins| bins.first())
+        .and_then(|bin| bin.get("name"))
+        .and_then(|name| name.as_str());
+    let name = bin_name
+        .or_else(|| manifest.get("package").and_then(|package| package.get("name")).and_then(|name| name.as_str()))
+        .context("Cargo.toml has no package name")?;
     
-    println!("{}", format!("Generating documentation in {} format", doc_format).color(theme::PRIMARY));
+    Ok(name.replace('-', "_"))
+}
+
+/// A documented item: its signature line and the text of its `///` comments
+#[derive(Debug, Clone, PartialEq)]
+struct DocItem {
+    signature: String,
+    docs: String,
+}
+
+/// Module docs (`//!`) and documented items in one source file
+///
+/// Only `pub` items are kept unless `private` is set.
+fn extract_docs(source: &str, private: bool) -> (String, Vec<DocItem>) {
+    let doc_text = |line: &str, marker: &str| line.trim_start().strip_prefix(marker).map(|text| text.strip_prefix(' ').unwrap_or(text).to_string());
     
-    if private {
-        println!("{}", "Including private functions and internal details".color(theme::INFO));
+    let module_docs: Vec<String> = source.lines().filter_map(|line| doc_text(line, "//!")).collect();
+    
+    let mut items = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    for line in source.lines() {
+        if let Some(text) = doc_text(line, "///") {
+            pending.push(text);
+            continue;
+        }
+        
+        let trimmed = line.trim();
+        if trimmed.starts_with("#[") || pending.is_empty() {
+            continue;
+        }
+        
+        let signature = trimmed.split_once(" {").map_or(trimmed, |(head, _)| head).trim_end_matches(';').to_string();
+        if private || signature.starts_with("pub ") {
+            items.push(DocItem { signature, docs: pending.join("\n") });
+        }
+        pending.clear();
     }
     
-    println!();
-    println!("{}", "Documentation Generation:".color(theme::ACCENT));
-    println!("  Parsing contract interfaces...");
-    println!("  Extracting function signatures...");
-    println!("  Generating API documentation...");
-    println!("  Creating usage examples...");
+    (module_docs.join("\n"), items)
+}
+
+/// Render one source file's docs as a markdown page titled with its module path
+fn render_markdown(module: &str, module_docs: &str, items: &[DocItem]) -> String {
+    let mut out = format!("# `{}`\n", module);
+    if !module_docs.is_empty() {
+        out.push_str(&format!("\n{}\n", module_docs));
+    }
+    for item in items {
+        out.push_str(&format!("\n## `{}`\n\n{}\n", item.signature, item.docs));
+    }
+    out
+}
+
+/// Write a markdown page per source file under `out_dir`, returning the pages written
+fn write_markdown_docs(src_dir: &Path, out_dir: &Path, private: bool) -> Result<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    let mut dirs = vec![src_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                sources.push(path);
+            }
+        }
+    }
+    sources.sort();
     
-    println!();
-    println!("{}", "Generated Documentation:".color(theme::ACCENT));
-    println!("  Contract API Reference");
-    println!("  Integration Guide");
-    println!("  Architecture Overview");
-    println!("  Security Considerations");
-    println!("  Deployment Instructions");
+    let mut pages = Vec::new();
+    let mut index = String::from("# API Reference\n\n");
+    for source in &sources {
+        let relative = source.strip_prefix(src_dir).unwrap_or(source).with_extension("");
+        let module = relative.components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("::");
+        
+        let content = fs::read_to_string(source)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        let (module_docs, items) = extract_docs(&content, private);
+        if module_docs.is_empty() && items.is_empty() {
+            continue;
+        }
+        
+        let page = out_dir.join(relative.with_extension("md"));
+        if let Some(parent) = page.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&page, render_markdown(&module, &module_docs, &items))
+            .with_context(|| format!("Failed to write {}", page.display()))?;
+        
+        index.push_str(&format!("- [`{}`]({})\n", module, relative.with_extension("md").display()));
+        pages.push(page);
+    }
+    
+    fs::create_dir_all(out_dir).with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let index_path = out_dir.join("index.md");
+    fs::write(&index_path, index).with_context(|| format!("Failed to write {}", index_path.display()))?;
+    pages.push(index_path);
+    
+    Ok(pages)
+}
+
+async fn docs(format: Option<String>, private: bool, open: bool, _cli: &Cli) -> Result<()> {
+    let doc_format = format.unwrap_or("html".to_string());
+    let root = crate_root()?;
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| root.join("target"));
     
+    println!("{}", format!("Generating {} documentation for {}", doc_format, root.display()).color(theme::PRIMARY));
+    if private {
+        println!("{}", "Including private items".color(theme::INFO));
+    }
     println!();
-    println!("{}", "Documentation generated successfully!".color(theme::SUCCESS));
     
     match doc_format.as_str() {
-        "html" => println!("Open: {}", "docs/index.html".color(theme::ACCENT)),
-        "markdown" => println!("Files: {}", "docs/*.md".color(theme::ACCENT)),
-        _ => println!("Output: {}", "docs/".color(theme::ACCENT)),
+        "html" => {
+            let mut cargo = std::process::Command::new("cargo");
+            cargo.arg("doc").arg("--no-deps").current_dir(&root);
+            if private {
+                cargo.arg("--document-private-items");
+            }
+            if open {
+                cargo.arg("--open");
+            }
+            
+            let status = cargo.status().context("Failed to run `cargo doc`; is cargo installed?")?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("`cargo doc` failed ({})", status));
+            }
+            
+            let manifest = fs::read_to_string(root.join("Cargo.toml")).context("Failed to read Cargo.toml")?;
+            let index = target_dir.join("doc").join(doc_crate_name(&manifest)?).join("index.html");
+            println!();
+            println!("{}", "Documentation generated successfully!".color(theme::SUCCESS));
+            println!("Open: {}", index.display().color(theme::ACCENT));
+        }
+        "markdown" => {
+            let out_dir = target_dir.join("doc-md");
+            let pages = write_markdown_docs(&root.join("src"), &out_dir, private)?;
+            
+            println!("{}", "Documentation generated successfully!".color(theme::SUCCESS));
+            println!("  Pages: {}", pages.len().color(theme::PRIMARY));
+            println!("Index: {}", out_dir.join("index.md").display().color(theme::ACCENT));
+        }
+        other => return Err(anyhow::anyhow!("Unsupported docs format '{}' (expected html or markdown)", other)),
     }
     
     Ok(())
@@ -166,3 +329,333 @@ async fn lint(fix: bool, cli: &Cli) -> Result<()> {
     
     Ok(())
 }
+
+/// Requests timed by `dev benchmark`, in display order
+const BENCHMARK_REQUESTS: [&str; 4] = ["chain_id", "block_number", "call total_assets", "estimate_fee"];
+
+/// Latency samples for one request against one endpoint
+#[derive(Default)]
+struct LatencySamples {
+    durations: Vec<Duration>,
+    errors: u32,
+}
+
+impl LatencySamples {
+    fn record<T>(&mut self, started: Instant, result: Result<T>) {
+        match result {
+            Ok(_) => self.durations.push(started.elapsed()),
+            Err(_) => self.errors += 1,
+        }
+    }
+    
+    fn summary(&self) -> String {
+        let mut sorted = self.durations.clone();
+        sorted.sort();
+        
+        match (percentile(&sorted, 50.0), percentile(&sorted, 95.0)) {
+            (Some(p50), Some(p95)) if self.errors == 0 => format!("{} / {} ms", p50.as_millis(), p95.as_millis()),
+            (Some(p50), Some(p95)) => format!("{} / {} ms ({} failed)", p50.as_millis(), p95.as_millis(), self.errors),
+            _ => format!("all {} failed", self.errors),
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending list
+fn percentile(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+async fn benchmark(rpc: Vec<String>, iterations: Option<u32>, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let iterations = iterations.unwrap_or(10).max(1);
+    let endpoints = if rpc.is_empty() { vec![config.rpc_url.clone()] } else { rpc };
+    let vault = validate_address(&config.contracts.vault)?;
+    
+    println!("{}", format!("Benchmarking {} endpoint(s), {} iterations each", endpoints.len(), iterations).color(theme::PRIMARY));
+    println!();
+    
+    let mut results = Vec::with_capacity(endpoints.len());
+    for endpoint in &endpoints {
+        let mut endpoint_config = config.clone();
+        endpoint_config.rpc_url = endpoint.clone();
+        
+        let pb = ProgressBar::new(iterations as u64);
+        pb.set_style(theme::progress_style());
+        pb.set_message(endpoint.clone());
+        
+        let account = connect_account(&endpoint_config).await
+            .with_context(|| format!("Failed to connect to {}", endpoint))?;
+        let provider = account.provider();
+        let (total_assets, _) = transaction::build_call(vault, "total_assets", vec![])?;
+        let view = FunctionCall {
+            contract_address: vault,
+            entry_point_selector: total_assets.selector,
+            calldata: vec![],
+        };
+        
+        let mut samples: [LatencySamples; 4] = Default::default();
+        for _ in 0..iterations {
+            let started = Instant::now();
+            samples[0].record(started, provider.chain_id().await.map_err(anyhow::Error::from));
+            
+            let started = Instant::now();
+            samples[1].record(started, provider.block_number().await.map_err(anyhow::Error::from));
+            
+            let started = Instant::now();
+            samples[2].record(started, provider.call(view.clone(), BlockId::Tag(BlockTag::Latest)).await.map_err(anyhow::Error::from));
+            
+            let started = Instant::now();
+            samples[3].record(started, transaction::estimate_max_fee(&account, vec![total_assets.clone()]).await);
+            
+            pb.inc(1);
+        }
+        pb.finish_and_clear();
+        
+        results.push(samples);
+    }
+    
+    let headers: Vec<&str> = std::iter::once("Request (p50 / p95)").chain(endpoints.iter().map(String::as_str)).collect();
+    let mut report = table::new_table(&headers, &config.display);
+    for (i, request) in BENCHMARK_REQUESTS.iter().enumerate() {
+        let mut row = vec![Cell::new(request)];
+        row.extend(results.iter().map(|samples| table::cell(samples[i].summary(), theme::PRIMARY)));
+        report.add_row(row);
+    }
+    println!("{}", report);
+    
+    Ok(())
+}
+
+async fn simulate_tx(
+    address: String,
+    function: String,
+    args: Vec<String>,
+    fork: Option<String>,
+    skip_validate: bool,
+    cli: &Cli,
+) -> Result<()> {
+    let mut config = Config::load(cli.config.as_deref())?;
+    if let Some(rpc_url) = fork {
+        config.rpc_url = rpc_url;
+    }
+    
+    println!("{}", format!("Simulating '{}' on contract {}", function, address).color(theme::PRIMARY));
+    println!("RPC: {}", config.rpc_url.color(theme::MUTED));
+    
+    let to = validate_address(&address)?;
+    let calldata = parse_calldata(&args)?;
+    let (call, _) = transaction::build_call(to, &function, calldata)?;
+    
+    let account = connect_account(&config).await?;
+    let simulated = transaction::simulate(&account, vec![call], skip_validate).await?;
+    
+    let trace = match simulated.transaction_trace {
+        TransactionTrace::Invoke(trace) => trace,
+        _ => return Err(anyhow::anyhow!("Node returned a non-invoke trace for an invoke simulation")),
+    };
+    
+    println!();
+    println!("{}", "Simulation Result:".color(theme::ACCENT));
+    let invocation = match &trace.execute_invocation {
+        ExecuteInvocation::Success(invocation) => {
+            println!("  Status: {}", "SUCCEEDED".color(theme::SUCCESS));
+            Some(invocation)
+        }
+        ExecuteInvocation::Reverted(reverted) => {
+            println!("  Status: {}", "REVERTED".color(theme::ERROR));
+            println!("  Reason: {}", reverted.revert_reason.color(theme::ERROR));
+            None
+        }
+    };
+    
+    let fee = &simulated.fee_estimation;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    println!();
+    println!("{}", "Estimated Fee:".color(theme::ACCENT));
+    println!("  Overall Fee: {} ({:?})", amounts.format(&felt_to_bigint(fee.overall_fee)).color(theme::PRIMARY), fee.unit);
+    println!("  Gas Consumed: {}", felt_to_bigint(fee.gas_consumed).color(theme::INFO));
+    println!("  Gas Price: {}", felt_to_bigint(fee.gas_price).color(theme::INFO));
+    
+    if let Some(invocation) = invocation {
+        println!();
+        println!("{}", "Resource Usage:".color(theme::ACCENT));
+        print_resources(&invocation.execution_resources);
+        
+        if !invocation.result.is_empty() {
+            println!();
+            println!("{}", "Return Data:".color(theme::ACCENT));
+            for felt in &invocation.result {
+                println!("  {:#x}", felt);
+            }
+        }
+        
+        let mut events = Vec::new();
+        collect_events(invocation, &mut events);
+        events.sort_by_key(|(_, event)| event.order);
+        
+        println!();
+        println!("{}", format!("Events ({}):", events.len()).color(theme::ACCENT));
+        for (emitter, event) in &events {
+            print_event(*emitter, event);
+        }
+    }
+    
+    if let Some(state_diff) = &trace.state_diff {
+        println!();
+        println!("{}", "State Diff:".color(theme::ACCENT));
+        print_state_diff(state_diff);
+    }
+    
+    println!();
+    println!("{}", "Simulation only. Nothing was broadcast.".color(theme::MUTED));
+    
+    Ok(())
+}
+
+/// Gather events from an invocation and all of its nested calls
+fn collect_events<'a>(invocation: &'a FunctionInvocation, events: &mut Vec<(FieldElement, &'a OrderedEvent)>) {
+    for event in &invocation.events {
+        events.push((invocation.contract_address, event));
+    }
+    for inner in &invocation.calls {
+        collect_events(inner, events);
+    }
+}
+
+fn print_event(emitter: FieldElement, event: &OrderedEvent) {
+    let name = event.keys.first()
+        .map(|key| event_name(*key).map(str::to_string).unwrap_or_else(|| format!("{:#x}", key)))
+        .unwrap_or_else(|| "<anonymous>".to_string());
+    
+    println!("  #{} {} from {}", event.order, name.color(theme::PRIMARY), format!("{:#x}", emitter).color(theme::MUTED));
+    for key in event.keys.iter().skip(1) {
+        println!("    key:  {:#x}", key);
+    }
+    for value in &event.data {
+        println!("    data: {:#x}", value);
+    }
+}
+
+fn print_resources(resources: &ExecutionResources) {
+    println!("  Steps: {}", resources.steps.color(theme::INFO));
+    
+    let builtins = [
+        ("Memory Holes", resources.memory_holes),
+        ("Range Check", resources.range_check_builtin_applications),
+        ("Pedersen", resources.pedersen_builtin_applications),
+        ("Poseidon", resources.poseidon_builtin_applications),
+        ("EC Op", resources.ec_op_builtin_applications),
+        ("ECDSA", resources.ecdsa_builtin_applications),
+        ("Bitwise", resources.bitwise_builtin_applications),
+        ("Keccak", resources.keccak_builtin_applications),
+        ("Segment Arena", resources.segment_arena_builtin),
+    ];
+    for (label, count) in builtins {
+        if let Some(count) = count.filter(|count| *count > 0) {
+            println!("  {}: {}", label, count.color(theme::INFO));
+        }
+    }
+}
+
+fn print_state_diff(state_diff: &StateDiff) {
+    for diff in &state_diff.storage_diffs {
+        println!("  Storage {}", format!("{:#x}", diff.address).color(theme::PRIMARY));
+        for entry in &diff.storage_entries {
+            println!("    {:#x} = {:#x}", entry.key, entry.value);
+        }
+    }
+    
+    for nonce in &state_diff.nonces {
+        println!("  Nonce {} -> {:#x}", format!("{:#x}", nonce.contract_address).color(theme::PRIMARY), nonce.nonce);
+    }
+    
+    for deployed in &state_diff.deployed_contracts {
+        println!("  Deployed {} (class {:#x})", format!("{:#x}", deployed.address).color(theme::PRIMARY), deployed.class_hash);
+    }
+}
+
+fn abi_gen(abi_path: &Path, out_dir: &Path, name: Option<String>) -> Result<()> {
+    let abi = abigen::read_abi(abi_path)?;
+    let name = name.unwrap_or_else(|| abigen::contract_name(abi_path));
+    let bindings = abigen::generate(&abi, &name, &abi_path.display().to_string())?;
+    
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    let path = out_dir.join(format!("{}.rs", bindings.module));
+    fs::write(&path, &bindings.source)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    
+    println!("{}", format!("Generated bindings for {}", name).color(theme::SUCCESS));
+    println!("  File: {}", path.display().color(theme::ACCENT));
+    println!("  Getters: {}", bindings.getters.len().color(theme::PRIMARY));
+    println!("  Call builders: {}", bindings.calls.len().color(theme::PRIMARY));
+    
+    if !bindings.skipped.is_empty() {
+        println!();
+        println!("{}", format!("Skipped {} function(s) with types not supported yet:", bindings.skipped.len()).color(theme::WARNING));
+        for (function, reason) in &bindings.skipped {
+            println!("  {} {}", function.color(theme::ACCENT), reason.color(theme::MUTED));
+        }
+    }
+    
+    println!();
+    println!("{}", format!("Add `pub mod {};` to the contracts module to use it", bindings.module).color(theme::MUTED));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SOURCE: &str = r#"//! Token helpers
+//! for the CLI
+
+/// Parse an amount
+///
+/// Rejects negatives.
+#[must_use]
+pub fn parse(input: &str) -> u64 {
+    0
+}
+
+/// Internal helper
+fn helper() {}
+
+fn undocumented() {}
+"#;
+    
+    #[test]
+    fn extracts_module_and_public_item_docs() {
+        let (module_docs, items) = extract_docs(SOURCE, false);
+        assert_eq!(module_docs, "Token helpers\nfor the CLI");
+        assert_eq!(items, vec![DocItem {
+            signature: "pub fn parse(input: &str) -> u64".to_string(),
+            docs: "Parse an amount\n\nRejects negatives.".to_string(),
+        }]);
+        
+        let (_, with_private) = extract_docs(SOURCE, true);
+        assert_eq!(with_private.len(), 2);
+        assert_eq!(with_private[1].signature, "fn helper()");
+    }
+    
+    #[test]
+    fn doc_crate_name_prefers_the_binary() {
+        assert_eq!(doc_crate_name("[package]\nname = \"definite-cli\"\n\n[[bin]]\nname = \"definite\"\n").unwrap(), "definite");
+        assert_eq!(doc_crate_name("[package]\nname = \"definite-cli\"\n").unwrap(), "definite_cli");
+    }
+    
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let samples: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+        
+        assert_eq!(percentile(&samples, 50.0), Some(Duration::from_millis(10)));
+        assert_eq!(percentile(&samples, 95.0), Some(Duration::from_millis(19)));
+        assert_eq!(percentile(&samples[..1], 95.0), Some(Duration::from_millis(1)));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use comfy_table::Cell;
+use owo_colors::OwoColorize;
+
+use crate::{Cli, table, theme};
+use crate::config::Config;
+use crate::export::ExportTarget;
+use crate::oplog::{OperationLog, OperationStatus, RecordFilter};
+use crate::utils::format_timestamp;
+use super::HistoryCommands;
+
+pub async fn handle_history_command(command: HistoryCommands, cli: &Cli) -> Result<()> {
+    match command {
+        HistoryCommands::Local { command, status, since, until, limit, json, out } => {
+            let filter = RecordFilter { command, status, since, until };
+            let export = match out {
+                Some(out) => Some(ExportTarget::parse(&out)),
+                None => json.then_some(ExportTarget::Stdout),
+            };
+            local(filter, limit, export, cli)
+        }
+    }
+}
+
+fn local(filter: RecordFilter, limit: usize, export: Option<ExportTarget>, cli: &Cli) -> Result<()> {
+    let log = OperationLog::open_default()?;
+    let (records, skipped) = log.read()?;
+    if skipped > 0 {
+        eprintln!("{}", format!("Warning: skipped {} unreadable line(s) in {}", skipped, log.path().display()).color(theme::WARNING));
+    }
+    
+    let matching: Vec<_> = records.iter().filter(|record| filter.matches(record)).collect();
+    let shown = &matching[matching.len().saturating_sub(limit)..];
+    
+    if let Some(target) = export {
+        let mut lines = String::new();
+        for record in shown {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        target.write(&lines)?;
+        if !target.is_stdout() {
+            println!("{}", format!("{} operations written to {}", shown.len(), target).color(theme::SUCCESS));
+        }
+        return Ok(());
+    }
+    
+    println!("{}", "Local Operations Log".color(theme::PRIMARY));
+    println!("  File: {}", log.path().display().color(theme::MUTED));
+    println!();
+    
+    if shown.is_empty() {
+        println!("{}", "No recorded operations match".color(theme::MUTED));
+        if records.is_empty() {
+            println!("{}", "Operations are recorded with --log-ops or transaction.log_operations = true".color(theme::MUTED));
+        }
+        return Ok(());
+    }
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let mut operations = table::new_table(&["Time", "Command", "Network", "Status", "Transaction"], &config.display);
+    for record in shown {
+        let status_color = match record.status {
+            OperationStatus::Succeeded => theme::SUCCESS,
+            OperationStatus::Submitted => theme::INFO,
+            OperationStatus::Failed => theme::ERROR,
+        };
+        let detail = match (&record.transaction_hash, &record.error) {
+            (Some(hash), Some(error)) => table::cell(format!("{}\n{}", hash, error), theme::ERROR),
+            (None, Some(error)) => table::cell(error, theme::ERROR),
+            (Some(hash), None) => table::cell(hash, theme::MUTED),
+            (None, None) => Cell::new(""),
+        };
+        operations.add_row(vec![
+            Cell::new(format_timestamp(record.timestamp)),
+            table::cell(&record.command, theme::ACCENT),
+            Cell::new(&record.network),
+            table::cell(record.status, status_color),
+            detail,
+        ]);
+    }
+    println!("{}", operations);
+    
+    if matching.len() > shown.len() {
+        println!();
+        println!("{}", format!("Showing the latest {} of {} matching operations (use --limit to see more)", shown.len(), matching.len()).color(theme::MUTED));
+    }
+    
+    Ok(())
+}
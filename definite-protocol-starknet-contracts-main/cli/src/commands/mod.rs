@@ -1,5 +1,6 @@
 use clap::Subcommand;
 use anyhow::Result;
+use std::path::PathBuf;
 
 pub mod user_simple;
 pub mod protocol;
@@ -7,51 +8,110 @@ pub mod contract;
 pub mod analytics;
 pub mod dev;
 pub mod config;
+pub mod history;
 
 use crate::Cli;
+use crate::config::ConfirmVia;
+use crate::validated::{address_or_name, since_date, until_date, Address, Amount};
 
 #[derive(Subcommand, Clone)]
 pub enum UserCommands {
     /// Deposit STRK tokens to receive hSTRK
     Deposit {
         #[arg(help = "Amount of STRK to deposit")]
-        amount: String,
+        amount: Amount,
         #[arg(long, help = "Recipient address (defaults to caller)")]
-        recipient: Option<String>,
+        recipient: Option<Address>,
         #[arg(long, help = "Maximum slippage in basis points")]
         max_slippage: Option<u16>,
+        #[arg(long, value_enum, default_value_t = crate::transaction::Finality::L2, help = "Wait for acceptance on l2 (fast) or l1 (reorg-safe, can take hours)")]
+        finality: crate::transaction::Finality,
+    },
+    /// Deposit STRK for many recipients from a CSV or JSON file
+    BatchDeposit {
+        #[arg(long, help = "CSV file of amount,recipient rows (or a JSON array)")]
+        file: String,
+        #[arg(long, help = "Abort on the first failed deposit")]
+        stop_on_error: bool,
+    },
+    /// Send STRK, hSTRK or another token straight to an address, without the vault
+    Transfer {
+        #[arg(help = "Token to send: strk, hstrk or a token contract address")]
+        token: String,
+        #[arg(help = "Recipient address or address book name", value_parser = address_or_name)]
+        to: String,
+        #[arg(help = "Amount to send")]
+        amount: Amount,
     },
     /// Withdraw STRK tokens by burning hSTRK
     Withdraw {
-        #[arg(help = "Amount of hSTRK to burn")]
-        shares: String,
+        #[arg(help = "Amount of hSTRK to burn", required_unless_present = "strk", conflicts_with = "strk")]
+        shares: Option<Amount>,
+        #[arg(long, help = "Amount of STRK to receive; the hSTRK to burn is computed from the exchange rate")]
+        strk: Option<Amount>,
         #[arg(long, help = "Minimum STRK amount to receive")]
-        min_amount: Option<String>,
+        min_amount: Option<Amount>,
     },
     /// Check token balances and positions
     Balance {
-        #[arg(help = "Address to check (defaults to configured address)")]
+        #[arg(help = "Address or address book name to check (defaults to configured address)", value_parser = address_or_name)]
         address: Option<String>,
         #[arg(long, help = "Show detailed breakdown")]
         detailed: bool,
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), help = "Refresh every N seconds until Ctrl+C")]
+        watch: Option<u64>,
+        #[arg(long, value_name = "LIST", value_delimiter = ',', conflicts_with = "address", value_parser = address_or_name, help = "Comma-separated addresses or address book names to check together")]
+        addresses: Vec<String>,
+        #[arg(long, value_name = "FILE", conflicts_with = "address", help = "File with one address or address book name per line")]
+        file: Option<String>,
+        #[arg(long, help = "Print balances as JSON")]
+        json: bool,
     },
     /// View transaction history
     History {
-        #[arg(help = "Address to check (defaults to configured address)")]
+        #[arg(help = "Address or address book name to check (defaults to configured address)", value_parser = address_or_name)]
         address: Option<String>,
         #[arg(long, short, help = "Number of transactions to show")]
         limit: Option<u32>,
-        #[arg(long, help = "Filter by transaction type")]
+        #[arg(long, help = "Filter by transaction type (deposit or withdraw)")]
         filter: Option<String>,
+        #[arg(long, value_name = "DATE", value_parser = since_date, help = "Only show transactions on or after this UTC date (YYYY-MM-DD or RFC 3339)")]
+        since: Option<u64>,
+        #[arg(long, value_name = "DATE", value_parser = until_date, help = "Only show transactions on or before this UTC date (YYYY-MM-DD or RFC 3339)")]
+        until: Option<u64>,
+    },
+    /// Sign a message or SNIP-12 typed data with the configured key, for off-chain login
+    SignMessage {
+        #[arg(required_unless_present = "typed_data", help = "Text to sign, at most 31 characters")]
+        message: Option<String>,
+        #[arg(long, value_name = "FILE", conflicts_with = "message", help = "Sign a SNIP-12 typed data JSON document instead")]
+        typed_data: Option<String>,
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    /// Check a message signature against an account
+    VerifyMessage {
+        #[arg(required_unless_present = "typed_data", help = "Text that was signed")]
+        message: Option<String>,
+        #[arg(long, value_name = "FILE", conflicts_with = "message", help = "Verify a SNIP-12 typed data JSON document instead")]
+        typed_data: Option<String>,
+        #[arg(long, value_parser = address_or_name, help = "Account or address book name that signed")]
+        address: String,
+        #[arg(long, value_name = "R,S", value_delimiter = ',', required = true, help = "Signature felts, comma-separated")]
+        signature: Vec<String>,
+        #[arg(long, value_name = "KEY", help = "Check against this public key offline instead of asking the account contract")]
+        public_key: Option<String>,
     },
     /// Calculate potential yields and returns
     Simulate {
         #[arg(help = "Amount to simulate")]
         amount: String,
-        #[arg(long, help = "Time period in days")]
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=36_500), help = "Time period in days, up to 100 years")]
         days: Option<u32>,
         #[arg(long, help = "Show detailed breakdown")]
         detailed: bool,
+        #[arg(long, value_enum, default_value_t = crate::utils::Compounding::Daily, help = "How often yield compounds: daily, monthly, or none for simple interest")]
+        compound: crate::utils::Compounding,
     },
 }
 
@@ -61,9 +121,60 @@ pub enum ProtocolCommands {
     Status {
         #[arg(long, help = "Show detailed metrics")]
         detailed: bool,
-        #[arg(long, help = "Refresh interval in seconds")]
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), help = "Refresh every N seconds until Ctrl+C")]
         watch: Option<u64>,
     },
+    /// Full-screen live dashboard of TVL, delta, risk, fees and rebalances
+    Dashboard {
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..), help = "Refresh interval in seconds")]
+        interval: u64,
+    },
+    /// Compare core metrics between two network deployments side by side
+    Compare {
+        #[arg(long, help = "First network, e.g. mainnet")]
+        network_a: String,
+        #[arg(long, help = "Second network, e.g. sepolia")]
+        network_b: String,
+    },
+    /// Monitor net delta against the rebalancing threshold
+    Delta {
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), help = "Refresh every N seconds until Ctrl+C")]
+        watch: Option<u64>,
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    /// Compare a token's price across oracle feed sources and flag a wide spread
+    Oracle {
+        #[arg(help = "Token to price: strk, hstrk or a token address")]
+        token: String,
+        #[arg(long, value_delimiter = ',', default_value = "pragma,dex_twap", help = "Feed source IDs to compare, as short strings or hex")]
+        sources: Vec<String>,
+        #[arg(long, value_name = "PERCENT", default_value_t = 1.0, help = "Flag a spread between sources above this percentage of the median")]
+        max_spread: f64,
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    /// Show governance-set vault and rebalancing parameters read from chain
+    Config {
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    /// Track the leverage ratio over time
+    Leverage {
+        #[arg(long, help = "Time period in days")]
+        period: Option<u32>,
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
+    },
+    /// Chart total value locked over time from historical vault reads
+    TvlHistory {
+        #[arg(long, help = "Time period in days")]
+        period: Option<u32>,
+        #[arg(long, conflicts_with = "csv", help = "Output the series as JSON")]
+        json: bool,
+        #[arg(long, help = "Output the series as CSV")]
+        csv: bool,
+    },
     /// Monitor risk metrics and circuit breakers
     Risk {
         #[arg(long, help = "Show historical risk data")]
@@ -80,6 +191,12 @@ pub enum ProtocolCommands {
     Emergency {
         #[command(subcommand)]
         action: EmergencyCommands,
+        #[arg(long, global = true, value_name = "FILE", help = "Write a multisig proposal instead of sending")]
+        propose: Option<String>,
+        #[arg(long, global = true, value_enum, help = "Also require this confirmation step on top of the one the emergency.* config sets per command")]
+        confirm_via: Option<ConfirmVia>,
+        #[arg(long, global = true, value_name = "TOKEN", help = "Token to check against DEFINITE_CONFIRM_TOKEN when confirming via token")]
+        confirm_token: Option<String>,
     },
     /// View protocol fees and revenue
     Fees {
@@ -87,6 +204,10 @@ pub enum ProtocolCommands {
         period: Option<u32>,
         #[arg(long, help = "Show fee breakdown")]
         breakdown: bool,
+        #[arg(long, help = "Collect accrued management fees")]
+        collect: bool,
+        #[arg(long, value_name = "FILE", requires = "collect", help = "Write a multisig proposal instead of sending")]
+        propose: Option<String>,
     },
 }
 
@@ -98,8 +219,19 @@ pub enum RebalanceCommands {
     Execute {
         #[arg(long, help = "Force execution even if not needed")]
         force: bool,
-        #[arg(long, help = "Dry run mode")]
-        dry_run: bool,
+    },
+    /// Compute the trades that bring net delta to a target, without executing them
+    Plan {
+        #[arg(long, default_value_t = 0.0, allow_hyphen_values = true, help = "Net delta to reach, in STRK")]
+        target_delta: f64,
+        #[arg(long, default_value_t = 0.0, help = "Share of the adjustment to make with options, from 0 to 1; perpetuals take the rest")]
+        options_share: f64,
+        #[arg(long, default_value_t = 0.5, allow_hyphen_values = true, help = "Delta of one option contract, used to size the options trade")]
+        option_delta: f64,
+        #[arg(long, help = "Assumed perpetual market liquidity in STRK for the cost estimate")]
+        pool_depth: Option<String>,
+        #[arg(long, help = "Output as JSON")]
+        json: bool,
     },
     /// View rebalancing history
     History {
@@ -133,6 +265,8 @@ pub enum EmergencyCommands {
     ClosePositions {
         #[arg(long, help = "Position type (perpetuals, options, all)")]
         position_type: Option<String>,
+        #[arg(long, help = "Assumed market liquidity in STRK for the impact estimate")]
+        pool_depth: Option<String>,
     },
 }
 
@@ -140,12 +274,14 @@ pub enum EmergencyCommands {
 pub enum ContractCommands {
     /// Deploy protocol contracts
     Deploy {
-        #[arg(long, help = "Deployment configuration file")]
+        #[arg(long, help = "Deployment plan (TOML) listing each contract's class hash and constructor arguments")]
         config: Option<String>,
         #[arg(long, help = "Network to deploy to")]
         network: Option<String>,
-        #[arg(long, help = "Dry run mode")]
-        dry_run: bool,
+        #[arg(long, value_name = "FILE", conflicts_with = "from_manifest", help = "Where to record the deployment (default deployment.json)")]
+        manifest: Option<String>,
+        #[arg(long, value_name = "FILE", help = "Resume the deployment recorded in this manifest, skipping contracts it already lists")]
+        from_manifest: Option<String>,
     },
     /// Verify deployed contracts
     Verify {
@@ -157,23 +293,86 @@ pub enum ContractCommands {
     /// Call contract functions
     Call {
         #[arg(help = "Contract address")]
-        address: String,
+        address: Address,
         #[arg(help = "Function name")]
         function: String,
         #[arg(help = "Function arguments")]
         args: Vec<String>,
     },
+    /// Print the ABI of a deployed contract
+    Abi {
+        #[arg(help = "Contract address")]
+        address: String,
+    },
+    /// Stream events emitted by a contract
+    Watch {
+        #[arg(help = "Contract address")]
+        address: String,
+        #[arg(long, help = "Only show events with this name")]
+        event: Option<String>,
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..), help = "Poll interval in seconds")]
+        interval: Option<u64>,
+        #[arg(long, value_name = "DATE", value_parser = since_date, help = "Replay events from this UTC date before following new blocks")]
+        since: Option<u64>,
+        #[arg(long, value_name = "DATE", value_parser = until_date, help = "Stop at this UTC date: scan the range once and exit instead of following")]
+        until: Option<u64>,
+    },
     /// Send transactions to contracts
     Send {
         #[arg(help = "Contract address")]
-        address: String,
+        address: Address,
         #[arg(help = "Function name")]
         function: String,
-        #[arg(help = "Function arguments")]
+        #[arg(help = "Function arguments", conflicts_with = "calldata_file")]
         args: Vec<String>,
+        #[arg(long, value_name = "PATH", help = "JSON array of hex or decimal felts used verbatim as calldata")]
+        calldata_file: Option<String>,
         #[arg(long, help = "Gas limit")]
         gas_limit: Option<u64>,
     },
+    /// Build and sign a transaction without broadcasting it
+    BuildTx {
+        #[arg(help = "Contract address")]
+        address: String,
+        #[arg(help = "Function name")]
+        function: String,
+        #[arg(help = "Function arguments")]
+        args: Vec<String>,
+        #[arg(long, help = "Account nonce to sign with")]
+        nonce: String,
+        #[arg(long, help = "Max fee to sign with, in wei; overrides --max-fee")]
+        max_fee_wei: Option<String>,
+        #[arg(long, short, help = "Write the signed transaction to a file instead of stdout")]
+        output: Option<String>,
+    },
+    /// Broadcast a transaction previously signed with build-tx
+    Broadcast {
+        #[arg(help = "Signed transaction file")]
+        file: String,
+    },
+    /// Replace a stuck transaction with a no-op at the same nonce and a higher fee
+    CancelTx {
+        #[arg(long, help = "Nonce of the stuck transaction")]
+        nonce: String,
+        #[arg(long, value_name = "HASH", help = "Hash of the stuck transaction, so the replacement outbids its max fee")]
+        tx_hash: Option<String>,
+        #[arg(long, value_name = "PERCENT", default_value_t = 50, help = "How far above the stuck or estimated fee to price the replacement")]
+        fee_bump: u32,
+    },
+    /// Break a transaction's calldata down into calls and named arguments
+    DecodeTx {
+        #[arg(help = "Transaction hash")]
+        tx_hash: String,
+    },
+    /// Compare getter results between two blocks, e.g. around an upgrade
+    Diff {
+        #[arg(help = "Contract address")]
+        address: String,
+        #[arg(long = "at-block", value_name = "BLOCK", required = true, num_args = 1, help = "Block number to read at; pass exactly twice")]
+        at_block: Vec<u64>,
+        #[arg(long = "getter", value_name = "NAME", help = "Getter to compare; repeat for several (defaults to the ABI's argument-free views)")]
+        getters: Vec<String>,
+    },
 }
 
 #[derive(Subcommand, Clone)]
@@ -182,8 +381,10 @@ pub enum AnalyticsCommands {
     Performance {
         #[arg(long, help = "Time period in days")]
         period: Option<u32>,
-        #[arg(long, help = "Export format (json, csv, pdf)")]
+        #[arg(long, help = "Export format (json, csv)")]
         format: Option<String>,
+        #[arg(long, value_name = "PATH", requires = "format", help = "Export destination, or - for stdout (defaults to performance.<format>)")]
+        out: Option<String>,
     },
     /// Analyze portfolio composition
     Portfolio {
@@ -203,8 +404,26 @@ pub enum AnalyticsCommands {
     Metrics {
         #[arg(long, help = "Metric type (tvl, volume, fees, apy)")]
         metric: Option<String>,
-        #[arg(long, help = "Real-time monitoring")]
+        #[arg(long, conflicts_with = "watch", help = "Real-time monitoring (same as --watch 5)")]
         live: bool,
+        #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), help = "Refresh every N seconds until Ctrl+C")]
+        watch: Option<u64>,
+    },
+    /// Reconstruct realized and unrealized PnL from vault deposits and withdrawals
+    Pnl {
+        #[arg(long, help = "Address or address book name (defaults to configured address)", value_parser = address_or_name)]
+        address: Option<String>,
+        #[arg(long, help = "Export the ledger in this format (json, csv)")]
+        format: Option<String>,
+        #[arg(long, value_name = "PATH", requires = "format", help = "Export destination, or - for stdout (defaults to pnl.<format>)")]
+        out: Option<String>,
+    },
+    /// Write every analytics report to a directory with a manifest
+    Export {
+        #[arg(long, default_value = "json", help = "Export format (json, csv)")]
+        format: String,
+        #[arg(long, help = "Output directory")]
+        out: String,
     },
 }
 
@@ -230,12 +449,43 @@ pub enum DevCommands {
         format: Option<String>,
         #[arg(long, help = "Include private functions")]
         private: bool,
+        #[arg(long, help = "Open the generated HTML docs in a browser")]
+        open: bool,
     },
     /// Lint and format code
     Lint {
         #[arg(long, help = "Auto-fix issues")]
         fix: bool,
     },
+    /// Measure RPC latency for common requests
+    Benchmark {
+        #[arg(long, help = "RPC URL to benchmark; repeat to compare endpoints (defaults to the configured RPC)")]
+        rpc: Vec<String>,
+        #[arg(long, short = 'n', help = "Iterations per request")]
+        iterations: Option<u32>,
+    },
+    /// Simulate a contract call without broadcasting it
+    SimulateTx {
+        #[arg(help = "Contract address")]
+        address: String,
+        #[arg(help = "Function name")]
+        function: String,
+        #[arg(help = "Function arguments")]
+        args: Vec<String>,
+        #[arg(long, help = "RPC URL of a forked or devnet node (defaults to the configured RPC)")]
+        fork: Option<String>,
+        #[arg(long, help = "Skip account signature validation")]
+        skip_validate: bool,
+    },
+    /// Generate typed Rust bindings from a Sierra ABI
+    AbiGen {
+        #[arg(long, value_name = "PATH", help = "ABI JSON or compiled Sierra contract class")]
+        abi: PathBuf,
+        #[arg(long, value_name = "DIR", help = "Directory to write the bindings module to")]
+        out: PathBuf,
+        #[arg(long, help = "Contract name for the generated type (defaults to the ABI file name)")]
+        name: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Clone)]
@@ -264,6 +514,41 @@ pub enum ConfigCommands {
     },
     /// Validate configuration
     Validate,
+    /// Diagnose common misconfigurations against the live network, most serious first
+    Doctor {
+        #[arg(long, help = "Print the findings as JSON")]
+        json: bool,
+    },
+    /// Replace the account private key after checking it controls the account; --keystore reads the new key from a keystore
+    RotateKey,
+    /// Check which JSON-RPC methods the configured endpoint supports
+    ProbeRpc {
+        #[arg(long, help = "Probe again instead of using the cached result for this endpoint")]
+        refresh: bool,
+        #[arg(long, help = "Print the report as JSON")]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+pub enum HistoryCommands {
+    /// Show operations recorded by --log-ops or transaction.log_operations
+    Local {
+        #[arg(long, help = "Only show operations whose command contains this text (e.g. deposit)")]
+        command: Option<String>,
+        #[arg(long, value_enum, help = "Only show operations with this status")]
+        status: Option<crate::oplog::OperationStatus>,
+        #[arg(long, value_name = "DATE", value_parser = since_date, help = "Only show operations on or after this UTC date (YYYY-MM-DD or RFC 3339)")]
+        since: Option<u64>,
+        #[arg(long, value_name = "DATE", value_parser = until_date, help = "Only show operations on or before this UTC date (YYYY-MM-DD or RFC 3339)")]
+        until: Option<u64>,
+        #[arg(long, short, default_value_t = 20, help = "Number of most recent operations to show")]
+        limit: usize,
+        #[arg(long, help = "Print matching records as JSON lines, including calldata")]
+        json: bool,
+        #[arg(long, value_name = "PATH", help = "Write matching records as JSON lines to a file, or - for stdout (same as --json)")]
+        out: Option<String>,
+    },
 }
 
 // Command handlers
@@ -290,3 +575,7 @@ pub async fn handle_dev_command(command: DevCommands, cli: &Cli) -> Result<()> {
 pub async fn handle_config_command(command: ConfigCommands, cli: &Cli) -> Result<()> {
     config::handle_config_command(command, cli).await
 }
+
+pub async fn handle_history_command(command: HistoryCommands, cli: &Cli) -> Result<()> {
+    history::handle_history_command(command, cli).await
+}
@@ -1,13 +1,31 @@
-use anyhow::Result;
+use anyhow::{Result, Context};
+use comfy_table::Cell;
 use owo_colors::OwoColorize;
-use dialoguer::{Confirm, Select};
+use dialoguer::Select;
 use indicatif::{ProgressBar, ProgressStyle};
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use starknet::accounts::{Account, Call, ConnectedAccount};
+use starknet::core::types::{BlockId, EventFilter, FieldElement};
+use starknet::core::utils::cairo_short_string_to_felt;
 
-use crate::{Cli, theme};
-use crate::contracts::{vault::VaultContract, risk::RiskContract, rebalancing::RebalancingContract};
-use crate::utils::{format_amount, format_percentage, format_timestamp, get_account};
+use crate::{Cli, audit, table, theme};
+use crate::oplog::{self, OperationRecord};
+use crate::spend;
+use crate::progress::{OutputFormat, Progress};
+use crate::prompt::Prompter;
+use crate::config::{is_configured, Config, ConfirmVia};
+use crate::transaction::{self, CallSummary, Confirmation, TransactionProposal};
+use crate::contracts::{vault::{exchange_rate, VaultConfig, VaultContract}, risk::{RiskContract, RiskMetrics}, rebalancing::RebalancingContract, hedging::{DeltaExposure, HedgingContract, PERPETUAL_DELTA_VIEWS, PORTFOLIO_DELTA_VIEWS}};
+use crate::contracts::oracle::{OracleContract, PriceData, PriceSpread};
+use crate::contracts::{events, selectors::selector, utils as contract_utils, BlockReader, ContractReader, ViewCall};
+use crate::contracts::batch::RpcReader;
+use crate::dashboard::{DeltaPanel, RebalanceEvent, RebalancePanel, RiskPanel, Snapshot};
+use crate::refresh::{Cached, RefreshPolicy};
+use crate::watch::{self, run_watched};
+use crate::utils::{calculate_price_impact, connect_account, format_duration, format_percentage, format_signed_percentage, format_timestamp, get_account, get_provider, normalize_address, parse_amount, sparkline, validate_address, wei_to_decimal, AmountFormat, Bps, RoundingMode};
 use super::{ProtocolCommands, RebalanceCommands, EmergencyCommands};
 
 pub async fn handle_protocol_command(command: ProtocolCommands, cli: &Cli) -> Result<()> {
@@ -15,81 +33,1348 @@ pub async fn handle_protocol_command(command: ProtocolCommands, cli: &Cli) -> Re
         ProtocolCommands::Status { detailed, watch } => {
             status(detailed, watch, cli).await
         }
+        ProtocolCommands::Dashboard { interval } => {
+            dashboard(interval, cli).await
+        }
+        ProtocolCommands::Compare { network_a, network_b } => {
+            compare(&network_a, &network_b, cli).await
+        }
+        ProtocolCommands::Delta { watch, json } => {
+            delta(watch, json, cli).await
+        }
+        ProtocolCommands::Oracle { token, sources, max_spread, json } => {
+            oracle(&token, &sources, max_spread, json, cli).await
+        }
+        ProtocolCommands::Config { json } => {
+            protocol_config(json, cli).await
+        }
+        ProtocolCommands::Leverage { period, json } => {
+            leverage(period, json, cli).await
+        }
+        ProtocolCommands::TvlHistory { period, json, csv } => {
+            tvl_history(period, json, csv, cli).await
+        }
         ProtocolCommands::Risk { history, alerts } => {
             risk(history, alerts, cli).await
         }
         ProtocolCommands::Rebalance { action } => {
             rebalance(action, cli).await
         }
-        ProtocolCommands::Emergency { action } => {
-            emergency(action, cli).await
+        ProtocolCommands::Emergency { action, propose, confirm_via, confirm_token } => {
+            emergency(action, propose, confirm_via, confirm_token, cli).await
         }
-        ProtocolCommands::Fees { period, breakdown } => {
-            fees(period, breakdown, cli).await
+        ProtocolCommands::Fees { period, breakdown, collect, propose } => {
+            fees(period, breakdown, collect, propose, cli).await
         }
     }
 }
 
+/// Watch cycles between reads of slow-changing values (fees, limits, vault config)
+const SLOW_REFRESH_CYCLES: u64 = 10;
+
+/// Contracts `protocol status` reads; optional ones are `None` when not configured
+struct StatusTargets {
+    vault: FieldElement,
+    risk_manager: Option<FieldElement>,
+    hedges: Option<(FieldElement, FieldElement)>,
+}
+
+/// Which reads are due on a status cycle
+struct DueReads {
+    vault_state: bool,
+    vault_config: bool,
+    risk_metrics: bool,
+    delta: bool,
+}
+
+/// Decoded answers for one status cycle; `None` for reads that were not due
+#[derive(Default)]
+struct StatusReads {
+    calls: usize,
+    vault_state: Option<Result<(BigUint, BigUint)>>,
+    vault_config: Option<Result<VaultConfig>>,
+    risk_metrics: Option<Result<RiskMetrics>>,
+    delta: Option<Result<f64>>,
+}
+
+impl StatusReads {
+    /// Make every due read in one batch and decode the answers
+    async fn read<R: ContractReader + Sync>(reader: &R, targets: &StatusTargets, due: &DueReads) -> StatusReads {
+        let vault = targets.vault;
+        let risk_manager = targets.risk_manager.filter(|_| due.risk_metrics);
+        let hedges = targets.hedges.filter(|_| due.delta);
+        
+        let mut calls = Vec::new();
+        if due.vault_state {
+            calls.extend([ViewCall::new(vault, "total_assets"), ViewCall::new(vault, "total_shares")]);
+        }
+        if due.vault_config {
+            calls.extend([ViewCall::new(vault, "get_config"), ViewCall::new(vault, "is_paused")]);
+        }
+        if let Some(risk_manager) = risk_manager {
+            calls.push(ViewCall::new(risk_manager, "get_risk_metrics"));
+        }
+        if let Some((perpetual, options)) = hedges {
+            calls.extend(PERPETUAL_DELTA_VIEWS.map(|view| ViewCall::new(perpetual, view)));
+            calls.extend(PORTFOLIO_DELTA_VIEWS.map(|view| ViewCall::new(options, view)));
+        }
+        
+        let mut reads = StatusReads { calls: calls.len(), ..StatusReads::default() };
+        let mut results = reader.batch(calls).await.into_iter();
+        let mut next = || results.next().expect("batch answers every call");
+        
+        if due.vault_state {
+            let (assets, shares) = (next(), next());
+            reads.vault_state = Some(assets.and_then(|assets| {
+                Ok((
+                    contract_utils::decode_u256_result(&assets, "total_assets")?,
+                    contract_utils::decode_u256_result(&shares?, "total_shares")?,
+                ))
+            }));
+        }
+        if due.vault_config {
+            let (config, paused) = (next(), next());
+            reads.vault_config = Some(config.and_then(|config| VaultConfig::decode(&config, &paused?)));
+        }
+        if risk_manager.is_some() {
+            reads.risk_metrics = Some(next().and_then(|data| RiskMetrics::decode(&data)));
+        }
+        if hedges.is_some() {
+            let (magnitude, sign, portfolio, negative) = (next(), next(), next(), next());
+            reads.delta = Some((|| -> Result<f64> {
+                let perpetual = DeltaExposure::decode(PERPETUAL_DELTA_VIEWS, &magnitude?, &sign?)?;
+                let options = DeltaExposure::decode(PORTFOLIO_DELTA_VIEWS, &portfolio?, &negative?)?;
+                Ok(perpetual.as_f64() + options.as_f64())
+            })());
+        }
+        
+        reads
+    }
+}
+
+/// One refresh of `protocol status --output json`
+///
+/// Amounts are wei strings, each followed by a `_formatted` copy in the display format.
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    total_assets: String,
+    total_assets_formatted: String,
+    total_shares: String,
+    total_shares_formatted: String,
+    exchange_rate: f64,
+    emergency_mode: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<StatusDetails>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusDetails {
+    management_fee_percent: f64,
+    performance_fee_percent: f64,
+    deposit_limit: String,
+    deposit_limit_formatted: String,
+    min_deposit: String,
+    min_deposit_formatted: String,
+    /// `None` when no risk manager is configured
+    risk_score: Option<u8>,
+    net_delta: Option<f64>,
+    leverage: Option<f64>,
+    liquidity: Option<f64>,
+}
+
+impl StatusReport {
+    fn new(
+        total_assets: &BigUint,
+        total_shares: &BigUint,
+        exchange_rate: f64,
+        vault_config: &VaultConfig,
+        risk: Option<&(RiskMetrics, Option<f64>)>,
+        detailed: bool,
+        amounts: &AmountFormat,
+    ) -> Self {
+        let details = detailed.then(|| StatusDetails {
+            management_fee_percent: vault_config.management_fee_percentage(),
+            performance_fee_percent: vault_config.performance_fee_percentage(),
+            deposit_limit: vault_config.deposit_limit.to_string(),
+            deposit_limit_formatted: amounts.format(&vault_config.deposit_limit),
+            min_deposit: vault_config.min_deposit.to_string(),
+            min_deposit_formatted: amounts.format(&vault_config.min_deposit),
+            risk_score: risk.map(|(metrics, _)| metrics.risk_score),
+            net_delta: risk.and_then(|(_, delta)| *delta),
+            leverage: risk.map(|(metrics, _)| metrics.leverage()),
+            liquidity: risk.map(|(metrics, _)| metrics.liquidity()),
+        });
+        
+        StatusReport {
+            total_assets: total_assets.to_string(),
+            total_assets_formatted: amounts.format(total_assets),
+            total_shares: total_shares.to_string(),
+            total_shares_formatted: amounts.format(total_shares),
+            exchange_rate,
+            emergency_mode: vault_config.emergency_mode,
+            details,
+        }
+    }
+}
+
+/// Stands in for a cached read that was due but missing from the batch
+fn not_read<T>(what: &str) -> Result<T> {
+    Err(anyhow::anyhow!("{} was not read", what))
+}
+
 async fn status(detailed: bool, watch: Option<u64>, cli: &Cli) -> Result<()> {
-    println!("{}", "Protocol Status Dashboard".color(theme::PRIMARY));
-    println!();
+    let config = Config::load(cli.config.as_deref())?;
+    let json = cli.output == OutputFormat::Json;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let contracts = &config.contracts;
+    let optional = |address: &str| is_configured(address).then(|| validate_address(address)).transpose();
+    let targets = StatusTargets {
+        vault: contracts.require("vault")?,
+        risk_manager: optional(&contracts.risk_manager)?,
+        hedges: match (optional(&contracts.perpetual_hedge)?, optional(&contracts.options_strategy)?) {
+            (Some(perpetual), Some(options)) => Some((perpetual, options)),
+            _ => None,
+        },
+    };
+    // Each cycle's reads go out as one JSON-RPC batch when the node accepts batches
+    let reader = RpcReader::new(&config, None)?;
     
-    let _account = get_account(cli).await?;
-    // let vault = VaultContract::new(&account).await?;
+    // Exchange rate, risk and delta move every block; the vault config only changes by governance
+    let policy = RefreshPolicy::new().every("vault_config", SLOW_REFRESH_CYCLES);
+    let caches = tokio::sync::Mutex::new((
+        Cached::<(BigUint, BigUint)>::new("vault_state"),
+        Cached::<VaultConfig>::new("vault_config"),
+        Cached::<RiskMetrics>::new("risk_metrics"),
+        Cached::<f64>::new("delta"),
+    ));
     
-    loop {
-        // Clear screen if watching
-        if watch.is_some() {
-            print!("\x1B[2J\x1B[1;1H");
-            println!("{}", "Protocol Status Dashboard (Live)".color(theme::PRIMARY));
-            println!();
+    let (config, targets, reader, policy, caches) = (&config, &targets, &reader, &policy, &caches);
+    run_watched(watch, cli.output, move |cycle| async move {
+        let mut guard = caches.lock().await;
+        let (vault_state, vault_config, risk_metrics, net_delta) = &mut *guard;
+        
+        let risk_due = detailed && targets.risk_manager.is_some();
+        let due = DueReads {
+            vault_state: vault_state.is_stale(policy, cycle),
+            vault_config: vault_config.is_stale(policy, cycle),
+            risk_metrics: risk_due && risk_metrics.is_stale(policy, cycle),
+            delta: risk_due && net_delta.is_stale(policy, cycle),
+        };
+        let round_trips = reader.round_trips();
+        let reads = StatusReads::read(reader, targets, &due).await;
+        tracing::debug!(calls = reads.calls, round_trips = reader.round_trips() - round_trips, "status reads");
+        let (total_assets, total_shares) = vault_state.get(policy, cycle, || async move {
+            reads.vault_state.unwrap_or_else(|| not_read("Vault state"))
+        }).await?.clone();
+        let vault_params = vault_config.get(policy, cycle, || async move {
+            reads.vault_config.unwrap_or_else(|| not_read("Vault config"))
+        }).await?.clone();
+        let exchange_rate = exchange_rate(&total_assets, &total_shares);
+        let risk = match targets.risk_manager {
+            Some(_) if detailed => {
+                let metrics = risk_metrics.get(policy, cycle, || async move {
+                    reads.risk_metrics.unwrap_or_else(|| not_read("Risk metrics"))
+                }).await?.clone();
+                let delta = match targets.hedges {
+                    Some(_) => Some(*net_delta.get(policy, cycle, || async move {
+                        reads.delta.unwrap_or_else(|| not_read("Net delta"))
+                    }).await?),
+                    None => None,
+                };
+                Some((metrics, delta))
+            }
+            _ => None,
+        };
+        
+        if json {
+            let report = StatusReport::new(&total_assets, &total_shares, exchange_rate, &vault_params, risk.as_ref(), detailed, &amounts);
+            // One object per line so watch output can be piped straight into a log
+            println!("{}", audit::to_json(cli, config, None, &report, false)?);
+            return Ok(());
         }
+        let amounts = amounts.with_raw(cli.raw);
         
-        // Simulated protocol metrics
-        let total_assets = BigUint::from(1000000u64) * BigUint::from(1000000000000000000u64); // 1M STRK
-        let total_shares = BigUint::from(950000u64) * BigUint::from(1000000000000000000u64); // 950K hSTRK
-        let exchange_rate = BigUint::from(1052631578947368421u64); // ~1.0526 exchange rate
+        println!("{}", watch::title("Protocol Status Dashboard", watch).color(theme::PRIMARY));
+        println!();
         
         // Display core metrics
         println!("{}", "Core Metrics:".color(theme::ACCENT));
-        println!("  Total Value Locked: {}", format_amount(total_assets.clone()).color(theme::SUCCESS));
-        println!("  Total hSTRK Supply: {}", format_amount(total_shares.clone()).color(theme::PRIMARY));
-        println!("  Exchange Rate: {}", format!("{:.6}", exchange_rate.to_f64().unwrap_or(0.0) / 1e18).color(theme::SECONDARY));
-        println!("  Emergency Mode: {}", "Normal".color(theme::SUCCESS));
+        let mut core = table::metrics_table(&config.display);
+        core.add_row(vec![Cell::new("Total Value Locked"), table::cell(amounts.format(&total_assets), theme::SUCCESS)]);
+        core.add_row(vec![Cell::new("Total hSTRK Supply"), table::cell(amounts.format(&total_shares), theme::PRIMARY)]);
+        core.add_row(vec![Cell::new("Exchange Rate"), table::cell(format!("{:.6}", exchange_rate), theme::SECONDARY)]);
+        core.add_row(vec![Cell::new("Emergency Mode"), if vault_params.emergency_mode {
+            table::cell("ACTIVE", theme::ERROR)
+        } else {
+            table::cell("Normal", theme::SUCCESS)
+        }]);
+        println!("{}", core);
         
         if detailed {
             println!();
             println!("{}", "Detailed Information:".color(theme::ACCENT));
-            println!("  Management Fee: {}%", "2.0".color(theme::SECONDARY));
-            println!("  Performance Fee: {}%", "20.0".color(theme::SECONDARY));
-            println!("  Deposit Limit: {}", format_amount(BigUint::from(10000000u64) * BigUint::from(1000000000000000000u64)).color(theme::INFO));
-            println!("  Min Deposit: {}", format_amount(BigUint::from(1000u64) * BigUint::from(1000000000000000000u64)).color(theme::INFO));
+            let mut details = table::metrics_table(&config.display);
+            details.add_row(vec![Cell::new("Management Fee"), table::cell(format!("{:.2}%", vault_params.management_fee_percentage()), theme::SECONDARY)]);
+            details.add_row(vec![Cell::new("Performance Fee"), table::cell(format!("{:.2}%", vault_params.performance_fee_percentage()), theme::SECONDARY)]);
+            details.add_row(vec![Cell::new("Deposit Limit"), table::cell(amounts.format(&vault_params.deposit_limit), theme::INFO)]);
+            details.add_row(vec![Cell::new("Min Deposit"), table::cell(amounts.format(&vault_params.min_deposit), theme::INFO)]);
+            println!("{}", details);
             
             // Simulated additional metrics
             println!();
             println!("{}", "Performance Metrics:".color(theme::ACCENT));
-            println!("  30-Day APY: {}%", "12.45".color(theme::SUCCESS));
-            println!("  7-Day APY: {}%", "11.89".color(theme::SUCCESS));
-            println!("  24h Volume: {}", "1.2M STRK".color(theme::PRIMARY));
-            println!("  Active Users: {}", "1,247".color(theme::PRIMARY));
+            let mut performance = table::metrics_table(&config.display);
+            // Gross APYs are still simulated; the net figures use the on-chain fee rates
+            for (label, gross) in [("30-Day APY", Decimal::new(1245, 4)), ("7-Day APY", Decimal::new(1189, 4))] {
+                let net = vault_params.net_apy(gross);
+                performance.add_row(vec![
+                    Cell::new(label),
+                    table::cell(format!(
+                        "{} net ({} gross)",
+                        format_percentage(net * Decimal::ONE_HUNDRED),
+                        format_percentage(gross * Decimal::ONE_HUNDRED)
+                    ), theme::SUCCESS),
+                ]);
+            }
+            performance.add_row(vec![Cell::new("24h Volume"), table::cell("1.2M STRK", theme::PRIMARY)]);
+            performance.add_row(vec![Cell::new("Active Users"), table::cell("1,247", theme::PRIMARY)]);
+            println!("{}", performance);
+        }
+        
+        match &risk {
+            _ if !detailed => {}
+            None => {
+                println!();
+                not_configured("Risk Metrics", "risk_manager");
+            }
+            Some((metrics, delta)) => {
+                println!();
+                println!("{}", "Risk Metrics:".color(theme::ACCENT));
+                let mut risk = table::metrics_table(&config.display);
+                risk.add_row(vec![Cell::new("Risk Score"), table::cell(format!("{}/100", metrics.risk_score), theme::SUCCESS)]);
+                if let Some(delta) = delta {
+                    risk.add_row(vec![Cell::new("Current Delta"), table::cell(format!("{:+.4}", delta), theme::SUCCESS)]);
+                }
+                risk.add_row(vec![Cell::new("Leverage Ratio"), table::cell(format!("{:.2}x", metrics.leverage()), theme::WARNING)]);
+                risk.add_row(vec![Cell::new("Liquidity Ratio"), table::cell(format!("{:.1}%", metrics.liquidity() * 100.0), theme::SUCCESS)]);
+                println!("{}", risk);
+            }
+        }
+        
+        Ok::<_, anyhow::Error>(())
+    }).await
+}
+
+/// Blocks searched for `RebalancingExecuted` events on each dashboard refresh
+const REBALANCE_LOOKBACK_BLOCKS: u64 = 5_000;
+
+/// Rebalances listed in the dashboard
+const RECENT_REBALANCES: usize = 5;
+
+async fn dashboard(interval: u64, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    // Fail before taking over the screen if the one required contract is missing
+    config.contracts.require("vault")?;
+    let account = get_account(cli).await?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    
+    crate::dashboard::run(
+        "Definite Protocol Dashboard",
+        std::time::Duration::from_secs(interval),
+        || read_dashboard(&account, &config, &amounts),
+    ).await
+}
+
+/// Read every dashboard panel, leaving out those whose contracts are not configured
+async fn read_dashboard<A>(account: &A, config: &Config, amounts: &AmountFormat) -> Result<Snapshot>
+where
+    A: ConnectedAccount + Sync,
+{
+    let contracts = &config.contracts;
+    let optional = |address: &str| is_configured(address).then(|| validate_address(address)).transpose();
+    
+    let vault = VaultContract::with_address(account, contracts.require("vault")?);
+    let (total_assets, total_shares, vault_params) = tokio::try_join!(
+        vault.total_assets(),
+        vault.total_shares(),
+        vault.get_vault_config(),
+    )?;
+    
+    let risk = match optional(&contracts.risk_manager)? {
+        Some(address) => {
+            let metrics = RiskContract::with_address(account, address).get_risk_metrics().await?;
+            Some(RiskPanel {
+                risk_score: metrics.risk_score,
+                leverage: metrics.leverage(),
+                liquidity: metrics.liquidity(),
+                drawdown_percent: metrics.current_drawdown_bps.to_percent(),
+                var_percent: metrics.portfolio_var_bps.to_percent(),
+            })
+        }
+        None => None,
+    };
+    
+    let engine = optional(&contracts.rebalancing_engine)?;
+    let delta = match (optional(&contracts.perpetual_hedge)?, optional(&contracts.options_strategy)?, engine) {
+        (Some(perpetual), Some(options), Some(engine)) => {
+            let (perpetual_delta, options_delta, threshold_bps) = tokio::try_join!(
+                HedgingContract::with_address(account, perpetual).perpetual_delta(),
+                HedgingContract::with_address(account, options).portfolio_delta(),
+                RebalancingContract::with_address(account, engine).execution_threshold(),
+            )?;
+            let report = DeltaReport::new(perpetual_delta.as_f64(), options_delta.as_f64(), threshold_bps);
+            Some(DeltaPanel {
+                perpetual: report.perpetual_delta,
+                options: report.options_delta,
+                net: report.net_delta,
+                threshold: report.threshold,
+                deviation: report.deviation,
+                status: report.status.label(),
+                status_color: report.status.color(),
+            })
+        }
+        _ => None,
+    };
+    
+    let rebalances = match engine {
+        Some(engine) => Some(read_rebalances(account, engine).await?),
+        None => None,
+    };
+    
+    // Gross APY is still simulated as in `status`; the net figure uses the on-chain fee rates
+    Ok(Snapshot {
+        tvl: amounts.format(&total_assets),
+        total_shares: amounts.format(&total_shares),
+        exchange_rate: exchange_rate(&total_assets, &total_shares),
+        emergency_mode: vault_params.emergency_mode,
+        management_fee: vault_params.management_fee_percentage(),
+        performance_fee: vault_params.performance_fee_percentage(),
+        net_apy: vault_params.net_apy(Decimal::new(1245, 4)),
+        delta,
+        risk,
+        rebalances,
+    })
+}
+
+/// Last rebalance time, pause state and the latest `RebalancingExecuted` events
+async fn read_rebalances<A>(account: &A, engine: FieldElement) -> Result<RebalancePanel>
+where
+    A: ConnectedAccount + Sync,
+{
+    let rebalancing = RebalancingContract::with_address(account, engine);
+    let (last_rebalance, paused, latest) = tokio::try_join!(
+        rebalancing.last_rebalancing(),
+        rebalancing.is_paused(),
+        contract_utils::get_current_block(account.provider()),
+    )?;
+    
+    let filter = EventFilter {
+        from_block: Some(BlockId::Number(latest.saturating_sub(REBALANCE_LOOKBACK_BLOCKS))),
+        to_block: Some(BlockId::Number(latest)),
+        address: Some(engine),
+        keys: Some(vec![vec![selector("RebalancingExecuted")?]]),
+    };
+    let emitted = events::fetch_events(account.provider(), filter, 100).await
+        .context("Failed to fetch rebalancing events")?;
+    
+    // The full engine indexes the keeper as a key; the simple engine puts it first in the data
+    let recent = emitted
+        .iter()
+        .rev()
+        .take(RECENT_REBALANCES)
+        .map(|event| RebalanceEvent {
+            block: event.block_number,
+            transaction: event.transaction_hash,
+            keeper: event.keys.get(1).or_else(|| event.data.first()).copied(),
+        })
+        .collect();
+    
+    Ok(RebalancePanel { last_rebalance, paused, recent })
+}
+
+/// Core metrics of one deployment, read for `protocol compare`
+#[derive(Debug, Clone)]
+struct NetworkSnapshot {
+    tvl: BigUint,
+    exchange_rate: f64,
+    net_apy: Decimal,
+    risk_score: Option<u8>,
+}
+
+/// Read a deployment's metrics through its own provider and account
+async fn read_snapshot(config: &Config) -> Result<NetworkSnapshot> {
+    let account = connect_account(config).await?;
+    let contracts = &config.contracts;
+    let vault = VaultContract::with_address(&account, contracts.require("vault")?);
+    
+    let (total_assets, total_shares, vault_params) = tokio::try_join!(
+        vault.total_assets(),
+        vault.total_shares(),
+        vault.get_vault_config(),
+    )?;
+    let risk_score = match is_configured(&contracts.risk_manager) {
+        true => Some(RiskContract::with_address(&account, validate_address(&contracts.risk_manager)?).get_risk_metrics().await?.risk_score),
+        false => None,
+    };
+    
+    // Gross APY is still simulated as in `status`; the net figure uses each deployment's fees
+    Ok(NetworkSnapshot {
+        exchange_rate: exchange_rate(&total_assets, &total_shares),
+        tvl: total_assets,
+        net_apy: vault_params.net_apy(Decimal::new(1245, 4)),
+        risk_score,
+    })
+}
+
+/// Change from `a` to `b` as a fraction of `a`, if `a` is non-zero
+fn relative_change(a: f64, b: f64) -> Option<f64> {
+    (a != 0.0).then(|| (b - a) / a)
+}
+
+async fn compare(network_a: &str, network_b: &str, cli: &Cli) -> Result<()> {
+    println!("{}", format!("Comparing {} and {}", network_a, network_b).color(theme::PRIMARY));
+    
+    let config_a = Config::load_network(cli.config.as_deref(), network_a)?;
+    let config_b = Config::load_network(cli.config.as_deref(), network_b)?;
+    let amounts = AmountFormat::new(&config_a.display, cli.human);
+    
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(theme::spinner_style());
+    pb.set_message("Reading both deployments...");
+    
+    let (a, b) = tokio::try_join!(
+        async { read_snapshot(&config_a).await.with_context(|| format!("Failed to read {}", network_a)) },
+        async { read_snapshot(&config_b).await.with_context(|| format!("Failed to read {}", network_b)) },
+    )?;
+    
+    pb.finish_and_clear();
+    
+    let tvl_a = a.tvl.to_f64().unwrap_or(0.0);
+    let tvl_b = b.tvl.to_f64().unwrap_or(0.0);
+    let percent = |ratio: Decimal| format_percentage(ratio * Decimal::ONE_HUNDRED);
+    let score = |risk_score: Option<u8>| risk_score.map(|s| format!("{}/100", s)).unwrap_or_else(|| "not configured".to_string());
+    
+    println!();
+    let mut comparison = table::new_table(&["Metric", network_a, network_b, "Difference"], &config_a.display);
+    comparison.add_row(vec![
+        Cell::new("Total Value Locked"),
+        table::cell(amounts.format(&a.tvl), theme::PRIMARY),
+        table::cell(amounts.format(&b.tvl), theme::PRIMARY),
+        table::cell(relative_change(tvl_a, tvl_b)
+            .and_then(|change| Decimal::try_from(change * 100.0).ok())
+            .map(format_signed_percentage)
+            .unwrap_or_else(|| "n/a".to_string()), theme::INFO),
+    ]);
+    comparison.add_row(vec![
+        Cell::new("Exchange Rate"),
+        table::cell(format!("{:.6}", a.exchange_rate), theme::SECONDARY),
+        table::cell(format!("{:.6}", b.exchange_rate), theme::SECONDARY),
+        table::cell(format!("{:+.6}", b.exchange_rate - a.exchange_rate), theme::INFO),
+    ]);
+    comparison.add_row(vec![
+        Cell::new("Net APY (30d)"),
+        table::cell(percent(a.net_apy), theme::SUCCESS),
+        table::cell(percent(b.net_apy), theme::SUCCESS),
+        table::cell(format!("{:+} pp", ((b.net_apy - a.net_apy) * Decimal::ONE_HUNDRED).round_dp(2)), theme::INFO),
+    ]);
+    comparison.add_row(vec![
+        Cell::new("Risk Score"),
+        table::cell(score(a.risk_score), theme::WARNING),
+        table::cell(score(b.risk_score), theme::WARNING),
+        table::cell(match (a.risk_score, b.risk_score) {
+            (Some(x), Some(y)) => format!("{:+}", y as i16 - x as i16),
+            _ => "n/a".to_string(),
+        }, theme::INFO),
+    ]);
+    println!("{}", comparison);
+    
+    Ok(())
+}
+
+/// Note a dashboard section that was skipped because its contract address is unset
+fn not_configured(section: &str, contract: &str) {
+    println!("{} {}", format!("{}:", section).color(theme::ACCENT), "not configured".color(theme::MUTED));
+    println!("  {}", format!("Set contracts.{} to enable this section", contract).color(theme::MUTED));
+}
+
+/// Point-in-time view of the protocol's net delta
+#[derive(Debug, Serialize)]
+struct DeltaReport {
+    timestamp: i64,
+    perpetual_delta: f64,
+    options_delta: f64,
+    net_delta: f64,
+    threshold: f64,
+    threshold_bps: Bps,
+    /// Net delta as a fraction of the threshold (1.0 = rebalance due)
+    deviation: f64,
+    status: DeltaStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DeltaStatus {
+    Neutral,
+    Approaching,
+    Exceeded,
+}
+
+impl DeltaReport {
+    fn new(perpetual_delta: f64, options_delta: f64, threshold_bps: Bps) -> Self {
+        // The rebalancing engine compares |delta| against the threshold as a ratio of 1 STRK
+        let threshold = threshold_bps.to_ratio();
+        let net_delta = perpetual_delta + options_delta;
+        let deviation = if threshold > 0.0 { net_delta.abs() / threshold } else { 0.0 };
+        
+        DeltaReport {
+            timestamp: chrono::Utc::now().timestamp(),
+            perpetual_delta,
+            options_delta,
+            net_delta,
+            threshold,
+            threshold_bps,
+            deviation,
+            status: DeltaStatus::from_deviation(deviation),
+        }
+    }
+}
+
+impl DeltaStatus {
+    fn from_deviation(deviation: f64) -> Self {
+        if deviation >= 1.0 {
+            DeltaStatus::Exceeded
+        } else if deviation >= 0.5 {
+            DeltaStatus::Approaching
+        } else {
+            DeltaStatus::Neutral
+        }
+    }
+    
+    fn color(self) -> owo_colors::Rgb {
+        match self {
+            DeltaStatus::Neutral => theme::SUCCESS,
+            DeltaStatus::Approaching => theme::WARNING,
+            DeltaStatus::Exceeded => theme::ERROR,
+        }
+    }
+    
+    fn label(self) -> &'static str {
+        match self {
+            DeltaStatus::Neutral => "NEUTRAL",
+            DeltaStatus::Approaching => "APPROACHING THRESHOLD",
+            DeltaStatus::Exceeded => "REBALANCE NEEDED",
+        }
+    }
+}
+
+async fn delta(watch: Option<u64>, json: bool, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let account = get_account(cli).await?;
+    
+    let contracts = &config.contracts;
+    let perpetual_address = contracts.require("perpetual_hedge")?;
+    let options_address = contracts.require("options_strategy")?;
+    let rebalancing_address = contracts.require("rebalancing_engine")?;
+    
+    let output = if json { OutputFormat::Json } else { OutputFormat::Human };
+    let (config, provider) = (&config, account.provider());
+    run_watched(watch, output, move |_| async move {
+        let block = if json { audit::report_block(cli, provider).await? } else { None };
+        let perpetual = HedgingContract::with_address(BlockReader::new(provider, block), perpetual_address);
+        let options = HedgingContract::with_address(BlockReader::new(provider, block), options_address);
+        let rebalancing = RebalancingContract::with_address(BlockReader::new(provider, block), rebalancing_address);
+        
+        let perpetual_delta = perpetual.perpetual_delta().await?;
+        let options_delta = options.portfolio_delta().await?;
+        let threshold_bps = rebalancing.execution_threshold().await?;
+        
+        let report = DeltaReport::new(
+            perpetual_delta.as_f64(),
+            options_delta.as_f64(),
+            threshold_bps,
+        );
+        
+        if json {
+            // One object per line so watch output can be piped straight into a log
+            println!("{}", audit::to_json(cli, config, block, &report, false)?);
+        } else {
+            println!("{}", watch::title("Net Delta Monitor", watch).color(theme::PRIMARY));
+            println!();
             
+            let status_color = report.status.color();
+            println!("{}", "Delta Exposure:".color(theme::ACCENT));
+            println!("  Perpetual Hedge: {}", format!("{:+.6}", report.perpetual_delta).color(theme::SECONDARY));
+            println!("  Options Strategy: {}", format!("{:+.6}", report.options_delta).color(theme::SECONDARY));
+            println!("  Net Delta: {}", format!("{:+.6}", report.net_delta).color(status_color));
             println!();
-            println!("{}", "Risk Metrics:".color(theme::ACCENT));
-            println!("  Risk Score: {}/100", "23".color(theme::SUCCESS));
-            println!("  Current Delta: {}", "0.02".color(theme::SUCCESS));
-            println!("  Leverage Ratio: {}x", "1.8".color(theme::WARNING));
-            println!("  Liquidity Ratio: {}%", "15.3".color(theme::SUCCESS));
+            println!("{}", "Hedge Quality:".color(theme::ACCENT));
+            println!("  Rebalancing Threshold: {}", format!("±{:.6} ({} bps)", report.threshold, report.threshold_bps.0).color(theme::INFO));
+            println!("  Deviation: {}", format!("{:.1}% of threshold", report.deviation * 100.0).color(status_color));
+            println!("  Status: {}", report.status.label().color(status_color));
         }
         
-        if let Some(interval) = watch {
-            println!();
-            println!("{}", format!("Refreshing in {} seconds... (Ctrl+C to exit)", interval).color(theme::MUTED));
-            tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
+        Ok::<_, anyhow::Error>(())
+    }).await
+}
+
+/// A feed source's reading in an oracle spread report
+#[derive(Debug, Serialize)]
+struct SourceReading {
+    source: String,
+    status: SourceStatus,
+    /// USD price with 8 decimals; `None` when the read failed or the source has no price
+    price: Option<String>,
+    price_usd: Option<f64>,
+    timestamp: Option<u64>,
+    confidence: Option<u8>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SourceStatus {
+    Priced,
+    /// The source has never reported a price for the token
+    Unpriced,
+    Failed,
+}
+
+/// Prices of one token across oracle feed sources, and how far apart they are
+#[derive(Debug, Serialize)]
+struct OracleSpreadReport {
+    token: String,
+    sources: Vec<SourceReading>,
+    /// The oracle's own aggregated price, as `get_price` returns it
+    aggregated_usd: Option<f64>,
+    median_usd: Option<f64>,
+    /// Spread across priced sources; `None` with fewer than two to compare
+    spread_bps: Option<Bps>,
+    max_spread_bps: Bps,
+    flagged: bool,
+}
+
+impl OracleSpreadReport {
+    fn new(token: String, readings: Vec<(String, Result<PriceData>)>, aggregated: Option<PriceData>, max_spread: Bps) -> Self {
+        let priced: Vec<BigUint> = readings.iter()
+            .filter_map(|(_, reading)| reading.as_ref().ok().filter(|price| !price.is_unpriced()).map(|price| price.price.clone()))
+            .collect();
+        let spread = PriceSpread::of(&priced);
+        let spread_bps = spread.as_ref().filter(|_| priced.len() > 1).map(|spread| spread.spread);
+        
+        let sources = readings.into_iter().map(|(source, reading)| match reading {
+            Ok(price) if !price.is_unpriced() => SourceReading {
+                source,
+                status: SourceStatus::Priced,
+                price: Some(price.price.to_string()),
+                price_usd: Some(price.usd()),
+                timestamp: Some(price.timestamp),
+                confidence: Some(price.confidence),
+                error: None,
+            },
+            Ok(_) => SourceReading { source, status: SourceStatus::Unpriced, price: None, price_usd: None, timestamp: None, confidence: None, error: None },
+            Err(e) => SourceReading { source, status: SourceStatus::Failed, price: None, price_usd: None, timestamp: None, confidence: None, error: Some(format!("{:#}", e)) },
+        }).collect();
+        
+        OracleSpreadReport {
+            token,
+            sources,
+            aggregated_usd: aggregated.filter(|price| !price.is_unpriced()).map(|price| price.usd()),
+            median_usd: spread.map(|spread| spread.median_usd()),
+            spread_bps,
+            max_spread_bps: max_spread,
+            flagged: spread_bps.is_some_and(|spread| spread > max_spread),
+        }
+    }
+    
+    fn print(&self, config: &Config) {
+        let mut sources = table::new_table(&["Source", "Price (USD)", "Updated", "Confidence"], &config.display);
+        for reading in &self.sources {
+            let price = match (reading.status, reading.price_usd) {
+                (SourceStatus::Priced, Some(usd)) => table::cell(format!("${:.4}", usd), theme::PRIMARY),
+                (SourceStatus::Failed, _) => table::cell("failed", theme::ERROR),
+                _ => table::cell("no price", theme::WARNING),
+            };
+            sources.add_row(vec![
+                Cell::new(&reading.source),
+                price,
+                table::cell(reading.timestamp.map_or("-".to_string(), format_timestamp), theme::MUTED),
+                table::cell(reading.confidence.map_or("-".to_string(), |confidence| format!("{}%", confidence)), theme::INFO),
+            ]);
+        }
+        println!("{}", sources);
+        for reading in &self.sources {
+            if let Some(error) = &reading.error {
+                println!("  {}", format!("{}: {}", reading.source, error).color(theme::MUTED));
+            }
+        }
+        
+        println!();
+        let usd = |price: Option<f64>| price.map_or("n/a".to_string(), |price| format!("${:.4}", price));
+        println!("  Oracle Aggregate: {}", usd(self.aggregated_usd).color(theme::SECONDARY));
+        println!("  Median of Sources: {}", usd(self.median_usd).color(theme::PRIMARY));
+        match self.spread_bps {
+            Some(spread) => {
+                let color = if self.flagged { theme::ERROR } else { theme::SUCCESS };
+                println!("  Max Spread: {} {}", spread.to_string().color(color), format!("(limit {})", self.max_spread_bps).color(theme::MUTED));
+            }
+            None => println!("  Max Spread: {}", "n/a (fewer than two sources priced)".color(theme::WARNING)),
+        }
+        if self.flagged {
+            println!("  {}", "Warning: feed sources disagree by more than the limit; the aggregate may be unreliable".color(theme::WARNING));
+        }
+    }
+}
+
+/// Parse a feed source ID, given as hex or as the short string the oracle registers it under
+fn source_id(source: &str) -> Result<FieldElement> {
+    if source.starts_with("0x") {
+        return FieldElement::from_hex_be(source).with_context(|| format!("Invalid source ID '{}'", source));
+    }
+    cairo_short_string_to_felt(source).map_err(|e| anyhow::anyhow!("Invalid source ID '{}': {}", source, e))
+}
+
+async fn oracle(token: &str, sources: &[String], max_spread: f64, json: bool, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let contracts = &config.contracts;
+    let oracle_address = contracts.require("price_oracle")?;
+    let max_spread = Bps::from_percent(max_spread).context("Invalid --max-spread")?;
+    let source_ids = sources.iter()
+        .map(|source| Ok((source.clone(), source_id(source)?)))
+        .collect::<Result<Vec<_>>>()?;
+    if source_ids.is_empty() {
+        return Err(anyhow::anyhow!("Pass at least one feed source with --sources"));
+    }
+    
+    let account = get_account(cli).await?;
+    let (symbol, token_address) = match token.to_lowercase().as_str() {
+        "strk" => ("STRK".to_string(), contracts.require("strk_token")?),
+        "hstrk" => ("hSTRK".to_string(), contracts.require("hstrk_token")?),
+        _ => {
+            let address = validate_address(token).with_context(|| format!("Unknown token '{}' (expected strk, hstrk or an address)", token))?;
+            (normalize_address(address), address)
+        }
+    };
+    
+    let provider = account.provider();
+    let block = if json { audit::report_block(cli, provider).await? } else { None };
+    let oracle = OracleContract::with_address(BlockReader::new(provider, block), oracle_address);
+    let (readings, aggregated) = tokio::join!(
+        futures::future::join_all(source_ids.into_iter().map(|(name, id)| {
+            let oracle = &oracle;
+            async move { (name, oracle.get_price_from_source(token_address, id).await) }
+        })),
+        oracle.get_price(token_address),
+    );
+    let report = OracleSpreadReport::new(symbol, readings, aggregated.ok(), max_spread);
+    
+    if json {
+        println!("{}", audit::to_json(cli, &config, block, &report, true)?);
+    } else {
+        println!("{}", format!("Oracle Sources for {}", report.token).color(theme::PRIMARY));
+        println!();
+        report.print(&config);
+    }
+    
+    if report.flagged {
+        return Err(anyhow::anyhow!("{} feed sources are {} apart, above the {} limit", report.token, report.spread_bps.unwrap_or(Bps(0)), max_spread));
+    }
+    Ok(())
+}
+
+/// Hedge leverage the perpetual hedge contract allows (`max_leverage`)
+const MAX_HEDGE_LEVERAGE: f64 = 2.0;
+
+/// Delta held by each instrument, in STRK
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct DeltaPosition {
+    perpetual: f64,
+    options: f64,
+    net: f64,
+}
+
+impl DeltaPosition {
+    fn new(perpetual: f64, options: f64) -> DeltaPosition {
+        DeltaPosition { perpetual, options, net: perpetual + options }
+    }
+    
+    /// Gross hedge notional over total assets, or `None` for an empty vault
+    fn leverage(&self, total_assets: f64) -> Option<f64> {
+        (total_assets > 0.0).then(|| (self.perpetual.abs() + self.options.abs()) / total_assets)
+    }
+}
+
+/// Trades that move net delta to a target, computed from view data only
+///
+/// A perpetual has a delta of 1 per STRK of notional, so the perpetual trade is the
+/// delta change itself. Option contracts are sized by the delta of one contract.
+/// Amounts are STRK; positive trades buy and negative trades sell.
+#[derive(Debug, Serialize)]
+struct RebalancePlan {
+    target_delta: f64,
+    total_assets: f64,
+    before: DeltaPosition,
+    after: DeltaPosition,
+    perpetual_trade: f64,
+    options_delta_change: f64,
+    option_delta: f64,
+    options_contracts: f64,
+    pool_depth: f64,
+    /// Perpetual market impact in STRK; options premiums are not included
+    estimated_cost: f64,
+    leverage_before: Option<f64>,
+    leverage_after: Option<f64>,
+    max_leverage: f64,
+}
+
+impl RebalancePlan {
+    fn new(
+        before: DeltaPosition,
+        total_assets: f64,
+        target_delta: f64,
+        options_share: f64,
+        option_delta: f64,
+        pool_depth: f64,
+    ) -> Result<RebalancePlan> {
+        if !target_delta.is_finite() {
+            return Err(anyhow::anyhow!("Target delta must be a finite number"));
+        }
+        if !(0.0..=1.0).contains(&options_share) {
+            return Err(anyhow::anyhow!("--options-share must be between 0 and 1, got {}", options_share));
+        }
+        if options_share > 0.0 && !(option_delta != 0.0 && option_delta.abs() <= 1.0) {
+            return Err(anyhow::anyhow!("--option-delta must be non-zero and between -1 and 1, got {}", option_delta));
+        }
+        
+        let change = target_delta - before.net;
+        let options_delta_change = change * options_share;
+        let perpetual_trade = change - options_delta_change;
+        let options_contracts = if options_delta_change == 0.0 { 0.0 } else { options_delta_change / option_delta };
+        let after = DeltaPosition::new(before.perpetual + perpetual_trade, before.options + options_delta_change);
+        
+        // Constant-product market of `pool_depth` STRK: trading `size` fills at depth / (depth + size)
+        let size = perpetual_trade.abs();
+        let estimated_cost = if size == 0.0 { 0.0 } else if pool_depth > 0.0 { size * size / (pool_depth + size) } else { size };
+        
+        Ok(RebalancePlan {
+            target_delta,
+            total_assets,
+            before,
+            after,
+            perpetual_trade,
+            options_delta_change,
+            option_delta,
+            options_contracts,
+            pool_depth,
+            estimated_cost,
+            leverage_before: before.leverage(total_assets),
+            leverage_after: after.leverage(total_assets),
+            max_leverage: MAX_HEDGE_LEVERAGE,
+        })
+    }
+    
+    fn exceeds_max_leverage(&self) -> bool {
+        self.leverage_after.is_some_and(|leverage| leverage > MAX_HEDGE_LEVERAGE)
+    }
+    
+    fn print(&self, config: &Config) {
+        println!("{}", "Delta Positions (STRK):".color(theme::ACCENT));
+        let mut positions = table::new_table(&["", "Perpetual", "Options", "Net"], &config.display);
+        for (label, position) in [("Current", &self.before), ("After Plan", &self.after)] {
+            positions.add_row(vec![
+                Cell::new(label),
+                table::cell(format!("{:+.6}", position.perpetual), theme::SECONDARY),
+                table::cell(format!("{:+.6}", position.options), theme::SECONDARY),
+                table::cell(format!("{:+.6}", position.net), theme::PRIMARY),
+            ]);
+        }
+        println!("{}", positions);
+        
+        println!();
+        println!("{}", "Trades:".color(theme::ACCENT));
+        let side = |amount: f64| if amount >= 0.0 { "buy" } else { "sell" };
+        println!("  Perpetual: {}", format!("{} {:.6} STRK", side(self.perpetual_trade), self.perpetual_trade.abs()).color(theme::PRIMARY));
+        if self.options_delta_change != 0.0 {
+            println!(
+                "  Options: {} ({:+.6} delta at {:+.2} per contract)",
+                format!("{} {:.4} contracts", side(self.options_contracts), self.options_contracts.abs()).color(theme::PRIMARY),
+                self.options_delta_change,
+                self.option_delta
+            );
         } else {
-            break;
+            println!("  Options: {}", "no change".color(theme::MUTED));
         }
+        
+        println!();
+        println!("{}", "Cost and Leverage:".color(theme::ACCENT));
+        println!("  Estimated Cost: {} {}", format!("{:.6} STRK", self.estimated_cost).color(theme::WARNING), format!("(perpetual impact at {:.0} STRK liquidity)", self.pool_depth).color(theme::MUTED));
+        let leverage = |value: Option<f64>| value.map_or("n/a (no assets)".to_string(), |value| format!("{:.2}x", value));
+        println!("  Hedge Leverage: {} -> {}", leverage(self.leverage_before).color(theme::INFO), leverage(self.leverage_after).color(if self.exceeds_max_leverage() { theme::ERROR } else { theme::SUCCESS }));
+        if self.exceeds_max_leverage() {
+            println!("  {}", format!("Warning: post-trade leverage exceeds the perpetual hedge's {:.0}x limit", MAX_HEDGE_LEVERAGE).color(theme::WARNING));
+        }
+    }
+}
+
+async fn rebalance_plan(
+    target_delta: f64,
+    options_share: f64,
+    option_delta: f64,
+    pool_depth: Option<String>,
+    json: bool,
+    cli: &Cli,
+) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let contracts = &config.contracts;
+    let vault_address = contracts.require("vault")?;
+    let perpetual_address = contracts.require("perpetual_hedge")?;
+    let options_address = contracts.require("options_strategy")?;
+    let depth = parse_amount(pool_depth.as_deref().unwrap_or(DEFAULT_CLOSE_DEPTH), RoundingMode::Down)?;
+    
+    let account = get_account(cli).await?;
+    let provider = account.provider();
+    let block = if json { audit::report_block(cli, provider).await? } else { None };
+    let (total_assets, perpetual, options) = tokio::try_join!(
+        VaultContract::with_address(BlockReader::new(provider, block), vault_address).total_assets(),
+        HedgingContract::with_address(BlockReader::new(provider, block), perpetual_address).perpetual_delta(),
+        HedgingContract::with_address(BlockReader::new(provider, block), options_address).portfolio_delta(),
+    )?;
+    
+    let strk = |wei: &BigUint| wei.to_f64().unwrap_or(0.0) / 1e18;
+    let plan = RebalancePlan::new(
+        DeltaPosition::new(perpetual.as_f64(), options.as_f64()),
+        strk(&total_assets),
+        target_delta,
+        options_share,
+        option_delta,
+        strk(&depth),
+    )?;
+    
+    if json {
+        println!("{}", audit::to_json(cli, &config, block, &plan, true)?);
+        return Ok(());
     }
     
+    println!("{}", format!("Rebalance Plan (target delta {:+.6})", target_delta).color(theme::PRIMARY));
+    println!();
+    plan.print(&config);
+    println!();
+    println!("{}", "Nothing was sent; run `protocol rebalance execute` to rebalance".color(theme::MUTED));
+    
+    Ok(())
+}
+
+/// A leverage reading at a point in the past
+#[derive(Debug, Serialize)]
+struct LeveragePoint {
+    timestamp: u64,
+    block: u64,
+    leverage: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct LeverageReport {
+    period_days: u32,
+    current: f64,
+    series: Vec<LeveragePoint>,
+}
+
+async fn leverage(period: Option<u32>, json: bool, cli: &Cli) -> Result<()> {
+    let period = period.unwrap_or(30);
+    let config = Config::load(cli.config.as_deref())?;
+    let account = get_account(cli).await?;
+    let provider = account.provider();
+    let risk_address = config.contracts.require("risk_manager")?;
+    
+    let block = if json { audit::report_block(cli, provider).await? } else { None };
+    let current = RiskContract::with_address(BlockReader::new(provider, block), risk_address).get_risk_metrics().await?.leverage();
+    
+    let pb = ProgressBar::new(period as u64);
+    pb.set_style(theme::progress_style());
+    pb.set_message("Sampling historical blocks");
+    
+    // One sample per day, oldest first; days before the risk manager existed are skipped
+    let now = chrono::Utc::now().timestamp() as u64;
+    let mut series = Vec::new();
+    for days_ago in (1..=period as u64).rev() {
+        let timestamp = now.saturating_sub(days_ago * 86_400);
+        let block = contract_utils::block_at_timestamp(provider, timestamp).await?;
+        
+        let sample = contract_utils::call_at_block(provider, risk_address, "get_risk_metrics", vec![], block)
+            .await
+            .and_then(|data| RiskMetrics::decode(&data));
+        if let Ok(metrics) = sample {
+            series.push(LeveragePoint { timestamp, block, leverage: metrics.leverage() });
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+    
+    series.push(LeveragePoint {
+        timestamp: now,
+        block: match block {
+            Some(block) => block,
+            None => contract_utils::get_current_block(provider).await?,
+        },
+        leverage: current,
+    });
+    
+    let report = LeverageReport { period_days: period, current, series };
+    
+    if json {
+        println!("{}", audit::to_json(cli, &config, block, &report, true)?);
+        return Ok(());
+    }
+    
+    let values: Vec<f64> = report.series.iter().map(|point| point.leverage).collect();
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let first = values[0];
+    
+    println!("{}", format!("Leverage Ratio ({} days)", period).color(theme::PRIMARY));
+    println!();
+    println!("  {}", sparkline(&values).color(theme::ACCENT));
+    println!();
+    
+    let mut summary = table::metrics_table(&config.display);
+    summary.add_row(vec![Cell::new("Current"), table::cell(format!("{:.2}x", current), theme::PRIMARY)]);
+    summary.add_row(vec![Cell::new("Change"), table::cell(format!("{:+.2}x", current - first), theme::SECONDARY)]);
+    summary.add_row(vec![Cell::new("Low"), table::cell(format!("{:.2}x", min), theme::SUCCESS)]);
+    summary.add_row(vec![Cell::new("High"), table::cell(format!("{:.2}x", max), theme::WARNING)]);
+    summary.add_row(vec![Cell::new("Samples"), table::cell(values.len(), theme::MUTED)]);
+    println!("{}", summary);
+    
+    Ok(())
+}
+
+/// Most samples a TVL history takes, however long the period
+const MAX_TVL_SAMPLES: u64 = 120;
+
+/// Samples for a period: hourly over a day, then sparser as the period grows
+fn tvl_sample_count(period_days: u32) -> u64 {
+    let per_day = match period_days {
+        0..=1 => 24,
+        2..=7 => 6,
+        8..=30 => 2,
+        _ => 1,
+    };
+    (period_days.max(1) as u64 * per_day).min(MAX_TVL_SAMPLES)
+}
+
+/// `count` blocks spread evenly over `[from, to]`, always ending at `to`
+///
+/// Blocks are spaced by number rather than by time, which keeps each sample to one
+/// timestamp read instead of a binary search. Short ranges yield fewer, distinct blocks.
+fn sample_blocks(from: u64, to: u64, count: u64) -> Vec<u64> {
+    if count <= 1 || from >= to {
+        return vec![to];
+    }
+    
+    let span = to - from;
+    let mut blocks: Vec<u64> = (0..count).map(|i| from + span * i / (count - 1)).collect();
+    blocks.dedup();
+    blocks
+}
+
+/// Vault total assets at a point in the past; amounts are wei strings
+#[derive(Debug, Serialize)]
+struct TvlPoint {
+    block: u64,
+    timestamp: u64,
+    total_assets: String,
+    #[serde(skip)]
+    assets: BigUint,
+}
+
+#[derive(Debug, Serialize)]
+struct TvlHistory {
+    period_days: u32,
+    /// Block the vault was deployed at, when that falls inside the period
+    deployed_at_block: Option<u64>,
+    series: Vec<TvlPoint>,
+}
+
+impl TvlHistory {
+    fn csv(&self) -> Result<String> {
+        let mut out = String::from("block,timestamp,total_assets_wei,total_assets_strk\n");
+        for point in &self.series {
+            out.push_str(&format!("{},{},{},{}\n", point.block, point.timestamp, point.total_assets, wei_to_decimal(&point.assets)?.normalize()));
+        }
+        Ok(out)
+    }
+}
+
+async fn tvl_history(period: Option<u32>, json: bool, csv: bool, cli: &Cli) -> Result<()> {
+    let period = period.unwrap_or(30);
+    let config = Config::load(cli.config.as_deref())?;
+    let provider = get_provider(&config)?;
+    let vault = validate_address(&config.contracts.vault)?;
+    
+    let latest = match audit::report_block(cli, &provider).await? {
+        Some(block) => block,
+        None => contract_utils::get_current_block(&provider).await?,
+    };
+    let now = contract_utils::get_block_timestamp(&provider, latest).await?;
+    let start = contract_utils::block_at_timestamp(&provider, now.saturating_sub(period as u64 * 86_400)).await?;
+    
+    // Before the vault existed there is nothing to read, so the series starts at its deployment
+    let first = contract_utils::deployment_block(&provider, vault, start, latest).await
+        .context("The configured vault is not deployed")?;
+    let deployed_at_block = (first > start).then_some(first);
+    
+    let blocks = sample_blocks(first, latest, tvl_sample_count(period));
+    let pb = ProgressBar::new(blocks.len() as u64);
+    pb.set_style(theme::progress_style());
+    pb.set_message("Reading total assets at historical blocks");
+    
+    let mut series = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        let total_assets = VaultContract::with_address(BlockReader::new(&provider, Some(block)), vault).total_assets().await
+            .with_context(|| format!("Failed to read total_assets at block {}", block))?;
+        let timestamp = contract_utils::get_block_timestamp(&provider, block).await?;
+        series.push(TvlPoint { block, timestamp, total_assets: total_assets.to_string(), assets: total_assets });
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+    
+    let history = TvlHistory { period_days: period, deployed_at_block, series };
+    
+    if json {
+        println!("{}", audit::to_json(cli, &config, Some(latest).filter(|_| cli.with_metadata), &history, true)?);
+        return Ok(());
+    }
+    if csv {
+        print!("{}", history.csv()?);
+        return Ok(());
+    }
+    
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let values: Vec<&BigUint> = history.series.iter().map(|point| &point.assets).collect();
+    let chart: Vec<f64> = values.iter().map(|value| value.to_f64().unwrap_or(0.0) / 1e18).collect();
+    let (first_value, current) = (values[0], values[values.len() - 1]);
+    let (low, high) = (values.iter().min().copied().unwrap_or(current), values.iter().max().copied().unwrap_or(current));
+    
+    println!("{}", format!("Total Value Locked ({} days)", period).color(theme::PRIMARY));
+    println!();
+    println!("  {}", sparkline(&chart).color(theme::ACCENT));
+    println!();
+    
+    let change = if first_value.bits() == 0 {
+        "n/a".to_string()
+    } else {
+        let (first_strk, current_strk) = (wei_to_decimal(first_value)?, wei_to_decimal(current)?);
+        format_signed_percentage((current_strk - first_strk) / first_strk * Decimal::ONE_HUNDRED)
+    };
+    let mut summary = table::metrics_table(&config.display);
+    summary.add_row(vec![Cell::new("Current"), table::cell(format!("{} STRK", amounts.format(current)), theme::PRIMARY)]);
+    summary.add_row(vec![Cell::new("Change"), table::cell(change, theme::SECONDARY)]);
+    summary.add_row(vec![Cell::new("Low"), table::cell(format!("{} STRK", amounts.format(low)), theme::MUTED)]);
+    summary.add_row(vec![Cell::new("High"), table::cell(format!("{} STRK", amounts.format(high)), theme::MUTED)]);
+    summary.add_row(vec![Cell::new("Samples"), table::cell(history.series.len(), theme::MUTED)]);
+    summary.add_row(vec![Cell::new("From"), table::cell(format_timestamp(history.series[0].timestamp), theme::MUTED)]);
+    println!("{}", summary);
+    
+    if let Some(block) = history.deployed_at_block {
+        println!();
+        println!("{}", format!("The vault was deployed at block {} during this period; the series starts there", block).color(theme::INFO));
+    }
+    
+    Ok(())
+}
+
+/// On-chain protocol parameters; token amounts are wei strings so JSON consumers keep full precision
+#[derive(Debug, Serialize)]
+struct ProtocolConfigReport {
+    vault: VaultParameters,
+    rebalancing: RebalancingParameters,
+}
+
+#[derive(Debug, Serialize)]
+struct VaultParameters {
+    management_fee_bps: Bps,
+    performance_fee_bps: Bps,
+    exit_fee_bps: Bps,
+    deposit_limit: String,
+    min_deposit: String,
+    withdrawal_delay_secs: u64,
+    emergency_mode: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RebalancingParameters {
+    execution_threshold_bps: Bps,
+    last_rebalancing: u64,
+    paused: bool,
+}
+
+async fn protocol_config(json: bool, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let contracts = &config.contracts;
+    let account = get_account(cli).await?;
+    let block = if json { audit::report_block(cli, account.provider()).await? } else { None };
+    let vault = VaultContract::with_address(BlockReader::new(account.provider(), block), contracts.require("vault")?);
+    let engine = RebalancingContract::with_address(BlockReader::new(account.provider(), block), contracts.require("rebalancing_engine")?);
+    
+    let vault_config = vault.get_vault_config().await
+        .context("Failed to read vault config")?;
+    let report = ProtocolConfigReport {
+        vault: VaultParameters {
+            management_fee_bps: vault_config.management_fee_bps,
+            performance_fee_bps: vault_config.performance_fee_bps,
+            exit_fee_bps: vault_config.exit_fee_bps,
+            deposit_limit: vault_config.deposit_limit.to_string(),
+            min_deposit: vault_config.min_deposit.to_string(),
+            withdrawal_delay_secs: vault_config.withdrawal_delay.to_u64().unwrap_or(u64::MAX),
+            emergency_mode: vault_config.emergency_mode,
+        },
+        rebalancing: RebalancingParameters {
+            execution_threshold_bps: engine.execution_threshold().await?,
+            last_rebalancing: engine.last_rebalancing().await?,
+            paused: engine.is_paused().await?,
+        },
+    };
+    
+    if json {
+        println!("{}", audit::to_json(cli, &config, block, &report, true)?);
+        return Ok(());
+    }
+    
+    let flag = |on: bool, label: &str| if on { table::cell(label, theme::ERROR) } else { table::cell("no", theme::SUCCESS) };
+    
+    println!("{}", "On-Chain Protocol Configuration".color(theme::PRIMARY));
+    println!();
+    
+    println!("{}", "Vault:".color(theme::ACCENT));
+    let mut vault_table = table::metrics_table(&config.display);
+    vault_table.add_row(vec![Cell::new("Management Fee"), table::cell(format!("{:.2}%", vault_config.management_fee_percentage()), theme::PRIMARY)]);
+    vault_table.add_row(vec![Cell::new("Performance Fee"), table::cell(format!("{:.2}%", vault_config.performance_fee_percentage()), theme::PRIMARY)]);
+    vault_table.add_row(vec![Cell::new("Exit Fee"), table::cell(vault_config.exit_fee_bps, theme::PRIMARY)]);
+    vault_table.add_row(vec![Cell::new("Deposit Limit"), if vault_config.deposit_limit == BigUint::from(0u32) {
+        table::cell("uncapped", theme::MUTED)
+    } else {
+        table::cell(format!("{} STRK", amounts.format(&vault_config.deposit_limit)), theme::INFO)
+    }]);
+    vault_table.add_row(vec![Cell::new("Minimum Deposit"), table::cell(format!("{} STRK", amounts.format(&vault_config.min_deposit)), theme::INFO)]);
+    vault_table.add_row(vec![Cell::new("Withdrawal Delay"), if report.vault.withdrawal_delay_secs == 0 {
+        table::cell("none", theme::MUTED)
+    } else {
+        table::cell(format_duration(report.vault.withdrawal_delay_secs), theme::INFO)
+    }]);
+    vault_table.add_row(vec![Cell::new("Emergency Mode"), flag(report.vault.emergency_mode, "ACTIVE")]);
+    println!("{}", vault_table);
+    
+    println!();
+    println!("{}", "Rebalancing:".color(theme::ACCENT));
+    let mut engine_table = table::metrics_table(&config.display);
+    engine_table.add_row(vec![Cell::new("Execution Threshold"), table::cell(report.rebalancing.execution_threshold_bps, theme::PRIMARY)]);
+    engine_table.add_row(vec![Cell::new("Last Rebalance"), if report.rebalancing.last_rebalancing == 0 {
+        table::cell("never", theme::MUTED)
+    } else {
+        table::cell(format_timestamp(report.rebalancing.last_rebalancing), theme::INFO)
+    }]);
+    engine_table.add_row(vec![Cell::new("Paused"), flag(report.rebalancing.paused, "PAUSED")]);
+    println!("{}", engine_table);
+    
     Ok(())
 }
 
@@ -157,56 +1442,70 @@ async fn rebalance(action: RebalanceCommands, cli: &Cli) -> Result<()> {
             println!("  Last Rebalance: {}", "2 hours ago".color(theme::MUTED));
         }
         
-        RebalanceCommands::Execute { force, dry_run } => {
-            if dry_run {
+        RebalanceCommands::Execute { force } => {
+            if cli.dry_run {
                 println!("{}", "Dry Run: Rebalancing Simulation".color(theme::WARNING));
             } else {
                 println!("{}", "Executing Protocol Rebalancing".color(theme::PRIMARY));
             }
             
-            if !force {
-                if !Confirm::new()
-                    .with_prompt("Proceed with rebalancing?")
-                    .default(false)
-                    .interact()?
-                {
-                    println!("{}", "Rebalancing cancelled".color(theme::WARNING));
-                    return Ok(());
-                }
-            }
+            let config = Config::load(cli.config.as_deref())?;
+            let engine = validate_address(&config.contracts.rebalancing_engine)?;
+            let (calls, summaries) = entry_point_calls(vec![(engine, "execute_rebalancing")])?;
             
-            let pb = ProgressBar::new(5);
-            pb.set_style(theme::progress_style());
+            let account = get_account(cli).await?;
+            let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+            let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+            let fee = transaction::prepare_max_fee(&account, &config, calls.clone(), fee_cap, gas_pricing, cli.skip_fee_check).await?;
+            fee.print(&AmountFormat::new(&config.display, cli.human));
             
-            pb.set_message("Analyzing current positions");
-            pb.inc(1);
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            if cli.dry_run {
+                transaction::print_dry_run_banner();
+                return Ok(());
+            }
+            let max_fee = fee.sendable()?;
             
-            pb.set_message("Calculating required adjustments");
-            pb.inc(1);
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            if !force && !Prompter::new(cli, &config.prompts).confirm("Proceed with rebalancing?", false)? {
+                println!("{}", "Rebalancing cancelled".color(theme::WARNING));
+                return Ok(());
+            }
             
-            pb.set_message("Executing perpetual adjustments");
-            pb.inc(1);
-            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+            let progress = Progress::new(2, cli.output);
+            progress.advance("sending", "Sending execute_rebalancing");
             
-            pb.set_message("Adjusting options positions");
-            pb.inc(1);
-            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+            let record = OperationRecord::new("protocol rebalance execute", &config, &summaries);
+            let tx_hash = match account.execute(calls).max_fee(max_fee).send().await
+                .context("Failed to send rebalancing transaction")
+                .map_err(transaction::explain_error)
+            {
+                Ok(result) => result.transaction_hash,
+                Err(e) => {
+                    progress.clear();
+                    oplog::record(cli, &config, record.failed(&e));
+                    return Err(e);
+                }
+            };
+            let record = record.sent(tx_hash);
+            spend::note_sent(&config, tx_hash);
             
-            pb.set_message("Finalizing rebalancing");
-            pb.inc(1);
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            progress.advance("waiting_for_confirmation", "Waiting for confirmation");
+            progress.keep_ticking();
+            if let Err(e) = transaction::wait_for_execution(account.provider(), tx_hash, config.transaction.timeout).await {
+                progress.clear();
+                oplog::record(cli, &config, record.failed(&e));
+                return Err(e);
+            }
+            oplog::record(cli, &config, record.succeeded());
             
-            pb.finish_with_message("Rebalancing completed successfully");
+            progress.finish("Rebalancing executed");
             
-            if !dry_run {
-                println!();
-                println!("{}", "Rebalancing Results:".color(theme::SUCCESS));
-                println!("  New Delta: {}", "0.001".color(theme::SUCCESS));
-                println!("  Gas Used: {}", "245,678".color(theme::INFO));
-                println!("  Transaction Hash: {}", "0x1234...abcd".color(theme::ACCENT));
-            }
+            println!();
+            println!("{}", "Rebalancing Results:".color(theme::SUCCESS));
+            println!("  Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+        }
+        
+        RebalanceCommands::Plan { target_delta, options_share, option_delta, pool_depth, json } => {
+            rebalance_plan(target_delta, options_share, option_delta, pool_depth, json, cli).await?;
         }
         
         RebalanceCommands::History { limit } => {
@@ -214,15 +1513,20 @@ async fn rebalance(action: RebalanceCommands, cli: &Cli) -> Result<()> {
             println!("{}", format!("Rebalancing History (Last {} entries)", entries).color(theme::PRIMARY));
             println!();
             
+            let config = Config::load(cli.config.as_deref())?;
+            let mut history = table::new_table(&["#", "Timestamp", "Delta Before", "Delta After", "Gas Used"], &config.display);
+            
             // Simulated history
             for i in 1..=entries.min(5) {
-                println!("{}", format!("Rebalancing #{}", i).color(theme::ACCENT));
-                println!("  Timestamp: {}", format_timestamp(1640995200 - (i as u64 * 3600)).color(theme::MUTED));
-                println!("  Delta Before: {}", "0.045".color(theme::WARNING));
-                println!("  Delta After: {}", "0.002".color(theme::SUCCESS));
-                println!("  Gas Used: {}", "234,567".color(theme::INFO));
-                println!();
+                history.add_row(vec![
+                    Cell::new(i),
+                    table::cell(format_timestamp(1640995200 - (i as u64 * 3600)), theme::MUTED),
+                    table::cell("0.045", theme::WARNING),
+                    table::cell("0.002", theme::SUCCESS),
+                    table::cell("234,567", theme::INFO),
+                ]);
             }
+            println!("{}", history);
         }
         
         RebalanceCommands::Config { interval, threshold } => {
@@ -248,7 +1552,13 @@ async fn rebalance(action: RebalanceCommands, cli: &Cli) -> Result<()> {
     Ok(())
 }
 
-async fn emergency(action: EmergencyCommands, cli: &Cli) -> Result<()> {
+async fn emergency(
+    action: EmergencyCommands,
+    propose: Option<String>,
+    confirm_via: Option<ConfirmVia>,
+    confirm_token: Option<String>,
+    cli: &Cli,
+) -> Result<()> {
     println!("{}", "EMERGENCY PROTOCOL CONTROLS".color(theme::ERROR));
     println!("{}", "⚠️  These actions can significantly impact protocol operations".color(theme::WARNING));
     println!();
@@ -257,60 +1567,308 @@ async fn emergency(action: EmergencyCommands, cli: &Cli) -> Result<()> {
         EmergencyCommands::Pause { component } => {
             println!("{}", format!("Pausing component: {}", component).color(theme::ERROR));
             
-            if !Confirm::new()
-                .with_prompt("This is an emergency action. Are you sure?")
-                .default(false)
-                .interact()?
-            {
-                println!("{}", "Emergency pause cancelled".color(theme::WARNING));
-                return Ok(());
+            let config = Config::load(cli.config.as_deref())?;
+            let (calls, summaries) = entry_point_calls(emergency_entry_points(&config, &component, true)?)?;
+            let summary = format!("Emergency pause of Definite Protocol component '{}'", component);
+            
+            if let Some(path) = propose {
+                return export_proposal(&path, &config, &summary, &summaries);
             }
             
-            println!("{}", format!("Component '{}' has been paused", component).color(theme::SUCCESS));
+            let confirmation = Confirmation::new(config.emergency.pause, confirm_via, confirm_token);
+            match send_calls(cli, &config, "protocol emergency pause", calls, &summaries, "This is an emergency action. Are you sure?", &confirmation).await? {
+                SendOutcome::Sent(tx_hash) => {
+                    println!("{}", format!("Component '{}' has been paused", component).color(theme::SUCCESS));
+                    println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+                }
+                SendOutcome::Declined => println!("{}", "Emergency pause cancelled".color(theme::WARNING)),
+                SendOutcome::DryRun => {}
+            }
         }
         
         EmergencyCommands::Resume { component } => {
             println!("{}", format!("Resuming component: {}", component).color(theme::PRIMARY));
-            println!("{}", format!("Component '{}' has been resumed", component).color(theme::SUCCESS));
+            
+            let config = Config::load(cli.config.as_deref())?;
+            let (calls, summaries) = entry_point_calls(emergency_entry_points(&config, &component, false)?)?;
+            let summary = format!("Resume Definite Protocol component '{}' after an emergency pause", component);
+            
+            if let Some(path) = propose {
+                return export_proposal(&path, &config, &summary, &summaries);
+            }
+            
+            let confirmation = Confirmation::new(config.emergency.resume, confirm_via, confirm_token);
+            match send_calls(cli, &config, "protocol emergency resume", calls, &summaries, "Resume operations?", &confirmation).await? {
+                SendOutcome::Sent(tx_hash) => {
+                    println!("{}", format!("Component '{}' has been resumed", component).color(theme::SUCCESS));
+                    println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+                }
+                SendOutcome::Declined => println!("{}", "Resume cancelled".color(theme::WARNING)),
+                SendOutcome::DryRun => {}
+            }
         }
         
         EmergencyCommands::EmergencyWithdraw => {
             println!("{}", "EMERGENCY WITHDRAWAL FOR ALL USERS".color(theme::ERROR));
-            println!("{}", "This will allow all users to withdraw immediately".color(theme::WARNING));
+            println!("{}", "This will unwind every hedge so all users can withdraw immediately".color(theme::WARNING));
             
-            if !Confirm::new()
-                .with_prompt("This is a critical emergency action. Confirm?")
-                .default(false)
-                .interact()?
-            {
-                println!("{}", "Emergency withdrawal cancelled".color(theme::WARNING));
-                return Ok(());
+            let config = Config::load(cli.config.as_deref())?;
+            let (calls, summaries) = entry_point_calls(close_entry_points(&config, "all")?)?;
+            let summary = "Emergency withdrawal: close all Definite Protocol hedge positions".to_string();
+            
+            if let Some(path) = propose {
+                return export_proposal(&path, &config, &summary, &summaries);
             }
             
-            println!("{}", "Emergency withdrawal mode activated".color(theme::SUCCESS));
+            let confirmation = Confirmation::new(config.emergency.emergency_withdraw, confirm_via, confirm_token);
+            match send_calls(cli, &config, "protocol emergency withdraw", calls, &summaries, "This is a critical emergency action. Confirm?", &confirmation).await? {
+                SendOutcome::Sent(tx_hash) => {
+                    println!("{}", "Emergency withdrawal mode activated".color(theme::SUCCESS));
+                    println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+                }
+                SendOutcome::Declined => println!("{}", "Emergency withdrawal cancelled".color(theme::WARNING)),
+                SendOutcome::DryRun => {}
+            }
         }
         
-        EmergencyCommands::ClosePositions { position_type } => {
+        EmergencyCommands::ClosePositions { position_type, pool_depth } => {
             let pos_type = position_type.unwrap_or("all".to_string());
             println!("{}", format!("Closing {} positions immediately", pos_type).color(theme::ERROR));
             
-            if !Confirm::new()
-                .with_prompt("This will close positions at market prices. Confirm?")
-                .default(false)
-                .interact()?
-            {
-                println!("{}", "Position closure cancelled".color(theme::WARNING));
-                return Ok(());
+            let config = Config::load(cli.config.as_deref())?;
+            let (calls, summaries) = entry_point_calls(close_entry_points(&config, &pos_type)?)?;
+            let summary = format!("Emergency close of all Definite Protocol {} positions", pos_type);
+            
+            if let Some(path) = propose {
+                return export_proposal(&path, &config, &summary, &summaries);
+            }
+            
+            let mut prompt = "This will close positions at market prices. Confirm?".to_string();
+            if pos_type != "options" {
+                let amounts = AmountFormat::new(&config.display, cli.human);
+                let depth = parse_amount(pool_depth.as_deref().unwrap_or(DEFAULT_CLOSE_DEPTH), RoundingMode::Down)?;
+                let preview = close_preview(cli, &config, depth).await?;
+                preview.print(&amounts);
+                
+                prompt = format!(
+                    "Closing perpetuals is expected to lose ${} ({:.2}% impact). Confirm?",
+                    amounts.format(&preview.loss),
+                    preview.impact_percent
+                );
             }
             
-            println!("{}", format!("All {} positions have been closed", pos_type).color(theme::SUCCESS));
+            let confirmation = Confirmation::new(config.emergency.close_positions, confirm_via, confirm_token);
+            match send_calls(cli, &config, "protocol emergency close-positions", calls, &summaries, &prompt, &confirmation).await? {
+                SendOutcome::Sent(tx_hash) => {
+                    println!("{}", format!("All {} positions have been closed", pos_type).color(theme::SUCCESS));
+                    println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+                }
+                SendOutcome::Declined => println!("{}", "Position closure cancelled".color(theme::WARNING)),
+                SendOutcome::DryRun => {}
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Liquidity assumed when estimating the cost of closing perpetuals, in STRK
+const DEFAULT_CLOSE_DEPTH: &str = "1000000";
+
+/// Estimated cost of market-closing the perpetual hedge
+///
+/// Values are USD with 18 decimals. Impact follows a constant-product market of
+/// `depth` STRK, so closing `size` fills at `depth / (depth + size)` of the mark price.
+#[derive(Debug)]
+struct ClosePreview {
+    size: BigUint,
+    mark_price: f64,
+    depth: BigUint,
+    mark_value: BigUint,
+    expected_proceeds: BigUint,
+    loss: BigUint,
+    impact_percent: f64,
+}
+
+impl ClosePreview {
+    fn estimate(size: BigUint, price: &PriceData, depth: BigUint) -> ClosePreview {
+        let mark_value = &size * &price.price / BigUint::from(10u32).pow(PriceData::DECIMALS);
+        let expected_proceeds = if depth == BigUint::from(0u32) {
+            BigUint::from(0u32)
+        } else {
+            &mark_value * &depth / (&depth + &size)
+        };
+        let impact_percent = calculate_price_impact(size.clone(), expected_proceeds.clone(), mark_value.clone());
+        
+        ClosePreview {
+            loss: &mark_value - &expected_proceeds,
+            size,
+            mark_price: price.usd(),
+            depth,
+            mark_value,
+            expected_proceeds,
+            impact_percent,
+        }
+    }
+    
+    fn print(&self, amounts: &AmountFormat) {
+        println!();
+        println!("{}", "Close Preview (Perpetuals):".color(theme::ACCENT));
+        println!("  Position Size: {} STRK", amounts.format(&self.size).color(theme::PRIMARY));
+        println!("  Oracle Price: {}", format!("${:.4}", self.mark_price).color(theme::INFO));
+        println!("  Mark Value: {}", format!("${}", amounts.format(&self.mark_value)).color(theme::INFO));
+        println!("  Assumed Liquidity: {} STRK", amounts.format(&self.depth).color(theme::MUTED));
+        println!("  Estimated Impact: {}", format!("{:.2}%", self.impact_percent).color(theme::WARNING));
+        println!("  Expected Proceeds: {}", format!("${}", amounts.format(&self.expected_proceeds)).color(theme::SUCCESS));
+        println!("  Expected Loss: {}", format!("${}", amounts.format(&self.loss)).color(theme::ERROR));
+        println!();
+    }
+}
+
+/// Read the perpetual position size and oracle price to preview a market close
+async fn close_preview(cli: &Cli, config: &Config, depth: BigUint) -> Result<ClosePreview> {
+    let contracts = &config.contracts;
+    let account = get_account(cli).await?;
+    let perpetual = HedgingContract::with_address(&account, contracts.require("perpetual_hedge")?);
+    let oracle = OracleContract::with_address(&account, contracts.require("price_oracle")?);
+    
+    let position = perpetual.perpetual_delta().await
+        .context("Failed to read perpetual position size")?;
+    let price = oracle.get_price(validate_address(&contracts.strk_token)?).await
+        .context("Failed to read STRK oracle price")?;
+    
+    Ok(ClosePreview::estimate(position.magnitude, &price, depth))
+}
+
+/// Contract entry points behind pausing or resuming a component
+fn emergency_entry_points(config: &Config, component: &str, pause: bool) -> Result<Vec<(FieldElement, &'static str)>> {
+    let vault = (validate_address(&config.contracts.vault)?, if pause { "emergency_pause" } else { "resume_operations" });
+    let engine = (validate_address(&config.contracts.rebalancing_engine)?, if pause { "pause_rebalancing" } else { "resume_rebalancing" });
+    
+    match component {
+        "vault" => Ok(vec![vault]),
+        "rebalancing" => Ok(vec![engine]),
+        "all" => Ok(vec![vault, engine]),
+        _ => Err(anyhow::anyhow!("Unknown component '{}' (expected vault, rebalancing or all)", component)),
+    }
+}
+
+/// Owner-only entry points that market-close the hedge positions of `position_type`
+fn close_entry_points(config: &Config, position_type: &str) -> Result<Vec<(FieldElement, &'static str)>> {
+    let perpetuals = (config.contracts.require("perpetual_hedge")?, "emergency_close_all");
+    let options = (config.contracts.require("options_strategy")?, "emergency_close_all_options");
+    
+    match position_type {
+        "perpetuals" => Ok(vec![perpetuals]),
+        "options" => Ok(vec![options]),
+        "all" => Ok(vec![perpetuals, options]),
+        _ => Err(anyhow::anyhow!("Unknown position type '{}' (expected perpetuals, options or all)", position_type)),
+    }
+}
+
+/// Build argument-less calls for each entry point
+fn entry_point_calls(entry_points: Vec<(FieldElement, &str)>) -> Result<(Vec<Call>, Vec<CallSummary>)> {
+    let mut calls = Vec::with_capacity(entry_points.len());
+    let mut summaries = Vec::with_capacity(entry_points.len());
+    
+    for (to, function) in entry_points {
+        let (call, summary) = transaction::build_call(to, function, vec![])?;
+        calls.push(call);
+        summaries.push(summary);
+    }
+    
+    Ok((calls, summaries))
+}
+
+/// How a `send_calls` ended
+enum SendOutcome {
+    Sent(FieldElement),
+    Declined,
+    DryRun,
+}
+
+/// Check fees, confirm, send `calls` from the configured account and wait for them to execute
+///
+/// Stops after the fee check under the global `--dry-run` flag. A revert is returned
+/// as an error. Sends are recorded in the operations log under `command` when it is enabled.
+async fn send_calls(
+    cli: &Cli,
+    config: &Config,
+    command: &str,
+    calls: Vec<Call>,
+    summaries: &[CallSummary],
+    prompt: &str,
+    confirmation: &Confirmation,
+) -> Result<SendOutcome> {
+    let account = get_account(cli).await?;
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), config)?;
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, config)?;
+    let fee = transaction::prepare_max_fee(&account, config, calls.clone(), fee_cap, gas_pricing, cli.skip_fee_check).await?;
+    fee.print(&AmountFormat::new(&config.display, cli.human));
+    
+    if cli.dry_run {
+        transaction::print_dry_run_banner();
+        return Ok(SendOutcome::DryRun);
+    }
+    let max_fee = fee.sendable()?;
+    if !confirmation.confirm(prompt, &Prompter::new(cli, &config.prompts))? {
+        return Ok(SendOutcome::Declined);
+    }
+    
+    let record = OperationRecord::new(command, config, summaries);
+    let tx_hash = match account.execute(calls).max_fee(max_fee).send().await
+        .context("Failed to send transaction")
+        .map_err(transaction::explain_error)
+    {
+        Ok(result) => result.transaction_hash,
+        Err(e) => {
+            oplog::record(cli, config, record.failed(&e));
+            return Err(e);
+        }
+    };
+    let record = record.sent(tx_hash);
+    spend::note_sent(config, tx_hash);
+    
+    println!("{}", format!("Waiting for transaction {:#x} to execute...", tx_hash).color(theme::MUTED));
+    if let Err(e) = transaction::wait_for_execution(account.provider(), tx_hash, config.transaction.timeout).await {
+        oplog::record(cli, config, record.failed(&e));
+        return Err(e);
+    }
+    oplog::record(cli, config, record.succeeded());
+    Ok(SendOutcome::Sent(tx_hash))
+}
+
+/// Write a multisig proposal for `calls` instead of sending them
+fn export_proposal(path: &str, config: &Config, summary: &str, calls: &[CallSummary]) -> Result<()> {
+    let proposal = TransactionProposal::new(summary, &config.chain_id, calls)?;
+    proposal.save(path)?;
+    
+    println!();
+    println!("{}", "Multisig Proposal Written (not sent)".color(theme::SUCCESS));
+    println!("  File: {}", path.color(theme::ACCENT));
+    println!("  Summary: {}", summary.color(theme::PRIMARY));
+    for call in &proposal.calls {
+        println!("  Call: {} on {}", call.entrypoint.color(theme::PRIMARY), call.contract_address.color(theme::INFO));
+        println!("    Selector: {}", call.selector.color(theme::MUTED));
+        if !call.calldata.is_empty() {
+            println!("    Calldata: {}", call.calldata.join(", ").color(theme::MUTED));
         }
     }
     
     Ok(())
 }
 
-async fn fees(period: Option<u32>, breakdown: bool, cli: &Cli) -> Result<()> {
+async fn fees(
+    period: Option<u32>,
+    breakdown: bool,
+    collect: bool,
+    propose: Option<String>,
+    cli: &Cli,
+) -> Result<()> {
+    if collect {
+        return collect_fees(propose, cli).await;
+    }
+    
     let days = period.unwrap_or(30);
     println!("{}", format!("Protocol Fees and Revenue ({} days)", days).color(theme::PRIMARY));
     println!();
@@ -344,3 +1902,195 @@ async fn fees(period: Option<u32>, breakdown: bool, cli: &Cli) -> Result<()> {
     
     Ok(())
 }
+
+async fn collect_fees(propose: Option<String>, cli: &Cli) -> Result<()> {
+    println!("{}", "Collecting protocol management fees".color(theme::PRIMARY));
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let vault = validate_address(&config.contracts.vault)?;
+    let (calls, summaries) = entry_point_calls(vec![(vault, "collect_management_fee")])?;
+    let summary = "Collect accrued management fees from the Definite Protocol vault into the treasury";
+    
+    if let Some(path) = propose {
+        return export_proposal(&path, &config, summary, &summaries);
+    }
+    
+    match send_calls(cli, &config, "protocol fees collect", calls, &summaries, "Collect management fees now?", &Confirmation::default()).await? {
+        SendOutcome::Sent(tx_hash) => {
+            println!("{}", "Management fees collected".color(theme::SUCCESS));
+            println!("Transaction Hash: {}", format!("{:#x}", tx_hash).color(theme::ACCENT));
+        }
+        SendOutcome::Declined => println!("{}", "Fee collection cancelled".color(theme::WARNING)),
+        SendOutcome::DryRun => {}
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+    
+    #[test]
+    fn status_json_carries_exact_wei_next_to_the_formatted_amount() {
+        let total_assets = parse_amount("1234.567890123456789", RoundingMode::Down).unwrap();
+        let vault_config = VaultConfig::decode(&[2000u64, 200, 10, 5, 0, 0, 0].map(FieldElement::from), &[FieldElement::ZERO]).unwrap();
+        let amounts = AmountFormat::default();
+        
+        let summary = serde_json::to_value(StatusReport::new(&total_assets, &total_assets, 1.0, &vault_config, None, false, &amounts)).unwrap();
+        assert_eq!(summary["total_assets"], "1234567890123456789000");
+        assert_eq!(summary["total_assets_formatted"], "1234.567890");
+        assert!(summary.get("details").is_none());
+        
+        let detailed = serde_json::to_value(StatusReport::new(&total_assets, &total_assets, 1.0, &vault_config, None, true, &amounts)).unwrap();
+        assert_eq!(detailed["details"]["min_deposit"], "5");
+        assert_eq!(detailed["details"]["risk_score"], serde_json::Value::Null);
+    }
+    
+    #[test]
+    fn rebalance_plan_splits_the_delta_change_between_instruments() {
+        let before = DeltaPosition::new(-900.0, 1_000.0);
+        let plan = RebalancePlan::new(before, 1_000.0, 0.0, 0.0, 0.5, 1_000_000.0).unwrap();
+        assert_eq!(plan.perpetual_trade, -100.0);
+        assert_eq!(plan.options_contracts, 0.0);
+        assert_eq!(plan.after, DeltaPosition::new(-1_000.0, 1_000.0));
+        assert!((plan.estimated_cost - 100.0 * 100.0 / 1_000_100.0).abs() < 1e-12);
+        assert_eq!(plan.leverage_before, Some(1.9));
+        assert_eq!(plan.leverage_after, Some(2.0));
+        assert!(!plan.exceeds_max_leverage());
+        
+        let split = RebalancePlan::new(before, 1_000.0, 50.0, 0.5, 0.25, 1_000_000.0).unwrap();
+        assert_eq!(split.perpetual_trade, -25.0);
+        assert_eq!(split.options_delta_change, -25.0);
+        assert_eq!(split.options_contracts, -100.0);
+        assert_eq!(split.after.net, 50.0);
+        
+        assert_eq!(RebalancePlan::new(before, 0.0, 0.0, 0.0, 0.5, 0.0).unwrap().leverage_after, None);
+        assert!(RebalancePlan::new(before, 1_000.0, 0.0, 1.5, 0.5, 1_000_000.0).is_err());
+        assert!(RebalancePlan::new(before, 1_000.0, 0.0, 0.5, 0.0, 1_000_000.0).is_err());
+    }
+    
+    #[tokio::test]
+    async fn status_reads_decode_only_the_due_calls_in_batch_order() {
+        let reader = FixtureReader::new()
+            .with("total_assets", &[5, 0])
+            .with("total_shares", &[4, 0])
+            .with("get_delta_exposure", &[2_000_000_000_000_000_000])
+            .with("is_net_short", &[1])
+            .with("get_portfolio_delta", &[500_000_000_000_000_000])
+            .with("is_delta_negative", &[0]);
+        let targets = StatusTargets {
+            vault: FieldElement::ONE,
+            risk_manager: None,
+            hedges: Some((FieldElement::TWO, FieldElement::from(3u32))),
+        };
+        let due = DueReads { vault_state: true, vault_config: false, risk_metrics: false, delta: true };
+        
+        let reads = StatusReads::read(&reader, &targets, &due).await;
+        assert_eq!(reads.calls, 6);
+        assert_eq!(reads.vault_state.unwrap().unwrap(), (BigUint::from(5u32), BigUint::from(4u32)));
+        assert!(reads.vault_config.is_none());
+        assert!(reads.risk_metrics.is_none());
+        assert_eq!(reads.delta.unwrap().unwrap(), -1.5);
+    }
+    
+    #[test]
+    fn compare_reports_relative_change_from_network_a() {
+        assert_eq!(relative_change(200.0, 250.0), Some(0.25));
+        assert_eq!(relative_change(0.0, 10.0), None);
+        assert_eq!(exchange_rate(&BigUint::from(5u32), &BigUint::from(0u32)), 1.0);
+    }
+    
+    #[test]
+    fn delta_report_nets_perpetual_and_options() {
+        let report = DeltaReport::new(0.03, -0.01, Bps(500));
+        assert!((report.net_delta - 0.02).abs() < 1e-12);
+        assert!((report.threshold - 0.05).abs() < 1e-12);
+        assert!((report.deviation - 0.4).abs() < 1e-9);
+        assert_eq!(report.status, DeltaStatus::Neutral);
+    }
+    
+    #[test]
+    fn delta_status_tracks_threshold() {
+        assert_eq!(DeltaReport::new(-0.03, 0.0, Bps(500)).status, DeltaStatus::Approaching);
+        assert_eq!(DeltaReport::new(0.05, 0.0, Bps(500)).status, DeltaStatus::Exceeded);
+        assert_eq!(DeltaReport::new(0.0, -0.2, Bps(500)).status, DeltaStatus::Exceeded);
+    }
+    
+    #[test]
+    fn delta_report_handles_zero_threshold() {
+        let report = DeltaReport::new(1.0, 0.0, Bps(0));
+        assert_eq!(report.deviation, 0.0);
+        assert_eq!(report.status, DeltaStatus::Neutral);
+    }
+    
+    #[test]
+    fn oracle_spread_leaves_out_failed_and_unpriced_sources() {
+        let price = |cents: u32| PriceData { price: BigUint::from(cents) * 1_000_000u32, timestamp: 1_700_000_000, sources_count: 1, confidence: 90 };
+        let readings = vec![
+            ("pragma".to_string(), Ok(price(100))),
+            ("dex_twap".to_string(), Ok(price(103))),
+            ("emergency".to_string(), Ok(price(0))),
+            ("backup".to_string(), Err(anyhow::anyhow!("rpc down"))),
+        ];
+        let report = OracleSpreadReport::new("STRK".to_string(), readings, Some(price(101)), Bps(100));
+        
+        assert_eq!(report.median_usd, Some(1.015));
+        assert_eq!(report.spread_bps, Some(Bps(295)));
+        assert!(report.flagged);
+        assert_eq!(report.sources.iter().map(|source| source.status).collect::<Vec<_>>(), vec![SourceStatus::Priced, SourceStatus::Priced, SourceStatus::Unpriced, SourceStatus::Failed]);
+        assert_eq!(report.sources[3].error.as_deref(), Some("rpc down"));
+        
+        let single = OracleSpreadReport::new("STRK".to_string(), vec![("pragma".to_string(), Ok(price(100)))], None, Bps(100));
+        assert_eq!(single.spread_bps, None);
+        assert!(!single.flagged);
+    }
+    
+    #[test]
+    fn source_ids_are_short_strings_or_hex() {
+        assert_eq!(source_id("pragma").unwrap(), cairo_short_string_to_felt("pragma").unwrap());
+        assert_eq!(source_id("0x10").unwrap(), FieldElement::from(16u32));
+        assert!(source_id(&"s".repeat(32)).is_err());
+    }
+    
+    #[test]
+    fn close_preview_applies_constant_product_impact() {
+        let strk = |n: u32| BigUint::from(n) * BigUint::from(10u32).pow(18);
+        let price = PriceData { price: BigUint::from(50_000_000u32), timestamp: 0, sources_count: 1, confidence: 100 };
+        
+        let preview = ClosePreview::estimate(strk(1_000), &price, strk(9_000));
+        assert_eq!(preview.mark_value, strk(500));
+        assert_eq!(preview.expected_proceeds, strk(450));
+        assert_eq!(preview.loss, strk(50));
+        assert!((preview.impact_percent - 10.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn close_entry_points_follow_the_position_type() {
+        let config = Config::default();
+        let names = |position_type| -> Vec<&str> {
+            close_entry_points(&config, position_type).unwrap().into_iter().map(|(_, name)| name).collect()
+        };
+        
+        assert_eq!(names("perpetuals"), vec!["emergency_close_all"]);
+        assert_eq!(names("options"), vec!["emergency_close_all_options"]);
+        assert_eq!(names("all"), vec!["emergency_close_all", "emergency_close_all_options"]);
+        assert!(close_entry_points(&config, "spot").is_err());
+    }
+    
+    #[test]
+    fn tvl_sampling_gets_coarser_over_longer_periods() {
+        assert_eq!(tvl_sample_count(1), 24);
+        assert_eq!(tvl_sample_count(7), 42);
+        assert_eq!(tvl_sample_count(30), 60);
+        assert_eq!(tvl_sample_count(365), MAX_TVL_SAMPLES);
+    }
+    
+    #[test]
+    fn sample_blocks_span_the_range_and_end_at_the_latest() {
+        assert_eq!(sample_blocks(100, 200, 5), vec![100, 125, 150, 175, 200]);
+        assert_eq!(sample_blocks(10, 12, 24), vec![10, 11, 12]);
+        assert_eq!(sample_blocks(300, 300, 24), vec![300]);
+    }
+}
@@ -132,7 +132,7 @@ async fn withdraw(
     
     let account = get_account(cli).await?;
     let vault = VaultContract::new(&account).await?;
-    let hstrk_token = TokenContract::new(&account, vault.hstrk_token_address()).await?;
+    let hstrk_token = TokenContract::new(&account, vault.fetch_hstrk_token().await?).await?;
     
     pb.set_message("Checking hSTRK balance");
     pb.inc(1);
@@ -212,8 +212,8 @@ async fn balance(
     println!();
     
     let vault = VaultContract::new(&account).await?;
-    let strk_token = TokenContract::new(&account, vault.strk_token_address()).await?;
-    let hstrk_token = TokenContract::new(&account, vault.hstrk_token_address()).await?;
+    let strk_token = TokenContract::new(&account, vault.fetch_strk_token().await?).await?;
+    let hstrk_token = TokenContract::new(&account, vault.fetch_hstrk_token().await?).await?;
     
     let strk_balance = strk_token.balance_of(check_address).await?;
     let hstrk_balance = hstrk_token.balance_of(check_address).await?;
@@ -1,101 +1,264 @@
 use anyhow::{Result, Context};
+use comfy_table::Cell;
 use owo_colors::OwoColorize;
 use indicatif::{ProgressBar, ProgressStyle};
-use dialoguer::{Confirm, Input};
 use num_bigint::BigUint;
+use rust_decimal::Decimal;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use starknet::accounts::{Account, Call, ConnectedAccount};
+use starknet::core::crypto::Signature;
+use starknet::core::types::{BlockId, EventFilter, FieldElement};
+use starknet::signers::{Signer, VerifyingKey};
+use std::path::Path;
 use std::str::FromStr;
 
-use crate::{Cli, theme};
-use crate::utils::{format_amount, parse_amount, get_account};
+use crate::{Cli, audit, table, theme, utils};
+use crate::oplog::{self, OperationRecord};
+use crate::spend;
+use crate::progress::{OutputFormat, Progress};
+use crate::prompt::Prompter;
+use crate::config::Config;
+use crate::contracts::{events, selectors::selector, utils as contract_utils, BlockReader, Contract, ContractReader};
+use crate::contracts::events::{decode_flow, FlowKind, VaultFlow};
+use crate::contracts::account::AccountContract;
+use crate::contracts::oracle::{OracleContract, PriceData};
+use crate::contracts::token::TokenContract;
+use crate::contracts::vault::{assets_for_shares, assets_to_shares, exchange_rate, has_zero_exchange_rate, oracle_fair_rate, rate_deviation, shares_for_assets, VaultConfig, VaultContract, RATE_DECIMALS};
+use crate::transaction::{self, Finality};
+use crate::typed_data::TypedData;
+use crate::validated::{Address, Amount};
+use crate::watch::{self, run_watched};
+use crate::utils::{calculate_daily_rate, projected_growth, format_duration, format_timestamp, time_until, format_percentage, parse_amount, parse_amount_smart, wei_to_decimal, format_units, get_account, resolve_target_address, normalize_address, parse_felt, validate_address, AmountFormat, Bps, Compounding, RoundingMode, WEI_DECIMALS};
 use super::UserCommands;
 
 pub async fn handle_user_command(command: UserCommands, cli: &Cli) -> Result<()> {
     match command {
-        UserCommands::Deposit { amount, recipient, max_slippage } => {
-            deposit(amount, recipient, max_slippage, cli).await
+        UserCommands::Deposit { amount, recipient, max_slippage, finality } => {
+            deposit(amount, recipient, max_slippage, finality, cli).await
         }
-        UserCommands::Withdraw { shares, min_amount } => {
-            withdraw(shares, min_amount, cli).await
+        UserCommands::BatchDeposit { file, stop_on_error } => {
+            batch_deposit(file, stop_on_error, cli).await
         }
-        UserCommands::Balance { address, detailed } => {
-            balance(address, detailed, cli).await
+        UserCommands::Transfer { token, to, amount } => {
+            transfer(&token, to, amount, cli).await
         }
-        UserCommands::Simulate { amount, days, detailed } => {
-            simulate(amount, days, detailed, cli).await
+        UserCommands::Withdraw { shares, strk, min_amount } => {
+            withdraw(shares, strk, min_amount, cli).await
         }
-        UserCommands::History { address, limit, filter } => {
-            history(address, limit, filter, cli).await
+        UserCommands::Balance { address, detailed, watch, mut addresses, file, json } => {
+            if let Some(file) = file {
+                addresses.extend(load_address_file(&file)?);
+            }
+            let json = json || cli.output == OutputFormat::Json;
+            if !addresses.is_empty() || json {
+                addresses.extend(address);
+                return multi_balance(addresses, json, watch, cli).await;
+            }
+            balance(address, detailed, watch, cli).await
+        }
+        UserCommands::Simulate { amount, days, detailed, compound } => {
+            simulate(amount, days, detailed, compound, cli).await
+        }
+        UserCommands::History { address, limit, filter, since, until } => {
+            history(address, limit, filter, since, until, cli).await
+        }
+        UserCommands::SignMessage { message, typed_data, json } => {
+            sign_message(message, typed_data, json, cli).await
+        }
+        UserCommands::VerifyMessage { message, typed_data, address, signature, public_key } => {
+            verify_message(message, typed_data, address, signature, public_key, cli).await
         }
     }
 }
 
 async fn deposit(
-    amount: String,
-    _recipient: Option<String>,
+    amount: Amount,
+    recipient: Option<Address>,
     max_slippage: Option<u16>,
+    finality: Finality,
     cli: &Cli,
 ) -> Result<()> {
     println!("{}", "Initiating STRK deposit to Definite Protocol".color(theme::PRIMARY));
     
-    let amount_wei = parse_amount(&amount)?;
-    let slippage = max_slippage.unwrap_or(100); // 1% default
+    let amount_wei = amount.wei(RoundingMode::Down);
+    let slippage = Bps::from(max_slippage.unwrap_or(100)); // 1% default
     
-    // Create progress bar
-    let pb = ProgressBar::new(5);
-    pb.set_style(theme::progress_style());
+    let progress = Progress::new(5, cli.output);
+    
+    progress.advance("connecting", "Connecting to Starknet");
     
-    pb.set_message("Connecting to Starknet");
-    pb.inc(1);
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let account = get_account(cli).await?;
+    let recipient = recipient.map_or(account.address(), |address| address.0);
+    let vault = VaultContract::with_address(&account, validate_address(&config.contracts.vault)?);
+    let strk_address = config.contracts.require("strk_token")?;
+    let strk_token = TokenContract::with_address(&account, strk_address);
     
-    let _account = get_account(cli).await?;
+    progress.update("checking_vault_limits", "Checking vault limits");
+    
+    let (vault_config, total_assets) = tokio::try_join!(vault.get_vault_config(), vault.total_assets())?;
+    let headroom = vault_config.deposit_headroom(&total_assets);
+    if let Err(e) = vault_config.check_deposit(&amount_wei, &total_assets, &amounts) {
+        progress.clear();
+        return Err(e);
+    }
+    
+    progress.advance("checking_balance", "Checking STRK balance");
+    
+    let balance = strk_token.balance_of(account.address()).await?;
+    if balance < amount_wei {
+        progress.clear();
+        return Err(anyhow::anyhow!(
+            "Insufficient STRK balance. Have: {}, Need: {}",
+            amounts.format(&balance),
+            amounts.format(&amount_wei)
+        ));
+    }
     
-    pb.set_message("Checking STRK balance");
-    pb.inc(1);
+    progress.advance("checking_allowance", "Checking allowance");
     
-    // Simulated balance check
-    let balance = amount_wei.clone() * BigUint::from(2u32);
+    // Approve in the same multicall as the deposit when the allowance is short
+    let mut calls = Vec::new();
+    let allowance = strk_token.allowance(account.address(), vault.address()).await?;
+    if allowance < amount_wei {
+        calls.push(strk_token.approve_call(vault.address(), amount_wei.clone())?);
+    }
+    calls.push(vault.deposit_call(amount_wei.clone())?);
     
-    pb.set_message("Checking allowance");
-    pb.inc(1);
+    progress.advance("calculating_exchange_rate", "Calculating exchange rate");
     
-    // Simulated allowance check
-    println!("{}", "STRK spending approved".color(theme::INFO));
+    let rate = vault.deposit_exchange_rate().await?;
+    let expected_hstrk = match assets_to_shares(&amount_wei, &rate, RATE_DECIMALS) {
+        Ok(shares) => shares,
+        Err(e) => {
+            progress.clear();
+            return Err(e);
+        }
+    };
+    if let Some(recipient) = transfer_recipient(recipient, account.address()) {
+        let hstrk = TokenContract::with_address(&account, config.contracts.require("hstrk_token")?);
+        calls.push(hstrk.transfer_call(recipient, vault.preview_deposit(&amount_wei).await?)?);
+    }
     
-    pb.set_message("Calculating exchange rate");
-    pb.inc(1);
+    progress.update("checking_oracle", "Comparing with oracle price");
+    let deviation_limit = config.transaction.max_oracle_deviation_bps.map(Bps);
+    let fair_rate = match oracle_rate(&account, &config, &vault, strk_address).await {
+        Ok(FairRate::Priced(fair)) => Some(fair),
+        Ok(FairRate::Unpriced(asset)) if deviation_limit.is_none() => {
+            progress.warn(&format!("Warning: the oracle has no {} price yet, so the deposit rate is not compared with it", asset));
+            None
+        }
+        Ok(FairRate::Unpriced(asset)) => {
+            progress.clear();
+            return Err(anyhow::anyhow!(
+                "The oracle has no {} price yet (its feed is not initialised), so the transaction.max_oracle_deviation_bps limit cannot be checked; set it to off to deposit anyway",
+                asset
+            ));
+        }
+        // Without a limit the comparison is only shown, so an unreadable oracle is not fatal
+        Err(_) if deviation_limit.is_none() => None,
+        Err(e) => {
+            progress.clear();
+            return Err(e.context("Cannot compare the vault's exchange rate with the oracle (set transaction.max_oracle_deviation_bps to off to skip this check)"));
+        }
+    };
+    let deviation = fair_rate.as_ref().map(|fair| rate_deviation(&rate, fair));
+    if let Err(e) = check_oracle_deviation(deviation, deviation_limit) {
+        progress.clear();
+        return Err(e);
+    }
     
-    // Simulated exchange rate
-    let exchange_rate = BigUint::from(1000000000000000000u64); // 1:1 rate
-    let expected_hstrk = amount_wei.clone();
+    progress.update("checking_fee", "Checking fee");
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+    let fee = transaction::prepare_max_fee(&account, &config, calls.clone(), fee_cap, gas_pricing, cli.skip_fee_check).await
+        .map_err(transaction::explain_error)?;
     
     println!();
     println!("{}", "Deposit Summary:".color(theme::ACCENT));
-    println!("  STRK Amount: {}", format_amount(amount_wei.clone()).color(theme::PRIMARY));
-    println!("  Expected hSTRK: {}", format_amount(expected_hstrk.clone()).color(theme::PRIMARY));
-    println!("  Exchange Rate: {}", format!("{:.6}", 1.0).color(theme::SECONDARY));
-    println!("  Max Slippage: {}%", (slippage as f64 / 100.0).color(theme::SECONDARY));
+    println!("  STRK Amount: {}", amounts.format(&amount_wei).color(theme::PRIMARY));
+    println!("  Expected hSTRK: {}", amounts.format(&expected_hstrk).color(theme::PRIMARY));
+    println!("  Exchange Rate: {}", wei_to_decimal(&rate)?.round_dp(6).color(theme::SECONDARY));
+    if let (Some(fair), Some(deviation)) = (&fair_rate, deviation) {
+        println!("  Oracle Fair Rate: {}", wei_to_decimal(fair)?.round_dp(6).color(theme::SECONDARY));
+        if let Ok(fair_hstrk) = assets_to_shares(&amount_wei, fair, RATE_DECIMALS) {
+            println!("  hSTRK at Oracle Rate: {}", amounts.format(&fair_hstrk).color(theme::SECONDARY));
+        }
+        let limit = deviation_limit.map_or(String::new(), |limit| format!(" (limit {})", limit));
+        println!("  Oracle Deviation: {}{}", deviation.color(theme::SECONDARY), limit.color(theme::MUTED));
+    }
+    println!("  Max Slippage: {}", slippage.color(theme::SECONDARY));
+    println!("  hSTRK will be minted to: {}", normalize_address(recipient).color(theme::INFO));
+    match &headroom {
+        Some(room) => println!("  Remaining Vault Capacity: {}", amounts.format(room).color(theme::SECONDARY)),
+        None => println!("  Remaining Vault Capacity: {}", "uncapped".color(theme::MUTED)),
+    }
+    if calls.len() > 1 {
+        println!("  Includes STRK approval: {}", "yes".color(theme::MUTED));
+    }
+    fee.print(&amounts);
     println!();
     
-    let confirm = Confirm::new()
-        .with_prompt("Proceed with deposit?")
-        .default(true)
-        .interact()?;
+    if cli.dry_run {
+        progress.clear();
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    let max_fee = fee.sendable().inspect_err(|_| progress.clear())?;
+    transaction::confirm_large_amount(&amount_wei, &config, cli.confirm_large)?;
+    
+    let confirm = Prompter::new(cli, &config.prompts).confirm("Proceed with deposit?", true)?;
     
     if confirm {
-        pb.set_message("Executing deposit transaction");
-        pb.inc(1);
+        progress.advance("sending", "Executing deposit transaction");
         
-        // Simulated transaction
-        std::thread::sleep(std::time::Duration::from_secs(2));
+        let record = OperationRecord::new("user deposit", &config, &calls);
+        let result = match account.execute(calls).max_fee(max_fee).send().await
+            .context("Failed to send deposit transaction")
+            .map_err(transaction::explain_error)
+        {
+            Ok(result) => result,
+            Err(e) => {
+                oplog::record(cli, &config, record.failed(&e));
+                return Err(e);
+            }
+        };
+        let record = record.sent(result.transaction_hash);
+        spend::note_sent(&config, result.transaction_hash);
         
-        pb.finish_with_message("Deposit completed successfully!");
+        progress.update("waiting_for_confirmation", "Waiting for confirmation");
+        if let Err(e) = transaction::wait_for_execution(account.provider(), result.transaction_hash, config.transaction.timeout).await {
+            progress.clear();
+            oplog::record(cli, &config, record.failed(&e));
+            return Err(e);
+        }
+        oplog::record(cli, &config, record.succeeded());
+        
+        if finality == Finality::L1 {
+            progress.keep_ticking();
+            progress.update("waiting_for_l1", "Executed on L2; waiting for L1 acceptance");
+            let accepted = transaction::wait_for_l1_acceptance(account.provider(), result.transaction_hash, transaction::L1_ACCEPTANCE_TIMEOUT_SECS, |waited| {
+                progress.update("waiting_for_l1", &format!("Executed on L2; waiting for L1 acceptance ({} so far)", format_duration(waited.as_secs())));
+            }).await;
+            if let Err(e) = accepted {
+                progress.clear();
+                return Err(e);
+            }
+        }
+        
+        progress.finish("Deposit confirmed!");
         
         println!();
         println!("{}", "Transaction Details:".color(theme::ACCENT));
-        println!("  Transaction Hash: {}", "0x1234...abcd".color(theme::INFO));
-        println!("  Block Number: {}", "12345".color(theme::INFO));
-        println!("  Gas Used: {}", "45,678".color(theme::MUTED));
-        println!("  hSTRK Received: {}", format_amount(expected_hstrk).color(theme::SUCCESS));
+        println!("  Transaction Hash: {}", format!("{:#x}", result.transaction_hash).color(theme::INFO));
+        println!("  Recipient: {}", normalize_address(recipient).color(theme::INFO));
+        println!("  Finality: {}", match finality {
+            Finality::L2 => "accepted on L2",
+            Finality::L1 => "accepted on L1",
+        }.color(theme::INFO));
+        println!("  Expected hSTRK: {}", amounts.format(&expected_hstrk).color(theme::SUCCESS));
     } else {
         println!("{}", "Deposit cancelled".color(theme::WARNING));
     }
@@ -103,62 +266,689 @@ async fn deposit(
     Ok(())
 }
 
+/// The oracle's fair STRK per hSTRK, or the asset it has no price for
+#[derive(Debug, PartialEq)]
+enum FairRate {
+    Priced(BigUint),
+    Unpriced(&'static str),
+}
+
+impl FairRate {
+    fn from_prices(hstrk: &PriceData, strk: &PriceData) -> Result<FairRate> {
+        if let Some((asset, _)) = [("hSTRK", hstrk), ("STRK", strk)].into_iter().find(|(_, price)| price.is_unpriced()) {
+            return Ok(FairRate::Unpriced(asset));
+        }
+        
+        oracle_fair_rate(hstrk, strk).map(FairRate::Priced)
+    }
+}
+
+/// Fair STRK per hSTRK from the configured price oracle
+async fn oracle_rate<A: ContractReader>(reader: A, config: &Config, vault: &VaultContract<A>, strk: FieldElement) -> Result<FairRate> {
+    let contracts = &config.contracts;
+    let oracle = OracleContract::with_address(reader, contracts.require("price_oracle")?);
+    let hstrk = contracts.require("hstrk_token")?;
+    
+    let (hstrk_price, strk_price) = tokio::try_join!(
+        async { oracle.get_price(hstrk).await.context("Failed to read hSTRK oracle price") },
+        async { oracle.get_price(strk).await.context("Failed to read STRK oracle price") },
+    )?;
+    FairRate::from_prices(&hstrk_price, &strk_price)
+}
+
+/// Refuse a deposit whose vault rate is further from the oracle's fair rate than `limit`
+fn check_oracle_deviation(deviation: Option<Bps>, limit: Option<Bps>) -> Result<()> {
+    match (deviation, limit) {
+        (Some(deviation), Some(limit)) if deviation > limit => Err(anyhow::anyhow!(
+            "The vault's exchange rate is {} away from the oracle's fair rate, above the {} limit; the vault rate may be stale or manipulated",
+            deviation,
+            limit
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Resolve the address hSTRK is minted to, defaulting to the caller
+fn deposit_recipient(recipient: Option<&str>, caller: FieldElement) -> Result<FieldElement> {
+    match recipient {
+        Some(address) => validate_address(address)
+            .with_context(|| format!("Invalid recipient '{}'", address)),
+        None => Ok(caller),
+    }
+}
+
+/// Who the minted hSTRK must be passed on to, if not the caller
+///
+/// The vault only mints to the caller, so a deposit for someone else appends a
+/// transfer of the previewed shares to the multicall. If another deposit moves the
+/// share price first, the vault mints less than that and the whole multicall reverts.
+fn transfer_recipient(recipient: FieldElement, caller: FieldElement) -> Option<FieldElement> {
+    (recipient != caller).then_some(recipient)
+}
+
+/// A single validated row of a batch deposit file
+#[derive(Debug, Clone, PartialEq)]
+struct DepositRow {
+    line: usize,
+    amount: BigUint,
+    recipient: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DepositEntry {
+    amount: serde_json::Value,
+    #[serde(default)]
+    recipient: Option<String>,
+}
+
+/// Parse a batch deposit file, choosing JSON or CSV from the extension
+fn load_deposit_file(path: &str) -> Result<Vec<DepositRow>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read deposit file {}", path))?;
+    
+    let is_json = Path::new(path)
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    
+    let rows = if is_json {
+        parse_deposit_json(&content)?
+    } else {
+        parse_deposit_csv(&content)?
+    };
+    
+    if rows.is_empty() {
+        return Err(anyhow::anyhow!("Deposit file {} contains no deposits", path));
+    }
+    
+    Ok(rows)
+}
+
+/// Parse `amount,recipient` rows; a leading header row and `#` comments are skipped
+fn parse_deposit_csv(content: &str) -> Result<Vec<DepositRow>> {
+    let mut rows = Vec::new();
+    
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        
+        let mut fields = trimmed.split(',').map(str::trim);
+        let amount = fields.next().unwrap_or_default();
+        let recipient = fields.next().filter(|r| !r.is_empty());
+        
+        if fields.next().is_some() {
+            return Err(anyhow::anyhow!("Line {}: expected `amount,recipient`, found extra columns", line));
+        }
+        
+        if rows.is_empty() && amount.eq_ignore_ascii_case("amount") {
+            continue;
+        }
+        
+        rows.push(validate_deposit_row(line, amount, recipient)?);
+    }
+    
+    Ok(rows)
+}
+
+/// Parse a JSON array of `{"amount": ..., "recipient": ...}` objects
+fn parse_deposit_json(content: &str) -> Result<Vec<DepositRow>> {
+    let entries: Vec<DepositEntry> = serde_json::from_str(content)
+        .context("Deposit file must be a JSON array of {amount, recipient} objects")?;
+    
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let amount = match &entry.amount {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                other => return Err(anyhow::anyhow!("Entry {}: invalid amount {}", index + 1, other)),
+            };
+            validate_deposit_row(index + 1, &amount, entry.recipient.as_deref())
+        })
+        .collect()
+}
+
+fn validate_deposit_row(line: usize, amount: &str, recipient: Option<&str>) -> Result<DepositRow> {
+    let amount_wei = parse_amount(amount, RoundingMode::Down)
+        .with_context(|| format!("Line {}: invalid amount '{}'", line, amount))?;
+    if amount_wei == BigUint::from(0u32) {
+        return Err(anyhow::anyhow!("Line {}: amount must be greater than zero", line));
+    }
+    
+    if let Some(address) = recipient {
+        validate_address(address)
+            .with_context(|| format!("Line {}: invalid recipient '{}'", line, address))?;
+    }
+    
+    Ok(DepositRow {
+        line,
+        amount: amount_wei,
+        recipient: recipient.map(str::to_string),
+    })
+}
+
+/// The calls for one batch row: its deposit, and a transfer of the minted hSTRK when it names another recipient
+async fn row_deposit_calls<A>(vault: &VaultContract<A>, hstrk: &TokenContract<A>, row: &DepositRow, caller: FieldElement) -> Result<Vec<Call>>
+where
+    A: Account + ConnectedAccount + Sync,
+{
+    let mut calls = vec![vault.deposit_call(row.amount.clone())?];
+    if let Some(recipient) = transfer_recipient(deposit_recipient(row.recipient.as_deref(), caller)?, caller) {
+        calls.push(hstrk.transfer_call(recipient, vault.preview_deposit(&row.amount).await?)?);
+    }
+    Ok(calls)
+}
+
+async fn batch_deposit(
+    file: String,
+    stop_on_error: bool,
+    cli: &Cli,
+) -> Result<()> {
+    let rows = load_deposit_file(&file)?;
+    let total = rows.iter().fold(BigUint::from(0u32), |acc, row| acc + &row.amount);
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    
+    if cli.dry_run {
+        println!("{}", "Dry Run: Batch Deposit Preview".color(theme::WARNING));
+    } else {
+        println!("{}", "Batch STRK deposit to Definite Protocol".color(theme::PRIMARY));
+    }
+    println!();
+    
+    println!("{}", format!("{:<6} {:>18}  {}", "Line", "Amount (STRK)", "Recipient").color(theme::ACCENT));
+    for row in &rows {
+        println!("{:<6} {:>18}  {}",
+            row.line,
+            amounts.format(&row.amount),
+            row.recipient.as_deref().unwrap_or("(caller)"));
+    }
+    println!();
+    println!("  Deposits: {}", rows.len().color(theme::PRIMARY));
+    println!("  Total STRK: {}", amounts.format(&total).color(theme::PRIMARY));
+    
+    let account = get_account(cli).await?;
+    let vault = VaultContract::with_address(&account, validate_address(&config.contracts.vault)?);
+    let strk_address = config.contracts.require("strk_token")?;
+    let strk_token = TokenContract::with_address(&account, strk_address);
+    let hstrk_token = TokenContract::with_address(&account, config.contracts.require("hstrk_token")?);
+    
+    // Each row lands on top of the ones before it, so headroom shrinks as we go
+    let (vault_config, mut projected_assets) = tokio::try_join!(vault.get_vault_config(), vault.total_assets())?;
+    for row in &rows {
+        vault_config.check_deposit(&row.amount, &projected_assets, &amounts)
+            .with_context(|| format!("Line {}", row.line))?;
+        projected_assets += &row.amount;
+    }
+    
+    let balance = strk_token.balance_of(account.address()).await?;
+    if balance < total {
+        return Err(anyhow::anyhow!(
+            "Insufficient STRK balance. Have: {}, Need: {}",
+            amounts.format(&balance),
+            amounts.format(&total)
+        ));
+    }
+    
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+    
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+    if !cli.skip_fee_check {
+        // One multicall approximates the fees of the separate deposit transactions
+        let mut calls = vec![strk_token.approve_call(vault.address(), total.clone())?];
+        for row in &rows {
+            calls.push(vault.deposit_call(row.amount.clone())?);
+        }
+        transaction::check_fee_balance(&account, &config, calls, gas_pricing).await?;
+    }
+    
+    println!();
+    if cli.dry_run {
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    if transaction::uses_v3(&config) {
+        return Err(anyhow::anyhow!(transaction::V3_NOT_SENDABLE));
+    }
+    transaction::confirm_large_amount(&total, &config, cli.confirm_large)?;
+    if !Prompter::new(cli, &config.prompts).confirm(&format!("Send {} deposits?", rows.len()), false)? {
+        println!("{}", "Batch deposit cancelled".color(theme::WARNING));
+        return Ok(());
+    }
+    
+    // Approve the whole batch once rather than per deposit
+    let allowance = strk_token.allowance(account.address(), vault.address()).await?;
+    if allowance < total {
+        println!("{}", "Approving STRK spending...".color(theme::INFO));
+        let approve = strk_token.approve_call(vault.address(), total.clone())?;
+        let record = OperationRecord::new("user batch-deposit approve", &config, [&approve]);
+        let result = transaction::retry_on_nonce_conflict(
+            transaction::NONCE_RETRY_ATTEMPTS,
+            NONCE_RETRY_DELAY,
+            || transaction::send_capped(&account, &config, vec![approve.clone()], fee_cap, gas_pricing, true),
+            |attempt, _| eprintln!("{}", nonce_retry_message("Approval", attempt).color(theme::WARNING)),
+        ).await;
+        let tx_hash = match result {
+            Ok(tx_hash) => tx_hash,
+            Err(e) => {
+                oplog::record(cli, &config, record.failed(&e));
+                return Err(e);
+            }
+        };
+        let record = record.sent(tx_hash);
+        spend::note_sent(&config, tx_hash);
+        
+        // The first deposit would revert on the old allowance if it ran ahead of the approval
+        if let Err(e) = transaction::wait_for_execution(account.provider(), tx_hash, config.transaction.timeout).await {
+            oplog::record(cli, &config, record.failed(&e));
+            return Err(e);
+        }
+        oplog::record(cli, &config, record.succeeded());
+    }
+    
+    let pb = ProgressBar::new(rows.len() as u64);
+    pb.set_style(theme::progress_style());
+    
+    let mut results = Vec::with_capacity(rows.len());
+    for row in &rows {
+        pb.set_message(format!("Depositing line {}", row.line));
+        let result = match row_deposit_calls(&vault, &hstrk_token, row, account.address()).await {
+            Ok(calls) => {
+                let result = transaction::retry_on_nonce_conflict(
+                    transaction::NONCE_RETRY_ATTEMPTS,
+                    NONCE_RETRY_DELAY,
+                    || transaction::send_capped(&account, &config, calls.clone(), fee_cap, gas_pricing, true),
+                    |attempt, _| pb.println(nonce_retry_message(&format!("Line {}", row.line), attempt).color(theme::WARNING).to_string()),
+                ).await
+                    .map_err(transaction::explain_error);
+                spend::note_outcome(&config, &result);
+                oplog::record(cli, &config, OperationRecord::new("user batch-deposit", &config, &calls).outcome(&result));
+                result
+            }
+            Err(e) => Err(e),
+        };
+        pb.inc(1);
+        
+        let failed = result.is_err();
+        results.push((row, result));
+        if failed && stop_on_error {
+            break;
+        }
+    }
+    pb.finish_and_clear();
+    
+    println!();
+    println!("{}", "Batch Summary:".color(theme::ACCENT));
+    println!("{}", format!("{:<6} {:>18}  {:<8} {}", "Line", "Amount (STRK)", "Status", "Tx Hash / Error").color(theme::ACCENT));
+    
+    let mut failures = 0;
+    for (row, result) in &results {
+        match result {
+            Ok(tx_hash) => println!("{:<6} {:>18}  {:<8} {}",
+                row.line,
+                amounts.format(&row.amount),
+                "OK".color(theme::SUCCESS),
+                format!("{:#x}", tx_hash).color(theme::INFO)),
+            Err(e) => {
+                failures += 1;
+                println!("{:<6} {:>18}  {:<8} {}",
+                    row.line,
+                    amounts.format(&row.amount),
+                    "FAILED".color(theme::ERROR),
+                    e.to_string().color(theme::MUTED));
+            }
+        }
+    }
+    
+    let skipped = rows.len() - results.len();
+    println!();
+    println!("  Succeeded: {}", (results.len() - failures).color(theme::SUCCESS));
+    println!("  Failed: {}", failures.color(theme::ERROR));
+    if skipped > 0 {
+        println!("  Skipped: {}", skipped.color(theme::WARNING));
+    }
+    
+    if failures > 0 {
+        return Err(anyhow::anyhow!("{} of {} deposits failed", failures, rows.len()));
+    }
+    
+    Ok(())
+}
+
+/// Base wait before resending after a nonce conflict; grows with each attempt
+const NONCE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+fn nonce_retry_message(what: &str, attempt: u32) -> String {
+    format!(
+        "{}: nonce conflict, retrying with a fresh nonce (attempt {}/{})",
+        what,
+        attempt + 1,
+        transaction::NONCE_RETRY_ATTEMPTS
+    )
+}
+
+/// Token named on the `user transfer` command line
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TransferToken {
+    Strk,
+    Hstrk,
+    Address(FieldElement),
+}
+
+impl TransferToken {
+    fn parse(token: &str) -> Result<TransferToken> {
+        match token.trim().to_lowercase().as_str() {
+            "strk" => Ok(TransferToken::Strk),
+            "hstrk" => Ok(TransferToken::Hstrk),
+            other => validate_address(other)
+                .map(TransferToken::Address)
+                .with_context(|| format!("Unknown token '{}': expected strk, hstrk or a token contract address", token)),
+        }
+    }
+}
+
+/// Refuse recipients that would lose the tokens
+fn check_transfer_recipient(recipient: FieldElement, token: FieldElement) -> Result<()> {
+    if recipient == FieldElement::ZERO {
+        return Err(anyhow::anyhow!("Refusing to transfer to the zero address"));
+    }
+    if recipient == token {
+        return Err(anyhow::anyhow!(
+            "{} is the token contract itself; tokens sent there cannot be recovered",
+            normalize_address(recipient)
+        ));
+    }
+    Ok(())
+}
+
+async fn transfer(token: &str, to: String, amount: Amount, cli: &Cli) -> Result<()> {
+    println!("{}", "Preparing token transfer".color(theme::PRIMARY));
+    
+    let amount_wei = amount.wei(RoundingMode::Down);
+    if amount_wei == BigUint::from(0u32) {
+        return Err(anyhow::anyhow!("Transfer amount must be greater than zero"));
+    }
+    let token = TransferToken::parse(token)?;
+    
+    let progress = Progress::new(3, cli.output);
+    
+    progress.advance("connecting", "Connecting to Starknet");
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let account = get_account(cli).await?;
+    let recipient = resolve_target_address(Some(to), &account, &config)?;
+    
+    let (symbol, token_address) = match token {
+        TransferToken::Strk => ("STRK".to_string(), config.contracts.require("strk_token")?),
+        TransferToken::Hstrk => ("hSTRK".to_string(), config.contracts.require("hstrk_token")?),
+        TransferToken::Address(address) => ("tokens".to_string(), address),
+    };
+    let token_contract = TokenContract::with_address(&account, token_address);
+    // Any other token is read and shown in its own decimals, so 1.5 USDC is 1500000 units
+    let decimals = match token {
+        TransferToken::Address(_) => token_contract.decimals().await
+            .context("Failed to read the token's decimals")?,
+        _ => WEI_DECIMALS,
+    };
+    let amount_units = amount.units(decimals, RoundingMode::Down)?;
+    if amount_units == BigUint::from(0u32) {
+        progress.clear();
+        return Err(anyhow::anyhow!("Transfer amount {} is below the token's smallest unit ({} decimals)", amount, decimals));
+    }
+    let show = |value: &BigUint| if decimals == WEI_DECIMALS { amounts.format(value) } else { format_units(value, decimals) };
+    check_transfer_recipient(recipient, token_address)?;
+    
+    progress.advance("checking_balance", &format!("Checking {} balance", symbol));
+    
+    let balance = token_contract.balance_of(account.address()).await?;
+    if balance < amount_units {
+        progress.clear();
+        return Err(anyhow::anyhow!(
+            "Insufficient {} balance. Have: {}, Need: {}",
+            symbol,
+            show(&balance),
+            show(&amount_units)
+        ));
+    }
+    
+    let calls = vec![token_contract.transfer_call(recipient, amount_units.clone())?];
+    
+    progress.update("checking_fee", "Checking fee");
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+    let fee = transaction::prepare_max_fee(&account, &config, calls.clone(), fee_cap, gas_pricing, cli.skip_fee_check).await
+        .map_err(transaction::explain_error)?;
+    
+    println!();
+    println!("{}", "Transfer Summary:".color(theme::ACCENT));
+    println!("  Token: {} {}", symbol.color(theme::PRIMARY), normalize_address(token_address).color(theme::MUTED));
+    println!("  Amount: {}", format!("{} {}", show(&amount_units), symbol).color(theme::PRIMARY));
+    println!("  From: {}", normalize_address(account.address()).color(theme::INFO));
+    println!("  To: {}", normalize_address(recipient).color(theme::INFO));
+    println!("  Balance After: {}", format!("{} {}", show(&(&balance - &amount_units)), symbol).color(theme::SECONDARY));
+    if recipient == account.address() {
+        println!("{}", "The recipient is your own account; this only spends a fee".color(theme::WARNING));
+    }
+    fee.print(&amounts);
+    println!();
+    
+    if cli.dry_run {
+        progress.clear();
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    let max_fee = fee.sendable().inspect_err(|_| progress.clear())?;
+    if decimals == WEI_DECIMALS {
+        transaction::confirm_large_amount(&amount_units, &config, cli.confirm_large)?;
+    }
+    
+    let prompt = format!("Send {} {} to {}?", show(&amount_units), symbol, normalize_address(recipient));
+    if !Prompter::new(cli, &config.prompts).confirm(&prompt, true)? {
+        progress.clear();
+        println!("{}", "Transfer cancelled".color(theme::WARNING));
+        return Ok(());
+    }
+    
+    progress.advance("sending", "Sending transfer");
+    
+    let record = OperationRecord::new("user transfer", &config, &calls);
+    let result = match account.execute(calls).max_fee(max_fee).send().await
+        .context("Failed to send transfer transaction")
+        .map_err(transaction::explain_error)
+    {
+        Ok(result) => result,
+        Err(e) => {
+            progress.clear();
+            oplog::record(cli, &config, record.failed(&e));
+            return Err(e);
+        }
+    };
+    let record = record.sent(result.transaction_hash);
+    spend::note_sent(&config, result.transaction_hash);
+    
+    progress.update("waiting_for_confirmation", "Waiting for confirmation");
+    if let Err(e) = transaction::wait_for_execution(account.provider(), result.transaction_hash, config.transaction.timeout).await {
+        progress.clear();
+        oplog::record(cli, &config, record.failed(&e));
+        return Err(e);
+    }
+    oplog::record(cli, &config, record.succeeded());
+    
+    progress.finish("Transfer confirmed!");
+    
+    println!();
+    println!("{}", "Transaction Details:".color(theme::ACCENT));
+    println!("  Transaction Hash: {}", format!("{:#x}", result.transaction_hash).color(theme::INFO));
+    println!("  Sent: {}", format!("{} {}", show(&amount_units), symbol).color(theme::SUCCESS));
+    println!("  Recipient: {}", normalize_address(recipient).color(theme::INFO));
+    
+    Ok(())
+}
+
 async fn withdraw(
-    shares: String,
-    min_amount: Option<String>,
+    shares: Option<Amount>,
+    strk: Option<Amount>,
+    min_amount: Option<Amount>,
     cli: &Cli,
 ) -> Result<()> {
     println!("{}", "Initiating hSTRK withdrawal from Definite Protocol".color(theme::PRIMARY));
     
-    let amount_wei = parse_amount(&shares)?;
+    // Shares are given, so round down; STRK targets and minimums are required, so round up
+    let requested_shares = shares.map(|s| s.wei(RoundingMode::Down));
+    let target_strk = strk.map(|s| s.wei(RoundingMode::Up));
+    let min_strk = min_amount.map(|s| s.wei(RoundingMode::Up));
     
-    let pb = ProgressBar::new(4);
-    pb.set_style(theme::progress_style());
+    let progress = Progress::new(4, cli.output);
+    
+    progress.advance("connecting", "Connecting to Starknet");
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let account = get_account(cli).await?;
+    let vault = VaultContract::with_address(&account, validate_address(&config.contracts.vault)?);
+    let hstrk_address = config.contracts.require("hstrk_token")?;
+    let hstrk_token = TokenContract::with_address(&account, hstrk_address);
+    
+    progress.advance("checking_balance", "Checking hSTRK balance");
     
-    pb.set_message("Connecting to Starknet");
-    pb.inc(1);
+    let balance = hstrk_token.balance_of(account.address()).await?;
     
-    let _account = get_account(cli).await?;
+    progress.advance("calculating_amount", "Calculating withdrawal amount");
     
-    pb.set_message("Checking hSTRK balance");
-    pb.inc(1);
+    let total_assets = vault.total_assets().await?;
+    let total_shares = vault.total_shares().await?;
+    if has_zero_exchange_rate(&total_assets, &total_shares) {
+        progress.clear();
+        return Err(anyhow::anyhow!("The vault holds no STRK behind its hSTRK, so a withdrawal would return nothing"));
+    }
+    // The exit fee comes out of the payout, so STRK targets and minimums are net of it
+    let vault_config = vault.get_vault_config().await?;
     
-    // Simulated balance check
-    let balance = amount_wei.clone() * BigUint::from(3u32);
+    let amount_wei = match (requested_shares, &target_strk) {
+        (Some(amount_wei), _) => {
+            if balance < amount_wei {
+                progress.clear();
+                return Err(anyhow::anyhow!(
+                    "Insufficient hSTRK balance. Have: {}, Need: {}",
+                    amounts.format(&balance),
+                    amounts.format(&amount_wei)
+                ));
+            }
+            amount_wei
+        }
+        (None, Some(target)) => {
+            let needed = shares_for_assets(&vault_config.gross_for_net(target)?, &total_assets, &total_shares)?;
+            if needed > balance {
+                progress.warn(&format!(
+                    "Warning: {} STRK needs {} hSTRK but only {} is held; withdrawing the full balance",
+                    amounts.format(target),
+                    amounts.format(&needed),
+                    amounts.format(&balance)
+                ));
+                balance
+            } else {
+                needed
+            }
+        }
+        (None, None) => unreachable!("clap requires shares or --strk"),
+    };
     
-    pb.set_message("Calculating withdrawal amount");
-    pb.inc(1);
+    if amount_wei == BigUint::from(0u32) {
+        progress.clear();
+        return Err(anyhow::anyhow!("Nothing to withdraw: the hSTRK amount is zero"));
+    }
     
-    let expected_strk = amount_wei.clone(); // 1:1 for simulation
+    let gross_strk = assets_for_shares(&amount_wei, &total_assets, &total_shares);
+    let expected_strk = vault_config.net_of_exit_fee(&gross_strk);
+    if let Some(min) = &min_strk {
+        if expected_strk < *min {
+            progress.clear();
+            return Err(anyhow::anyhow!(
+                "Expected {} STRK is below the minimum of {} STRK",
+                amounts.format(&expected_strk),
+                amounts.format(min)
+            ));
+        }
+    }
+    let unlock_time = vault_config.withdrawal_unlock_time(chrono::Utc::now().timestamp() as u64);
+    
+    progress.update("checking_fee", "Checking fee");
+    let fee_cap = transaction::fee_cap(cli.max_fee.as_deref(), &config)?;
+    let gas_pricing = transaction::GasPricing::from_cli(cli.auto_gas, &config)?;
+    let calls = vec![vault.withdraw_call(amount_wei.clone())?];
+    let fee = transaction::prepare_max_fee(&account, &config, calls.clone(), fee_cap, gas_pricing, cli.skip_fee_check).await
+        .map_err(transaction::explain_error)?;
     
     println!();
     println!("{}", "Withdrawal Summary:".color(theme::ACCENT));
-    println!("  hSTRK Amount: {}", format_amount(amount_wei.clone()).color(theme::PRIMARY));
-    println!("  Expected STRK: {}", format_amount(expected_strk.clone()).color(theme::PRIMARY));
-    println!("  Exchange Rate: {}", format!("{:.6}", 1.0).color(theme::SECONDARY));
+    println!("  hSTRK Burned: {}", amounts.format(&amount_wei).color(theme::PRIMARY));
+    println!("  Expected STRK: {}", amounts.format(&expected_strk).color(theme::PRIMARY));
+    println!("  Exit Fee ({}): {}", vault_config.exit_fee_bps, amounts.format(&(&gross_strk - &expected_strk)).color(theme::SECONDARY));
+    if let Some(target) = &target_strk {
+        println!("  Requested STRK: {}", amounts.format(target).color(theme::MUTED));
+    }
+    if let Some(min) = &min_strk {
+        println!("  Minimum STRK: {}", amounts.format(min).color(theme::MUTED));
+    }
+    println!("  Exchange Rate: {}", format!("{:.6}", exchange_rate(&total_assets, &total_shares)).color(theme::SECONDARY));
     println!("  Max Slippage: {}%", "0.5".color(theme::SECONDARY));
+    match unlock_time {
+        Some(unlock) => {
+            println!("  Withdrawal Delay: {}", time_until(unlock).color(theme::WARNING));
+            println!("  Unlocks At: {}", format_timestamp(unlock).color(theme::WARNING));
+        }
+        None => println!("  Withdrawal Delay: {}", "none".color(theme::MUTED)),
+    }
+    fee.print(&amounts);
     println!();
     
-    let confirm = Confirm::new()
-        .with_prompt("Proceed with withdrawal?")
-        .default(true)
-        .interact()?;
+    if cli.dry_run {
+        progress.clear();
+        transaction::print_dry_run_banner();
+        return Ok(());
+    }
+    let max_fee = fee.sendable().inspect_err(|_| progress.clear())?;
+    transaction::confirm_large_amount(&expected_strk, &config, cli.confirm_large)?;
+    
+    let confirm = Prompter::new(cli, &config.prompts).confirm("Proceed with withdrawal?", true)?;
     
     if confirm {
-        pb.set_message("Executing withdrawal transaction");
-        pb.inc(1);
+        progress.advance("sending", "Executing withdrawal transaction");
         
-        // Simulated transaction
-        std::thread::sleep(std::time::Duration::from_secs(2));
+        let record = OperationRecord::new("user withdraw", &config, &calls);
+        let result = match account.execute(calls).max_fee(max_fee).send().await
+            .context("Failed to send withdrawal transaction")
+            .map_err(transaction::explain_error)
+        {
+            Ok(result) => result,
+            Err(e) => {
+                progress.clear();
+                oplog::record(cli, &config, record.failed(&e));
+                return Err(e);
+            }
+        };
+        let record = record.sent(result.transaction_hash);
+        spend::note_sent(&config, result.transaction_hash);
         
-        pb.finish_with_message("Withdrawal completed successfully!");
+        progress.update("waiting_for_confirmation", "Waiting for confirmation");
+        if let Err(e) = transaction::wait_for_execution(account.provider(), result.transaction_hash, config.transaction.timeout).await {
+            progress.clear();
+            oplog::record(cli, &config, record.failed(&e));
+            return Err(e);
+        }
+        oplog::record(cli, &config, record.succeeded());
+        
+        progress.finish("Withdrawal confirmed!");
         
         println!();
         println!("{}", "Transaction Details:".color(theme::ACCENT));
-        println!("  Transaction Hash: {}", "0x5678...efgh".color(theme::INFO));
-        println!("  Block Number: {}", "12346".color(theme::INFO));
-        println!("  Gas Used: {}", "52,341".color(theme::MUTED));
-        println!("  STRK Received: {}", format_amount(expected_strk).color(theme::SUCCESS));
+        println!("  Transaction Hash: {}", format!("{:#x}", result.transaction_hash).color(theme::INFO));
+        println!("  Expected STRK: {}", amounts.format(&expected_strk).color(theme::SUCCESS));
     } else {
         println!("{}", "Withdrawal cancelled".color(theme::WARNING));
     }
@@ -166,103 +956,644 @@ async fn withdraw(
     Ok(())
 }
 
-async fn balance(address: Option<String>, detailed: bool, cli: &Cli) -> Result<()> {
-    println!("{}", "Fetching account balances".color(theme::PRIMARY));
+/// The typed data to sign or verify: the `--typed-data` document, or `message` wrapped for this chain
+fn message_typed_data(message: Option<&str>, typed_data: Option<&str>, chain_id: FieldElement) -> Result<TypedData> {
+    match typed_data {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path))?;
+            TypedData::from_json(&content).with_context(|| format!("Failed to load typed data from {}", path))
+        }
+        None => TypedData::plain_message(message.unwrap_or_default(), chain_id),
+    }
+}
+
+#[derive(Serialize)]
+struct SignedMessage {
+    signer: String,
+    hash: String,
+    r: String,
+    s: String,
+}
+
+async fn sign_message(message: Option<String>, typed_data: Option<String>, json: bool, cli: &Cli) -> Result<()> {
+    let config = utils::account_config(cli)?;
+    let account = utils::connect_checked(cli, &config).await?;
     
-    let _account = get_account(cli).await?;
+    let typed_data = message_typed_data(message.as_deref(), typed_data.as_deref(), account.chain_id())?;
+    let hash = typed_data.message_hash(account.address())?;
+    let signature = utils::local_wallet(&config)?
+        .sign_hash(&hash)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to sign the message: {}", e))?;
     
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(theme::spinner_style());
-    pb.set_message("Loading balances...");
+    let signed = SignedMessage {
+        signer: format!("{:#066x}", account.address()),
+        hash: format!("{:#x}", hash),
+        r: format!("{:#x}", signature.r),
+        s: format!("{:#x}", signature.s),
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&signed)?);
+        return Ok(());
+    }
     
-    // Simulated loading
-    std::thread::sleep(std::time::Duration::from_secs(1));
+    println!("{}", "Message Signed:".color(theme::ACCENT));
+    println!("  Signer: {}", signed.signer.color(theme::PRIMARY));
+    println!("  Message Hash: {}", signed.hash.color(theme::MUTED));
+    println!("  r: {}", signed.r.color(theme::SUCCESS));
+    println!("  s: {}", signed.s.color(theme::SUCCESS));
+    println!();
+    println!("Verify with: {} --address {} --signature {},{}", "definite user verify-message".color(theme::INFO), signed.signer, signed.r, signed.s);
     
-    pb.finish_and_clear();
+    Ok(())
+}
+
+async fn verify_message(
+    message: Option<String>,
+    typed_data: Option<String>,
+    address: String,
+    signature: Vec<String>,
+    public_key: Option<String>,
+    cli: &Cli,
+) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let account = get_account(cli).await?;
+    let signer = resolve_target_address(Some(address), &account, &config)?;
+    let signature = signature.iter().map(|felt| parse_felt(felt.trim())).collect::<Result<Vec<_>>>()?;
     
-    println!();
-    println!("{}", "Account Balances:".color(theme::ACCENT));
-    println!("  STRK Balance: {}", "1,234.567890".color(theme::PRIMARY));
-    println!("  hSTRK Balance: {}", "987.654321".color(theme::PRIMARY));
-    println!("  ETH Balance: {}", "0.123456".color(theme::SECONDARY));
-    println!();
+    let typed_data = message_typed_data(message.as_deref(), typed_data.as_deref(), account.chain_id())?;
+    let hash = typed_data.message_hash(signer)?;
+    println!("Message Hash: {}", format!("{:#x}", hash).color(theme::MUTED));
     
-    println!("{}", "Portfolio Summary:".color(theme::ACCENT));
-    println!("  Total Value (USD): {}", "$2,468.91".color(theme::SUCCESS));
-    println!("  24h Change: {}", "+2.34%".color(theme::SUCCESS));
-    println!("  APY: {}", "15.67%".color(theme::INFO));
+    let valid = match public_key {
+        Some(key) => {
+            let [r, s] = signature[..] else {
+                return Err(anyhow::anyhow!("Offline verification takes a signature of exactly two felts (r,s), got {}", signature.len()));
+            };
+            VerifyingKey::from_scalar(parse_felt(key.trim()).context("Invalid public key")?)
+                .verify(&hash, &Signature { r, s })
+                .map_err(|e| anyhow::anyhow!("Failed to verify the signature: {}", e))?
+        }
+        None => AccountContract::with_address(&account, signer)
+            .is_valid_signature(hash, &signature)
+            .await?,
+    };
     
+    let shown = config.display.address(signer);
+    if !valid {
+        return Err(anyhow::anyhow!("Signature is not valid for {}", shown));
+    }
+    println!("{} {}", "✓ Valid signature from".color(theme::SUCCESS), shown.color(theme::PRIMARY));
     Ok(())
 }
 
-async fn simulate(amount: String, days: Option<u32>, detailed: bool, cli: &Cli) -> Result<()> {
-    let period = days.unwrap_or(30);
-    println!("{}", format!("Simulating yield for {} days", period).color(theme::PRIMARY));
+/// Shown with balances when hSTRK is outstanding but the vault holds no STRK
+const ZERO_RATE_WARNING: &str = "Warning: the vault's exchange rate is zero, so hSTRK currently redeems for no STRK";
+
+async fn balance(address: Option<String>, detailed: bool, watch: Option<u64>, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let account = get_account(cli).await?;
+    let target = resolve_target_address(address, &account, &config)?;
+    
+    let vault = VaultContract::with_address(&account, validate_address(&config.contracts.vault)?);
+    let strk = TokenContract::with_address(&account, config.contracts.require("strk_token")?);
+    let hstrk = TokenContract::with_address(&account, config.contracts.require("hstrk_token")?);
+    let eth = TokenContract::with_address(&account, transaction::fee_payment_token()?);
+    let amounts = AmountFormat::new(&config.display, cli.human).with_raw(cli.raw);
+    
+    let (config, vault, strk, hstrk, eth) = (&config, &vault, &strk, &hstrk, &eth);
+    run_watched(watch, OutputFormat::Human, move |_| async move {
+        println!("{}", watch::title("Account Balances", watch).color(theme::PRIMARY));
+        
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(theme::spinner_style());
+        pb.set_message("Loading balances...");
+        
+        let (strk_balance, hstrk_balance, eth_balance, total_assets, total_shares) = tokio::try_join!(
+            strk.balance_of(target),
+            hstrk.balance_of(target),
+            eth.balance_of(target),
+            vault.total_assets(),
+            vault.total_shares(),
+        )?;
+        
+        pb.finish_and_clear();
+        
+        println!();
+        println!("{}", format!("Account Balances for {}:", normalize_address(target)).color(theme::ACCENT));
+        let mut balances = table::new_table(&["Asset", if cli.raw { "Balance (wei)" } else { "Balance" }], &config.display);
+        balances.add_row(vec![Cell::new("STRK"), table::cell(amounts.format(&strk_balance), theme::PRIMARY)]);
+        balances.add_row(vec![Cell::new("hSTRK"), table::cell(amounts.format(&hstrk_balance), theme::PRIMARY)]);
+        balances.add_row(vec![Cell::new("ETH"), table::cell(amounts.format(&eth_balance), theme::SECONDARY)]);
+        println!("{}", balances);
+        if has_zero_exchange_rate(&total_assets, &total_shares) {
+            println!("{}", ZERO_RATE_WARNING.color(theme::WARNING));
+        }
+        
+        Ok::<_, anyhow::Error>(())
+    }).await
+}
 
-    let amount_wei = parse_amount(&amount)?;
+/// Balance reads in flight at once when checking many addresses
+const CONCURRENT_BALANCE_READS: usize = 8;
 
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(theme::spinner_style());
-    pb.set_message("Running simulation...");
+/// Balances read for one entry of a multi-address check, or why they could not be
+type BalanceRead = (String, Result<(FieldElement, BigUint, BigUint)>);
 
-    // Simulated calculation
-    std::thread::sleep(std::time::Duration::from_secs(1));
+/// One address in `user balance --json`; amounts are wei strings so spreadsheets keep full precision
+///
+/// Each amount is followed by a `_formatted` copy in the display format, for people reading along.
+#[derive(Debug, Serialize)]
+struct AddressBalance {
+    target: String,
+    address: Option<String>,
+    strk: Option<String>,
+    strk_formatted: Option<String>,
+    hstrk: Option<String>,
+    hstrk_formatted: Option<String>,
+    error: Option<String>,
+}
 
-    pb.finish_and_clear();
+#[derive(Debug, Serialize)]
+struct BalanceTotals {
+    strk: String,
+    strk_formatted: String,
+    hstrk: String,
+    hstrk_formatted: String,
+    hstrk_value: String,
+    hstrk_value_formatted: String,
+    succeeded: usize,
+    failed: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct MultiBalanceReport {
+    balances: Vec<AddressBalance>,
+    total: BalanceTotals,
+}
+
+/// Read one line per address or address book name; blank lines and `#` comments are skipped
+fn load_address_file(path: &str) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read address file {}", path))?;
+    
+    let targets = parse_address_list(&content);
+    if targets.is_empty() {
+        return Err(anyhow::anyhow!("Address file {} contains no addresses", path));
+    }
+    
+    Ok(targets)
+}
+
+fn parse_address_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check STRK and hSTRK balances of many addresses concurrently, reporting failures per address
+async fn multi_balance(targets: Vec<String>, json: bool, watch: Option<u64>, cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let account = get_account(cli).await?;
+    
+    let vault_address = validate_address(&config.contracts.vault)?;
+    let strk_address = config.contracts.require("strk_token")?;
+    let hstrk_address = config.contracts.require("hstrk_token")?;
+    
+    let output = if json { OutputFormat::Json } else { OutputFormat::Human };
+    let (config, account, targets) = (&config, &account, &targets);
+    run_watched(watch, output, move |_| async move {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(theme::spinner_style());
+        pb.set_message(format!("Loading balances for {} addresses...", targets.len()));
+        
+        let block = if json { audit::report_block(cli, account.provider()).await? } else { None };
+        let vault = VaultContract::with_address(BlockReader::new(account.provider(), block), vault_address);
+        let strk = TokenContract::with_address(BlockReader::new(account.provider(), block), strk_address);
+        let hstrk = TokenContract::with_address(BlockReader::new(account.provider(), block), hstrk_address);
+        let (strk, hstrk) = (&strk, &hstrk);
+        
+        let (total_assets, total_shares) = tokio::try_join!(vault.total_assets(), vault.total_shares())?;
+        let reads: Vec<BalanceRead> = stream::iter(targets.iter())
+            .map(|target| async move {
+                let read = async {
+                    let address = resolve_target_address(Some(target.clone()), account, config)?;
+                    let (strk_balance, hstrk_balance) = tokio::try_join!(strk.balance_of(address), hstrk.balance_of(address))?;
+                    Ok::<_, anyhow::Error>((address, strk_balance, hstrk_balance))
+                };
+                (target.clone(), read.await)
+            })
+            .buffered(CONCURRENT_BALANCE_READS)
+            .collect()
+            .await;
+        
+        pb.finish_and_clear();
+        
+        let (mut strk_total, mut hstrk_total) = (BigUint::from(0u32), BigUint::from(0u32));
+        for (_, strk_balance, hstrk_balance) in reads.iter().filter_map(|(_, read)| read.as_ref().ok()) {
+            strk_total += strk_balance;
+            hstrk_total += hstrk_balance;
+        }
+        let hstrk_value = assets_for_shares(&hstrk_total, &total_assets, &total_shares);
+        let failed = reads.iter().filter(|(_, read)| read.is_err()).count();
+        
+        if json {
+            let report = MultiBalanceReport {
+                balances: reads.iter().map(|(target, read)| match read {
+                    Ok((address, strk_balance, hstrk_balance)) => AddressBalance {
+                        target: target.clone(),
+                        address: Some(normalize_address(*address)),
+                        strk: Some(strk_balance.to_string()),
+                        strk_formatted: Some(amounts.format(strk_balance)),
+                        hstrk: Some(hstrk_balance.to_string()),
+                        hstrk_formatted: Some(amounts.format(hstrk_balance)),
+                        error: None,
+                    },
+                    Err(e) => AddressBalance {
+                        target: target.clone(),
+                        address: None,
+                        strk: None,
+                        strk_formatted: None,
+                        hstrk: None,
+                        hstrk_formatted: None,
+                        error: Some(format!("{:#}", e)),
+                    },
+                }).collect(),
+                total: BalanceTotals {
+                    strk: strk_total.to_string(),
+                    strk_formatted: amounts.format(&strk_total),
+                    hstrk: hstrk_total.to_string(),
+                    hstrk_formatted: amounts.format(&hstrk_total),
+                    hstrk_value: hstrk_value.to_string(),
+                    hstrk_value_formatted: amounts.format(&hstrk_value),
+                    succeeded: reads.len() - failed,
+                    failed,
+                },
+            };
+            println!("{}", audit::to_json(cli, config, block, &report, false)?);
+        } else {
+            let amounts = amounts.with_raw(cli.raw);
+            println!("{}", watch::title("Account Balances", watch).color(theme::PRIMARY));
+            println!();
+            
+            let headers = if cli.raw { ["Address", "STRK (wei)", "hSTRK (wei)"] } else { ["Address", "STRK", "hSTRK"] };
+            let mut table = table::new_table(&headers, &config.display);
+            for (target, read) in &reads {
+                match read {
+                    Ok((address, strk_balance, hstrk_balance)) => {
+                        let label = if normalize_address(*address) == *target {
+                            config.display.address(*address)
+                        } else {
+                            format!("{} ({})", target, config.display.address(*address))
+                        };
+                        table.add_row(vec![
+                            Cell::new(label),
+                            table::cell(amounts.format(strk_balance), theme::PRIMARY),
+                            table::cell(amounts.format(hstrk_balance), theme::PRIMARY),
+                        ]);
+                    }
+                    Err(e) => {
+                        table.add_row(vec![Cell::new(target), table::cell(format!("failed: {:#}", e), theme::ERROR), Cell::new("")]);
+                    }
+                }
+            }
+            println!("{}", table);
+            println!();
+            
+            println!("{}", "Totals:".color(theme::ACCENT));
+            let mut summary = table::metrics_table(&config.display);
+            summary.add_row(vec![Cell::new("STRK"), table::cell(amounts.format(&strk_total), theme::SUCCESS)]);
+            summary.add_row(vec![Cell::new("hSTRK"), table::cell(amounts.format(&hstrk_total), theme::SUCCESS)]);
+            summary.add_row(vec![Cell::new("hSTRK Value (STRK)"), table::cell(amounts.format(&hstrk_value), theme::SECONDARY)]);
+            summary.add_row(vec![Cell::new("Addresses"), table::cell(format!("{} read, {} failed", reads.len() - failed, failed), theme::MUTED)]);
+            println!("{}", summary);
+            if has_zero_exchange_rate(&total_assets, &total_shares) {
+                println!("{}", ZERO_RATE_WARNING.color(theme::WARNING));
+            }
+        }
+        
+        if failed == reads.len() {
+            return Err(anyhow::anyhow!("Could not read the balance of any address"));
+        }
+        Ok::<_, anyhow::Error>(())
+    }).await
+}
 
+async fn simulate(amount: String, days: Option<u32>, detailed: bool, compound: Compounding, cli: &Cli) -> Result<()> {
+    let period = days.unwrap_or(30);
+    println!("{}", format!("Simulating yield for {} days", period).color(theme::PRIMARY));
+    
+    let amount_wei = parse_amount_smart(&amount, RoundingMode::Down)?;
+    let principal = wei_to_decimal(&amount_wei)?;
+    
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(theme::spinner_style());
+    pb.set_message("Reading vault fees...");
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let amounts = AmountFormat::new(&config.display, cli.human);
+    let account = get_account(cli).await?;
+    let vault = VaultContract::with_address(&account, validate_address(&config.contracts.vault)?);
+    let vault_config = vault.get_vault_config().await?;
+    pb.finish_and_clear();
+    
+    // Simulated gross projection; fees are netted before compounding
+    let gross_apy = Decimal::new(1567, 4);
+    let net_apy = vault_config.net_apy(gross_apy);
+    let growth = projected_growth(calculate_daily_rate(net_apy), period, compound);
+    let (expected_yield, end_value) = principal.checked_mul(growth)
+        .and_then(|expected_yield| Some((expected_yield, principal.checked_add(expected_yield)?)))
+        .with_context(|| format!("Projected yield on {} STRK over {} days is too large to represent", principal, period))?;
+    
+    let percent = |ratio: Decimal| format_percentage(ratio * Decimal::ONE_HUNDRED);
+    
     println!();
     println!("{}", "Simulation Results:".color(theme::ACCENT));
-    println!("  Initial Amount: {}", format_amount(amount_wei.clone()).color(theme::PRIMARY));
-    println!("  Projected APY: {}", "15.67%".color(theme::SUCCESS));
-    println!("  Expected Yield: {}", "0.234 STRK".color(theme::SUCCESS));
+    println!("  Initial Amount: {}", amounts.format(&amount_wei).color(theme::PRIMARY));
+    println!("  Gross APY: {}", percent(gross_apy).color(theme::SECONDARY));
+    println!("  Net APY: {}", percent(net_apy).color(theme::SUCCESS));
+    println!("  Compounding: {}", compound.to_string().color(theme::INFO));
+    let places = config.display.decimal_places.min(18).into();
+    println!("  Expected Yield (net): {}", format!("{} STRK", expected_yield.round_dp(places)).color(theme::SUCCESS));
+    println!("  End Value: {}", format!("{} STRK", end_value.round_dp(places)).color(theme::PRIMARY));
     println!("  Risk Score: {}", "Low".color(theme::INFO));
-
+    
     if detailed {
+        let performance_drag = gross_apy * vault_config.performance_fee_bps.to_decimal_ratio();
+        let management_drag = vault_config.management_fee_bps.to_decimal_ratio();
+        
         println!();
         println!("{}", "Detailed Breakdown:".color(theme::ACCENT));
-        println!("  Base APY: {}", "12.50%".color(theme::SECONDARY));
-        println!("  Hedging Premium: {}", "2.17%".color(theme::SECONDARY));
-        println!("  Protocol Fees: {}", "-0.50%".color(theme::WARNING));
-        println!("  Net APY: {}", "15.67%".color(theme::SUCCESS));
+        println!("  Gross APY: {}", percent(gross_apy).color(theme::SECONDARY));
+        println!("  Performance Fee ({}): {}", vault_config.performance_fee_bps, format!("-{}", percent(performance_drag)).color(theme::WARNING));
+        println!("  Management Fee: {}", format!("-{}", percent(management_drag)).color(theme::WARNING));
+        println!("  Net APY: {}", percent(net_apy).color(theme::SUCCESS));
     }
-
+    
     Ok(())
 }
 
-async fn history(address: Option<String>, limit: Option<u32>, filter: Option<String>, cli: &Cli) -> Result<()> {
-    let tx_limit = limit.unwrap_or(10);
-    println!("{}", format!("Transaction History (last {} transactions)", tx_limit).color(theme::PRIMARY));
-
-    let _account = get_account(cli).await?;
-
-    let pb = ProgressBar::new_spinner();
+async fn history(address: Option<String>, limit: Option<u32>, filter: Option<String>, since: Option<u64>, until: Option<u64>, cli: &Cli) -> Result<()> {
+    let tx_limit = limit.unwrap_or(10) as usize;
+    let kinds = history_kinds(filter.as_deref())?;
+    let json = cli.output == OutputFormat::Json;
+    if !json {
+        println!("{}", format!("Transaction History (last {} transactions)", tx_limit).color(theme::PRIMARY));
+    }
+    
+    let config = Config::load(cli.config.as_deref())?;
+    let account = get_account(cli).await?;
+    let target = resolve_target_address(address, &account, &config)?;
+    let provider = account.provider();
+    
+    let pb = if json { ProgressBar::hidden() } else { ProgressBar::new_spinner() };
     pb.set_style(theme::spinner_style());
-    pb.set_message("Loading transaction history...");
-
-    // Simulated loading
-    std::thread::sleep(std::time::Duration::from_secs(1));
-
+    pb.set_message(format!("Loading transaction history for {}...", normalize_address(target)));
+    
+    let (from, to) = contract_utils::blocks_between(provider, since, until).await?;
+    let selectors = kinds
+        .iter()
+        .map(|kind| selector(history_event(*kind)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let event_filter = EventFilter {
+        from_block: Some(BlockId::Number(from)),
+        to_block: Some(BlockId::Number(to)),
+        address: Some(validate_address(&config.contracts.vault)?),
+        keys: Some(vec![selectors, vec![target]]),
+    };
+    let emitted = events::fetch_events(provider, event_filter, 100).await
+        .context("Failed to fetch vault events")?;
+    
+    // Events arrive oldest first; show the most recent ones
+    let mut flows = emitted
+        .iter()
+        .filter_map(|event| decode_flow(event).transpose())
+        .collect::<Result<Vec<_>>>()?;
+    flows.reverse();
+    flows.truncate(tx_limit);
+    
+    let mut timestamps = std::collections::HashMap::new();
+    for block in flows.iter().filter_map(|flow| flow.block) {
+        if let std::collections::hash_map::Entry::Vacant(entry) = timestamps.entry(block) {
+            entry.insert(contract_utils::get_block_timestamp(provider, block).await?);
+        }
+    }
+    
     pb.finish_and_clear();
-
+    
+    let time_of = |flow: &VaultFlow| flow.block.and_then(|block| timestamps.get(&block).copied());
+    if json {
+        let amounts = AmountFormat::new(&config.display, cli.human);
+        let report = HistoryReport {
+            address: normalize_address(target),
+            from_block: from,
+            to_block: to,
+            transactions: flows.iter().map(|flow| HistoryEntry::new(flow, time_of(flow), &amounts)).collect(),
+        };
+        println!("{}", audit::to_json(cli, &config, Some(to), &report, true)?);
+        return Ok(());
+    }
+    
+    println!();
+    println!("  Blocks: {}", format!("{}-{}", from, to).color(theme::MUTED));
+    if let Some(filter_type) = filter {
+        println!("  Filter: {}", filter_type.color(theme::INFO));
+    }
     println!();
-    println!("{}", "Recent Transactions:".color(theme::ACCENT));
+    
+    if flows.is_empty() {
+        println!("{}", "No deposits or withdrawals found".color(theme::MUTED));
+        return Ok(());
+    }
+    
+    let (strk_header, hstrk_header) = if cli.raw { ("STRK (wei)", "hSTRK (wei)") } else { ("STRK", "hSTRK") };
+    let mut transactions = table::new_table(&["Time", "Type", strk_header, hstrk_header, "Transaction"], &config.display);
+    for flow in &flows {
+        let time = time_of(flow)
+            .map(format_timestamp)
+            .unwrap_or_else(|| "pending".to_string());
+        let (strk, hstrk) = if cli.raw {
+            (flow.assets_wei.to_string(), flow.shares_wei.to_string())
+        } else {
+            (flow.assets.round_dp(6).to_string(), flow.shares.round_dp(6).to_string())
+        };
+        transactions.add_row(vec![
+            Cell::new(time),
+            table::cell(flow.kind.label(), theme::INFO),
+            table::cell(strk, theme::PRIMARY),
+            table::cell(hstrk, theme::SECONDARY),
+            table::cell(format!("{:#x}", flow.transaction), theme::MUTED),
+        ]);
+    }
+    println!("{}", transactions);
+    
+    Ok(())
+}
+
+/// `user history --output json`; amounts are wei strings, each with its formatted value alongside
+#[derive(Debug, Serialize)]
+struct HistoryReport {
+    address: String,
+    from_block: u64,
+    to_block: u64,
+    transactions: Vec<HistoryEntry>,
+}
 
-    // Simulated transaction history
-    for i in 1..=tx_limit.min(5) {
-        let tx_type = if i % 2 == 0 { "Deposit" } else { "Withdraw" };
-        let amount = format!("{}.{:06}", 100 + i * 50, i * 123456);
-        let hash = format!("0x{:04x}...{:04x}", i * 1234, i * 5678);
+#[derive(Debug, Serialize)]
+struct HistoryEntry {
+    kind: &'static str,
+    block: Option<u64>,
+    timestamp: Option<u64>,
+    transaction: String,
+    strk: String,
+    strk_formatted: String,
+    hstrk: String,
+    hstrk_formatted: String,
+}
 
-        println!("  {} {} STRK - {}",
-                tx_type.color(theme::INFO),
-                amount.color(theme::PRIMARY),
-                hash.color(theme::MUTED));
+impl HistoryEntry {
+    fn new(flow: &VaultFlow, timestamp: Option<u64>, amounts: &AmountFormat) -> Self {
+        HistoryEntry {
+            kind: flow.kind.label(),
+            block: flow.block,
+            timestamp,
+            transaction: format!("{:#x}", flow.transaction),
+            strk: flow.assets_wei.to_string(),
+            strk_formatted: amounts.format(&flow.assets_wei),
+            hstrk: flow.shares_wei.to_string(),
+            hstrk_formatted: amounts.format(&flow.shares_wei),
+        }
     }
+}
 
-    if let Some(filter_type) = filter {
-        println!();
-        println!("Filter applied: {}", filter_type.color(theme::INFO));
+/// Which vault flows `user history --filter` selects
+fn history_kinds(filter: Option<&str>) -> Result<Vec<FlowKind>> {
+    match filter.map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+        None => Ok(vec![FlowKind::Deposit, FlowKind::Withdraw]),
+        Some("deposit") | Some("deposits") => Ok(vec![FlowKind::Deposit]),
+        Some("withdraw") | Some("withdrawal") | Some("withdrawals") => Ok(vec![FlowKind::Withdraw]),
+        Some(other) => Err(anyhow::anyhow!("Unknown history filter '{}' (expected deposit or withdraw)", other)),
     }
+}
 
-    Ok(())
+fn history_event(kind: FlowKind) -> &'static str {
+    match kind {
+        FlowKind::Deposit => "Deposited",
+        FlowKind::Withdraw => "Withdrawn",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::vault::deposit_calldata;
+    
+    #[test]
+    fn oracle_deviation_is_enforced_only_with_a_limit() {
+        assert!(check_oracle_deviation(Some(Bps(250)), Some(Bps(200))).unwrap_err().to_string().contains("2.50% away"));
+        assert!(check_oracle_deviation(Some(Bps(200)), Some(Bps(200))).is_ok());
+        assert!(check_oracle_deviation(Some(Bps(5_000)), None).is_ok());
+        assert!(check_oracle_deviation(None, None).is_ok());
+    }
+    
+    #[test]
+    fn transfer_token_accepts_symbols_and_addresses() {
+        assert_eq!(TransferToken::parse("STRK").unwrap(), TransferToken::Strk);
+        assert_eq!(TransferToken::parse(" hstrk ").unwrap(), TransferToken::Hstrk);
+        assert_eq!(TransferToken::parse("0x49d36").unwrap(), TransferToken::Address(FieldElement::from(0x49d36u32)));
+        assert!(TransferToken::parse("eth").is_err());
+    }
+    
+    #[test]
+    fn transfer_refuses_unrecoverable_recipients() {
+        let token = FieldElement::from(0x49d36u32);
+        assert!(check_transfer_recipient(FieldElement::ZERO, token).is_err());
+        assert!(check_transfer_recipient(token, token).unwrap_err().to_string().contains("token contract itself"));
+        assert!(check_transfer_recipient(FieldElement::from(0x123u32), token).is_ok());
+    }
+    
+    #[test]
+    fn address_list_skips_blank_lines_and_comments() {
+        let content = "# treasury wallets\n0x1234\n\n  treasury  \n# cold storage\n0xabcd\n";
+        assert_eq!(parse_address_list(content), vec!["0x1234", "treasury", "0xabcd"]);
+    }
+    
+    #[test]
+    fn history_filter_selects_flow_kinds() {
+        assert_eq!(history_kinds(None).unwrap(), vec![FlowKind::Deposit, FlowKind::Withdraw]);
+        assert_eq!(history_kinds(Some("Deposit")).unwrap(), vec![FlowKind::Deposit]);
+        assert_eq!(history_kinds(Some("withdrawals")).unwrap(), vec![FlowKind::Withdraw]);
+        assert!(history_kinds(Some("transfer")).is_err());
+    }
+    
+    #[test]
+    fn parse_deposit_csv_skips_header_and_comments() {
+        let rows = parse_deposit_csv("amount,recipient\n# treasury top-up\n1.5,0x123\n\n2,\n").unwrap();
+        
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 3);
+        assert_eq!(rows[0].amount, parse_amount("1.5", RoundingMode::Down).unwrap());
+        assert_eq!(rows[0].recipient.as_deref(), Some("0x123"));
+        assert_eq!(rows[1].line, 5);
+        assert_eq!(rows[1].recipient, None);
+    }
+    
+    #[test]
+    fn parse_deposit_csv_reports_line_numbers() {
+        let err = parse_deposit_csv("1,0x1\nlots,0x2\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("Line 2"));
+        
+        let err = parse_deposit_csv("1,not-an-address\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("Line 1"));
+        
+        assert!(parse_deposit_csv("0,0x1\n").is_err());
+        assert!(parse_deposit_csv("1,0x1,extra\n").is_err());
+    }
+    
+    #[test]
+    fn parse_deposit_json_accepts_strings_and_numbers() {
+        let rows = parse_deposit_json(r#"[{"amount": "10", "recipient": "0xabc"}, {"amount": 2.5}]"#).unwrap();
+        
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].amount, parse_amount("10", RoundingMode::Down).unwrap());
+        assert_eq!(rows[1].amount, parse_amount("2.5", RoundingMode::Down).unwrap());
+        assert_eq!(rows[1].recipient, None);
+        
+        assert!(parse_deposit_json(r#"[{"amount": true}]"#).is_err());
+        assert!(parse_deposit_json(r#"{"amount": "1"}"#).is_err());
+    }
+    
+    #[test]
+    fn explicit_recipient_gets_a_transfer_rather_than_other_calldata() {
+        let caller = FieldElement::from_hex_be("0x111").unwrap();
+        let amount = parse_amount("10", RoundingMode::Down).unwrap();
+        
+        // The vault takes only the amount, so the recipient never reaches the deposit itself
+        assert_eq!(deposit_calldata(&amount).unwrap().len(), 2);
+        assert_eq!(transfer_recipient(deposit_recipient(None, caller).unwrap(), caller), None);
+        assert_eq!(
+            transfer_recipient(deposit_recipient(Some("0x222"), caller).unwrap(), caller),
+            Some(FieldElement::from_hex_be("0x222").unwrap())
+        );
+        assert_eq!(transfer_recipient(deposit_recipient(Some("0x111"), caller).unwrap(), caller), None);
+    }
+    
+    #[test]
+    fn zero_oracle_price_is_unpriced_rather_than_a_zero_rate() {
+        let price = |cents: u32| PriceData { price: BigUint::from(cents) * 1_000_000u32, timestamp: 0, sources_count: 1, confidence: 100 };
+        
+        assert_eq!(FairRate::from_prices(&price(55), &price(0)).unwrap(), FairRate::Unpriced("STRK"));
+        assert_eq!(FairRate::from_prices(&price(0), &price(0)).unwrap(), FairRate::Unpriced("hSTRK"));
+        assert!(matches!(FairRate::from_prices(&price(55), &price(50)).unwrap(), FairRate::Priced(_)));
+    }
+    
+    #[test]
+    fn invalid_recipient_is_rejected() {
+        assert!(deposit_recipient(Some("not-an-address"), FieldElement::ONE).is_err());
+    }
 }
@@ -1,11 +1,25 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::fs;
 
+/// Config schema version written by this binary
+pub const CONFIG_SCHEMA_VERSION: u32 = 2;
+
+/// Private key earlier versions put in every new config
+///
+/// It is public in this repository's history, so configs still holding it are flagged.
+/// New configs start with no key at all.
+pub const LEAKED_DEFAULT_PRIVATE_KEY: &str = "0x3f9721e722755ce2f6d925fff04676805c8d4cdd8d1b3931753e917a85f4ce2";
+
 /// CLI configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Version of the config file layout, used to run migrations on load
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    
     /// Starknet RPC URL
     pub rpc_url: String,
     
@@ -15,7 +29,8 @@ pub struct Config {
     /// Private key (encrypted or plain)
     pub private_key: String,
     
-    /// Chain ID
+    /// Chain ID, as hex or a short string like `SN_MAIN`; empty means fetch it from the node
+    #[serde(default)]
     pub chain_id: String,
     
     /// Network name
@@ -29,6 +44,120 @@ pub struct Config {
     
     /// Display preferences
     pub display: DisplayConfig,
+    
+    /// Named addresses usable in place of hex addresses
+    #[serde(default)]
+    pub address_book: BTreeMap<String, String>,
+    
+    /// RPC transport settings
+    #[serde(default)]
+    pub rpc: RpcConfig,
+    
+    /// Limits for outbound HTTP that is not Starknet RPC
+    #[serde(default)]
+    pub http: HttpConfig,
+    
+    /// Confirmation required by each emergency command
+    #[serde(default)]
+    pub emergency: EmergencyConfig,
+    
+    /// Defaults for yes/no prompts
+    #[serde(default)]
+    pub prompts: PromptsConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcConfig {
+    /// Extra HTTP headers sent with every RPC request, e.g. API keys
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Seconds allowed to establish a connection
+    #[serde(default = "default_http_connect_timeout")]
+    pub connect_timeout: u64,
+    
+    /// Seconds allowed for a whole request, including reading the response
+    #[serde(default = "default_http_timeout")]
+    pub timeout: u64,
+    
+    /// Largest response body accepted, in bytes
+    #[serde(default = "default_http_max_response_bytes")]
+    pub max_response_bytes: u64,
+}
+
+/// How an action is confirmed, from weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmVia {
+    /// A yes/no prompt
+    #[default]
+    Prompt,
+    /// The prompt, then re-typing a one-time code printed to the terminal
+    Code,
+    /// The prompt, with `--confirm-token` matching the `DEFINITE_CONFIRM_TOKEN` environment variable
+    Token,
+}
+
+impl std::fmt::Display for ConfirmVia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfirmVia::Prompt => write!(f, "prompt"),
+            ConfirmVia::Code => write!(f, "code"),
+            ConfirmVia::Token => write!(f, "token"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencyConfig {
+    #[serde(default)]
+    pub pause: ConfirmVia,
+    
+    #[serde(default)]
+    pub resume: ConfirmVia,
+    
+    #[serde(default = "default_critical_confirmation")]
+    pub emergency_withdraw: ConfirmVia,
+    
+    #[serde(default = "default_critical_confirmation")]
+    pub close_positions: ConfirmVia,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptsConfig {
+    /// Answer selected when Enter is pressed at a non-critical prompt; unset keeps each prompt's own default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_yes: Option<bool>,
+    
+    /// What a routine yes/no prompt does when there is no terminal to ask on
+    #[serde(default)]
+    pub no_tty: NoTtyPolicy,
+}
+
+/// How routine yes/no prompts are answered without a TTY, as in CI or a pipe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NoTtyPolicy {
+    /// Fail and say to pass `--yes` or `--no`
+    #[default]
+    Error,
+    /// Answer yes, as `--yes` would
+    Yes,
+    /// Answer no, as `--no` would
+    No,
+}
+
+impl std::fmt::Display for NoTtyPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoTtyPolicy::Error => write!(f, "error"),
+            NoTtyPolicy::Yes => write!(f, "yes"),
+            NoTtyPolicy::No => write!(f, "no"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +185,98 @@ pub struct TransactionConfig {
     
     /// Number of confirmation blocks to wait
     pub confirmations: u32,
+    
+    /// Token used to pay transaction fees (ETH or STRK)
+    #[serde(default = "default_fee_token")]
+    pub fee_token: String,
+    
+    /// Hard ceiling on the fee of any single transaction, in fee token units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<String>,
+    
+    /// STRK amount above which a transaction needs `--confirm-large` or a re-typed confirmation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_transaction_amount: Option<String>,
+    
+    /// Largest gap, in bps, between the vault's deposit rate and the oracle's fair rate
+    /// before a deposit is refused; unset skips the check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_oracle_deviation_bps: Option<u32>,
+    
+    /// Buffer applied to the network gas price under `--auto-gas`
+    #[serde(default = "default_gas_price_multiplier")]
+    pub gas_price_multiplier: f64,
+    
+    /// Append every state-changing operation to `~/.definite/history.jsonl`
+    #[serde(default)]
+    pub log_operations: bool,
+}
+
+/// ETH token address, identical on mainnet and sepolia
+pub const ETH_TOKEN_ADDRESS: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+fn default_fee_token() -> String {
+    "ETH".to_string()
+}
+
+fn default_gas_price_multiplier() -> f64 {
+    1.5
+}
+
+fn default_address_chars() -> usize {
+    4
+}
+
+fn default_show_banner() -> bool {
+    true
+}
+
+fn default_critical_confirmation() -> ConfirmVia {
+    ConfirmVia::Code
+}
+
+fn default_http_connect_timeout() -> u64 {
+    10
+}
+
+fn default_http_timeout() -> u64 {
+    30
+}
+
+fn default_http_max_response_bytes() -> u64 {
+    1024 * 1024
+}
+
+/// Files written before `schema_version` existed are version 1
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// A migration step, returning a description of each change it made
+type Migration = fn(&mut toml::Table) -> Vec<String>;
+
+/// Upgrade steps keyed by the version they upgrade from
+const MIGRATIONS: &[(u32, Migration)] = &[
+    (1, migrate_v1_to_v2),
+];
+
+/// v2 added the address book and a configurable fee token
+fn migrate_v1_to_v2(table: &mut toml::Table) -> Vec<String> {
+    let mut changes = Vec::new();
+    
+    if !table.contains_key("address_book") {
+        table.insert("address_book".to_string(), toml::Value::Table(toml::Table::new()));
+        changes.push("added empty [address_book]".to_string());
+    }
+    
+    if let Some(toml::Value::Table(transaction)) = table.get_mut("transaction") {
+        if !transaction.contains_key("fee_token") {
+            transaction.insert("fee_token".to_string(), toml::Value::String(default_fee_token()));
+            changes.push(format!("set transaction.fee_token = \"{}\"", default_fee_token()));
+        }
+    }
+    
+    changes
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,23 +292,131 @@ pub struct DisplayConfig {
     
     /// Date format string
     pub date_format: String,
+    
+    /// How addresses are shown in tables and lists; JSON output always has them in full
+    #[serde(default)]
+    pub address_style: AddressStyle,
+    
+    /// Hex digits kept at each end of a `short` address
+    #[serde(default = "default_address_chars")]
+    pub address_chars: usize,
+    
+    /// Print the banner on stderr before each command's output
+    #[serde(default = "default_show_banner")]
+    pub show_banner: bool,
+}
+
+/// Full 64-digit addresses, or `0x04ca…4aa4` with both ends kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressStyle {
+    #[default]
+    Full,
+    Short,
+}
+
+impl std::fmt::Display for AddressStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressStyle::Full => write!(f, "full"),
+            AddressStyle::Short => write!(f, "short"),
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// An address as tables and lists show it under `address_style`
+    pub fn address(&self, address: starknet::core::types::FieldElement) -> String {
+        match self.address_style {
+            AddressStyle::Full => crate::utils::normalize_address(address),
+            AddressStyle::Short => crate::utils::shorten_address(address, self.address_chars),
+        }
+    }
+    
+    /// Like [`DisplayConfig::address`] for an address kept as text, which is shown as-is if it does not parse
+    pub fn address_text(&self, address: &str) -> String {
+        crate::utils::validate_address(address)
+            .map(|felt| self.address(felt))
+            .unwrap_or_else(|_| address.to_string())
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
+            schema_version: CONFIG_SCHEMA_VERSION,
             rpc_url: "https://starknet-sepolia.infura.io/v3/f96264cf853c424ab5678e8301ca0462".to_string(),
             account_address: "0x01f411b366890429179d868cfc5ae89cd22c595cdcd31859f54759c16a9cc20e".to_string(),
-            private_key: "0x3f9721e722755ce2f6d925fff04676805c8d4cdd8d1b3931753e917a85f4ce2".to_string(),
+            private_key: String::new(),
             chain_id: "0x534e5f5345504f4c4941".to_string(),
             network: "sepolia".to_string(),
             contracts: ContractAddresses::default(),
             transaction: TransactionConfig::default(),
             display: DisplayConfig::default(),
+            address_book: BTreeMap::new(),
+            rpc: RpcConfig::default(),
+            http: HttpConfig::default(),
+            emergency: EmergencyConfig::default(),
+            prompts: PromptsConfig::default(),
         }
     }
 }
 
+/// Make a file that holds the private key readable and writable by its owner only
+///
+/// Windows has no mode bits; there the file keeps the ACL of its directory.
+fn restrict_to_owner(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict permissions on {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Whether a contract address has been set; unset addresses are left empty or `0x0`
+pub fn is_configured(address: &str) -> bool {
+    crate::utils::validate_address(address)
+        .map(|felt| felt != starknet::core::types::FieldElement::ZERO)
+        .unwrap_or(false)
+}
+
+impl ContractAddresses {
+    /// Parse the address under `contracts.<name>`, failing with a hint when it has not been configured
+    pub fn require(&self, name: &str) -> Result<starknet::core::types::FieldElement> {
+        let (_, address) = self.entries()
+            .into_iter()
+            .find(|(key, _)| *key == name)
+            .with_context(|| format!("Unknown contract '{}'", name))?;
+        if !is_configured(address) {
+            return Err(anyhow::anyhow!(
+                "The {} contract is not configured (set contracts.{} with `definite config set`)",
+                name.replace('_', " "),
+                name
+            ));
+        }
+        
+        crate::utils::validate_address(address)
+    }
+    
+    /// Every contract address with its config key under `contracts.`
+    pub fn entries(&self) -> [(&'static str, &str); 8] {
+        [
+            ("vault", &self.vault),
+            ("hstrk_token", &self.hstrk_token),
+            ("strk_token", &self.strk_token),
+            ("price_oracle", &self.price_oracle),
+            ("risk_manager", &self.risk_manager),
+            ("perpetual_hedge", &self.perpetual_hedge),
+            ("options_strategy", &self.options_strategy),
+            ("rebalancing_engine", &self.rebalancing_engine),
+        ]
+    }
+}
+
 impl Default for ContractAddresses {
     fn default() -> Self {
         ContractAddresses {
@@ -110,6 +439,33 @@ impl Default for TransactionConfig {
             max_fee_per_gas: "1000000000".to_string(), // 1 gwei
             timeout: 300, // 5 minutes
             confirmations: 1,
+            fee_token: default_fee_token(),
+            max_fee: None,
+            max_transaction_amount: None,
+            max_oracle_deviation_bps: None,
+            gas_price_multiplier: default_gas_price_multiplier(),
+            log_operations: false,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            connect_timeout: default_http_connect_timeout(),
+            timeout: default_http_timeout(),
+            max_response_bytes: default_http_max_response_bytes(),
+        }
+    }
+}
+
+impl Default for EmergencyConfig {
+    fn default() -> Self {
+        EmergencyConfig {
+            pause: ConfirmVia::Prompt,
+            resume: ConfirmVia::Prompt,
+            emergency_withdraw: default_critical_confirmation(),
+            close_positions: default_critical_confirmation(),
         }
     }
 }
@@ -121,6 +477,9 @@ impl Default for DisplayConfig {
             use_colors: true,
             verbose: false,
             date_format: "%Y-%m-%d %H:%M:%S UTC".to_string(),
+            address_style: AddressStyle::Full,
+            address_chars: default_address_chars(),
+            show_banner: default_show_banner(),
         }
     }
 }
@@ -134,8 +493,27 @@ impl Config {
             let content = fs::read_to_string(&path)
                 .context("Failed to read config file")?;
             
-            toml::from_str(&content)
-                .context("Failed to parse config file")
+            let mut table: toml::Table = toml::from_str(&content)
+                .context("Failed to parse config file")?;
+            let changes = Self::migrate(&mut table)?;
+            
+            let config: Config = toml::Value::Table(table).try_into()
+                .context("Failed to parse config file")?;
+            
+            if !changes.is_empty() {
+                let backup = path.with_extension("toml.bak");
+                fs::copy(&path, &backup)
+                    .context("Failed to back up config file before migration")?;
+                restrict_to_owner(&backup)?;
+                config.save(Some(path.to_str().unwrap()))?;
+                
+                eprintln!("Migrated config {} to schema version {} (backup: {})", path.display(), CONFIG_SCHEMA_VERSION, backup.display());
+                for change in &changes {
+                    eprintln!("  - {}", change);
+                }
+            }
+            
+            Ok(config)
         } else {
             // Create default config
             let config = Config::default();
@@ -144,6 +522,38 @@ impl Config {
         }
     }
     
+    /// Upgrade a raw config table to the current schema, returning what changed
+    fn migrate(table: &mut toml::Table) -> Result<Vec<String>> {
+        let version = match table.get("schema_version") {
+            Some(value) => value.as_integer()
+                .and_then(|v| u32::try_from(v).ok())
+                .context("schema_version must be a positive integer")?,
+            None => legacy_schema_version(),
+        };
+        
+        if version > CONFIG_SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "Config schema version {} is newer than this CLI supports ({}). Upgrade definite-cli to use this config.",
+                version,
+                CONFIG_SCHEMA_VERSION
+            ));
+        }
+        
+        let mut changes = Vec::new();
+        for (from, migration) in MIGRATIONS {
+            if *from >= version {
+                changes.extend(migration(table));
+            }
+        }
+        
+        if version < CONFIG_SCHEMA_VERSION {
+            table.insert("schema_version".to_string(), toml::Value::Integer(CONFIG_SCHEMA_VERSION.into()));
+            changes.push(format!("schema_version {} -> {}", version, CONFIG_SCHEMA_VERSION));
+        }
+        
+        Ok(changes)
+    }
+    
     /// Save configuration to file
     pub fn save(&self, config_path: Option<&str>) -> Result<()> {
         let path = Self::get_config_path(config_path)?;
@@ -157,14 +567,72 @@ impl Config {
         let content = toml::to_string_pretty(self)
             .context("Failed to serialize config")?;
         
-        fs::write(&path, content)
+        // The file holds the private key, so only the owner may read it
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)
+            .context("Failed to write config file")?;
+        // `mode` only applies to new files; tighten an existing one before the key goes in
+        restrict_to_owner(&path)?;
+        std::io::Write::write_all(&mut file, content.as_bytes())
             .context("Failed to write config file")?;
         
         Ok(())
     }
     
+    /// Copy the config file aside before a risky rewrite, returning the backup path
+    pub fn backup(config_path: Option<&str>) -> Result<PathBuf> {
+        let path = Self::get_config_path(config_path)?;
+        let stamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
+        let backup = path.with_extension(format!("toml.{}.bak", stamp));
+        
+        fs::copy(&path, &backup)
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+        restrict_to_owner(&backup)?;
+        
+        Ok(backup)
+    }
+    
+    /// Load the config for a named network
+    ///
+    /// `config.<network>.toml` next to the main config is used when it exists;
+    /// otherwise the main config is used if its `network` matches.
+    pub fn load_network(config_path: Option<&str>, network: &str) -> Result<Self> {
+        let path = Self::network_config_path(config_path, network)?;
+        if path.exists() {
+            return Self::load(path.to_str());
+        }
+        
+        let main = Self::get_config_path(config_path)?;
+        if main.exists() {
+            let config = Self::load(main.to_str())?;
+            if config.network == network {
+                return Ok(config);
+            }
+        }
+        
+        Err(anyhow::anyhow!(
+            "No config for network '{}': create {} (e.g. `definite --config {} config init`)",
+            network,
+            path.display(),
+            path.display()
+        ))
+    }
+    
+    /// Path of the per-network config, a sibling of the main config
+    fn network_config_path(config_path: Option<&str>, network: &str) -> Result<PathBuf> {
+        if network.is_empty() || !network.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+            return Err(anyhow::anyhow!("Invalid network name '{}'", network));
+        }
+        
+        let main = Self::get_config_path(config_path)?;
+        Ok(main.with_file_name(format!("config.{}.toml", network)))
+    }
+    
     /// Get configuration file path
-    fn get_config_path(config_path: Option<&str>) -> Result<PathBuf> {
+    pub fn get_config_path(config_path: Option<&str>) -> Result<PathBuf> {
         if let Some(path) = config_path {
             Ok(PathBuf::from(path))
         } else {
@@ -195,16 +663,7 @@ impl Config {
             .context("Invalid account address")?;
         
         // Validate contract addresses if not zero
-        for (name, address) in [
-            ("vault", &self.contracts.vault),
-            ("hstrk_token", &self.contracts.hstrk_token),
-            ("strk_token", &self.contracts.strk_token),
-            ("price_oracle", &self.contracts.price_oracle),
-            ("risk_manager", &self.contracts.risk_manager),
-            ("perpetual_hedge", &self.contracts.perpetual_hedge),
-            ("options_strategy", &self.contracts.options_strategy),
-            ("rebalancing_engine", &self.contracts.rebalancing_engine),
-        ] {
+        for (name, address) in self.contracts.entries() {
             if address != "0x0" && !address.is_empty() {
                 crate::utils::validate_address(address)
                     .with_context(|| format!("Invalid {} contract address", name))?;
@@ -218,23 +677,51 @@ impl Config {
     pub fn set_value(&mut self, key: &str, value: &str) -> Result<()> {
         match key {
             "rpc_url" => self.rpc_url = value.to_string(),
-            "account_address" => self.account_address = value.to_string(),
+            "account_address" => {
+                self.account_address = crate::utils::canonical_address(value)
+                    .context("Invalid account address")?;
+            }
             "private_key" => self.private_key = value.to_string(),
-            "chain_id" => self.chain_id = value.to_string(),
+            "chain_id" => {
+                let chain_id = if value == "auto" { "" } else { value.trim() };
+                if !chain_id.is_empty() {
+                    crate::utils::parse_chain_id(chain_id).context("Invalid chain ID")?;
+                }
+                self.chain_id = chain_id.to_string();
+            }
             "network" => self.network = value.to_string(),
-            "contracts.vault" => self.contracts.vault = value.to_string(),
-            "contracts.hstrk_token" => self.contracts.hstrk_token = value.to_string(),
-            "contracts.strk_token" => self.contracts.strk_token = value.to_string(),
-            "contracts.price_oracle" => self.contracts.price_oracle = value.to_string(),
-            "contracts.risk_manager" => self.contracts.risk_manager = value.to_string(),
-            "contracts.perpetual_hedge" => self.contracts.perpetual_hedge = value.to_string(),
-            "contracts.options_strategy" => self.contracts.options_strategy = value.to_string(),
-            "contracts.rebalancing_engine" => self.contracts.rebalancing_engine = value.to_string(),
+            "contracts.vault" | "contracts.hstrk_token" | "contracts.strk_token" | "contracts.price_oracle"
+            | "contracts.risk_manager" | "contracts.perpetual_hedge" | "contracts.options_strategy" | "contracts.rebalancing_engine" => {
+                let address = crate::utils::canonical_address(value)
+                    .with_context(|| format!("Invalid address for {}", key))?;
+                match key {
+                    "contracts.vault" => self.contracts.vault = address,
+                    "contracts.hstrk_token" => self.contracts.hstrk_token = address,
+                    "contracts.strk_token" => self.contracts.strk_token = address,
+                    "contracts.price_oracle" => self.contracts.price_oracle = address,
+                    "contracts.risk_manager" => self.contracts.risk_manager = address,
+                    "contracts.perpetual_hedge" => self.contracts.perpetual_hedge = address,
+                    "contracts.options_strategy" => self.contracts.options_strategy = address,
+                    _ => self.contracts.rebalancing_engine = address,
+                }
+            }
             "transaction.gas_limit" => {
                 self.transaction.gas_limit = value.parse()
                     .context("Invalid gas limit value")?;
             }
-            "transaction.max_fee_per_gas" => self.transaction.max_fee_per_gas = value.to_string(),
+            "transaction.max_fee_per_gas" => {
+                value.parse::<u128>()
+                    .context("Invalid max fee per gas (expected an integer amount in wei or fri)")?;
+                self.transaction.max_fee_per_gas = value.to_string();
+            }
+            "transaction.gas_price_multiplier" => {
+                let multiplier: f64 = value.parse()
+                    .context("Invalid gas price multiplier")?;
+                if !multiplier.is_finite() || multiplier < 1.0 {
+                    return Err(anyhow::anyhow!("Gas price multiplier must be at least 1.0"));
+                }
+                self.transaction.gas_price_multiplier = multiplier;
+            }
             "transaction.timeout" => {
                 self.transaction.timeout = value.parse()
                     .context("Invalid timeout value")?;
@@ -243,9 +730,39 @@ impl Config {
                 self.transaction.confirmations = value.parse()
                     .context("Invalid confirmations value")?;
             }
+            "transaction.fee_token" => {
+                let token = value.to_uppercase();
+                if token != "ETH" && token != "STRK" {
+                    return Err(anyhow::anyhow!("Fee token must be ETH or STRK"));
+                }
+                self.transaction.fee_token = token;
+            }
+            "transaction.max_fee" => {
+                crate::transaction::parse_max_fee(value)?;
+                self.transaction.max_fee = Some(value.to_string());
+            }
+            "transaction.max_transaction_amount" => {
+                crate::utils::parse_amount_smart(value, crate::utils::RoundingMode::Down)
+                    .context("Invalid max transaction amount")?;
+                self.transaction.max_transaction_amount = Some(value.to_string());
+            }
+            "transaction.max_oracle_deviation_bps" => {
+                self.transaction.max_oracle_deviation_bps = match value.trim() {
+                    "" | "off" => None,
+                    bps => Some(bps.parse().context("Invalid max oracle deviation (expected basis points, or off)")?),
+                };
+            }
+            "transaction.log_operations" => {
+                self.transaction.log_operations = value.parse()
+                    .context("Invalid log_operations value")?;
+            }
             "display.decimal_places" => {
-                self.display.decimal_places = value.parse()
+                let places: u8 = value.parse()
                     .context("Invalid decimal places value")?;
+                if places > 18 {
+                    return Err(anyhow::anyhow!("display.decimal_places must be at most 18, the token precision"));
+                }
+                self.display.decimal_places = places;
             }
             "display.use_colors" => {
                 self.display.use_colors = value.parse()
@@ -256,7 +773,68 @@ impl Config {
                     .context("Invalid verbose value")?;
             }
             "display.date_format" => self.display.date_format = value.to_string(),
-            _ => return Err(anyhow::anyhow!("Unknown configuration key: {}", key)),
+            "display.show_banner" => {
+                self.display.show_banner = value.parse()
+                    .context("Invalid show_banner value")?;
+            }
+            "display.address_style" => {
+                self.display.address_style = <AddressStyle as clap::ValueEnum>::from_str(value, true)
+                    .map_err(|_| anyhow::anyhow!("Invalid display.address_style '{}' (expected full or short)", value))?;
+            }
+            "display.address_chars" => {
+                let chars: usize = value.parse()
+                    .context("Invalid address_chars value")?;
+                if !(1..=31).contains(&chars) {
+                    return Err(anyhow::anyhow!("display.address_chars must be between 1 and 31"));
+                }
+                self.display.address_chars = chars;
+            }
+            "http.connect_timeout" | "http.timeout" | "http.max_response_bytes" => {
+                let limit: u64 = value.parse()
+                    .with_context(|| format!("Invalid {} value", key))?;
+                if limit == 0 {
+                    return Err(anyhow::anyhow!("{} must be greater than zero", key));
+                }
+                match key {
+                    "http.connect_timeout" => self.http.connect_timeout = limit,
+                    "http.timeout" => self.http.timeout = limit,
+                    _ => self.http.max_response_bytes = limit,
+                }
+            }
+            "prompts.default_yes" => {
+                self.prompts.default_yes = match value {
+                    "unset" => None,
+                    _ => Some(value.parse().context("Invalid default_yes value (expected true, false or unset)")?),
+                };
+            }
+            "prompts.no_tty" => {
+                self.prompts.no_tty = <NoTtyPolicy as clap::ValueEnum>::from_str(value, true)
+                    .map_err(|_| anyhow::anyhow!("Invalid prompts.no_tty value '{}' (expected error, yes or no)", value))?;
+            }
+            "emergency.pause" | "emergency.resume" | "emergency.emergency_withdraw" | "emergency.close_positions" => {
+                let via = <ConfirmVia as clap::ValueEnum>::from_str(value, true)
+                    .map_err(|_| anyhow::anyhow!("Invalid {} value '{}' (expected prompt, code or token)", key, value))?;
+                match key {
+                    "emergency.pause" => self.emergency.pause = via,
+                    "emergency.resume" => self.emergency.resume = via,
+                    "emergency.emergency_withdraw" => self.emergency.emergency_withdraw = via,
+                    _ => self.emergency.close_positions = via,
+                }
+            }
+            _ if key.starts_with("rpc.headers.") => {
+                let name = &key["rpc.headers.".len()..];
+                if name.is_empty() || !name.bytes().all(|b| b.is_ascii_graphic() && b != b':') {
+                    return Err(anyhow::anyhow!("Invalid RPC header name '{}'", name));
+                }
+                self.rpc.headers.insert(name.to_string(), value.to_string());
+            }
+            _ => {
+                let name = key.strip_prefix("address_book.")
+                    .ok_or_else(|| anyhow::anyhow!("Unknown configuration key: {}", key))?;
+                let address = crate::utils::canonical_address(value)
+                    .with_context(|| format!("Invalid address for address book entry '{}'", name))?;
+                self.address_book.insert(name.to_string(), address);
+            }
         }
         
         Ok(())
@@ -280,15 +858,245 @@ impl Config {
             "contracts.rebalancing_engine" => &self.contracts.rebalancing_engine,
             "transaction.gas_limit" => return Ok(self.transaction.gas_limit.to_string()),
             "transaction.max_fee_per_gas" => &self.transaction.max_fee_per_gas,
+            "transaction.gas_price_multiplier" => return Ok(self.transaction.gas_price_multiplier.to_string()),
             "transaction.timeout" => return Ok(self.transaction.timeout.to_string()),
             "transaction.confirmations" => return Ok(self.transaction.confirmations.to_string()),
+            "transaction.fee_token" => &self.transaction.fee_token,
+            "transaction.max_fee" => self.transaction.max_fee.as_deref().unwrap_or("unset"),
+            "transaction.max_transaction_amount" => self.transaction.max_transaction_amount.as_deref().unwrap_or("unset"),
+            "transaction.max_oracle_deviation_bps" => return Ok(self.transaction.max_oracle_deviation_bps.map_or("off".to_string(), |bps| bps.to_string())),
+            "transaction.log_operations" => return Ok(self.transaction.log_operations.to_string()),
             "display.decimal_places" => return Ok(self.display.decimal_places.to_string()),
             "display.use_colors" => return Ok(self.display.use_colors.to_string()),
             "display.verbose" => return Ok(self.display.verbose.to_string()),
             "display.date_format" => &self.display.date_format,
-            _ => return Err(anyhow::anyhow!("Unknown configuration key: {}", key)),
+            "display.address_style" => return Ok(self.display.address_style.to_string()),
+            "display.address_chars" => return Ok(self.display.address_chars.to_string()),
+            "display.show_banner" => return Ok(self.display.show_banner.to_string()),
+            "http.connect_timeout" => return Ok(self.http.connect_timeout.to_string()),
+            "http.timeout" => return Ok(self.http.timeout.to_string()),
+            "http.max_response_bytes" => return Ok(self.http.max_response_bytes.to_string()),
+            "prompts.default_yes" => return Ok(self.prompts.default_yes.map_or("unset".to_string(), |yes| yes.to_string())),
+            "prompts.no_tty" => return Ok(self.prompts.no_tty.to_string()),
+            "emergency.pause" => return Ok(self.emergency.pause.to_string()),
+            "emergency.resume" => return Ok(self.emergency.resume.to_string()),
+            "emergency.emergency_withdraw" => return Ok(self.emergency.emergency_withdraw.to_string()),
+            "emergency.close_positions" => return Ok(self.emergency.close_positions.to_string()),
+            _ if key.starts_with("rpc.headers.") => {
+                let name = &key["rpc.headers.".len()..];
+                self.rpc.headers.get(name)
+                    .with_context(|| format!("No RPC header named '{}'", name))?;
+                "***HIDDEN***" // Header values usually carry API keys
+            }
+            _ => {
+                let name = key.strip_prefix("address_book.")
+                    .ok_or_else(|| anyhow::anyhow!("Unknown configuration key: {}", key))?;
+                self.address_book.get(name)
+                    .with_context(|| format!("No address book entry named '{}'", name))?
+            }
         };
         
         Ok(value.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const V1_CONFIG: &str = r#"
+rpc_url = "http://localhost:5050"
+account_address = "0x1"
+private_key = "0x2"
+chain_id = "0x534e5f5345504f4c4941"
+network = "devnet"
+
+[contracts]
+vault = "0x0"
+hstrk_token = "0x0"
+strk_token = "0x0"
+price_oracle = "0x0"
+risk_manager = "0x0"
+perpetual_hedge = "0x0"
+options_strategy = "0x0"
+rebalancing_engine = "0x0"
+
+[transaction]
+gas_limit = 1000000
+max_fee_per_gas = "1000000000"
+timeout = 300
+confirmations = 1
+
+[display]
+decimal_places = 6
+use_colors = true
+verbose = false
+date_format = "%Y-%m-%d %H:%M:%S UTC"
+"#;
+    
+    fn temp_config(content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("definite-config-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(&path, content).unwrap();
+        path
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn saved_config_is_private_and_has_no_default_key() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = std::env::temp_dir().join(format!("definite-config-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.toml");
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        
+        // A missing config is created on load
+        let created = Config::load(path.to_str()).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert!(created.private_key.is_empty());
+        assert!(!fs::read_to_string(&path).unwrap().contains(LEAKED_DEFAULT_PRIVATE_KEY));
+        
+        // An existing world-readable file is tightened when saved over
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        created.save(path.to_str()).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(&Config::backup(path.to_str()).unwrap()), 0o600);
+        
+        fs::remove_dir_all(dir).unwrap();
+    }
+    
+    #[test]
+    fn loads_v1_config_and_rewrites_it() {
+        let path = temp_config(V1_CONFIG);
+        
+        let config = Config::load(path.to_str()).unwrap();
+        assert_eq!(config.schema_version, CONFIG_SCHEMA_VERSION);
+        assert_eq!(config.transaction.fee_token, "ETH");
+        assert!(config.address_book.is_empty());
+        assert_eq!(config.network, "devnet");
+        
+        let rewritten: toml::Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["schema_version"].as_integer(), Some(CONFIG_SCHEMA_VERSION as i64));
+        assert_eq!(fs::read_to_string(path.with_extension("toml.bak")).unwrap(), V1_CONFIG);
+        
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+    
+    #[test]
+    fn current_config_is_not_rewritten() {
+        let content = toml::to_string_pretty(&Config::default()).unwrap();
+        let path = temp_config(&content);
+        
+        Config::load(path.to_str()).unwrap();
+        assert!(!path.with_extension("toml.bak").exists());
+        
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+    
+    #[test]
+    fn refuses_newer_schema() {
+        let content = format!("schema_version = {}\n{}", CONFIG_SCHEMA_VERSION + 1, V1_CONFIG);
+        let path = temp_config(&content);
+        
+        let err = Config::load(path.to_str()).unwrap_err();
+        assert!(err.to_string().contains("newer than this CLI supports"));
+        
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+    
+    #[test]
+    fn unset_contract_addresses_are_not_configured() {
+        assert!(!is_configured("0x0"));
+        assert!(!is_configured(""));
+        assert!(!is_configured("0x000"));
+        assert!(is_configured(&Config::default().contracts.vault));
+        
+        let contracts = ContractAddresses { risk_manager: "0x0".to_string(), ..ContractAddresses::default() };
+        let err = contracts.require("risk_manager").unwrap_err();
+        assert!(err.to_string().contains("contracts.risk_manager"));
+        assert_eq!(contracts.require("vault").unwrap(), crate::utils::validate_address(&contracts.vault).unwrap());
+        assert!(contracts.require("treasury").is_err());
+    }
+    
+    #[test]
+    fn rpc_headers_are_set_and_hidden() {
+        let mut config = Config::default();
+        config.set_value("rpc.headers.x-api-key", "secret").unwrap();
+        
+        assert_eq!(config.rpc.headers["x-api-key"], "secret");
+        assert_eq!(config.get_value("rpc.headers.x-api-key").unwrap(), "***HIDDEN***");
+        assert!(config.get_value("rpc.headers.authorization").is_err());
+        assert!(config.set_value("rpc.headers.bad name", "value").is_err());
+        
+        let round_trip: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(round_trip.rpc.headers, config.rpc.headers);
+    }
+    
+    #[test]
+    fn network_configs_sit_next_to_the_main_config() {
+        let path = temp_config(V1_CONFIG);
+        let main = path.to_str();
+        
+        // The main config is used when its own network matches
+        assert_eq!(Config::load_network(main, "devnet").unwrap().network, "devnet");
+        assert!(Config::load_network(main, "mainnet").is_err());
+        assert!(Config::load_network(main, "../mainnet").is_err());
+        
+        let staging = V1_CONFIG.replace("network = \"devnet\"", "network = \"staging\"");
+        fs::write(path.with_file_name("config.staging.toml"), staging).unwrap();
+        assert_eq!(Config::load_network(main, "staging").unwrap().network, "staging");
+    }
+    
+    #[test]
+    fn addresses_are_stored_in_canonical_form() {
+        let mut config = Config::default();
+        config.set_value("address_book.short", "0x49D36").unwrap();
+        config.set_value("address_book.padded", "0x0000049d36").unwrap();
+        config.set_value("contracts.vault", "49d36").unwrap();
+        
+        assert_eq!(config.address_book["short"], config.address_book["padded"]);
+        assert_eq!(config.contracts.vault, config.address_book["short"]);
+        assert_eq!(config.contracts.vault.len(), 66);
+        assert!(config.set_value("contracts.vault", "not-hex").is_err());
+    }
+    
+    #[test]
+    fn http_limits_default_and_reject_zero() {
+        let mut config: Config = toml::from_str(V1_CONFIG).unwrap();
+        assert_eq!(config.http.timeout, 30);
+        assert_eq!(config.http.max_response_bytes, 1024 * 1024);
+        
+        config.set_value("http.timeout", "5").unwrap();
+        assert_eq!(config.get_value("http.timeout").unwrap(), "5");
+        assert!(config.set_value("http.connect_timeout", "0").is_err());
+        assert!(config.set_value("http.max_response_bytes", "lots").is_err());
+    }
+    
+    #[test]
+    fn address_style_defaults_to_full_and_can_be_shortened() {
+        let mut config: Config = toml::from_str(V1_CONFIG).unwrap();
+        let address = starknet::core::types::FieldElement::from(0x49d36u32);
+        assert_eq!(config.display.address_style, AddressStyle::Full);
+        assert_eq!(config.display.address(address).len(), 66);
+        
+        config.set_value("display.address_style", "SHORT").unwrap();
+        config.set_value("display.address_chars", "6").unwrap();
+        assert_eq!(config.get_value("display.address_style").unwrap(), "short");
+        assert_eq!(config.display.address(address), "0x000000…049d36");
+        
+        assert!(config.set_value("display.address_style", "tiny").is_err());
+        assert!(config.set_value("display.address_chars", "0").is_err());
+        assert!(config.set_value("display.address_chars", "32").is_err());
+    }
+    
+    #[test]
+    fn no_tty_policy_defaults_to_error() {
+        let mut config: Config = toml::from_str(V1_CONFIG).unwrap();
+        assert_eq!(config.prompts.no_tty, NoTtyPolicy::Error);
+        
+        config.set_value("prompts.no_tty", "No").unwrap();
+        assert_eq!(config.get_value("prompts.no_tty").unwrap(), "no");
+        assert!(config.set_value("prompts.no_tty", "maybe").is_err());
+    }
+}
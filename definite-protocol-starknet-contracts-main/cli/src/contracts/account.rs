@@ -0,0 +1,116 @@
+use anyhow::Result;
+use starknet::core::types::FieldElement;
+use starknet::core::utils::cairo_short_string_to_felt;
+
+use super::{Contract, ContractReader};
+
+/// Getters account contracts expose for their signing key: OpenZeppelin
+/// (`get_public_key`, `getPublicKey`) and Argent (`get_owner`, `getSigner`)
+const PUBLIC_KEY_GETTERS: &[&str] = &["get_public_key", "getPublicKey", "get_owner", "getSigner"];
+
+/// SNIP-6 signature check, then the camelCase name older accounts expose
+const SIGNATURE_CHECKS: &[&str] = &["is_valid_signature", "isValidSignature"];
+
+/// Starknet account contract interface
+pub struct AccountContract<A> {
+    address: FieldElement,
+    account: A,
+}
+
+impl<A> AccountContract<A> {
+    pub fn with_address(account: A, address: FieldElement) -> AccountContract<A> {
+        AccountContract { address, account }
+    }
+}
+
+impl<A: ContractReader> AccountContract<A> {
+    /// Public key the account contract checks signatures against
+    pub async fn public_key(&self) -> Result<FieldElement> {
+        let mut last_error = None;
+        
+        for getter in PUBLIC_KEY_GETTERS {
+            match self.account.call_contract(self.address, getter, vec![]).await {
+                Ok(result) => match result.first() {
+                    Some(key) if *key != FieldElement::ZERO => return Ok(*key),
+                    _ => last_error = Some(anyhow::anyhow!("{} returned no public key", getter)),
+                },
+                Err(e) => last_error = Some(e),
+            }
+        }
+        
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("No getter to read"))
+            .context("Could not read the account's public key"))
+    }
+    
+    /// Ask the account contract whether `signature` is its signature over `hash`
+    ///
+    /// SNIP-6 accounts answer `'VALID'`; older accounts answer `1`. Any other answer
+    /// means invalid. An error means neither entry point could be called.
+    pub async fn is_valid_signature(&self, hash: FieldElement, signature: &[FieldElement]) -> Result<bool> {
+        let valid = cairo_short_string_to_felt("VALID").expect("VALID is a valid short string");
+        let mut calldata = vec![hash, FieldElement::from(signature.len())];
+        calldata.extend_from_slice(signature);
+        
+        let mut last_error = None;
+        for check in SIGNATURE_CHECKS {
+            match self.account.call_contract(self.address, check, calldata.clone()).await {
+                Ok(result) => return Ok(matches!(result.first(), Some(answer) if *answer == valid || *answer == FieldElement::ONE)),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("No signature check to call"))
+            .context("The account does not expose is_valid_signature"))
+    }
+}
+
+impl<A> Contract for AccountContract<A> {
+    fn address(&self) -> FieldElement {
+        self.address
+    }
+    
+    fn name(&self) -> &str {
+        "Account"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+    
+    #[tokio::test]
+    async fn public_key_falls_back_to_argent_owner() {
+        let reader = FixtureReader::new().with("get_owner", &[0xabc]);
+        let account = AccountContract::with_address(reader, FieldElement::ONE);
+        
+        assert_eq!(account.public_key().await.unwrap(), FieldElement::from(0xabcu32));
+    }
+    
+    #[tokio::test]
+    async fn public_key_errors_when_no_getter_answers() {
+        let account = AccountContract::with_address(FixtureReader::new(), FieldElement::ONE);
+        
+        assert!(account.public_key().await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn signature_checks_accept_valid_and_legacy_one() {
+        let valid = FixtureReader::new().with("is_valid_signature", &[0x56414c4944]);
+        let account = AccountContract::with_address(valid, FieldElement::ONE);
+        assert!(account.is_valid_signature(FieldElement::TWO, &[FieldElement::ONE, FieldElement::TWO]).await.unwrap());
+        
+        let legacy = FixtureReader::new().with("isValidSignature", &[1]);
+        let account = AccountContract::with_address(legacy, FieldElement::ONE);
+        assert!(account.is_valid_signature(FieldElement::TWO, &[]).await.unwrap());
+        
+        let rejected = FixtureReader::new().with("is_valid_signature", &[0]);
+        let account = AccountContract::with_address(rejected, FieldElement::ONE);
+        assert!(!account.is_valid_signature(FieldElement::TWO, &[]).await.unwrap());
+        
+        let account = AccountContract::with_address(FixtureReader::new(), FieldElement::ONE);
+        assert!(account.is_valid_signature(FieldElement::TWO, &[]).await.is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! View calls bundled into one JSON-RPC batch request
+//!
+//! A status report makes many small, independent view calls. Sent one by one, each
+//! costs a full round trip to the node. [`RpcReader`] posts them as a single JSON-RPC
+//! batch instead, and falls back to concurrent single requests when the node does
+//! not accept batches.
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use starknet::core::types::FieldElement;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::{selectors, ContractReader, ViewCall};
+use crate::config::Config;
+use crate::http::{http_client, read_body};
+
+/// Reads over raw JSON-RPC to the configured node, pinned to one block or the latest
+pub struct RpcReader {
+    client: reqwest::Client,
+    url: url::Url,
+    headers: Vec<(String, String)>,
+    max_response_bytes: u64,
+    block: Value,
+    /// Cleared once the node has rejected a batch, so later batches skip the attempt
+    batching: AtomicBool,
+    round_trips: AtomicUsize,
+}
+
+impl RpcReader {
+    pub fn new(config: &Config, block_number: Option<u64>) -> Result<RpcReader> {
+        Ok(RpcReader {
+            client: http_client(&config.http)?,
+            url: url::Url::parse(&config.rpc_url).context("Invalid RPC URL")?,
+            headers: config.rpc.headers.iter().map(|(name, value)| (name.clone(), value.clone())).collect(),
+            max_response_bytes: config.http.max_response_bytes,
+            block: block_number.map_or(json!("latest"), |number| json!({ "block_number": number })),
+            batching: AtomicBool::new(true),
+            round_trips: AtomicUsize::new(0),
+        })
+    }
+    
+    /// HTTP requests made so far, batched or not
+    pub fn round_trips(&self) -> usize {
+        self.round_trips.load(Ordering::SeqCst)
+    }
+    
+    fn request(&self, id: usize, call: &ViewCall) -> Result<Value> {
+        Ok(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "starknet_call",
+            "params": {
+                "request": {
+                    "contract_address": format!("{:#x}", call.contract_address),
+                    "entry_point_selector": format!("{:#x}", selectors::selector(call.function)?),
+                    "calldata": call.calldata.iter().map(|felt| format!("{:#x}", felt)).collect::<Vec<_>>(),
+                },
+                "block_id": self.block,
+            },
+        }))
+    }
+    
+    async fn post(&self, body: &Value) -> Result<Value> {
+        self.round_trips.fetch_add(1, Ordering::SeqCst);
+        let response = self.headers
+            .iter()
+            .fold(self.client.post(self.url.clone()), |request, (name, value)| request.header(name, value))
+            .json(body)
+            .send()
+            .await
+            .context("RPC request failed")?
+            .error_for_status()
+            .context("RPC request failed")?;
+        
+        let body = read_body(response, self.max_response_bytes).await?;
+        serde_json::from_slice(&body).context("Invalid JSON-RPC response")
+    }
+    
+    /// Send `calls` as one batch, or `None` when the node answered with something other than a batch reply
+    async fn try_batch(&self, calls: &[ViewCall]) -> Result<Option<Vec<Result<Vec<FieldElement>>>>> {
+        let requests = calls.iter().enumerate().map(|(id, call)| self.request(id, call)).collect::<Result<Vec<_>>>()?;
+        let reply = match self.post(&Value::Array(requests)).await {
+            Ok(reply) => reply,
+            // A node without batch support may refuse the request outright
+            Err(e) if is_refused(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        
+        Ok(split_batch_reply(&reply, calls))
+    }
+}
+
+impl ContractReader for RpcReader {
+    fn call_contract<'a>(
+        &'a self,
+        contract_address: FieldElement,
+        function: &'a str,
+        calldata: Vec<FieldElement>,
+    ) -> BoxFuture<'a, Result<Vec<FieldElement>>> {
+        Box::pin(async move {
+            let call = ViewCall { contract_address, function, calldata };
+            let reply = self.post(&self.request(0, &call)?).await?;
+            call_result(&reply, function)
+        })
+    }
+    
+    fn batch<'a>(&'a self, calls: Vec<ViewCall<'a>>) -> BoxFuture<'a, Vec<Result<Vec<FieldElement>>>> {
+        Box::pin(async move {
+            if calls.len() > 1 && self.batching.load(Ordering::SeqCst) {
+                match self.try_batch(&calls).await {
+                    Ok(Some(results)) => return results,
+                    Ok(None) => {
+                        tracing::info!(endpoint = %self.url, "node does not accept JSON-RPC batches; sending view calls one by one");
+                        self.batching.store(false, Ordering::SeqCst);
+                    }
+                    Err(e) => {
+                        let message = format!("{:#}", e);
+                        return calls.iter().map(|_| Err(anyhow::anyhow!("{}", message))).collect();
+                    }
+                }
+            }
+            
+            concurrent_calls(self, calls).await
+        })
+    }
+}
+
+/// Run `calls` as concurrent single requests, answering in order
+pub async fn concurrent_calls<'a, R: ContractReader + ?Sized>(reader: &'a R, calls: Vec<ViewCall<'a>>) -> Vec<Result<Vec<FieldElement>>> {
+    futures::future::join_all(calls.into_iter().map(|call| {
+        reader.call_contract(call.contract_address, call.function, call.calldata)
+    })).await
+}
+
+/// Whether the node answered with a 4xx status; a 5xx may be transient and says nothing about batch support
+fn is_refused(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.downcast_ref::<reqwest::Error>()
+            .and_then(reqwest::Error::status)
+            .is_some_and(|status| status.is_client_error())
+    })
+}
+
+/// The result of a single `starknet_call` reply
+fn call_result(reply: &Value, function: &str) -> Result<Vec<FieldElement>> {
+    if let Some(error) = reply.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+        let data = error.get("data").map(|data| format!(": {}", data)).unwrap_or_default();
+        return Err(anyhow::anyhow!("{} call failed: {}{}", function, message, data));
+    }
+    
+    reply.get("result")
+        .and_then(Value::as_array)
+        .with_context(|| format!("{} reply has neither a result nor an error", function))?
+        .iter()
+        .map(|felt| {
+            felt.as_str()
+                .and_then(|hex| FieldElement::from_hex_be(hex).ok())
+                .with_context(|| format!("{} returned a malformed felt {}", function, felt))
+        })
+        .collect()
+}
+
+/// Match a batch reply to its calls by ID, or `None` if it is not a complete batch reply
+///
+/// Nodes may answer a batch in any order. A node without batch support answers with a
+/// single error object instead of an array.
+fn split_batch_reply(reply: &Value, calls: &[ViewCall]) -> Option<Vec<Result<Vec<FieldElement>>>> {
+    let replies = reply.as_array()?;
+    if replies.len() != calls.len() {
+        return None;
+    }
+    
+    let mut by_id: Vec<Option<&Value>> = vec![None; calls.len()];
+    for reply in replies {
+        let id = reply.get("id")?.as_u64()? as usize;
+        *by_id.get_mut(id)? = Some(reply);
+    }
+    
+    by_id.into_iter()
+        .zip(calls)
+        .map(|(reply, call)| reply.map(|reply| call_result(reply, call.function)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+    
+    fn calls() -> Vec<ViewCall<'static>> {
+        vec![
+            ViewCall { contract_address: FieldElement::ONE, function: "total_assets", calldata: vec![] },
+            ViewCall { contract_address: FieldElement::ONE, function: "total_shares", calldata: vec![] },
+        ]
+    }
+    
+    #[test]
+    fn batch_replies_are_matched_by_id() {
+        let reply = json!([
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": 40, "message": "Contract error" } },
+            { "jsonrpc": "2.0", "id": 0, "result": ["0x5", "0x0"] },
+        ]);
+        let results = split_batch_reply(&reply, &calls()).unwrap();
+        
+        assert_eq!(results[0].as_ref().unwrap(), &vec![FieldElement::from(5u32), FieldElement::ZERO]);
+        assert!(format!("{:#}", results[1].as_ref().unwrap_err()).contains("total_shares call failed: Contract error"));
+    }
+    
+    #[test]
+    fn replies_that_are_not_a_full_batch_mean_no_batch_support() {
+        let single = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32600, "message": "Invalid request" } });
+        assert!(split_batch_reply(&single, &calls()).is_none());
+        
+        let short = json!([{ "jsonrpc": "2.0", "id": 0, "result": [] }]);
+        assert!(split_batch_reply(&short, &calls()).is_none());
+        
+        let duplicate = json!([{ "id": 0, "result": [] }, { "id": 0, "result": [] }]);
+        assert!(split_batch_reply(&duplicate, &calls()).is_none());
+    }
+    
+    #[tokio::test]
+    async fn default_batch_answers_each_call_in_order() {
+        let reader = FixtureReader::new().with("total_assets", &[7, 0]);
+        let results = reader.batch(calls()).await;
+        
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), &vec![FieldElement::from(7u32), FieldElement::ZERO]);
+        assert!(results[1].is_err());
+        assert_eq!(reader.call_count(), 2);
+    }
+}
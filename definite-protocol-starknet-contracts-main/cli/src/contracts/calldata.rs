@@ -0,0 +1,154 @@
+//! Typed encoding of call arguments into felts, in Cairo serialization order
+
+use anyhow::Result;
+use num_bigint::BigUint;
+use starknet::core::types::FieldElement;
+
+use super::utils;
+
+/// Bytes packed into each full word of a Cairo `ByteArray`
+const BYTES_PER_WORD: usize = 31;
+
+/// Builds calldata one typed argument at a time
+///
+/// Arguments are appended in the order they are pushed, which must match the
+/// order of the Cairo function's parameters (or a struct's members).
+#[derive(Debug, Clone, Default)]
+pub struct CalldataBuilder {
+    felts: Vec<FieldElement>,
+}
+
+impl CalldataBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// A raw `felt252`
+    pub fn push_felt(mut self, felt: FieldElement) -> Self {
+        self.felts.push(felt);
+        self
+    }
+    
+    /// A `u256` as its (low, high) pair of 128-bit limbs
+    pub fn push_u256(mut self, value: &BigUint) -> Result<Self> {
+        self.felts.extend(utils::encode_u256(value)?);
+        Ok(self)
+    }
+    
+    /// A `bool` as 0 or 1
+    pub fn push_bool(self, value: bool) -> Self {
+        self.push_felt(if value { FieldElement::ONE } else { FieldElement::ZERO })
+    }
+    
+    /// A `ContractAddress`, which is serialized as a single felt
+    pub fn push_address(self, address: FieldElement) -> Self {
+        self.push_felt(address)
+    }
+    
+    /// A `ByteArray`: the count of full 31-byte words, the words, then the
+    /// pending word and its length in bytes
+    pub fn push_bytearray(mut self, text: &str) -> Result<Self> {
+        let chunks: Vec<&[u8]> = text.as_bytes().chunks(BYTES_PER_WORD).collect();
+        let (full, pending) = match chunks.split_last() {
+            Some((last, full)) if last.len() < BYTES_PER_WORD => (full, *last),
+            _ => (&chunks[..], &[][..]),
+        };
+        
+        self.felts.push(FieldElement::from(full.len() as u64));
+        for word in full {
+            self.felts.push(bytes_to_felt(word)?);
+        }
+        self.felts.push(bytes_to_felt(pending)?);
+        self.felts.push(FieldElement::from(pending.len() as u64));
+        Ok(self)
+    }
+    
+    /// An `Array<T>` or `Span<T>`: the length, then each item as encoded by `push`
+    pub fn push_array<T>(mut self, items: &[T], push: impl Fn(Self, &T) -> Result<Self>) -> Result<Self> {
+        self.felts.push(FieldElement::from(items.len() as u64));
+        for item in items {
+            self = push(self, item)?;
+        }
+        Ok(self)
+    }
+    
+    pub fn build(self) -> Vec<FieldElement> {
+        self.felts
+    }
+}
+
+/// Big-endian bytes (at most 31) as a felt
+fn bytes_to_felt(bytes: &[u8]) -> Result<FieldElement> {
+    let mut padded = [0u8; 32];
+    padded[32 - bytes.len()..].copy_from_slice(bytes);
+    Ok(FieldElement::from_bytes_be(&padded)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn felts(values: &[u64]) -> Vec<FieldElement> {
+        values.iter().map(|v| FieldElement::from(*v)).collect()
+    }
+    
+    #[test]
+    fn push_felt_address_and_bool_take_one_felt_each() {
+        let calldata = CalldataBuilder::new()
+            .push_felt(FieldElement::from(7u32))
+            .push_address(FieldElement::from(0x49d3u32))
+            .push_bool(true)
+            .push_bool(false)
+            .build();
+        
+        assert_eq!(calldata, felts(&[7, 0x49d3, 1, 0]));
+    }
+    
+    #[test]
+    fn push_u256_splits_low_and_high_limbs() {
+        let value = (BigUint::from(1u32) << 128) + 5u32;
+        let calldata = CalldataBuilder::new().push_u256(&value).unwrap().build();
+        
+        assert_eq!(calldata, felts(&[5, 1]));
+        assert!(CalldataBuilder::new().push_u256(&(BigUint::from(1u32) << 256)).is_err());
+    }
+    
+    #[test]
+    fn push_bytearray_packs_full_and_pending_words() {
+        let short = CalldataBuilder::new().push_bytearray("hello").unwrap().build();
+        assert_eq!(short, felts(&[0, 0x68656c6c6f, 5]));
+        
+        let empty = CalldataBuilder::new().push_bytearray("").unwrap().build();
+        assert_eq!(empty, felts(&[0, 0, 0]));
+        
+        let long = CalldataBuilder::new().push_bytearray("abcdefghijklmnopqrstuvwxyz12345678").unwrap().build();
+        assert_eq!(long, vec![
+            FieldElement::ONE,
+            FieldElement::from_hex_be("0x6162636465666768696a6b6c6d6e6f707172737475767778797a3132333435").unwrap(),
+            FieldElement::from(0x363738u32),
+            FieldElement::from(3u32),
+        ]);
+        
+        // An exact multiple of 31 bytes leaves an empty pending word
+        let exact = CalldataBuilder::new().push_bytearray(&"a".repeat(31)).unwrap().build();
+        assert_eq!(exact.len(), 4);
+        assert_eq!(exact[0], FieldElement::ONE);
+        assert_eq!(&exact[2..], &felts(&[0, 0])[..]);
+    }
+    
+    #[test]
+    fn push_array_prefixes_length() {
+        let amounts = [BigUint::from(3u32), BigUint::from(1u32) << 128];
+        let calldata = CalldataBuilder::new()
+            .push_array(&amounts, |builder, amount| builder.push_u256(amount))
+            .unwrap()
+            .push_bool(true)
+            .build();
+        
+        assert_eq!(calldata, felts(&[2, 3, 0, 0, 1, 1]));
+        
+        let none: [FieldElement; 0] = [];
+        let empty = CalldataBuilder::new().push_array(&none, |builder, felt| Ok(builder.push_felt(*felt))).unwrap().build();
+        assert_eq!(empty, felts(&[0]));
+    }
+}
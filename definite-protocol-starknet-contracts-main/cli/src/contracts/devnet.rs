@@ -0,0 +1,113 @@
+//! Tests that send transactions to a local devnet (e.g. `starknet-devnet --seed 0`)
+//!
+//! They are skipped unless `DEFINITE_DEVNET_RPC` is set. A devnet run also needs a
+//! funded predeployed account and the protocol deployed on it:
+//!
+//! ```text
+//! DEFINITE_DEVNET_RPC=http://127.0.0.1:5050/rpc
+//! DEFINITE_DEVNET_ACCOUNT=0x...       # predeployed account address
+//! DEFINITE_DEVNET_PRIVATE_KEY=0x...   # its private key
+//! DEFINITE_DEVNET_VAULT=0x...         # ProtocolVault deployed on the devnet
+//! DEFINITE_DEVNET_STRK=0x...          # the STRK token the vault was set up with
+//! DEFINITE_DEVNET_HSTRK=0x...         # the hSTRK token the vault mints
+//! ```
+//!
+//! The vault has no getters for its tokens, so their addresses are passed in too.
+
+use anyhow::{Result, Context};
+use num_bigint::BigUint;
+use starknet::accounts::{Account, ConnectedAccount, ExecutionEncoding, SingleOwnerAccount};
+use starknet::core::types::FieldElement;
+use starknet::providers::jsonrpc::{HttpTransport, JsonRpcClient};
+use starknet::providers::Provider;
+use starknet::signers::{LocalWallet, SigningKey};
+
+use super::Contract;
+use super::token::TokenContract;
+use super::utils::wait_for_transaction;
+use super::vault::VaultContract;
+
+struct Devnet {
+    account: SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>,
+    vault: FieldElement,
+    strk: FieldElement,
+    hstrk: FieldElement,
+}
+
+impl Devnet {
+    /// Connect to the configured devnet, or `None` when no devnet is configured
+    async fn connect() -> Result<Option<Devnet>> {
+        let Ok(rpc_url) = std::env::var("DEFINITE_DEVNET_RPC") else {
+            return Ok(None);
+        };
+        let felt = |name: &str| -> Result<FieldElement> {
+            let value = std::env::var(name).with_context(|| format!("{} must be set when DEFINITE_DEVNET_RPC is", name))?;
+            FieldElement::from_hex_be(&value).with_context(|| format!("{} is not a valid felt", name))
+        };
+        
+        let provider = JsonRpcClient::new(HttpTransport::new(url::Url::parse(&rpc_url).context("Invalid DEFINITE_DEVNET_RPC")?));
+        let chain_id = provider.chain_id().await.context("Devnet is not reachable")?;
+        let signer = LocalWallet::from(SigningKey::from_secret_scalar(felt("DEFINITE_DEVNET_PRIVATE_KEY")?));
+        let account = SingleOwnerAccount::new(provider, signer, felt("DEFINITE_DEVNET_ACCOUNT")?, chain_id, ExecutionEncoding::New);
+        
+        Ok(Some(Devnet {
+            account,
+            vault: felt("DEFINITE_DEVNET_VAULT")?,
+            strk: felt("DEFINITE_DEVNET_STRK")?,
+            hstrk: felt("DEFINITE_DEVNET_HSTRK")?,
+        }))
+    }
+}
+
+/// Deposit more than `u64::MAX` wei and check the vault accounts for every wei of it
+///
+/// An amount truncated to 64 bits, or sent as one felt where the contract reads a
+/// `u256`, either reverts or leaves the totals short, so this covers the encoding of
+/// the approval and deposit as well as the decoding of the balances read back.
+#[tokio::test]
+async fn deposit_above_u64_round_trips_through_u256() -> Result<()> {
+    let Some(devnet) = Devnet::connect().await? else {
+        eprintln!("skipping devnet round trip: DEFINITE_DEVNET_RPC is not set");
+        return Ok(());
+    };
+    let account = &devnet.account;
+    let vault = VaultContract::with_address(account, devnet.vault);
+    let strk = TokenContract::with_address(account, devnet.strk);
+    let hstrk = TokenContract::with_address(account, devnet.hstrk);
+    
+    // About 18.4 STRK: realistic, but needs more than 64 bits
+    let amount = (BigUint::from(1u32) << 64) + 123_456_789u32;
+    
+    let (assets_before, shares_before, hstrk_before) = tokio::try_join!(
+        vault.total_assets(),
+        vault.total_shares(),
+        hstrk.balance_of(account.address()),
+    )?;
+    
+    let deposit = vault.deposit_call(amount.clone())?;
+    assert_eq!(deposit.calldata.len(), 2, "deposit(amount: u256) takes exactly two felts");
+    let calls = vec![strk.approve_call(vault.address(), amount.clone())?, deposit];
+    let tx_hash = account.execute(calls).send().await
+        .context("Devnet rejected the approve and deposit multicall")?
+        .transaction_hash;
+    assert!(wait_for_transaction(account.provider(), tx_hash, 30).await?, "deposit {:#x} was not confirmed", tx_hash);
+    
+    let (assets_after, shares_after, hstrk_after) = tokio::try_join!(
+        vault.total_assets(),
+        vault.total_shares(),
+        hstrk.balance_of(account.address()),
+    )?;
+    
+    // The vault mints 1:1 into an empty vault and rounds down otherwise
+    let expected_shares = if shares_before == BigUint::from(0u32) {
+        amount.clone()
+    } else {
+        &amount * &shares_before / &assets_before
+    };
+    
+    assert_eq!(assets_after - assets_before, amount);
+    assert_eq!(&shares_after - &shares_before, expected_shares);
+    assert_eq!(hstrk_after - hstrk_before, shares_after - shares_before);
+    
+    Ok(())
+}
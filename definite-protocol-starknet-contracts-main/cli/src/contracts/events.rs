@@ -0,0 +1,197 @@
+use anyhow::{Result, Context};
+use num_bigint::BigUint;
+use rust_decimal::Decimal;
+use starknet::core::types::{EmittedEvent, EventFilter, FieldElement};
+use starknet::core::utils::get_selector_from_name;
+use starknet::providers::Provider;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::contracts::utils::decode_u256;
+use crate::utils::wei_to_decimal;
+
+/// Events emitted by the protocol contracts
+const PROTOCOL_EVENTS: &[&str] = &[
+    // ProtocolVault
+    "Deposited",
+    "Withdrawn",
+    "YieldReported",
+    "ManagementFeeCollected",
+    "PerformanceFeeCollected",
+    "HedgeRebalancingTriggered",
+    "VaultPauseStateChanged",
+    "ConfigUpdated",
+    "ContractAddressUpdated",
+    "StrategyUpdated",
+    // hSTRK / ERC20
+    "Transfer",
+    "Approval",
+    "Minted",
+    "Burned",
+    "Paused",
+    "Unpaused",
+    "ProtocolVaultUpdated",
+    // Hedging
+    "HedgeExecuted",
+    "DeltaUpdated",
+    "DeltaExposureUpdated",
+    "PositionOpened",
+    "PositionClosed",
+    "MarginAdded",
+    "FundingCollected",
+    "PositionHealthWarning",
+    "OptionPositionOpened",
+    "OptionPositionClosed",
+    "OptionDeltaHedged",
+    "GreeksUpdated",
+    "ExpiryManaged",
+    "VolatilitySelling",
+    "StrategyExecuted",
+    "StrategyParametersUpdated",
+    // Rebalancing
+    "RebalancingExecuted",
+    "RebalancingPauseStateChanged",
+    "RebalancingCheck",
+    "RebalancingNeeded",
+    "RebalancingParamsUpdated",
+    "KeeperRewardPaid",
+    "KeeperUpdated",
+    // Risk and oracle
+    "RiskScoreUpdated",
+    "RiskLevelChanged",
+    "RiskThresholdsUpdated",
+    "AutomatedRiskResponse",
+    "CircuitBreakerTriggered",
+    "CircuitBreakerDeactivated",
+    "CircuitBreakerParamsUpdated",
+    "EmergencyModeActivated",
+    "PriceUpdated",
+    "EmergencyPriceUpdate",
+    "PriceSourceFailed",
+    "TrustedSourceUpdated",
+    "AggregationModeUpdated",
+    "MaxStalenessUpdated",
+    "UpdateThresholdUpdated",
+    "DEXAuthorizationUpdated",
+    "TokensSent",
+];
+
+/// Resolve an event's first key to the name of a known protocol event
+///
+/// The event names are hashed once into a reverse lookup on first use.
+pub fn event_name(key: FieldElement) -> Option<&'static str> {
+    static BY_SELECTOR: OnceLock<HashMap<FieldElement, &'static str>> = OnceLock::new();
+    BY_SELECTOR
+        .get_or_init(|| {
+            PROTOCOL_EVENTS
+                .iter()
+                .filter_map(|name| Some((get_selector_from_name(name).ok()?, *name)))
+                .collect()
+        })
+        .get(&key)
+        .copied()
+}
+
+/// Fetch every event matching `filter`, following continuation tokens page by page
+pub async fn fetch_events<P: Provider>(provider: &P, filter: EventFilter, chunk_size: u64) -> Result<Vec<EmittedEvent>> {
+    let mut events = Vec::new();
+    for_each_page(provider, filter, chunk_size, |page| {
+        events.extend(page);
+        Ok(())
+    }).await?;
+    Ok(events)
+}
+
+/// Hand each page of events matching `filter` to `on_page` as it arrives
+///
+/// Unlike [`fetch_events`], only one page is held at a time, so long histories can be
+/// processed in bounded memory. An error from `on_page` stops paging.
+pub async fn for_each_page<P, F>(provider: &P, filter: EventFilter, chunk_size: u64, mut on_page: F) -> Result<()>
+where
+    P: Provider,
+    F: FnMut(Vec<EmittedEvent>) -> Result<()>,
+{
+    let mut continuation_token = None;
+    
+    loop {
+        let page = provider.get_events(filter.clone(), continuation_token, chunk_size).await?;
+        on_page(page.events)?;
+        
+        match page.continuation_token {
+            Some(token) => continuation_token = Some(token),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Which side of the vault a flow event is on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlowKind {
+    Deposit,
+    Withdraw,
+}
+
+impl FlowKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            FlowKind::Deposit => "Deposit",
+            FlowKind::Withdraw => "Withdraw",
+        }
+    }
+}
+
+/// A deposit or withdrawal decoded from a vault event, in STRK and hSTRK
+#[derive(Debug, Clone, PartialEq)]
+pub struct VaultFlow {
+    pub block: Option<u64>,
+    pub transaction: FieldElement,
+    pub kind: FlowKind,
+    pub assets: Decimal,
+    pub shares: Decimal,
+    /// `assets` and `shares` as the exact wei the event carried
+    pub assets_wei: BigUint,
+    pub shares_wei: BigUint,
+}
+
+/// Decode a `Deposited` or `Withdrawn` event; any other event is skipped
+///
+/// Both events carry `assets: u256, shares: u256` first. For withdrawals
+/// `assets` is the STRK paid out after the exit fee.
+pub fn decode_flow(event: &EmittedEvent) -> Result<Option<VaultFlow>> {
+    let kind = match event.keys.first().copied().and_then(event_name) {
+        Some("Deposited") => FlowKind::Deposit,
+        Some("Withdrawn") => FlowKind::Withdraw,
+        _ => return Ok(None),
+    };
+    
+    let context = || format!("Malformed {} event in {:#x}", kind.label(), event.transaction_hash);
+    let assets_wei = decode_u256(&event.data, 0).with_context(context)?;
+    let shares_wei = decode_u256(&event.data, 2).with_context(context)?;
+    
+    Ok(Some(VaultFlow {
+        block: event.block_number,
+        transaction: event.transaction_hash,
+        kind,
+        assets: wei_to_decimal(&assets_wei)?,
+        shares: wei_to_decimal(&shares_wei)?,
+        assets_wei,
+        shares_wei,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn resolves_known_event_selectors() {
+        let key = get_selector_from_name("Deposited").unwrap();
+        assert_eq!(event_name(key), Some("Deposited"));
+    }
+    
+    #[test]
+    fn unknown_selector_is_none() {
+        let key = get_selector_from_name("NotAProtocolEvent").unwrap();
+        assert_eq!(event_name(key), None);
+    }
+}
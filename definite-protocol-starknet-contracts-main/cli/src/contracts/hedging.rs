@@ -1,16 +1,17 @@
 use anyhow::Result;
 use starknet::core::types::FieldElement;
-use starknet::accounts::Account;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 
-use super::Contract;
+use super::{Contract, ContractReader, utils};
 
 /// Hedging contracts interface
-pub struct HedgingContract<A: Account> {
+pub struct HedgingContract<A> {
     address: FieldElement,
     account: A,
 }
 
-impl<A: Account> HedgingContract<A> {
+impl<A> HedgingContract<A> {
     pub async fn new(account: &A) -> Result<HedgingContract<A>> 
     where
         A: Clone,
@@ -22,9 +23,38 @@ impl<A: Account> HedgingContract<A> {
             account: account.clone(),
         })
     }
+    
+    pub fn with_address(account: A, address: FieldElement) -> HedgingContract<A> {
+        HedgingContract { address, account }
+    }
 }
 
-impl<A: Account> Contract for HedgingContract<A> {
+/// Views read for the perpetual hedge's delta: its unsigned magnitude, then its sign flag
+pub const PERPETUAL_DELTA_VIEWS: [&str; 2] = ["get_delta_exposure", "is_net_short"];
+
+/// Views read for the options book's delta: its unsigned magnitude, then its sign flag
+pub const PORTFOLIO_DELTA_VIEWS: [&str; 2] = ["get_portfolio_delta", "is_delta_negative"];
+
+impl<A: ContractReader> HedgingContract<A> {
+    /// Get the net delta of the perpetual hedge (`get_delta_exposure` / `is_net_short`)
+    pub async fn perpetual_delta(&self) -> Result<DeltaExposure> {
+        self.delta(PERPETUAL_DELTA_VIEWS).await
+    }
+    
+    /// Get the net delta of the options book (`get_portfolio_delta` / `is_delta_negative`)
+    pub async fn portfolio_delta(&self) -> Result<DeltaExposure> {
+        self.delta(PORTFOLIO_DELTA_VIEWS).await
+    }
+    
+    async fn delta(&self, views: [&str; 2]) -> Result<DeltaExposure> {
+        let magnitude = self.account.call_contract(self.address, views[0], vec![]).await?;
+        let sign = self.account.call_contract(self.address, views[1], vec![]).await?;
+        
+        DeltaExposure::decode(views, &magnitude, &sign)
+    }
+}
+
+impl<A> Contract for HedgingContract<A> {
     fn address(&self) -> FieldElement {
         self.address
     }
@@ -33,3 +63,31 @@ impl<A: Account> Contract for HedgingContract<A> {
         "HedgingStrategy"
     }
 }
+
+/// Signed delta exposure, stored on-chain as an unsigned magnitude plus a sign flag
+#[derive(Debug, Clone)]
+pub struct DeltaExposure {
+    pub magnitude: BigUint,
+    pub negative: bool,
+}
+
+impl DeltaExposure {
+    /// Decode the responses of a delta's two `views`, in the order they are listed
+    pub fn decode(views: [&str; 2], magnitude: &[FieldElement], sign: &[FieldElement]) -> Result<DeltaExposure> {
+        let (Some(magnitude), Some(sign)) = (magnitude.first(), sign.first()) else {
+            let empty = if magnitude.is_empty() { views[0] } else { views[1] };
+            return Err(anyhow::anyhow!("No return data from {} call", empty));
+        };
+        
+        Ok(DeltaExposure {
+            magnitude: utils::felt_to_bigint(*magnitude),
+            negative: *sign != FieldElement::ZERO,
+        })
+    }
+    
+    /// Delta in STRK units (18 decimals), negative when net short
+    pub fn as_f64(&self) -> f64 {
+        let value = self.magnitude.to_f64().unwrap_or(0.0) / 1e18;
+        if self.negative { -value } else { value }
+    }
+}
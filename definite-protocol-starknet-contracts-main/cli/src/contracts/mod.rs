@@ -4,11 +4,19 @@ pub mod oracle;
 pub mod risk;
 pub mod hedging;
 pub mod rebalancing;
+pub mod events;
+pub mod calldata;
+pub mod account;
+pub mod selectors;
+pub mod batch;
 
 use anyhow::Result;
-use starknet::core::types::FieldElement;
-use starknet::accounts::Account;
+use futures::future::BoxFuture;
+use starknet::core::types::{FieldElement, BlockId, BlockTag, FunctionCall};
+use starknet::accounts::ConnectedAccount;
+use starknet::providers::Provider;
 use num_bigint::BigUint;
+use serde::Serialize;
 
 /// Common contract interface
 pub trait Contract {
@@ -16,12 +24,113 @@ pub trait Contract {
     fn name(&self) -> &str;
 }
 
-/// Contract deployment configuration
+/// One view call, as handed to [`ContractReader::batch`]
 #[derive(Debug, Clone)]
+pub struct ViewCall<'a> {
+    pub contract_address: FieldElement,
+    pub function: &'a str,
+    pub calldata: Vec<FieldElement>,
+}
+
+impl<'a> ViewCall<'a> {
+    pub fn new(contract_address: FieldElement, function: &'a str) -> ViewCall<'a> {
+        ViewCall { contract_address, function, calldata: vec![] }
+    }
+}
+
+/// Read-only access to contract state
+///
+/// Every connected account implements this by forwarding to its provider.
+/// Contract wrappers only require this trait for view functions, so tests can
+/// substitute canned responses instead of talking to an RPC node.
+pub trait ContractReader {
+    fn call_contract<'a>(
+        &'a self,
+        contract_address: FieldElement,
+        function: &'a str,
+        calldata: Vec<FieldElement>,
+    ) -> BoxFuture<'a, Result<Vec<FieldElement>>>;
+    
+    /// Make independent view calls, answering each in the order given
+    ///
+    /// By default the calls run concurrently, one request each. A reader that can
+    /// bundle them into fewer round trips, such as [`batch::RpcReader`], overrides this.
+    fn batch<'a>(&'a self, calls: Vec<ViewCall<'a>>) -> BoxFuture<'a, Vec<Result<Vec<FieldElement>>>>
+    where
+        Self: Sync,
+    {
+        Box::pin(batch::concurrent_calls(self, calls))
+    }
+}
+
+impl<A: ConnectedAccount + Sync> ContractReader for A {
+    fn call_contract<'a>(
+        &'a self,
+        contract_address: FieldElement,
+        function: &'a str,
+        calldata: Vec<FieldElement>,
+    ) -> BoxFuture<'a, Result<Vec<FieldElement>>> {
+        Box::pin(async move {
+            let result = self.provider().call(
+                FunctionCall {
+                    contract_address,
+                    entry_point_selector: selectors::selector(function)?,
+                    calldata,
+                },
+                BlockId::Tag(BlockTag::Latest),
+            ).await?;
+            
+            Ok(result)
+        })
+    }
+}
+
+/// Reads pinned to one block, or to the latest block when none is given
+///
+/// Building every wrapper of a report over one `BlockReader` makes its reads a
+/// consistent snapshot even when blocks arrive between calls.
+pub struct BlockReader<'a, P> {
+    provider: &'a P,
+    block: BlockId,
+}
+
+impl<'a, P> BlockReader<'a, P> {
+    pub fn new(provider: &'a P, block_number: Option<u64>) -> Self {
+        let block = block_number.map_or(BlockId::Tag(BlockTag::Latest), BlockId::Number);
+        BlockReader { provider, block }
+    }
+}
+
+impl<P: Provider + Sync> ContractReader for BlockReader<'_, P> {
+    fn call_contract<'a>(
+        &'a self,
+        contract_address: FieldElement,
+        function: &'a str,
+        calldata: Vec<FieldElement>,
+    ) -> BoxFuture<'a, Result<Vec<FieldElement>>> {
+        Box::pin(async move {
+            let result = self.provider.call(
+                FunctionCall {
+                    contract_address,
+                    entry_point_selector: selectors::selector(function)?,
+                    calldata,
+                },
+                self.block,
+            ).await?;
+            
+            Ok(result)
+        })
+    }
+}
+
+/// Contract deployment configuration
+///
+/// Parameters are kept in a `BTreeMap` so serialized configs list them in a stable order.
+#[derive(Debug, Clone, Serialize)]
 pub struct DeploymentConfig {
     pub network: String,
     pub owner: FieldElement,
-    pub initial_params: std::collections::HashMap<String, String>,
+    pub initial_params: std::collections::BTreeMap<String, String>,
 }
 
 /// Contract call result
@@ -36,8 +145,8 @@ pub struct CallResult {
 /// Utility functions for contract interaction
 pub mod utils {
     use super::*;
-    use starknet::core::types::{FieldElement, BlockId, BlockTag};
-    use starknet::providers::Provider;
+    use starknet::core::types::{FieldElement, BlockId, BlockTag, MaybeUnknownErrorCode, StarknetError};
+    use starknet::providers::{Provider, ProviderError};
     
     /// Convert BigUint to FieldElement
     pub fn bigint_to_felt(value: &BigUint) -> Result<FieldElement> {
@@ -58,9 +167,66 @@ pub mod utils {
         BigUint::from_bytes_be(&felt.to_bytes_be())
     }
     
+    /// Decode a Cairo `u256` returned as a (low, high) felt pair starting at `offset`
+    pub fn decode_u256(data: &[FieldElement], offset: usize) -> Result<BigUint> {
+        match (data.get(offset), data.get(offset + 1)) {
+            (Some(low), Some(high)) => {
+                let (low, high) = (felt_to_bigint(*low), felt_to_bigint(*high));
+                if low.bits() > 128 || high.bits() > 128 {
+                    return Err(anyhow::anyhow!("Malformed u256 at offset {}: a limb exceeds 128 bits", offset));
+                }
+                Ok(low + (high << 128))
+            }
+            _ => Err(anyhow::anyhow!(
+                "Expected u256 at offset {} but response has {} felts",
+                offset,
+                data.len()
+            )),
+        }
+    }
+    
+    /// Decode the response of a view returning a single `u256`
+    ///
+    /// An empty or oddly sized response means the call or its decoding went wrong, so
+    /// it is an error rather than zero; only an explicit `(0, 0)` pair reads as zero.
+    pub fn decode_u256_result(data: &[FieldElement], function: &str) -> Result<BigUint> {
+        match data.len() {
+            2 => decode_u256(data, 0),
+            0 => Err(anyhow::anyhow!("No return data from {} call", function)),
+            n => Err(anyhow::anyhow!("Unexpected {} response: expected a u256 (2 felts), got {} felts", function, n)),
+        }
+    }
+    
+    /// Decode the response of a view returning a single felt-sized value
+    pub fn decode_felt_result(data: &[FieldElement], function: &str) -> Result<FieldElement> {
+        match data {
+            [value] => Ok(*value),
+            [] => Err(anyhow::anyhow!("No return data from {} call", function)),
+            _ => Err(anyhow::anyhow!("Unexpected {} response: expected 1 felt, got {} felts", function, data.len())),
+        }
+    }
+    
+    /// Decode the response of a view returning a Cairo `u8`..`u128` into the matching Rust integer
+    pub fn decode_uint_result<T: TryFrom<BigUint>>(data: &[FieldElement], function: &str) -> Result<T> {
+        let value = felt_to_bigint(decode_felt_result(data, function)?);
+        T::try_from(value.clone()).map_err(|_| {
+            anyhow::anyhow!("{} returned {}, which is out of range for {}", function, value, std::any::type_name::<T>())
+        })
+    }
+    
+    /// Encode a Cairo `u256` as the (low, high) felt pair it is passed as, the inverse of `decode_u256`
+    pub fn encode_u256(value: &BigUint) -> Result<[FieldElement; 2]> {
+        if value.bits() > 256 {
+            return Err(anyhow::anyhow!("Value too large for u256"));
+        }
+        
+        let low_mask = (BigUint::from(1u32) << 128) - 1u32;
+        Ok([bigint_to_felt(&(value & low_mask))?, bigint_to_felt(&(value >> 128))?])
+    }
+    
     /// Format contract address for display
     pub fn format_address(address: FieldElement) -> String {
-        format!("0x{:064x}", address)
+        crate::utils::normalize_address(address)
     }
     
     /// Parse address from string
@@ -81,6 +247,139 @@ pub mod utils {
         }
     }
     
+    /// Timestamp of a block
+    pub async fn get_block_timestamp<P: Provider>(provider: &P, block_number: u64) -> Result<u64> {
+        let block = provider.get_block_with_tx_hashes(BlockId::Number(block_number)).await?;
+        match block {
+            starknet::core::types::MaybePendingBlockWithTxHashes::Block(block) => Ok(block.timestamp),
+            starknet::core::types::MaybePendingBlockWithTxHashes::PendingBlock(block) => Ok(block.timestamp),
+        }
+    }
+
+    /// Resolve the last block produced at or before `timestamp` by binary search
+    ///
+    /// Returns block 0 when the timestamp predates the chain.
+    pub async fn block_at_timestamp<P: Provider>(provider: &P, timestamp: u64) -> Result<u64> {
+        let (mut low, mut high) = (0, get_current_block(provider).await?);
+
+        if get_block_timestamp(provider, high).await? <= timestamp {
+            return Ok(high);
+        }
+
+        // Invariant: block `high` is after the timestamp and `low` is not (or is genesis)
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if get_block_timestamp(provider, mid).await? <= timestamp {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Ok(low)
+    }
+
+    /// Resolve a `[since, until]` time window (unix seconds) to an inclusive block range
+    ///
+    /// An open `since` starts at genesis and an open `until` ends at the latest block.
+    /// Fails when no block was produced inside the window.
+    pub async fn blocks_between<P: Provider>(provider: &P, since: Option<u64>, until: Option<u64>) -> Result<(u64, u64)> {
+        if let (Some(since), Some(until)) = (since, until) {
+            if since > until {
+                return Err(anyhow::anyhow!("--since must not be later than --until"));
+            }
+        }
+        
+        let from = match since {
+            Some(since) if get_block_timestamp(provider, 0).await? < since => {
+                block_at_timestamp(provider, since - 1).await? + 1
+            }
+            _ => 0,
+        };
+        let to = match until {
+            Some(until) => block_at_timestamp(provider, until).await?,
+            None => get_current_block(provider).await?,
+        };
+        
+        let produced_in_window = from <= to
+            && match until {
+                Some(until) => get_block_timestamp(provider, to).await? <= until,
+                None => true,
+            };
+        if !produced_in_window {
+            let describe = |ts: Option<u64>, open: &str| ts.map(crate::utils::format_timestamp).unwrap_or_else(|| open.to_string());
+            return Err(anyhow::anyhow!(
+                "No blocks were produced between {} and {}",
+                describe(since, "genesis"),
+                describe(until, "now")
+            ));
+        }
+        
+        Ok((from, to))
+    }
+    
+    /// Whether the node reported that no contract is deployed at the address
+    pub fn is_contract_not_found(err: &ProviderError) -> bool {
+        matches!(
+            err,
+            ProviderError::StarknetError(e) if e.code == MaybeUnknownErrorCode::Known(StarknetError::ContractNotFound)
+        )
+    }
+    
+    /// Whether a contract is deployed at `address` as of `block_number`
+    pub async fn is_deployed_at<P: Provider>(provider: &P, address: FieldElement, block_number: u64) -> Result<bool> {
+        match provider.get_class_hash_at(BlockId::Number(block_number), address).await {
+            Ok(_) => Ok(true),
+            Err(err) if is_contract_not_found(&err) => Ok(false),
+            Err(err) => Err(anyhow::Error::from(err).context(format!("Failed to check for a contract at block {}", block_number))),
+        }
+    }
+    
+    /// First block in `[from, to]` at which `address` is deployed, found by binary search
+    ///
+    /// Fails when the contract is not deployed at `to` either.
+    pub async fn deployment_block<P: Provider>(provider: &P, address: FieldElement, from: u64, to: u64) -> Result<u64> {
+        if is_deployed_at(provider, address, from).await? {
+            return Ok(from);
+        }
+        if !is_deployed_at(provider, address, to).await? {
+            return Err(anyhow::anyhow!("No contract is deployed at {} as of block {}", format_address(address), to));
+        }
+        
+        // Invariant: the contract is missing at `low` and present at `high`
+        let (mut low, mut high) = (from, to);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if is_deployed_at(provider, address, mid).await? {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        
+        Ok(high)
+    }
+    
+    /// Call a view function as of a historical block
+    pub async fn call_at_block<P: Provider>(
+        provider: &P,
+        contract_address: FieldElement,
+        function: &str,
+        calldata: Vec<FieldElement>,
+        block_number: u64,
+    ) -> Result<Vec<FieldElement>> {
+        let result = provider.call(
+            FunctionCall {
+                contract_address,
+                entry_point_selector: selectors::selector(function)?,
+                calldata,
+            },
+            BlockId::Number(block_number),
+        ).await?;
+
+        Ok(result)
+    }
+
     /// Wait for transaction confirmation
     pub async fn wait_for_transaction<P: Provider>(
         provider: &P,
@@ -99,6 +398,58 @@ pub mod utils {
     }
 }
 
+/// Canned contract responses for unit tests
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    /// A `ContractReader` that answers each function name with a fixed response
+    #[derive(Default)]
+    pub struct FixtureReader {
+        responses: HashMap<String, Vec<FieldElement>>,
+        calls: AtomicUsize,
+    }
+    
+    impl FixtureReader {
+        pub fn new() -> Self {
+            Self::default()
+        }
+        
+        pub fn with(mut self, function: &str, response: &[u64]) -> Self {
+            let felts = response.iter().map(|v| FieldElement::from(*v)).collect();
+            self.responses.insert(function.to_string(), felts);
+            self
+        }
+        
+        /// Number of calls made against this reader so far
+        pub fn call_count(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+    
+    impl ContractReader for FixtureReader {
+        fn call_contract<'a>(
+            &'a self,
+            _contract_address: FieldElement,
+            function: &'a str,
+            _calldata: Vec<FieldElement>,
+        ) -> BoxFuture<'a, Result<Vec<FieldElement>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let response = self.responses
+                .get(function)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No fixture for {}", function));
+            Box::pin(async move { response })
+        }
+    }
+}
+
+/// Send-and-read round trips against a local devnet, skipped unless `DEFINITE_DEVNET_RPC` is set
+#[cfg(test)]
+mod devnet;
+
 /// Contract addresses for different networks
 pub mod addresses {
     use starknet::core::types::FieldElement;
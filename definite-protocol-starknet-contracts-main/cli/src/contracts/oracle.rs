@@ -1,17 +1,19 @@
 use anyhow::Result;
 use starknet::core::types::FieldElement;
-use starknet::accounts::Account;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 
-use super::Contract;
+use super::{Contract, ContractReader, utils};
+use crate::utils::Bps;
 
 /// Price Oracle contract interface
-pub struct OracleContract<A: Account> {
+pub struct OracleContract<A> {
     address: FieldElement,
     account: A,
 }
 
-impl<A: Account> OracleContract<A> {
-    pub async fn new(account: &A) -> Result<OracleContract<A>> 
+impl<A> OracleContract<A> {
+    pub async fn new(account: &A) -> Result<OracleContract<A>>
     where
         A: Clone,
     {
@@ -22,9 +24,29 @@ impl<A: Account> OracleContract<A> {
             account: account.clone(),
         })
     }
+    
+    pub fn with_address(account: A, address: FieldElement) -> OracleContract<A> {
+        OracleContract { address, account }
+    }
+}
+
+impl<A: ContractReader> OracleContract<A> {
+    /// Get the aggregated price of an asset
+    pub async fn get_price(&self, asset: FieldElement) -> Result<PriceData> {
+        let call_result = self.account.call_contract(self.address, "get_price", vec![asset]).await?;
+        PriceData::decode(&call_result)
+    }
+    
+    /// Get the price one feed source reports for an asset, before aggregation
+    ///
+    /// `source` is the source's ID, a short string such as `'pragma'` or `'dex_twap'`.
+    pub async fn get_price_from_source(&self, asset: FieldElement, source: FieldElement) -> Result<PriceData> {
+        let call_result = self.account.call_contract(self.address, "get_price_from_source", vec![asset, source]).await?;
+        PriceData::decode(&call_result)
+    }
 }
 
-impl<A: Account> Contract for OracleContract<A> {
+impl<A> Contract for OracleContract<A> {
     fn address(&self) -> FieldElement {
         self.address
     }
@@ -33,3 +55,167 @@ impl<A: Account> Contract for OracleContract<A> {
         "PriceOracle"
     }
 }
+
+/// Oracle price (`PriceData` in the price oracle)
+#[derive(Debug, Clone)]
+pub struct PriceData {
+    /// USD price with 8 decimals
+    pub price: BigUint,
+    pub timestamp: u64,
+    pub sources_count: u8,
+    pub confidence: u8,
+}
+
+impl PriceData {
+    /// Decimals of `price`
+    pub const DECIMALS: u32 = 8;
+    
+    /// Felts in a `get_price` or `get_price_from_source` response
+    pub const FELTS: usize = 5;
+    
+    /// Decode the `get_price` response; the price is a `u256` felt pair
+    pub fn decode(data: &[FieldElement]) -> Result<PriceData> {
+        if data.len() != Self::FELTS {
+            return Err(anyhow::anyhow!(
+                "Unexpected price response: expected {} felts, got {}",
+                Self::FELTS,
+                data.len()
+            ));
+        }
+        
+        let small = |i: usize| utils::felt_to_bigint(data[i]).to_u64().unwrap_or(u64::MAX);
+        
+        Ok(PriceData {
+            price: utils::decode_u256(data, 0)?,
+            timestamp: small(2),
+            sources_count: small(3).min(u8::MAX as u64) as u8,
+            confidence: small(4).min(u8::MAX as u64) as u8,
+        })
+    }
+    
+    /// Price in USD
+    pub fn usd(&self) -> f64 {
+        self.price.to_f64().unwrap_or(0.0) / 10f64.powi(Self::DECIMALS as i32)
+    }
+    
+    /// Whether the oracle has no price for the asset yet
+    ///
+    /// A feed that was never initialised returns zero. Valuing with it would show
+    /// the asset as worthless, so callers treat it as unpriced instead.
+    pub fn is_unpriced(&self) -> bool {
+        self.price == BigUint::from(0u32)
+    }
+    
+    /// USD value of an 18-decimal token `amount`, with 18 decimals
+    pub fn value_of(&self, amount: &BigUint) -> BigUint {
+        amount * &self.price / BigUint::from(10u32).pow(Self::DECIMALS)
+    }
+}
+
+/// How far apart the prices of several feed sources are
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSpread {
+    /// Median of the source prices, with 8 decimals; the mean of the middle two for an even count
+    pub median: BigUint,
+    pub min: BigUint,
+    pub max: BigUint,
+    /// `max - min` relative to the median, in basis points
+    pub spread: Bps,
+}
+
+impl PriceSpread {
+    /// Spread of `prices`, or `None` when there are none to compare
+    ///
+    /// Unpriced (zero) sources should be left out by the caller; a zero median
+    /// would make every spread infinite.
+    pub fn of(prices: &[BigUint]) -> Option<PriceSpread> {
+        let mut sorted = prices.to_vec();
+        sorted.sort();
+        let (min, max) = (sorted.first()?.clone(), sorted.last()?.clone());
+        
+        let middle = sorted.len() / 2;
+        let median = if sorted.len() % 2 == 0 {
+            (&sorted[middle - 1] + &sorted[middle]) / 2u32
+        } else {
+            sorted[middle].clone()
+        };
+        let spread = if median == BigUint::from(0u32) {
+            Bps(u32::MAX)
+        } else {
+            Bps::from_biguint(&((&max - &min) * 10_000u32 / &median))
+        };
+        
+        Some(PriceSpread { median, min, max, spread })
+    }
+    
+    /// Median in USD
+    pub fn median_usd(&self) -> f64 {
+        self.median.to_f64().unwrap_or(0.0) / 10f64.powi(PriceData::DECIMALS as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+    
+    #[tokio::test]
+    async fn get_price_decodes_price_data() {
+        let reader = FixtureReader::new().with("get_price", &[45_000_000, 0, 1_700_000_000, 3, 92]);
+        let oracle = OracleContract::with_address(reader, FieldElement::ONE);
+        
+        let price = oracle.get_price(FieldElement::TWO).await.unwrap();
+        assert_eq!(price.price, BigUint::from(45_000_000u32));
+        assert_eq!(price.usd(), 0.45);
+        assert_eq!(price.timestamp, 1_700_000_000);
+        assert_eq!(price.sources_count, 3);
+        assert_eq!(price.confidence, 92);
+    }
+    
+    #[tokio::test]
+    async fn uninitialised_feed_is_unpriced() {
+        let reader = FixtureReader::new().with("get_price", &[0, 0, 0, 0, 0]);
+        let price = OracleContract::with_address(reader, FieldElement::ONE).get_price(FieldElement::TWO).await.unwrap();
+        assert!(price.is_unpriced());
+        
+        let priced = PriceData { price: BigUint::from(45_000_000u32), timestamp: 0, sources_count: 1, confidence: 100 };
+        assert!(!priced.is_unpriced());
+        // 2 tokens at $0.45
+        assert_eq!(priced.value_of(&(BigUint::from(2u32) * BigUint::from(10u32).pow(18))), BigUint::from(9u32) * BigUint::from(10u32).pow(17));
+    }
+    
+    #[tokio::test]
+    async fn source_prices_are_read_per_source() {
+        let reader = FixtureReader::new().with("get_price_from_source", &[44_000_000, 0, 1_700_000_000, 1, 80]);
+        let oracle = OracleContract::with_address(reader, FieldElement::ONE);
+        
+        let price = oracle.get_price_from_source(FieldElement::TWO, FieldElement::from(3u32)).await.unwrap();
+        assert_eq!(price.usd(), 0.44);
+        assert_eq!(price.sources_count, 1);
+    }
+    
+    #[test]
+    fn spread_is_measured_against_the_median() {
+        let prices = |values: &[u32]| values.iter().map(|value| BigUint::from(*value)).collect::<Vec<_>>();
+        
+        let odd = PriceSpread::of(&prices(&[101_000_000, 99_000_000, 100_000_000])).unwrap();
+        assert_eq!(odd.median, BigUint::from(100_000_000u32));
+        assert_eq!(odd.spread, Bps(200));
+        assert_eq!(odd.median_usd(), 1.0);
+        
+        let even = PriceSpread::of(&prices(&[45_000_000, 44_000_000])).unwrap();
+        assert_eq!(even.median, BigUint::from(44_500_000u32));
+        assert_eq!((even.min, even.max), (BigUint::from(44_000_000u32), BigUint::from(45_000_000u32)));
+        assert_eq!(even.spread, Bps(224));
+        
+        assert_eq!(PriceSpread::of(&prices(&[50_000_000])).unwrap().spread, Bps(0));
+        assert!(PriceSpread::of(&[]).is_none());
+    }
+    
+    #[test]
+    fn decode_rejects_wrong_length_response() {
+        assert!(PriceData::decode(&[FieldElement::ONE; 4]).is_err());
+        assert!(PriceData::decode(&[FieldElement::ONE; 6]).is_err());
+        assert!(PriceData::decode(&[FieldElement::ONE; 5]).is_ok());
+    }
+}
@@ -1,16 +1,17 @@
 use anyhow::Result;
 use starknet::core::types::FieldElement;
-use starknet::accounts::Account;
+use num_traits::ToPrimitive;
 
-use super::Contract;
+use super::{Contract, ContractReader, utils};
+use crate::utils::Bps;
 
 /// Rebalancing Engine contract interface
-pub struct RebalancingContract<A: Account> {
+pub struct RebalancingContract<A> {
     address: FieldElement,
     account: A,
 }
 
-impl<A: Account> RebalancingContract<A> {
+impl<A> RebalancingContract<A> {
     pub async fn new(account: &A) -> Result<RebalancingContract<A>> 
     where
         A: Clone,
@@ -22,9 +23,48 @@ impl<A: Account> RebalancingContract<A> {
             account: account.clone(),
         })
     }
+    
+    pub fn with_address(account: A, address: FieldElement) -> RebalancingContract<A> {
+        RebalancingContract { address, account }
+    }
+}
+
+impl<A: ContractReader> RebalancingContract<A> {
+    /// Get the delta threshold above which rebalancing executes
+    pub async fn execution_threshold(&self) -> Result<Bps> {
+        let call_result = self.account.call_contract(self.address, "get_execution_threshold", vec![]).await?;
+        
+        if call_result.is_empty() {
+            return Err(anyhow::anyhow!("No return data from execution threshold call"));
+        }
+        
+        Ok(Bps::from_biguint(&utils::felt_to_bigint(call_result[0])))
+    }
+    
+    /// Get the timestamp of the last executed rebalance (0 if none)
+    pub async fn last_rebalancing(&self) -> Result<u64> {
+        let call_result = self.account.call_contract(self.address, "get_last_rebalancing", vec![]).await?;
+        
+        if call_result.is_empty() {
+            return Err(anyhow::anyhow!("No return data from last rebalancing call"));
+        }
+        
+        Ok(utils::felt_to_bigint(call_result[0]).to_u64().unwrap_or(u64::MAX))
+    }
+    
+    /// Check whether rebalancing is paused
+    pub async fn is_paused(&self) -> Result<bool> {
+        let call_result = self.account.call_contract(self.address, "is_paused", vec![]).await?;
+        
+        if call_result.is_empty() {
+            return Err(anyhow::anyhow!("No return data from is paused call"));
+        }
+        
+        Ok(call_result[0] != FieldElement::ZERO)
+    }
 }
 
-impl<A: Account> Contract for RebalancingContract<A> {
+impl<A> Contract for RebalancingContract<A> {
     fn address(&self) -> FieldElement {
         self.address
     }
@@ -1,35 +1,121 @@
 use anyhow::Result;
 use starknet::core::types::FieldElement;
-use starknet::accounts::Account;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 
-use super::Contract;
+use super::{Contract, ContractReader, utils};
+use crate::utils::Bps;
 
 /// Risk Manager contract interface
-pub struct RiskContract<A: Account> {
+pub struct RiskContract<A> {
     address: FieldElement,
     account: A,
 }
 
-impl<A: Account> RiskContract<A> {
-    pub async fn new(account: &A) -> Result<RiskContract<A>> 
+impl<A> RiskContract<A> {
+    pub async fn new(account: &A) -> Result<RiskContract<A>>
     where
         A: Clone,
     {
         let address = FieldElement::from_hex_be("0x5")?; // Placeholder
-        
+
         Ok(RiskContract {
             address,
             account: account.clone(),
         })
     }
+
+    pub fn with_address(account: A, address: FieldElement) -> RiskContract<A> {
+        RiskContract { address, account }
+    }
 }
 
-impl<A: Account> Contract for RiskContract<A> {
+impl<A: ContractReader> RiskContract<A> {
+    /// Get the latest risk metrics snapshot
+    pub async fn get_risk_metrics(&self) -> Result<RiskMetrics> {
+        let call_result = self.account.call_contract(self.address, "get_risk_metrics", vec![]).await?;
+        RiskMetrics::decode(&call_result)
+    }
+}
+
+impl<A> Contract for RiskContract<A> {
     fn address(&self) -> FieldElement {
         self.address
     }
-    
+
     fn name(&self) -> &str {
         "RiskManager"
     }
 }
+
+/// Risk metrics snapshot (`RiskMetrics` in the risk manager)
+#[derive(Debug, Clone)]
+pub struct RiskMetrics {
+    pub risk_score: u8,
+    pub portfolio_var_bps: Bps,
+    pub leverage_ratio: BigUint,
+    pub liquidity_ratio: BigUint,
+    pub current_drawdown_bps: Bps,
+    pub correlation_risk: u8,
+    pub volatility_risk: u8,
+    pub timestamp: u64,
+}
+
+impl RiskMetrics {
+    /// Decode the `get_risk_metrics` response; the two ratios are `u256` felt pairs
+    pub fn decode(data: &[FieldElement]) -> Result<RiskMetrics> {
+        if data.len() < 10 {
+            return Err(anyhow::anyhow!("Insufficient return data from risk metrics call"));
+        }
+
+        let small = |i: usize| utils::felt_to_bigint(data[i]).to_u64().unwrap_or(u64::MAX);
+
+        Ok(RiskMetrics {
+            risk_score: small(0).min(u8::MAX as u64) as u8,
+            portfolio_var_bps: Bps::from_biguint(&utils::felt_to_bigint(data[1])),
+            leverage_ratio: utils::decode_u256(data, 2)?,
+            liquidity_ratio: utils::decode_u256(data, 4)?,
+            current_drawdown_bps: Bps::from_biguint(&utils::felt_to_bigint(data[6])),
+            correlation_risk: small(7).min(u8::MAX as u64) as u8,
+            volatility_risk: small(8).min(u8::MAX as u64) as u8,
+            timestamp: small(9),
+        })
+    }
+
+    /// Leverage as a multiple (the contract stores 1e18 = 1.0x)
+    pub fn leverage(&self) -> f64 {
+        self.leverage_ratio.to_f64().unwrap_or(0.0) / 1e18
+    }
+
+    /// Liquidity as a ratio (the contract stores 1e18 = 100%)
+    pub fn liquidity(&self) -> f64 {
+        self.liquidity_ratio.to_f64().unwrap_or(0.0) / 1e18
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+
+    #[tokio::test]
+    async fn get_risk_metrics_decodes_u256_ratios() {
+        let reader = FixtureReader::new().with(
+            "get_risk_metrics",
+            &[23, 150, 1_500_000_000_000_000_000, 0, 153_000_000_000_000_000, 0, 40, 10, 20, 1_700_000_000],
+        );
+        let risk = RiskContract::with_address(reader, FieldElement::ONE);
+
+        let metrics = risk.get_risk_metrics().await.unwrap();
+        assert_eq!(metrics.risk_score, 23);
+        assert_eq!(metrics.portfolio_var_bps, Bps(150));
+        assert!((metrics.leverage() - 1.5).abs() < 1e-9);
+        assert_eq!(metrics.current_drawdown_bps, Bps(40));
+        assert_eq!(metrics.timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn decode_rejects_short_response() {
+        assert!(RiskMetrics::decode(&[FieldElement::ONE; 9]).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! Entry point and event selectors, hashed once per process
+//!
+//! `get_selector_from_name` runs a Keccak hash on every call, and view reads, event
+//! filters and sends all need a selector. Watch loops and batch sends repeat the same
+//! few names, so those are hashed once into a table on first use.
+
+use starknet::core::types::FieldElement;
+use starknet::core::utils::{get_selector_from_name, NonAsciiNameError};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Names the CLI calls or filters on itself
+const COMMON: &[&str] = &[
+    // Vault
+    "deposit",
+    "withdraw",
+    "total_assets",
+    "total_shares",
+    "calculate_exchange_rate",
+    "get_config",
+    "is_paused",
+    "collect_management_fee",
+    "collect_management_fees",
+    "emergency_pause",
+    "resume_operations",
+    // Tokens
+    "balance_of",
+    "allowance",
+    "approve",
+    "transfer",
+    "decimals",
+    "total_supply",
+    // Oracle, risk and rebalancing
+    "get_price",
+    "get_price_from_source",
+    "get_risk_metrics",
+    "get_execution_threshold",
+    "get_last_rebalancing",
+    // Hedging
+    "emergency_close_all",
+    "emergency_close_all_options",
+    // Deployment
+    "deployContract",
+    // Events
+    "Deposited",
+    "Withdrawn",
+    "RebalancingExecuted",
+];
+
+fn table() -> &'static HashMap<&'static str, FieldElement> {
+    static TABLE: OnceLock<HashMap<&'static str, FieldElement>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        COMMON
+            .iter()
+            .map(|name| (*name, get_selector_from_name(name).expect("common selector names are ASCII")))
+            .collect()
+    })
+}
+
+/// Selector for `name`, from the precomputed table when it is a common name
+///
+/// Any other name, such as a function typed into `contract call`, is hashed on each call.
+pub fn selector(name: &str) -> Result<FieldElement, NonAsciiNameError> {
+    match table().get(name) {
+        Some(selector) => Ok(*selector),
+        None => get_selector_from_name(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn cached_and_computed_selectors_agree() {
+        for name in COMMON {
+            assert_eq!(selector(name).unwrap(), get_selector_from_name(name).unwrap(), "{}", name);
+        }
+        assert_eq!(selector("set_protocol_vault").unwrap(), get_selector_from_name("set_protocol_vault").unwrap());
+        assert!(selector("dépôt").is_err());
+    }
+}
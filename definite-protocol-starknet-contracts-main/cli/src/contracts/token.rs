@@ -1,18 +1,18 @@
 use anyhow::Result;
-use starknet::core::types::{FieldElement, BlockId, BlockTag};
+use starknet::core::types::FieldElement;
 use starknet::accounts::{Account, Call, ConnectedAccount};
-use starknet::providers::Provider;
 use num_bigint::BigUint;
 
-use super::{Contract, utils};
+use super::{Contract, ContractReader, selectors, utils};
+use super::calldata::CalldataBuilder;
 
 /// ERC20 Token contract interface
-pub struct TokenContract<A: Account> {
+pub struct TokenContract<A> {
     address: FieldElement,
     account: A,
 }
 
-impl<A: Account + ConnectedAccount + Sync> TokenContract<A> {
+impl<A> TokenContract<A> {
     pub async fn new(account: &A, token_address: FieldElement) -> Result<TokenContract<A>> 
     where
         A: Clone,
@@ -23,86 +23,31 @@ impl<A: Account + ConnectedAccount + Sync> TokenContract<A> {
         })
     }
     
+    pub fn with_address(account: A, address: FieldElement) -> TokenContract<A> {
+        TokenContract { address, account }
+    }
+}
+
+impl<A: ContractReader> TokenContract<A> {
     /// Get token balance for an address
     pub async fn balance_of(&self, owner: FieldElement) -> Result<BigUint> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("balance_of")?,
-                calldata: vec![owner],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.is_empty() {
-            return Ok(BigUint::from(0u32));
-        }
+        let call_result = self.account.call_contract(self.address, "balance_of", vec![owner]).await?;
         
-        Ok(utils::felt_to_bigint(call_result[0]))
+        decode_amount(&call_result, "balance_of")
     }
     
     /// Get allowance for spender
     pub async fn allowance(&self, owner: FieldElement, spender: FieldElement) -> Result<BigUint> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("allowance")?,
-                calldata: vec![owner, spender],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.is_empty() {
-            return Ok(BigUint::from(0u32));
-        }
-        
-        Ok(utils::felt_to_bigint(call_result[0]))
-    }
-    
-    /// Approve spender to spend tokens
-    pub async fn approve(&self, spender: FieldElement, amount: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
-        let amount_felt = utils::bigint_to_felt(&amount)?;
-        
-        let call = Call {
-            to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("approve")?,
-            calldata: vec![spender, amount_felt],
-        };
-        
-        let result = self.account.execute(vec![call]).send().await?;
-        Ok(result.transaction_hash)
-    }
-    
-    /// Transfer tokens
-    pub async fn transfer(&self, to: FieldElement, amount: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
-        let amount_felt = utils::bigint_to_felt(&amount)?;
-        
-        let call = Call {
-            to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("transfer")?,
-            calldata: vec![to, amount_felt],
-        };
+        let call_result = self.account.call_contract(self.address, "allowance", vec![owner, spender]).await?;
         
-        let result = self.account.execute(vec![call]).send().await?;
-        Ok(result.transaction_hash)
+        decode_amount(&call_result, "allowance")
     }
     
     /// Get total supply
     pub async fn total_supply(&self) -> Result<BigUint> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("total_supply")?,
-                calldata: vec![],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
+        let call_result = self.account.call_contract(self.address, "total_supply", vec![]).await?;
         
-        if call_result.is_empty() {
-            return Ok(BigUint::from(0u32));
-        }
-        
-        Ok(utils::felt_to_bigint(call_result[0]))
+        decode_amount(&call_result, "total_supply")
     }
     
     /// Get token name
@@ -119,24 +64,47 @@ impl<A: Account + ConnectedAccount + Sync> TokenContract<A> {
     
     /// Get token decimals
     pub async fn decimals(&self) -> Result<u8> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("decimals")?,
-                calldata: vec![],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.is_empty() {
-            return Ok(18); // Default to 18 decimals
-        }
+        let call_result = self.account.call_contract(self.address, "decimals", vec![]).await?;
         
-        Ok(call_result[0].to_bytes_be()[31])
+        decode_decimals(&call_result)
+    }
+}
+
+impl<A: Account + ConnectedAccount + Sync> TokenContract<A> {
+    /// Approve spender to spend tokens
+    pub async fn approve(&self, spender: FieldElement, amount: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
+        let call = self.approve_call(spender, amount)?;
+        let result = self.account.execute(vec![call]).send().await?;
+        Ok(result.transaction_hash)
+    }
+    
+    /// Build the approve call without sending it
+    pub fn approve_call(&self, spender: FieldElement, amount: BigUint) -> Result<Call> {
+        Ok(Call {
+            to: self.address,
+            selector: selectors::selector("approve")?,
+            calldata: CalldataBuilder::new().push_address(spender).push_u256(&amount)?.build(),
+        })
+    }
+    
+    /// Transfer tokens
+    pub async fn transfer(&self, to: FieldElement, amount: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
+        let call = self.transfer_call(to, amount)?;
+        let result = self.account.execute(vec![call]).send().await?;
+        Ok(result.transaction_hash)
+    }
+    
+    /// Build the transfer call without sending it
+    pub fn transfer_call(&self, to: FieldElement, amount: BigUint) -> Result<Call> {
+        Ok(Call {
+            to: self.address,
+            selector: selectors::selector("transfer")?,
+            calldata: CalldataBuilder::new().push_address(to).push_u256(&amount)?.build(),
+        })
     }
 }
 
-impl<A: Account> Contract for TokenContract<A> {
+impl<A> Contract for TokenContract<A> {
     fn address(&self) -> FieldElement {
         self.address
     }
@@ -145,3 +113,92 @@ impl<A: Account> Contract for TokenContract<A> {
         "ERC20Token"
     }
 }
+
+/// Decode an amount returned as a Cairo `u256`
+///
+/// A single felt is accepted for legacy tokens that return `felt252` balances.
+/// Anything else that is not a (low, high) pair is an error, never zero.
+fn decode_amount(data: &[FieldElement], function: &str) -> Result<BigUint> {
+    match data.len() {
+        1 => Ok(utils::felt_to_bigint(data[0])),
+        _ => utils::decode_u256_result(data, function),
+    }
+}
+
+/// Decode a `u8` decimals response, defaulting to 18 when the token returns nothing
+fn decode_decimals(data: &[FieldElement]) -> Result<u8> {
+    let Some(felt) = data.first() else {
+        return Ok(18);
+    };
+    
+    if *felt >= FieldElement::from(256u32) {
+        return Err(anyhow::anyhow!("Token returned an out-of-range decimals value {:#x}", felt));
+    }
+    
+    Ok(felt.to_bytes_be()[31])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+    
+    #[tokio::test]
+    async fn balance_of_decodes_u256_low_word() {
+        let reader = FixtureReader::new().with("balance_of", &[1_500_000_000_000_000_000, 0]);
+        let token = TokenContract::with_address(reader, FieldElement::ONE);
+        
+        let balance = token.balance_of(FieldElement::TWO).await.unwrap();
+        assert_eq!(balance, BigUint::from(1_500_000_000_000_000_000u64));
+    }
+    
+    #[tokio::test]
+    async fn balance_of_decodes_u256_high_word() {
+        let reader = FixtureReader::new().with("balance_of", &[5, 1]);
+        let token = TokenContract::with_address(reader, FieldElement::ONE);
+        
+        let balance = token.balance_of(FieldElement::TWO).await.unwrap();
+        assert_eq!(balance, (BigUint::from(1u32) << 128) + BigUint::from(5u32));
+    }
+    
+    #[tokio::test]
+    async fn balance_of_accepts_zero_and_felt_responses() {
+        let zero = TokenContract::with_address(FixtureReader::new().with("balance_of", &[0, 0]), FieldElement::ONE);
+        let felt = TokenContract::with_address(FixtureReader::new().with("balance_of", &[42]), FieldElement::ONE);
+        
+        assert_eq!(zero.balance_of(FieldElement::TWO).await.unwrap(), BigUint::from(0u32));
+        assert_eq!(felt.balance_of(FieldElement::TWO).await.unwrap(), BigUint::from(42u32));
+    }
+    
+    #[tokio::test]
+    async fn balance_of_rejects_empty_and_garbage_responses() {
+        let empty = TokenContract::with_address(FixtureReader::new().with("balance_of", &[]), FieldElement::ONE);
+        let long = TokenContract::with_address(FixtureReader::new().with("balance_of", &[1, 0, 7]), FieldElement::ONE);
+        
+        let err = empty.balance_of(FieldElement::TWO).await.unwrap_err();
+        assert!(err.to_string().contains("No return data from balance_of"));
+        assert!(long.balance_of(FieldElement::TWO).await.is_err());
+        
+        // A low word above 128 bits is not a valid u256 limb
+        let wide = FieldElement::from_hex_be("0x100000000000000000000000000000000").unwrap();
+        assert!(utils::decode_u256(&[wide, FieldElement::ZERO], 0).is_err());
+    }
+    
+    #[tokio::test]
+    async fn decimals_reads_small_values() {
+        let usdc = TokenContract::with_address(FixtureReader::new().with("decimals", &[6]), FieldElement::ONE);
+        let strk = TokenContract::with_address(FixtureReader::new().with("decimals", &[18]), FieldElement::ONE);
+        let silent = TokenContract::with_address(FixtureReader::new().with("decimals", &[]), FieldElement::ONE);
+        
+        assert_eq!(usdc.decimals().await.unwrap(), 6);
+        assert_eq!(strk.decimals().await.unwrap(), 18);
+        assert_eq!(silent.decimals().await.unwrap(), 18);
+    }
+    
+    #[tokio::test]
+    async fn decimals_rejects_oversized_felt() {
+        // 0x112 would read as 18 from the low byte alone
+        let token = TokenContract::with_address(FixtureReader::new().with("decimals", &[0x112]), FieldElement::ONE);
+        assert!(token.decimals().await.is_err());
+    }
+}
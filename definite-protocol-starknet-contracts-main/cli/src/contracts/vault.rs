@@ -1,19 +1,22 @@
 use anyhow::{Result, Context};
-use starknet::core::types::{FieldElement, BlockId, BlockTag};
+use starknet::core::types::FieldElement;
 use starknet::accounts::{Account, Call, ConnectedAccount};
-use starknet::providers::Provider;
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
 
-use super::{Contract, CallResult, utils};
+use super::{Contract, CallResult, ContractReader, selectors, utils};
+use super::calldata::CalldataBuilder;
+use super::oracle::PriceData;
+use crate::utils::{AmountFormat, Bps};
 
 /// Protocol Vault contract interface
-pub struct VaultContract<A: Account> {
+pub struct VaultContract<A> {
     address: FieldElement,
     account: A,
 }
 
-impl<A: Account + ConnectedAccount + Sync> VaultContract<A> {
+impl<A> VaultContract<A> {
     pub async fn new(account: &A) -> Result<VaultContract<A>> 
     where
         A: Clone,
@@ -21,161 +24,114 @@ impl<A: Account + ConnectedAccount + Sync> VaultContract<A> {
         // In a real implementation, this would load the address from config
         let address = FieldElement::from_hex_be("0x1")?; // Placeholder
         
-        Ok(VaultContract {
-            address,
-            account: account.clone(),
-        })
+        Ok(VaultContract::with_address(account.clone(), address))
     }
     
     pub fn with_address(account: A, address: FieldElement) -> VaultContract<A> {
-        VaultContract { address, account }
+        VaultContract {
+            address,
+            account,
+        }
     }
-    
-    /// Deposit STRK tokens and receive hSTRK
-    pub async fn deposit(
-        &self,
-        amount: BigUint,
-        recipient: Option<String>,
-    ) -> Result<FieldElement> where <A as Account>::SignError: 'static {
-        let amount_felt = utils::bigint_to_felt(&amount)?;
-        let recipient_felt = if let Some(addr) = recipient {
-            utils::parse_address(&addr)?
-        } else {
-            self.account.address()
-        };
-        
-        let call = Call {
-            to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("deposit")?,
-            calldata: vec![amount_felt, recipient_felt],
-        };
-        
-        let result = self.account.execute(vec![call]).send().await?;
-        Ok(result.transaction_hash)
+}
+
+impl<A: ContractReader> VaultContract<A> {
+    /// Calculate current exchange rate (assets per share)
+    pub async fn calculate_exchange_rate(&self) -> Result<BigUint> {
+        let call_result = self.account.call_contract(self.address, "calculate_exchange_rate", vec![]).await?;
+        utils::decode_u256_result(&call_result, "calculate_exchange_rate")
     }
     
-    /// Withdraw STRK tokens by burning hSTRK
-    pub async fn withdraw(&self, shares: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
-        let shares_felt = utils::bigint_to_felt(&shares)?;
-        
-        let call = Call {
-            to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("withdraw")?,
-            calldata: vec![shares_felt],
-        };
+    /// The exchange rate deposits are priced at, or 1:1 while no hSTRK exists
+    ///
+    /// A fresh vault may return zero from `calculate_exchange_rate` or revert, so
+    /// the contract is only asked once shares are outstanding.
+    pub async fn deposit_exchange_rate(&self) -> Result<BigUint> {
+        if self.total_shares().await? == BigUint::from(0u32) {
+            return Ok(BigUint::from(WAD));
+        }
         
-        let result = self.account.execute(vec![call]).send().await?;
-        Ok(result.transaction_hash)
+        self.calculate_exchange_rate().await
     }
     
-    /// Calculate current exchange rate (assets per share)
-    pub async fn calculate_exchange_rate(&self) -> Result<BigUint> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("calculate_exchange_rate")?,
-                calldata: vec![],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.is_empty() {
-            return Err(anyhow::anyhow!("No return data from exchange rate call"));
-        }
-        
-        Ok(utils::felt_to_bigint(call_result[0]))
+    /// Shares a deposit of `amount` would mint right now, computed exactly as the vault does
+    pub async fn preview_deposit(&self, amount: &BigUint) -> Result<BigUint> {
+        let (assets, shares) = tokio::try_join!(self.total_assets(), self.total_shares())?;
+        Ok(shares_for_deposit(amount, &assets, &shares))
     }
     
     /// Get total assets under management
     pub async fn total_assets(&self) -> Result<BigUint> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("total_assets")?,
-                calldata: vec![],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.is_empty() {
-            return Err(anyhow::anyhow!("No return data from total assets call"));
-        }
-        
-        Ok(utils::felt_to_bigint(call_result[0]))
+        let call_result = self.account.call_contract(self.address, "total_assets", vec![]).await?;
+        utils::decode_u256_result(&call_result, "total_assets")
     }
     
     /// Get total shares outstanding
     pub async fn total_shares(&self) -> Result<BigUint> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("total_shares")?,
-                calldata: vec![],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.is_empty() {
-            return Err(anyhow::anyhow!("No return data from total shares call"));
-        }
-        
-        Ok(utils::felt_to_bigint(call_result[0]))
+        let call_result = self.account.call_contract(self.address, "total_shares", vec![]).await?;
+        utils::decode_u256_result(&call_result, "total_shares")
     }
     
-    /// Collect management fees
-    pub async fn collect_management_fee(&self) -> Result<FieldElement> where <A as Account>::SignError: 'static {
-        let call = Call {
-            to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("collect_management_fee")?,
-            calldata: vec![],
-        };
-        
+    /// Get vault configuration, with the pause flag read alongside it
+    pub async fn get_vault_config(&self) -> Result<VaultConfig> {
+        let (config, paused) = tokio::try_join!(
+            self.account.call_contract(self.address, "get_config", vec![]),
+            self.account.call_contract(self.address, "is_paused", vec![]),
+        )?;
+        VaultConfig::decode(&config, &paused)
+    }
+}
+
+impl<A: Account + ConnectedAccount + Sync> VaultContract<A> {
+    /// Deposit STRK tokens and receive hSTRK
+    pub async fn deposit(&self, amount: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
+        let call = self.deposit_call(amount)?;
         let result = self.account.execute(vec![call]).send().await?;
         Ok(result.transaction_hash)
     }
     
-    /// Get vault configuration
-    pub async fn get_vault_config(&self) -> Result<VaultConfig> {
-        let call_result = self.account.provider().call(
-            starknet::core::types::FunctionCall {
-                contract_address: self.address,
-                entry_point_selector: starknet::core::utils::get_selector_from_name("get_vault_config")?,
-                calldata: vec![],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        ).await?;
-        
-        if call_result.len() < 6 {
-            return Err(anyhow::anyhow!("Insufficient return data from vault config call"));
-        }
-        
-        Ok(VaultConfig {
-            management_fee_bps: utils::felt_to_bigint(call_result[0]),
-            performance_fee_bps: utils::felt_to_bigint(call_result[1]),
-            deposit_limit: utils::felt_to_bigint(call_result[2]),
-            min_deposit: utils::felt_to_bigint(call_result[3]),
-            withdrawal_delay: utils::felt_to_bigint(call_result[4]),
-            emergency_mode: call_result[5] != FieldElement::ZERO,
+    /// Build the deposit call without sending it; hSTRK is always minted to the caller
+    pub fn deposit_call(&self, amount: BigUint) -> Result<Call> {
+        Ok(Call {
+            to: self.address,
+            selector: selectors::selector("deposit")?,
+            calldata: deposit_calldata(&amount)?,
         })
     }
     
-    /// Get hSTRK token address
-    pub fn hstrk_token_address(&self) -> FieldElement {
-        // In a real implementation, this would be fetched from the contract
-        FieldElement::from_hex_be("0x2").unwrap() // Placeholder
+    /// Withdraw STRK tokens by burning hSTRK
+    pub async fn withdraw(&self, shares: BigUint) -> Result<FieldElement> where <A as Account>::SignError: 'static {
+        let call = self.withdraw_call(shares)?;
+        let result = self.account.execute(vec![call]).send().await?;
+        Ok(result.transaction_hash)
+    }
+    
+    /// Build the withdraw call without sending it
+    pub fn withdraw_call(&self, shares: BigUint) -> Result<Call> {
+        Ok(Call {
+            to: self.address,
+            selector: selectors::selector("withdraw")?,
+            calldata: CalldataBuilder::new().push_u256(&shares)?.build(),
+        })
     }
     
-    /// Get STRK token address
-    pub fn strk_token_address(&self) -> FieldElement {
-        // In a real implementation, this would be fetched from the contract
-        FieldElement::from_hex_be("0x3").unwrap() // Placeholder
+    /// Collect management fees
+    pub async fn collect_management_fee(&self) -> Result<FieldElement> where <A as Account>::SignError: 'static {
+        let call = Call {
+            to: self.address,
+            selector: selectors::selector("collect_management_fee")?,
+            calldata: vec![],
+        };
+        
+        let result = self.account.execute(vec![call]).send().await?;
+        Ok(result.transaction_hash)
     }
     
     /// Emergency pause the vault
     pub async fn emergency_pause(&self) -> Result<FieldElement> where <A as Account>::SignError: 'static {
         let call = Call {
             to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("emergency_pause")?,
+            selector: selectors::selector("emergency_pause")?,
             calldata: vec![],
         };
         
@@ -187,7 +143,7 @@ impl<A: Account + ConnectedAccount + Sync> VaultContract<A> {
     pub async fn resume_operations(&self) -> Result<FieldElement> where <A as Account>::SignError: 'static {
         let call = Call {
             to: self.address,
-            selector: starknet::core::utils::get_selector_from_name("resume_operations")?,
+            selector: selectors::selector("resume_operations")?,
             calldata: vec![],
         };
         
@@ -196,7 +152,107 @@ impl<A: Account + ConnectedAccount + Sync> VaultContract<A> {
     }
 }
 
-impl<A: Account> Contract for VaultContract<A> {
+/// Calldata for `deposit(amount: u256)`, the only argument the vault takes
+pub fn deposit_calldata(amount: &BigUint) -> Result<Vec<FieldElement>> {
+    Ok(CalldataBuilder::new().push_u256(amount)?.build())
+}
+
+/// Shares minted for `amount`: 1:1 into an empty vault, otherwise `amount * shares / assets` rounded down
+pub fn shares_for_deposit(amount: &BigUint, total_assets: &BigUint, total_shares: &BigUint) -> BigUint {
+    if *total_shares == BigUint::from(0u32) || *total_assets == BigUint::from(0u32) {
+        return amount.clone();
+    }
+    amount * total_shares / total_assets
+}
+
+/// 1.0 in the 18-decimal fixed point `calculate_exchange_rate` returns
+pub const WAD: u64 = 1_000_000_000_000_000_000;
+
+/// Decimals of the vault's exchange rate
+pub const RATE_DECIMALS: u32 = 18;
+
+/// 1.0 in a fixed point with `decimals` decimals
+fn fixed_point_one(decimals: u32) -> BigUint {
+    if decimals == RATE_DECIMALS {
+        return BigUint::from(WAD);
+    }
+    BigUint::from(10u32).pow(decimals)
+}
+
+/// Shares worth `assets` at `rate` (assets per share, with `decimals` decimals)
+///
+/// Rounds down, like the vault when it mints, so a preview never promises more
+/// shares than the deposit receives.
+pub fn assets_to_shares(assets: &BigUint, rate: &BigUint, decimals: u32) -> Result<BigUint> {
+    if *rate == BigUint::from(0u32) {
+        return Err(anyhow::anyhow!(
+            "The vault reports an exchange rate of zero while hSTRK is outstanding, so deposits cannot be priced"
+        ));
+    }
+    
+    Ok(assets * fixed_point_one(decimals) / rate)
+}
+
+/// Assets `shares` redeem for at `rate` (assets per share, with `decimals` decimals)
+///
+/// Rounds down, like the vault when it pays out. Together with [`assets_to_shares`]
+/// a round trip never returns more assets than went in.
+pub fn shares_to_assets(shares: &BigUint, rate: &BigUint, decimals: u32) -> BigUint {
+    shares * rate / fixed_point_one(decimals)
+}
+
+/// Fair STRK per hSTRK from oracle USD prices, in the vault's 18-decimal rate format
+pub fn oracle_fair_rate(hstrk: &PriceData, strk: &PriceData) -> Result<BigUint> {
+    if strk.price == BigUint::from(0u32) || hstrk.price == BigUint::from(0u32) {
+        return Err(anyhow::anyhow!("The oracle reports a price of zero for STRK or hSTRK"));
+    }
+    
+    Ok(&hstrk.price * BigUint::from(WAD) / &strk.price)
+}
+
+/// How far `quoted` is from `fair`, rounded up so a limit is never passed by rounding
+pub fn rate_deviation(quoted: &BigUint, fair: &BigUint) -> Bps {
+    if *fair == BigUint::from(0u32) {
+        return Bps(u32::MAX);
+    }
+    
+    let gap = if quoted > fair { quoted - fair } else { fair - quoted };
+    Bps::from_biguint(&((gap * 10_000u32 + fair - 1u32) / fair))
+}
+
+/// Whether hSTRK exists but is backed by nothing, so it currently redeems for zero STRK
+pub fn has_zero_exchange_rate(total_assets: &BigUint, total_shares: &BigUint) -> bool {
+    *total_shares > BigUint::from(0u32) && *total_assets == BigUint::from(0u32)
+}
+
+/// STRK per hSTRK for display; 1.0 before the first deposit
+pub fn exchange_rate(total_assets: &BigUint, total_shares: &BigUint) -> f64 {
+    match total_shares.to_f64() {
+        Some(shares) if shares > 0.0 => total_assets.to_f64().unwrap_or(0.0) / shares,
+        _ => 1.0,
+    }
+}
+
+/// STRK returned for burning `shares`, rounded down like the vault's `withdraw`
+pub fn assets_for_shares(shares: &BigUint, total_assets: &BigUint, total_shares: &BigUint) -> BigUint {
+    if *total_shares == BigUint::from(0u32) {
+        return BigUint::from(0u32);
+    }
+    
+    shares * total_assets / total_shares
+}
+
+/// Fewest shares whose withdrawal returns at least `assets`, rounded up so the vault's
+/// rounded-down conversion never falls short
+pub fn shares_for_assets(assets: &BigUint, total_assets: &BigUint, total_shares: &BigUint) -> Result<BigUint> {
+    if *total_assets == BigUint::from(0u32) {
+        return Err(anyhow::anyhow!("Vault holds no assets to withdraw"));
+    }
+    
+    Ok((assets * total_shares + total_assets - 1u32) / total_assets)
+}
+
+impl<A> Contract for VaultContract<A> {
     fn address(&self) -> FieldElement {
         self.address
     }
@@ -206,14 +262,22 @@ impl<A: Account> Contract for VaultContract<A> {
     }
 }
 
-/// Vault configuration structure
+/// Vault configuration (`VaultConfig` in the protocol vault) and pause state
 #[derive(Debug, Clone)]
 pub struct VaultConfig {
-    pub management_fee_bps: BigUint,
-    pub performance_fee_bps: BigUint,
+    pub management_fee_bps: Bps,
+    pub performance_fee_bps: Bps,
+    /// Taken from the assets of every withdrawal
+    pub exit_fee_bps: Bps,
+    /// The vault's `max_tvl`
     pub deposit_limit: BigUint,
     pub min_deposit: BigUint,
+    /// Seconds before a withdrawal request can be claimed
+    ///
+    /// The protocol vault's config has no delay and pays withdrawals out at once,
+    /// so this is zero when read from chain.
     pub withdrawal_delay: BigUint,
+    /// The vault's pause flag, set by `emergency_pause`
     pub emergency_mode: bool,
 }
 
@@ -230,11 +294,425 @@ pub struct YieldReport {
 }
 
 impl VaultConfig {
+    /// Felts in the `get_config` response
+    pub const FELTS: usize = 7;
+    
+    /// Decode the `get_config` response together with the `is_paused` response
+    ///
+    /// The struct is serialized in declaration order: the `u16` performance,
+    /// management and exit fees, then `min_deposit` and `max_tvl` as `u256` low/high pairs.
+    pub fn decode(config: &[FieldElement], paused: &[FieldElement]) -> Result<VaultConfig> {
+        if config.len() != Self::FELTS {
+            return Err(anyhow::anyhow!(
+                "Unexpected get_config response: expected {} felts, got {}",
+                Self::FELTS,
+                config.len()
+            ));
+        }
+        
+        let fee = |index: usize, field: &str| -> Result<Bps> {
+            Ok(Bps(utils::decode_uint_result::<u16>(&config[index..=index], field)?.into()))
+        };
+        
+        Ok(VaultConfig {
+            performance_fee_bps: fee(0, "performance_fee_bps")?,
+            management_fee_bps: fee(1, "management_fee_bps")?,
+            exit_fee_bps: fee(2, "exit_fee_bps")?,
+            min_deposit: utils::decode_u256(config, 3)?,
+            deposit_limit: utils::decode_u256(config, 5)?,
+            withdrawal_delay: BigUint::from(0u32),
+            emergency_mode: utils::decode_felt_result(paused, "is_paused")? != FieldElement::ZERO,
+        })
+    }
+    
+    /// Reject a deposit the vault would revert: emergency mode, below `min_deposit`,
+    /// or past the `deposit_limit` headroom left by current TVL (a zero limit means uncapped)
+    pub fn check_deposit(&self, amount: &BigUint, total_assets: &BigUint, amounts: &AmountFormat) -> Result<()> {
+        if self.emergency_mode {
+            return Err(anyhow::anyhow!("Vault is in emergency mode; deposits are disabled"));
+        }
+        
+        if *amount < self.min_deposit {
+            return Err(anyhow::anyhow!(
+                "Deposit of {} is below the vault minimum of {}",
+                amounts.format(amount),
+                amounts.format(&self.min_deposit)
+            ));
+        }
+        
+        if let Some(headroom) = self.deposit_headroom(total_assets) {
+            if *amount > headroom {
+                return Err(anyhow::anyhow!(
+                    "Deposit of {} exceeds the remaining vault capacity (limit {}); you can deposit up to {}",
+                    amounts.format(amount),
+                    amounts.format(&self.deposit_limit),
+                    amounts.format(&headroom)
+                ));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Room left under `deposit_limit` at the current TVL, or `None` when deposits are uncapped
+    pub fn deposit_headroom(&self, total_assets: &BigUint) -> Option<BigUint> {
+        if self.deposit_limit == BigUint::from(0u32) {
+            return None;
+        }
+        
+        Some(if *total_assets >= self.deposit_limit {
+            BigUint::from(0u32)
+        } else {
+            &self.deposit_limit - total_assets
+        })
+    }
+    
+    /// STRK paid out for `gross` withdrawn assets once `withdraw` takes the exit fee
+    pub fn net_of_exit_fee(&self, gross: &BigUint) -> BigUint {
+        gross - gross * self.exit_fee_bps.0 / 10_000u32
+    }
+    
+    /// Gross assets to withdraw so at least `net` STRK is paid out after the exit fee, rounded up
+    pub fn gross_for_net(&self, net: &BigUint) -> Result<BigUint> {
+        let kept = 10_000u32.checked_sub(self.exit_fee_bps.0).filter(|kept| *kept > 0)
+            .ok_or_else(|| anyhow::anyhow!("The vault's exit fee of {} leaves nothing to withdraw", self.exit_fee_bps))?;
+        Ok((net * 10_000u32 + kept - 1u32) / kept)
+    }
+    
+    /// When shares withdrawn at `now` become claimable, or `None` for an immediate-withdraw vault
+    pub fn withdrawal_unlock_time(&self, now: u64) -> Option<u64> {
+        let delay = self.withdrawal_delay.to_u64().unwrap_or(u64::MAX);
+        if delay == 0 {
+            None
+        } else {
+            Some(now.saturating_add(delay))
+        }
+    }
+    
     pub fn management_fee_percentage(&self) -> f64 {
-        self.management_fee_bps.to_f64().unwrap_or(0.0) / 10000.0
+        self.management_fee_bps.to_percent()
     }
     
     pub fn performance_fee_percentage(&self) -> f64 {
-        self.performance_fee_bps.to_f64().unwrap_or(0.0) / 10000.0
+        self.performance_fee_bps.to_percent()
+    }
+    
+    /// APY left to depositors after fees, as a ratio like `gross_apy`
+    ///
+    /// The performance fee takes its cut of the yield, and the management fee is
+    /// charged on assets whether or not there was yield, so net can go negative.
+    pub fn net_apy(&self, gross_apy: Decimal) -> Decimal {
+        gross_apy * (Decimal::ONE - self.performance_fee_bps.to_decimal_ratio())
+            - self.management_fee_bps.to_decimal_ratio()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::testing::FixtureReader;
+    
+    #[test]
+    fn deposit_calldata_encodes_amount_as_u256() {
+        let amount = (BigUint::from(1u32) << 130) + (BigUint::from(1u32) << 64) + 7u32;
+        let calldata = deposit_calldata(&amount).unwrap();
+        
+        assert_eq!(calldata.len(), 2);
+        assert_eq!(calldata[0], FieldElement::from_hex_be("0x10000000000000007").unwrap());
+        assert_eq!(calldata[1], FieldElement::from(4u32));
+        assert_eq!(utils::decode_u256(&calldata, 0).unwrap(), amount);
+        
+        assert!(deposit_calldata(&(BigUint::from(1u32) << 256)).is_err());
+    }
+    
+    #[test]
+    fn deposit_preview_matches_the_vault_mint() {
+        let amount = BigUint::from(1_000u32);
+        assert_eq!(shares_for_deposit(&amount, &BigUint::from(0u32), &BigUint::from(0u32)), amount);
+        // 1000 * 2 / 3 rounds down like the contract's integer division
+        assert_eq!(shares_for_deposit(&amount, &BigUint::from(3u32), &BigUint::from(2u32)), BigUint::from(666u32));
+    }
+    
+    #[tokio::test]
+    async fn total_assets_decodes_u256_and_rejects_missing_data() {
+        let vault = VaultContract::with_address(FixtureReader::new().with("total_assets", &[7, 1]), FieldElement::ONE);
+        assert_eq!(vault.total_assets().await.unwrap(), (BigUint::from(1u32) << 128) + BigUint::from(7u32));
+        
+        let zero = VaultContract::with_address(FixtureReader::new().with("total_assets", &[0, 0]), FieldElement::ONE);
+        assert_eq!(zero.total_assets().await.unwrap(), BigUint::from(0u32));
+        
+        let responses: [&[u64]; 3] = [&[], &[5], &[5, 0, 0]];
+        for response in responses {
+            let vault = VaultContract::with_address(FixtureReader::new().with("total_assets", response), FieldElement::ONE);
+            assert!(vault.total_assets().await.is_err(), "{:?} should be rejected", response);
+        }
+    }
+    
+    #[tokio::test]
+    async fn empty_vault_bootstraps_deposits_at_one_to_one() {
+        // No calculate_exchange_rate fixture: a fresh vault must not be asked for it
+        let reader = FixtureReader::new().with("total_shares", &[0, 0]);
+        let vault = VaultContract::with_address(reader, FieldElement::ONE);
+        
+        let rate = vault.deposit_exchange_rate().await.unwrap();
+        assert_eq!(rate, BigUint::from(WAD));
+        
+        let amount = BigUint::from(25u32) * BigUint::from(WAD);
+        assert_eq!(assets_to_shares(&amount, &rate, RATE_DECIMALS).unwrap(), amount);
+        assert_eq!(exchange_rate(&BigUint::from(0u32), &BigUint::from(0u32)), 1.0);
+    }
+    
+    #[tokio::test]
+    async fn zero_exchange_rate_with_shares_outstanding_is_rejected() {
+        let reader = FixtureReader::new()
+            .with("total_shares", &[100, 0])
+            .with("calculate_exchange_rate", &[0, 0]);
+        let vault = VaultContract::with_address(reader, FieldElement::ONE);
+        
+        let rate = vault.deposit_exchange_rate().await.unwrap();
+        assert!(assets_to_shares(&BigUint::from(10u32), &rate, RATE_DECIMALS).is_err());
+        assert!(has_zero_exchange_rate(&BigUint::from(0u32), &BigUint::from(100u32)));
+        assert!(!has_zero_exchange_rate(&BigUint::from(0u32), &BigUint::from(0u32)));
+        
+        // 2 STRK per hSTRK halves the shares minted
+        let doubled = BigUint::from(WAD) * 2u32;
+        assert_eq!(assets_to_shares(&BigUint::from(10u32), &doubled, RATE_DECIMALS).unwrap(), BigUint::from(5u32));
+    }
+    
+    #[tokio::test]
+    async fn get_vault_config_decodes_fixture() {
+        let reader = FixtureReader::new()
+            .with("get_config", &[2000, 200, 10, 1_000, 0, 10_000_000, 0])
+            .with("is_paused", &[0]);
+        let vault = VaultContract::with_address(reader, FieldElement::ONE);
+        
+        let config = vault.get_vault_config().await.unwrap();
+        
+        assert_eq!(config.management_fee_bps, Bps(200));
+        assert_eq!(config.performance_fee_bps, Bps(2000));
+        assert_eq!(config.exit_fee_bps, Bps(10));
+        assert_eq!(config.deposit_limit, BigUint::from(10_000_000u32));
+        assert_eq!(config.min_deposit, BigUint::from(1_000u32));
+        assert_eq!(config.withdrawal_delay, BigUint::from(0u32));
+        assert!(!config.emergency_mode);
+        assert!((config.management_fee_percentage() - 2.0).abs() < f64::EPSILON);
+    }
+    
+    #[test]
+    fn vault_config_decodes_the_constructor_defaults() {
+        // The vault's default config: 20% performance, 1% management and 0.1% exit fees,
+        // a 10 STRK minimum deposit and a 1M STRK TVL cap, each amount a (low, high) pair
+        let felt = |value: &str| FieldElement::from_dec_str(value).unwrap();
+        let layout = [felt("2000"), felt("100"), felt("10"), felt("10000000000000000000"), felt("0"), felt("1000000000000000000000000"), felt("0")];
+        let config = VaultConfig::decode(&layout, &[FieldElement::ONE]).unwrap();
+        
+        assert_eq!(config.performance_fee_bps, Bps(2000));
+        assert_eq!(config.management_fee_bps, Bps(100));
+        assert_eq!(config.exit_fee_bps, Bps(10));
+        assert_eq!(config.min_deposit, BigUint::from(10u32) * BigUint::from(10u32).pow(18));
+        assert_eq!(config.deposit_limit, BigUint::from(1_000_000u32) * BigUint::from(10u32).pow(18));
+        assert!(config.emergency_mode);
+        
+        // A cap above 2^128 carries into the high limb
+        let mut wide = layout;
+        wide[6] = FieldElement::ONE;
+        assert_eq!(VaultConfig::decode(&wide, &[FieldElement::ZERO]).unwrap().deposit_limit, (BigUint::from(1u32) << 128) + BigUint::from(1_000_000u32) * BigUint::from(10u32).pow(18));
+        
+        // Fees are u16 and the old six-felt layout no longer lines up
+        let mut oversized = layout;
+        oversized[1] = FieldElement::from(70_000u32);
+        assert!(VaultConfig::decode(&oversized, &[FieldElement::ZERO]).is_err());
+        assert!(VaultConfig::decode(&layout[..6], &[FieldElement::ZERO]).is_err());
+    }
+    
+    #[test]
+    fn net_apy_subtracts_fee_drag() {
+        let config = VaultConfig::decode(&[2000u64, 200, 0, 0, 0, 0, 0].map(FieldElement::from), &[FieldElement::ZERO]).unwrap();
+        
+        // 15% gross keeps 80% after the performance fee, minus the 2% management fee
+        assert_eq!(config.net_apy(Decimal::new(15, 2)), Decimal::new(10, 2));
+        assert_eq!(config.net_apy(Decimal::ZERO), Decimal::new(-2, 2));
+    }
+    
+    #[tokio::test]
+    async fn get_vault_config_reads_pause_flag() {
+        let reader = FixtureReader::new().with("get_config", &[0, 0, 0, 0, 0, 0, 0]).with("is_paused", &[1]);
+        let vault = VaultContract::with_address(reader, FieldElement::ONE);
+        
+        assert!(vault.get_vault_config().await.unwrap().emergency_mode);
+    }
+    
+    #[tokio::test]
+    async fn get_vault_config_rejects_short_response() {
+        let reader = FixtureReader::new().with("get_config", &[2000, 200]).with("is_paused", &[0]);
+        let vault = VaultContract::with_address(reader, FieldElement::ONE);
+        
+        assert!(vault.get_vault_config().await.is_err());
+    }
+    
+    fn config_with_limits(deposit_limit: u32, min_deposit: u32, emergency_mode: bool) -> VaultConfig {
+        VaultConfig {
+            management_fee_bps: Bps(0),
+            performance_fee_bps: Bps(0),
+            exit_fee_bps: Bps(0),
+            deposit_limit: BigUint::from(deposit_limit),
+            min_deposit: BigUint::from(min_deposit),
+            withdrawal_delay: BigUint::from(0u32),
+            emergency_mode,
+        }
+    }
+    
+    #[test]
+    fn check_deposit_enforces_minimum_and_headroom() {
+        let config = config_with_limits(1000, 10, false);
+        
+        assert!(config.check_deposit(&BigUint::from(5u32), &BigUint::from(0u32), &AmountFormat::default()).is_err());
+        assert!(config.check_deposit(&BigUint::from(300u32), &BigUint::from(700u32), &AmountFormat::default()).is_ok());
+        assert!(config.check_deposit(&BigUint::from(301u32), &BigUint::from(700u32), &AmountFormat::default()).is_err());
+        assert!(config.check_deposit(&BigUint::from(10u32), &BigUint::from(1200u32), &AmountFormat::default()).is_err());
+    }
+    
+    #[test]
+    fn check_deposit_treats_zero_limit_as_uncapped() {
+        let config = config_with_limits(0, 0, false);
+        assert!(config.check_deposit(&BigUint::from(u32::MAX), &BigUint::from(u32::MAX), &AmountFormat::default()).is_ok());
+        assert_eq!(config.deposit_headroom(&BigUint::from(u32::MAX)), None);
+    }
+    
+    #[test]
+    fn deposit_headroom_suggests_max_depositable() {
+        let config = config_with_limits(1000, 0, false);
+        assert_eq!(config.deposit_headroom(&BigUint::from(700u32)), Some(BigUint::from(300u32)));
+        assert_eq!(config.deposit_headroom(&BigUint::from(1200u32)), Some(BigUint::from(0u32)));
+        
+        let err = config.check_deposit(&BigUint::from(301u32), &BigUint::from(700u32), &AmountFormat::default()).unwrap_err();
+        assert!(err.to_string().contains("you can deposit up to"));
+    }
+    
+    #[test]
+    fn withdrawal_unlock_time_follows_delay() {
+        let mut config = config_with_limits(0, 0, false);
+        assert_eq!(config.withdrawal_unlock_time(1_700_000_000), None);
+        
+        config.withdrawal_delay = BigUint::from(86_400u32);
+        assert_eq!(config.withdrawal_unlock_time(1_700_000_000), Some(1_700_086_400));
+    }
+    
+    #[test]
+    fn shares_for_assets_rounds_up_to_cover_target() {
+        // 3 shares back 10 assets, so 1 share is worth 3.33 assets
+        let (total_assets, total_shares) = (BigUint::from(10u32), BigUint::from(3u32));
+        
+        let shares = shares_for_assets(&BigUint::from(4u32), &total_assets, &total_shares).unwrap();
+        assert_eq!(shares, BigUint::from(2u32));
+        assert!(assets_for_shares(&shares, &total_assets, &total_shares) >= BigUint::from(4u32));
+        
+        let exact = shares_for_assets(&BigUint::from(10u32), &total_assets, &total_shares).unwrap();
+        assert_eq!(exact, BigUint::from(3u32));
+        assert!(shares_for_assets(&BigUint::from(1u32), &BigUint::from(0u32), &total_shares).is_err());
+    }
+    
+    #[test]
+    fn strk_targets_are_sized_past_the_exit_fee() {
+        let mut config = config_with_limits(0, 0, false);
+        config.exit_fee_bps = Bps(50);
+        
+        // 0.5% of 1000 is 5
+        assert_eq!(config.net_of_exit_fee(&BigUint::from(1_000u32)), BigUint::from(995u32));
+        
+        // 100 net needs 100.5 gross, rounded up to 101
+        let gross = config.gross_for_net(&BigUint::from(100u32)).unwrap();
+        assert_eq!(gross, BigUint::from(101u32));
+        assert!(config.net_of_exit_fee(&gross) >= BigUint::from(100u32));
+        
+        // Through the share conversion the payout still covers the target
+        let (total_assets, total_shares) = (BigUint::from(10u32), BigUint::from(3u32));
+        let shares = shares_for_assets(&gross, &total_assets, &total_shares).unwrap();
+        assert!(config.net_of_exit_fee(&assets_for_shares(&shares, &total_assets, &total_shares)) >= BigUint::from(100u32));
+        
+        config.exit_fee_bps = Bps(10_000);
+        assert!(config.gross_for_net(&BigUint::from(1u32)).is_err());
+    }
+    
+    fn price(usd_cents: u32) -> PriceData {
+        PriceData { price: BigUint::from(usd_cents) * 1_000_000u32, timestamp: 0, sources_count: 1, confidence: 100 }
+    }
+    
+    #[test]
+    fn oracle_fair_rate_divides_hstrk_by_strk_price() {
+        // hSTRK at $0.55 and STRK at $0.50: 1.1 STRK per hSTRK
+        let fair = oracle_fair_rate(&price(55), &price(50)).unwrap();
+        assert_eq!(fair, BigUint::from(11u32) * BigUint::from(WAD) / 10u32);
+        assert!(oracle_fair_rate(&price(55), &price(0)).is_err());
+        assert!(oracle_fair_rate(&price(0), &price(50)).is_err());
+    }
+    
+    #[test]
+    fn rate_deviation_is_symmetric_and_rounds_up() {
+        let fair = BigUint::from(WAD);
+        assert_eq!(rate_deviation(&fair, &fair), Bps(0));
+        assert_eq!(rate_deviation(&(BigUint::from(WAD) * 103u32 / 100u32), &fair), Bps(300));
+        assert_eq!(rate_deviation(&(BigUint::from(WAD) * 97u32 / 100u32), &fair), Bps(300));
+        assert_eq!(rate_deviation(&(BigUint::from(WAD) + 1u32), &fair), Bps(1));
+        assert_eq!(rate_deviation(&fair, &BigUint::from(0u32)), Bps(u32::MAX));
+    }
+    
+    /// Deterministic xorshift, so the property tests below need no extra dependency
+    fn samples(seed: u64, count: usize) -> impl Iterator<Item = u64> {
+        let mut state = seed;
+        std::iter::repeat_with(move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .take(count)
+    }
+    
+    #[test]
+    fn round_trip_never_over_credits() {
+        let values: Vec<u64> = samples(0x9e37_79b9_7f4a_7c15, 2_000).collect();
+        for pair in values.chunks(2) {
+            let assets = BigUint::from(pair[0]) * 1_000u32;
+            // Rates from just above zero up to about 18 assets per share
+            let rate = BigUint::from(pair[1] % (18 * WAD) + 1);
+            
+            let shares = assets_to_shares(&assets, &rate, RATE_DECIMALS).unwrap();
+            let back = shares_to_assets(&shares, &rate, RATE_DECIMALS);
+            assert!(back <= assets, "{} -> {} shares -> {} at rate {}", assets, shares, back, rate);
+            
+            // And the other way round: shares -> assets -> shares
+            let assets_out = shares_to_assets(&assets, &rate, RATE_DECIMALS);
+            assert!(assets_to_shares(&assets_out, &rate, RATE_DECIMALS).unwrap() <= assets);
+        }
+    }
+    
+    #[test]
+    fn round_trip_loses_at_most_one_share_worth() {
+        for (index, raw) in samples(42, 500).enumerate() {
+            let decimals = [6, 8, RATE_DECIMALS][index % 3];
+            let one = BigUint::from(10u32).pow(decimals);
+            let rate = &one + BigUint::from(raw % 1_000_000);
+            let assets = BigUint::from(raw);
+            
+            let back = shares_to_assets(&assets_to_shares(&assets, &rate, decimals).unwrap(), &rate, decimals);
+            // Flooring twice loses less than the value of one share plus one unit
+            assert!(&assets - &back <= &rate / &one + 1u32);
+        }
+    }
+    
+    #[test]
+    fn conversions_at_one_to_one_are_exact() {
+        let amount = BigUint::from(123_456_789u64) * BigUint::from(WAD);
+        let rate = BigUint::from(WAD);
+        assert_eq!(assets_to_shares(&amount, &rate, RATE_DECIMALS).unwrap(), amount);
+        assert_eq!(shares_to_assets(&amount, &rate, RATE_DECIMALS), amount);
+        assert_eq!(shares_to_assets(&BigUint::from(3u32), &BigUint::from(1_500_000u32), 6), BigUint::from(4u32));
+    }
+    
+    #[test]
+    fn check_deposit_refuses_emergency_mode() {
+        let err = config_with_limits(0, 0, true).check_deposit(&BigUint::from(1u32), &BigUint::from(0u32), &AmountFormat::default()).unwrap_err();
+        assert!(err.to_string().contains("emergency"));
     }
 }
@@ -0,0 +1,468 @@
+//! Full-screen live dashboard for `protocol dashboard`
+//!
+//! This module only renders and handles keys; the protocol command supplies a
+//! fresh `Snapshot` on every refresh. The terminal is put back into its normal
+//! state on quit, on error and on panic.
+
+use anyhow::{Result, Context};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use futures::FutureExt;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table};
+use rust_decimal::Decimal;
+use starknet::core::types::FieldElement;
+use std::future::Future;
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+
+use crate::shutdown::shutdown_signal;
+use crate::theme;
+use crate::utils::{format_duration, format_percentage, format_timestamp};
+
+/// How long to wait for a key press before redrawing
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Protocol state read on one dashboard refresh
+///
+/// Panels whose contracts are not configured are `None` and shown as such.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub tvl: String,
+    pub total_shares: String,
+    pub exchange_rate: f64,
+    pub emergency_mode: bool,
+    pub management_fee: f64,
+    pub performance_fee: f64,
+    pub net_apy: Decimal,
+    pub delta: Option<DeltaPanel>,
+    pub risk: Option<RiskPanel>,
+    pub rebalances: Option<RebalancePanel>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeltaPanel {
+    pub perpetual: f64,
+    pub options: f64,
+    pub net: f64,
+    pub threshold: f64,
+    /// Net delta as a fraction of the threshold (1.0 = rebalance due)
+    pub deviation: f64,
+    pub status: &'static str,
+    pub status_color: owo_colors::Rgb,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskPanel {
+    pub risk_score: u8,
+    pub leverage: f64,
+    pub liquidity: f64,
+    pub drawdown_percent: f64,
+    pub var_percent: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RebalancePanel {
+    /// Unix timestamp of the last executed rebalance, 0 if there has been none
+    pub last_rebalance: u64,
+    pub paused: bool,
+    /// Most recent first
+    pub recent: Vec<RebalanceEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RebalanceEvent {
+    pub block: Option<u64>,
+    pub transaction: FieldElement,
+    pub keeper: Option<FieldElement>,
+}
+
+/// A dashboard panel, in focus order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Tvl,
+    Delta,
+    Risk,
+    Fees,
+    Rebalances,
+}
+
+impl Panel {
+    const ALL: [Panel; 5] = [Panel::Tvl, Panel::Delta, Panel::Risk, Panel::Fees, Panel::Rebalances];
+    
+    fn title(self) -> &'static str {
+        match self {
+            Panel::Tvl => "TVL",
+            Panel::Delta => "Delta",
+            Panel::Risk => "Risk",
+            Panel::Fees => "Fees",
+            Panel::Rebalances => "Recent Rebalances",
+        }
+    }
+    
+    fn index(self) -> usize {
+        Panel::ALL.iter().position(|panel| *panel == self).unwrap_or(0)
+    }
+    
+    fn next(self) -> Panel {
+        Panel::ALL[(self.index() + 1) % Panel::ALL.len()]
+    }
+    
+    fn previous(self) -> Panel {
+        Panel::ALL[(self.index() + Panel::ALL.len() - 1) % Panel::ALL.len()]
+    }
+}
+
+/// What the event loop should do after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Continue,
+    Refresh,
+    Quit,
+}
+
+/// Focus, zoom and the latest data shown on screen
+struct Dashboard {
+    focus: Panel,
+    zoomed: bool,
+    snapshot: Option<Snapshot>,
+    /// Set when the last refresh failed; the previous snapshot stays on screen
+    error: Option<String>,
+    updated_at: Option<u64>,
+}
+
+impl Dashboard {
+    fn new() -> Dashboard {
+        Dashboard { focus: Panel::Tvl, zoomed: false, snapshot: None, error: None, updated_at: None }
+    }
+    
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        // Windows reports releases too; only act once per press
+        if key.kind != KeyEventKind::Press {
+            return Action::Continue;
+        }
+        
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Char('q') => Action::Quit,
+            KeyCode::Esc if self.zoomed => {
+                self.zoomed = false;
+                Action::Continue
+            }
+            KeyCode::Esc => Action::Quit,
+            KeyCode::Char('r') => Action::Refresh,
+            KeyCode::Tab | KeyCode::Right | KeyCode::Down | KeyCode::Char('l') | KeyCode::Char('j') => {
+                self.focus = self.focus.next();
+                Action::Continue
+            }
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Up | KeyCode::Char('h') | KeyCode::Char('k') => {
+                self.focus = self.focus.previous();
+                Action::Continue
+            }
+            KeyCode::Char(digit @ '1'..='5') => {
+                self.focus = Panel::ALL[digit as usize - '1' as usize];
+                Action::Continue
+            }
+            KeyCode::Enter | KeyCode::Char('z') => {
+                self.zoomed = !self.zoomed;
+                Action::Continue
+            }
+            _ => Action::Continue,
+        }
+    }
+    
+    fn render(&self, frame: &mut Frame, title: &str, interval: Duration) {
+        let [header, body, footer] = split(Direction::Vertical, frame.size(), [
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]);
+        
+        let updated = match self.updated_at {
+            Some(timestamp) => format!("Updated {}", format_timestamp(timestamp)),
+            None => "Loading...".to_string(),
+        };
+        let heading = Line::from(vec![
+            Span::styled(title.to_string(), style(theme::PRIMARY).add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {} · refresh every {}s", updated, interval.as_secs()), style(theme::MUTED)),
+        ]);
+        frame.render_widget(
+            Paragraph::new(heading).block(Block::default().borders(Borders::ALL).border_style(style(theme::MUTED))),
+            header,
+        );
+        
+        if self.zoomed {
+            self.render_panel(frame, self.focus, body);
+        } else {
+            let [top, middle, bottom] = split(Direction::Vertical, body, [
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Min(5),
+            ]);
+            let [tvl, delta] = split(Direction::Horizontal, top, [Constraint::Percentage(50), Constraint::Percentage(50)]);
+            let [risk, fees] = split(Direction::Horizontal, middle, [Constraint::Percentage(50), Constraint::Percentage(50)]);
+            
+            self.render_panel(frame, Panel::Tvl, tvl);
+            self.render_panel(frame, Panel::Delta, delta);
+            self.render_panel(frame, Panel::Risk, risk);
+            self.render_panel(frame, Panel::Fees, fees);
+            self.render_panel(frame, Panel::Rebalances, bottom);
+        }
+        
+        let status = match &self.error {
+            Some(error) => Line::from(Span::styled(format!("Refresh failed: {}", error), style(theme::ERROR))),
+            None => Line::from(Span::styled(
+                "q quit · r refresh · tab/arrows move · 1-5 jump · enter zoom",
+                style(theme::MUTED),
+            )),
+        };
+        frame.render_widget(Paragraph::new(status), footer);
+    }
+    
+    fn render_panel(&self, frame: &mut Frame, panel: Panel, area: Rect) {
+        let border = if panel == self.focus { theme::ACCENT } else { theme::MUTED };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(style(border))
+            .title(Span::styled(format!(" {} ", panel.title()), style(theme::ACCENT)));
+        
+        let Some(snapshot) = &self.snapshot else {
+            frame.render_widget(Paragraph::new(Span::styled("Loading...", style(theme::MUTED))).block(block), area);
+            return;
+        };
+        
+        if panel == Panel::Rebalances {
+            return render_rebalances(frame, snapshot.rebalances.as_ref(), block, area);
+        }
+        
+        let lines = match panel {
+            Panel::Tvl => vec![
+                metric("Total Value Locked", snapshot.tvl.clone(), theme::SUCCESS),
+                metric("hSTRK Supply", snapshot.total_shares.clone(), theme::PRIMARY),
+                metric("Exchange Rate", format!("{:.6}", snapshot.exchange_rate), theme::SECONDARY),
+                if snapshot.emergency_mode {
+                    metric("Vault", "EMERGENCY MODE".to_string(), theme::ERROR)
+                } else {
+                    metric("Vault", "Normal".to_string(), theme::SUCCESS)
+                },
+            ],
+            Panel::Delta => match &snapshot.delta {
+                Some(delta) => vec![
+                    metric("Perpetual Hedge", format!("{:+.6}", delta.perpetual), theme::SECONDARY),
+                    metric("Options Strategy", format!("{:+.6}", delta.options), theme::SECONDARY),
+                    metric("Net Delta", format!("{:+.6}", delta.net), delta.status_color),
+                    metric("Threshold", format!("±{:.6} ({:.1}% used)", delta.threshold, delta.deviation * 100.0), theme::INFO),
+                    metric("Status", delta.status.to_string(), delta.status_color),
+                ],
+                None => not_configured("perpetual_hedge, options_strategy and rebalancing_engine"),
+            },
+            Panel::Risk => match &snapshot.risk {
+                Some(risk) => vec![
+                    metric("Risk Score", format!("{}/100", risk.risk_score), theme::SUCCESS),
+                    metric("Leverage", format!("{:.2}x", risk.leverage), theme::WARNING),
+                    metric("Liquidity", format!("{:.1}%", risk.liquidity * 100.0), theme::SUCCESS),
+                    metric("Drawdown", format!("{:.2}%", risk.drawdown_percent), theme::WARNING),
+                    metric("Value at Risk", format!("{:.2}%", risk.var_percent), theme::INFO),
+                ],
+                None => not_configured("risk_manager"),
+            },
+            Panel::Fees => vec![
+                metric("Management Fee", format!("{:.2}%", snapshot.management_fee), theme::SECONDARY),
+                metric("Performance Fee", format!("{:.2}%", snapshot.performance_fee), theme::SECONDARY),
+                metric("Net APY (30d)", format_percentage(snapshot.net_apy * Decimal::ONE_HUNDRED), theme::SUCCESS),
+            ],
+            Panel::Rebalances => unreachable!("rendered as a table above"),
+        };
+        
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+fn render_rebalances(frame: &mut Frame, rebalances: Option<&RebalancePanel>, block: Block, area: Rect) {
+    let Some(rebalances) = rebalances else {
+        frame.render_widget(Paragraph::new(not_configured("rebalancing_engine")).block(block), area);
+        return;
+    };
+    
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [summary, list] = split(Direction::Vertical, inner, [Constraint::Length(2), Constraint::Min(0)]);
+    
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    let last = match rebalances.last_rebalance {
+        0 => "never".to_string(),
+        timestamp => format!("{} ({} ago)", format_timestamp(timestamp), format_duration(now.saturating_sub(timestamp))),
+    };
+    let engine = if rebalances.paused {
+        metric("Engine", "Paused".to_string(), theme::WARNING)
+    } else {
+        metric("Engine", "Active".to_string(), theme::SUCCESS)
+    };
+    frame.render_widget(Paragraph::new(vec![metric("Last Rebalance", last, theme::INFO), engine]), summary);
+    
+    let rows = rebalances.recent.iter().map(|event| {
+        Row::new(vec![
+            Span::styled(event.block.map(|block| block.to_string()).unwrap_or_else(|| "pending".to_string()), style(theme::MUTED)),
+            Span::styled(event.keeper.map(|keeper| format!("{:#x}", keeper)).unwrap_or_default(), style(theme::INFO)),
+            Span::styled(format!("{:#x}", event.transaction), style(theme::PRIMARY)),
+        ])
+    });
+    let widths = [Constraint::Length(10), Constraint::Percentage(40), Constraint::Percentage(50)];
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Block", "Keeper", "Transaction"]).style(style(theme::SECONDARY).add_modifier(Modifier::BOLD)))
+        .widths(&widths);
+    
+    if rebalances.recent.is_empty() {
+        frame.render_widget(Paragraph::new(Span::styled("No recent rebalances", style(theme::MUTED))), list);
+    } else {
+        frame.render_widget(table, list);
+    }
+}
+
+/// Run the dashboard until the user quits or SIGTERM arrives, calling `refresh` every `interval`
+///
+/// A failed refresh is shown in the footer and the previous data stays on screen,
+/// so a flaky RPC node does not end a monitoring session.
+pub async fn run<F, Fut>(title: &str, interval: Duration, mut refresh: F) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Snapshot>>,
+{
+    let mut terminal = TerminalGuard::enter()?;
+    let mut dashboard = Dashboard::new();
+    let mut next_refresh = Instant::now();
+    // Raw mode turns Ctrl+C into a key press, so this mostly catches SIGTERM
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    
+    loop {
+        if shutdown.as_mut().now_or_never().is_some() {
+            break;
+        }
+        if Instant::now() >= next_refresh {
+            match refresh().await {
+                Ok(snapshot) => {
+                    dashboard.snapshot = Some(snapshot);
+                    dashboard.error = None;
+                    dashboard.updated_at = Some(chrono::Utc::now().timestamp().max(0) as u64);
+                }
+                Err(e) => dashboard.error = Some(format!("{:#}", e)),
+            }
+            next_refresh = Instant::now() + interval;
+        }
+        
+        terminal.0.draw(|frame| dashboard.render(frame, title, interval))
+            .context("Failed to draw dashboard")?;
+        
+        if event::poll(POLL_INTERVAL).context("Failed to read terminal input")? {
+            if let Event::Key(key) = event::read().context("Failed to read terminal input")? {
+                match dashboard.handle_key(key) {
+                    Action::Quit => break,
+                    Action::Refresh => next_refresh = Instant::now(),
+                    Action::Continue => {}
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Raw mode and the alternate screen, undone when dropped
+struct TerminalGuard(Terminal<CrosstermBackend<Stdout>>);
+
+impl TerminalGuard {
+    fn enter() -> Result<TerminalGuard> {
+        // Restore before the panic message is printed, or it is lost on the alternate screen
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+        
+        enable_raw_mode().context("Failed to switch the terminal to raw mode")?;
+        let terminal = execute!(io::stdout(), EnterAlternateScreen)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Terminal::new(CrosstermBackend::new(io::stdout())).map_err(anyhow::Error::from));
+        
+        match terminal {
+            Ok(terminal) => Ok(TerminalGuard(terminal)),
+            Err(e) => {
+                restore_terminal();
+                Err(e.context("Failed to open the dashboard screen"))
+            }
+        }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+        let _ = self.0.show_cursor();
+    }
+}
+
+/// Best-effort return to the normal screen; safe to call more than once
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+}
+
+fn split<const N: usize>(direction: Direction, area: Rect, constraints: [Constraint; N]) -> [Rect; N] {
+    let chunks = Layout::default().direction(direction).constraints(constraints).split(area);
+    std::array::from_fn(|i| chunks[i])
+}
+
+fn style(color: owo_colors::Rgb) -> Style {
+    Style::default().fg(Color::Rgb(color.0, color.1, color.2))
+}
+
+fn metric(label: &str, value: String, color: owo_colors::Rgb) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{}: ", label), style(theme::MUTED)),
+        Span::styled(value, style(color)),
+    ])
+}
+
+fn not_configured(contracts: &str) -> Vec<Line<'static>> {
+    vec![
+        Line::from(Span::styled("not configured", style(theme::MUTED))),
+        Line::from(Span::styled(format!("Set contracts.{} to enable this panel", contracts), style(theme::MUTED))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+    
+    #[test]
+    fn focus_wraps_in_both_directions() {
+        let mut dashboard = Dashboard::new();
+        
+        assert_eq!(dashboard.handle_key(press(KeyCode::BackTab)), Action::Continue);
+        assert_eq!(dashboard.focus, Panel::Rebalances);
+        dashboard.handle_key(press(KeyCode::Tab));
+        assert_eq!(dashboard.focus, Panel::Tvl);
+        dashboard.handle_key(press(KeyCode::Char('4')));
+        assert_eq!(dashboard.focus, Panel::Fees);
+    }
+    
+    #[test]
+    fn escape_leaves_zoom_before_quitting() {
+        let mut dashboard = Dashboard::new();
+        
+        dashboard.handle_key(press(KeyCode::Enter));
+        assert!(dashboard.zoomed);
+        assert_eq!(dashboard.handle_key(press(KeyCode::Esc)), Action::Continue);
+        assert!(!dashboard.zoomed);
+        assert_eq!(dashboard.handle_key(press(KeyCode::Esc)), Action::Quit);
+        
+        assert_eq!(dashboard.handle_key(press(KeyCode::Char('r'))), Action::Refresh);
+        assert_eq!(dashboard.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), Action::Quit);
+    }
+}
@@ -0,0 +1,299 @@
+//! Deployment plans and the `deployment.json` manifest that makes `contract deploy` resumable
+
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use starknet::accounts::Call;
+use starknet::core::types::FieldElement;
+use starknet::core::utils::{get_udc_deployed_address, UdcUniqueSettings, UdcUniqueness};
+use std::fs;
+
+use crate::contracts::calldata::CalldataBuilder;
+use crate::contracts::selectors;
+use crate::utils::{parse_felt, parse_felt_arg};
+
+/// Manifest written by `contract deploy` when no path is given
+pub const DEFAULT_MANIFEST: &str = "deployment.json";
+
+/// Universal Deployer Contract, at the same address on mainnet and sepolia
+pub const UDC_ADDRESS: &str = "0x041a78e741e5af2fec34b695679bc6891742439f7afb8484ecd7766661ad02bf";
+
+/// Constructor argument standing for the deploying account's address
+const DEPLOYER_REF: &str = "@deployer";
+
+/// Contracts to deploy, in order, read from the `contract deploy --config` file
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeploymentPlan {
+    pub contracts: Vec<PlannedContract>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlannedContract {
+    pub name: String,
+    /// Class hash of the already-declared class
+    pub class_hash: String,
+    /// Felt arguments; `@<name>` is the address of an earlier contract and `@deployer` the deploying account
+    #[serde(default)]
+    pub constructor_args: Vec<String>,
+}
+
+impl DeploymentPlan {
+    /// Load a TOML plan, checking names are unique and references point backwards
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read deployment plan {}", path))?;
+        let plan: DeploymentPlan = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse deployment plan {}", path))?;
+        plan.validate()?;
+        Ok(plan)
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if self.contracts.is_empty() {
+            return Err(anyhow::anyhow!("The deployment plan lists no contracts"));
+        }
+        
+        for (index, contract) in self.contracts.iter().enumerate() {
+            let earlier = &self.contracts[..index];
+            if earlier.iter().any(|other| other.name == contract.name) {
+                return Err(anyhow::anyhow!("Contract '{}' appears twice in the deployment plan", contract.name));
+            }
+            parse_felt(&contract.class_hash)
+                .with_context(|| format!("Invalid class hash for '{}'", contract.name))?;
+            
+            for arg in &contract.constructor_args {
+                match arg.strip_prefix('@') {
+                    Some(_) if arg == DEPLOYER_REF => {}
+                    Some(name) => {
+                        if !earlier.iter().any(|other| other.name == name) {
+                            return Err(anyhow::anyhow!(
+                                "'{}' refers to {}, which is not deployed before it",
+                                contract.name,
+                                arg
+                            ));
+                        }
+                    }
+                    None => {
+                        parse_felt_arg(arg)
+                            .with_context(|| format!("Invalid constructor argument for '{}'", contract.name))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Record of a deployment, saved after every contract so an interrupted run can resume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentManifest {
+    pub network: String,
+    pub chain_id: String,
+    pub deployer: String,
+    pub contracts: Vec<DeployedContract>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployedContract {
+    pub name: String,
+    pub class_hash: String,
+    pub address: String,
+    /// Constructor calldata as sent, with references resolved
+    pub constructor_args: Vec<String>,
+    pub salt: String,
+    pub transaction_hash: String,
+    pub block: Option<u64>,
+    /// Unix timestamp of when the deployment was confirmed
+    pub timestamp: u64,
+}
+
+impl DeploymentManifest {
+    pub fn new(network: &str, chain_id: &str, deployer: FieldElement) -> Self {
+        DeploymentManifest {
+            network: network.to_string(),
+            chain_id: chain_id.to_string(),
+            deployer: format!("{:#x}", deployer),
+            contracts: Vec::new(),
+        }
+    }
+    
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read deployment manifest {}", path))?;
+        
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse deployment manifest {}", path))
+    }
+    
+    /// Write the manifest as pretty-printed JSON
+    pub fn save(&self, path: &str) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize deployment manifest")?;
+        
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write deployment manifest {}", path))
+    }
+    
+    pub fn get(&self, name: &str) -> Option<&DeployedContract> {
+        self.contracts.iter().find(|contract| contract.name == name)
+    }
+    
+    /// Contracts of `plan` that still need deploying, in plan order
+    ///
+    /// A contract already in the manifest is skipped, but only if it was deployed
+    /// from the class hash the plan now asks for.
+    pub fn pending<'a>(&self, plan: &'a DeploymentPlan) -> Result<Vec<&'a PlannedContract>> {
+        let mut pending = Vec::new();
+        for planned in &plan.contracts {
+            match self.get(&planned.name) {
+                Some(deployed) => {
+                    if parse_felt(&deployed.class_hash)? != parse_felt(&planned.class_hash)? {
+                        return Err(anyhow::anyhow!(
+                            "'{}' was deployed from class {} but the plan now uses {}; start a new manifest to redeploy it",
+                            planned.name,
+                            deployed.class_hash,
+                            planned.class_hash
+                        ));
+                    }
+                }
+                None => pending.push(planned),
+            }
+        }
+        Ok(pending)
+    }
+    
+    /// Constructor calldata for `contract`, with `@` references replaced by deployed addresses
+    pub fn resolve_args(&self, contract: &PlannedContract) -> Result<Vec<FieldElement>> {
+        contract.constructor_args
+            .iter()
+            .map(|arg| match arg.strip_prefix('@') {
+                Some(_) if arg == DEPLOYER_REF => parse_felt(&self.deployer),
+                Some(name) => {
+                    let deployed = self.get(name)
+                        .with_context(|| format!("'{}' needs {} to be deployed first", contract.name, arg))?;
+                    parse_felt(&deployed.address)
+                }
+                None => parse_felt_arg(arg),
+            })
+            .collect()
+    }
+}
+
+/// A random salt, so repeated plans never collide on an address
+pub fn random_salt() -> FieldElement {
+    let mut bytes = [0u8; 32];
+    bytes[16..].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+    FieldElement::from_bytes_be(&bytes).expect("128-bit salt fits in a felt")
+}
+
+/// UDC `deployContract` call for a unique deployment, and the address it will deploy to
+pub fn udc_deploy(
+    deployer: FieldElement,
+    class_hash: FieldElement,
+    salt: FieldElement,
+    constructor_args: &[FieldElement],
+) -> Result<(Call, FieldElement)> {
+    let udc = parse_felt(UDC_ADDRESS)?;
+    let calldata = CalldataBuilder::new()
+        .push_felt(class_hash)
+        .push_felt(salt)
+        .push_bool(true)
+        .push_array(constructor_args, |builder, arg| Ok(builder.push_felt(*arg)))?
+        .build();
+    let call = Call {
+        to: udc,
+        selector: selectors::selector("deployContract")?,
+        calldata,
+    };
+    
+    let uniqueness = UdcUniqueness::Unique(UdcUniqueSettings {
+        deployer_address: deployer,
+        udc_contract_address: udc,
+    });
+    let address = get_udc_deployed_address(salt, class_hash, &uniqueness, constructor_args);
+    
+    Ok((call, address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const PLAN: &str = r#"
+        [[contracts]]
+        name = "hstrk_token"
+        class_hash = "0x111"
+        constructor_args = ["'hSTRK'", "@deployer"]
+        
+        [[contracts]]
+        name = "price_oracle"
+        class_hash = "0x222"
+        
+        [[contracts]]
+        name = "vault"
+        class_hash = "0x333"
+        constructor_args = ["@hstrk_token", "@price_oracle", "1000"]
+    "#;
+    
+    fn deployed(name: &str, class_hash: &str, address: &str) -> DeployedContract {
+        DeployedContract {
+            name: name.to_string(),
+            class_hash: class_hash.to_string(),
+            address: address.to_string(),
+            constructor_args: Vec::new(),
+            salt: "0x1".to_string(),
+            transaction_hash: "0xabc".to_string(),
+            block: Some(10),
+            timestamp: 1_700_000_000,
+        }
+    }
+    
+    fn plan() -> DeploymentPlan {
+        let plan: DeploymentPlan = toml::from_str(PLAN).unwrap();
+        plan.validate().unwrap();
+        plan
+    }
+    
+    #[test]
+    fn resume_skips_contracts_in_a_partial_manifest() {
+        let plan = plan();
+        let mut manifest = DeploymentManifest::new("sepolia", "0x534e5f5345504f4c4941", FieldElement::from(0xdeu32));
+        
+        let names = |pending: Vec<&PlannedContract>| pending.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(manifest.pending(&plan).unwrap()), vec!["hstrk_token", "price_oracle", "vault"]);
+        
+        // Interrupted after the first two contracts
+        manifest.contracts.push(deployed("hstrk_token", "0x111", "0xaaa"));
+        manifest.contracts.push(deployed("price_oracle", "0x0222", "0xbbb"));
+        let manifest: DeploymentManifest = serde_json::from_str(&serde_json::to_string(&manifest).unwrap()).unwrap();
+        
+        let pending = manifest.pending(&plan).unwrap();
+        assert_eq!(names(pending.clone()), vec!["vault"]);
+        assert_eq!(
+            manifest.resolve_args(pending[0]).unwrap(),
+            vec![FieldElement::from(0xaaau32), FieldElement::from(0xbbbu32), FieldElement::from(1000u32)]
+        );
+        
+        let token = &plan.contracts[0];
+        assert_eq!(manifest.resolve_args(token).unwrap()[1], FieldElement::from(0xdeu32));
+    }
+    
+    #[test]
+    fn resume_refuses_a_contract_deployed_from_another_class() {
+        let plan = plan();
+        let mut manifest = DeploymentManifest::new("sepolia", "0x534e5f5345504f4c4941", FieldElement::ONE);
+        manifest.contracts.push(deployed("hstrk_token", "0x999", "0xaaa"));
+        
+        assert!(manifest.pending(&plan).unwrap_err().to_string().contains("was deployed from class 0x999"));
+    }
+    
+    #[test]
+    fn plan_rejects_forward_references_and_duplicates() {
+        let forward = PLAN.replace(r#"constructor_args = ["'hSTRK'", "@deployer"]"#, r#"constructor_args = ["@vault"]"#);
+        let plan: DeploymentPlan = toml::from_str(&forward).unwrap();
+        assert!(plan.validate().is_err());
+        
+        let duplicate = PLAN.replace(r#"name = "price_oracle""#, r#"name = "hstrk_token""#);
+        let plan: DeploymentPlan = toml::from_str(&duplicate).unwrap();
+        assert!(plan.validate().is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! Opinionated configuration checks for `config doctor`
+//!
+//! `config validate` only checks that values parse. The doctor also asks the node
+//! whether the account, chain and contracts are what the config claims, and ranks
+//! what it finds so the most dangerous problem is fixed first.
+
+use num_bigint::BigUint;
+use serde::Serialize;
+use starknet::core::types::{BlockId, BlockTag, FieldElement};
+use starknet::providers::Provider;
+use starknet::signers::SigningKey;
+use std::path::Path;
+
+use crate::config::{is_configured, Config, ETH_TOKEN_ADDRESS, LEAKED_DEFAULT_PRIVATE_KEY};
+use crate::contracts::BlockReader;
+use crate::contracts::account::AccountContract;
+use crate::contracts::token::TokenContract;
+use crate::deployment::{DeploymentManifest, DEFAULT_MANIFEST};
+use crate::utils::{configured_chain_id, get_provider, normalize_address, parse_felt};
+
+/// How urgently a finding needs fixing; findings are listed most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Funds are at risk or nothing can be sent
+    Critical,
+    /// Some commands will fail
+    Error,
+    /// Likely a mistake, but commands may still work
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Critical => write!(f, "critical"),
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    /// Short name of the check that raised it
+    pub check: &'static str,
+    pub problem: String,
+    pub fix: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, check: &'static str, problem: impl Into<String>, fix: impl Into<String>) -> Finding {
+        Finding { severity, check, problem: problem.into(), fix: fix.into() }
+    }
+}
+
+/// Chain ID a network name implies, for the networks the CLI ships templates for
+pub fn expected_chain_id(network: &str) -> Option<&'static str> {
+    match network.to_lowercase().as_str() {
+        "mainnet" => Some("SN_MAIN"),
+        "sepolia" => Some("SN_SEPOLIA"),
+        "testnet" | "goerli" => Some("SN_GOERLI"),
+        _ => None,
+    }
+}
+
+/// Whether the config still holds the private key earlier versions wrote into new configs
+///
+/// That key is public in this repository, so anything it controls can be drained by anyone.
+pub fn uses_default_key(config: &Config) -> bool {
+    match (parse_felt(&config.private_key), parse_felt(LEAKED_DEFAULT_PRIVATE_KEY)) {
+        (Ok(key), Ok(default)) => key == default,
+        _ => false,
+    }
+}
+
+/// Checks that need no network access
+pub fn offline_findings(config: &Config) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    
+    if let Err(e) = config.validate() {
+        findings.push(Finding::new(Severity::Critical, "config", format!("{:#}", e), "Fix the value with `config set`, or start over with `config init`"));
+    }
+    if uses_default_key(config) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "private key",
+            "The private key is the public default from the CLI source; anyone can sign for this account",
+            "Create your own account and run `config rotate-key`; never hold funds under the default key",
+        ));
+    }
+    if let Err(e) = configured_chain_id(config) {
+        findings.push(Finding::new(Severity::Error, "chain id", format!("{:#}", e), "Set chain_id to SN_MAIN, SN_SEPOLIA, a hex value, or `auto` to fetch it from the node"));
+    }
+    
+    findings
+}
+
+/// Compare a configured contract with the deployment manifest entry of the same name
+pub fn manifest_finding(name: &str, address: FieldElement, class_hash: FieldElement, manifest: &DeploymentManifest) -> Option<Finding> {
+    let deployed = manifest.get(name)?;
+    let (recorded_address, recorded_class) = (parse_felt(&deployed.address).ok()?, parse_felt(&deployed.class_hash).ok()?);
+    
+    if recorded_address != address {
+        return Some(Finding::new(
+            Severity::Warning,
+            "contracts",
+            format!("contracts.{} is {}, but {} recorded {}", name, normalize_address(address), DEFAULT_MANIFEST, normalize_address(recorded_address)),
+            format!("Run `config set contracts.{} {}` if the manifest is current", name, deployed.address),
+        ));
+    }
+    if recorded_class != class_hash {
+        return Some(Finding::new(
+            Severity::Warning,
+            "contracts",
+            format!("contracts.{} now runs class {:#x}, not the deployed {:#x}", name, class_hash, recorded_class),
+            "Check that the upgrade was intended; the CLI may not match the new class",
+        ));
+    }
+    None
+}
+
+/// Run every check, most severe findings first
+pub async fn diagnose(config: &Config) -> Vec<Finding> {
+    let mut findings = offline_findings(config);
+    if findings.iter().any(|finding| finding.check == "config") {
+        findings.sort_by_key(|finding| finding.severity);
+        return findings;
+    }
+    
+    match get_provider(config) {
+        Ok(provider) => findings.extend(online_findings(config, &provider).await),
+        Err(e) => findings.push(Finding::new(Severity::Critical, "rpc", format!("{:#}", e), "Set rpc_url to a Starknet JSON-RPC endpoint")),
+    }
+    
+    findings.sort_by_key(|finding| finding.severity);
+    findings
+}
+
+async fn online_findings<P: Provider + Sync>(config: &Config, provider: &P) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    
+    let node_chain = match provider.chain_id().await {
+        Ok(chain_id) => chain_id,
+        Err(e) => {
+            findings.push(Finding::new(
+                Severity::Critical,
+                "rpc",
+                format!("The RPC endpoint {} did not answer: {}", config.rpc_url, e),
+                "Check rpc_url, [rpc.headers] and your connection; `config probe-rpc` shows what the endpoint supports",
+            ));
+            return findings;
+        }
+    };
+    findings.extend(chain_findings(config, node_chain));
+    
+    let latest = BlockId::Tag(BlockTag::Latest);
+    
+    // Key controls address
+    if let Ok(account) = parse_felt(&config.account_address) {
+        match provider.get_class_hash_at(latest, account).await {
+            Err(_) => findings.push(Finding::new(
+                Severity::Critical,
+                "account",
+                format!("No account is deployed at {} on this network", normalize_address(account)),
+                "Deploy the account first, or set account_address to the deployed one",
+            )),
+            Ok(_) => {
+                let derived = parse_felt(&config.private_key)
+                    .map(|key| SigningKey::from_secret_scalar(key).verifying_key().scalar());
+                match (derived, AccountContract::with_address(BlockReader::new(provider, None), account).public_key().await) {
+                    (Ok(derived), Ok(on_chain)) if derived != on_chain => findings.push(Finding::new(
+                        Severity::Critical,
+                        "account",
+                        format!("The private key derives public key {:#x}, but the account expects {:#x}", derived, on_chain),
+                        "Use the key that controls this account: `config rotate-key` checks it before saving",
+                    )),
+                    (Ok(_), Err(_)) => findings.push(Finding::new(
+                        Severity::Warning,
+                        "account",
+                        "Could not read the account's public key, so the private key was not checked against it",
+                        "The account may use a non-standard getter; sending a small transaction will confirm the key",
+                    )),
+                    _ => {}
+                }
+                
+                // Sent transactions are v1, so fees are paid in ETH whatever transaction.fee_token says
+                let eth = FieldElement::from_hex_be(ETH_TOKEN_ADDRESS).expect("the ETH token address is valid hex");
+                match TokenContract::with_address(BlockReader::new(provider, None), eth).balance_of(account).await {
+                    Ok(balance) if balance == BigUint::from(0u32) => findings.push(Finding::new(
+                        Severity::Error,
+                        "fee token",
+                        "The account holds no ETH to pay transaction fees",
+                        format!("Fund {} with ETH", normalize_address(account)),
+                    )),
+                    Ok(_) => {}
+                    Err(e) => findings.push(Finding::new(
+                        Severity::Warning,
+                        "fee token",
+                        format!("Could not read the ETH balance: {:#}", e),
+                        "Check the RPC node",
+                    )),
+                }
+            }
+        }
+    }
+    
+    // Contracts are deployed, and match the deployment manifest when there is one
+    let manifest = Path::new(DEFAULT_MANIFEST).exists()
+        .then(|| DeploymentManifest::load(DEFAULT_MANIFEST).ok())
+        .flatten();
+    for (name, address) in config.contracts.entries() {
+        if !is_configured(address) {
+            continue;
+        }
+        let Ok(address) = parse_felt(address) else { continue };
+        match provider.get_class_hash_at(latest, address).await {
+            Ok(class_hash) => {
+                if let Some(finding) = manifest.as_ref().and_then(|manifest| manifest_finding(name, address, class_hash, manifest)) {
+                    findings.push(finding);
+                }
+            }
+            Err(_) => findings.push(Finding::new(
+                Severity::Error,
+                "contracts",
+                format!("Nothing is deployed at contracts.{} ({}) on this network", name, normalize_address(address)),
+                format!("Set contracts.{} to the {} deployment, or to 0x0 if you do not use it", name, config.network),
+            )),
+        }
+    }
+    
+    findings
+}
+
+/// Compare the node's chain with the configured `chain_id` and `network`
+pub fn chain_findings(config: &Config, node_chain: FieldElement) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let node_name = starknet::core::utils::parse_cairo_short_string(&node_chain)
+        .unwrap_or_else(|_| format!("{:#x}", node_chain));
+    
+    if let Ok(Some(configured)) = configured_chain_id(config) {
+        if configured != node_chain {
+            findings.push(Finding::new(
+                Severity::Critical,
+                "chain id",
+                format!("chain_id is {} but the RPC node is on {}; every signed transaction will be rejected", config.chain_id, node_name),
+                format!("Run `config set chain_id {}`, or `config set chain_id auto`", node_name),
+            ));
+        }
+    }
+    if let Some(expected) = expected_chain_id(&config.network) {
+        if expected != node_name {
+            findings.push(Finding::new(
+                Severity::Error,
+                "network",
+                format!("network is {} but the RPC node is on {}", config.network, node_name),
+                "Point rpc_url at a node for the configured network, or correct `network`",
+            ));
+        }
+    }
+    
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::DeployedContract;
+    
+    fn sn(name: &str) -> FieldElement {
+        starknet::core::utils::cairo_short_string_to_felt(name).unwrap()
+    }
+    
+    #[test]
+    fn default_key_is_flagged_as_critical() {
+        let mut config = Config::default();
+        assert!(!uses_default_key(&config));
+        config.private_key = LEAKED_DEFAULT_PRIVATE_KEY.to_string();
+        assert!(uses_default_key(&config));
+        let findings = offline_findings(&config);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[0].check, "private key");
+        
+        let mut own = Config::default();
+        own.private_key = "0x1234".to_string();
+        assert!(!uses_default_key(&own));
+        assert!(offline_findings(&own).is_empty());
+    }
+    
+    #[test]
+    fn chain_mismatch_is_reported_against_chain_id_and_network() {
+        let mut config = Config::default();
+        config.chain_id = "SN_SEPOLIA".to_string();
+        config.network = "sepolia".to_string();
+        assert!(chain_findings(&config, sn("SN_SEPOLIA")).is_empty());
+        
+        let findings = chain_findings(&config, sn("SN_MAIN"));
+        let checks: Vec<_> = findings.iter().map(|finding| (finding.severity, finding.check)).collect();
+        assert_eq!(checks, vec![(Severity::Critical, "chain id"), (Severity::Error, "network")]);
+        assert!(findings[0].fix.contains("config set chain_id SN_MAIN"));
+        
+        // A fetched chain id and an unknown network name leave nothing to compare
+        config.chain_id = String::new();
+        config.network = "devnet".to_string();
+        assert!(chain_findings(&config, sn("SN_MAIN")).is_empty());
+    }
+    
+    #[test]
+    fn manifest_mismatches_are_warnings() {
+        let mut manifest = DeploymentManifest::new("sepolia", "SN_SEPOLIA", FieldElement::ONE);
+        manifest.contracts.push(DeployedContract {
+            name: "vault".to_string(),
+            class_hash: "0x333".to_string(),
+            address: "0xaaa".to_string(),
+            constructor_args: Vec::new(),
+            salt: "0x1".to_string(),
+            transaction_hash: "0xabc".to_string(),
+            block: None,
+            timestamp: 0,
+        });
+        let (address, class_hash) = (FieldElement::from(0xaaau32), FieldElement::from(0x333u32));
+        
+        assert!(manifest_finding("vault", address, class_hash, &manifest).is_none());
+        assert!(manifest_finding("price_oracle", address, class_hash, &manifest).is_none());
+        assert!(manifest_finding("vault", FieldElement::from(0xbbbu32), class_hash, &manifest).unwrap().problem.contains("recorded"));
+        assert!(manifest_finding("vault", address, FieldElement::from(0x444u32), &manifest).unwrap().problem.contains("not the deployed"));
+    }
+    
+    #[test]
+    fn severities_sort_most_urgent_first() {
+        let mut severities = vec![Severity::Warning, Severity::Critical, Severity::Error];
+        severities.sort();
+        assert_eq!(severities, vec![Severity::Critical, Severity::Error, Severity::Warning]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Where exports are written: a file, or stdout when `--out -` is given for piping
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Destination of an export, chosen with `--out <path|->`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportTarget {
+    Stdout,
+    File(PathBuf),
+}
+
+impl ExportTarget {
+    /// `-` means stdout; without `--out` the command's conventional file name is used
+    pub fn from_arg(out: Option<&str>, default: impl FnOnce() -> PathBuf) -> ExportTarget {
+        out.map(ExportTarget::parse).unwrap_or_else(|| ExportTarget::File(default()))
+    }
+    
+    pub fn parse(out: &str) -> ExportTarget {
+        match out {
+            "-" => ExportTarget::Stdout,
+            path => ExportTarget::File(PathBuf::from(path)),
+        }
+    }
+    
+    pub fn is_stdout(&self) -> bool {
+        matches!(self, ExportTarget::Stdout)
+    }
+    
+    /// Write a fully rendered export
+    pub fn write(&self, content: &str) -> Result<()> {
+        match self {
+            ExportTarget::Stdout => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(content.as_bytes())
+                    .and_then(|_| stdout.flush())
+                    .context("Failed to write the export to stdout")
+            }
+            ExportTarget::File(path) => fs::write(path, content)
+                .with_context(|| format!("Failed to write {}", path.display())),
+        }
+    }
+    
+    /// Open the target for writing piece by piece
+    ///
+    /// A file is written to `<path>.partial` and only moved into place by
+    /// [`ExportWriter::finish`], so a failed export never leaves a truncated file behind.
+    pub fn open(&self) -> Result<ExportWriter> {
+        match self {
+            ExportTarget::Stdout => Ok(ExportWriter { out: Box::new(io::stdout()), file: None, target: self.clone() }),
+            ExportTarget::File(path) => {
+                let partial = partial_path(path);
+                let file = File::create(&partial)
+                    .with_context(|| format!("Failed to create {}", partial.display()))?;
+                Ok(ExportWriter {
+                    out: Box::new(io::BufWriter::new(file)),
+                    file: Some(PartialFile { partial, finished: false }),
+                    target: self.clone(),
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExportTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportTarget::Stdout => write!(f, "stdout"),
+            ExportTarget::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
+
+struct PartialFile {
+    partial: PathBuf,
+    finished: bool,
+}
+
+/// An export being written incrementally; see [`ExportTarget::open`]
+pub struct ExportWriter {
+    out: Box<dyn Write>,
+    file: Option<PartialFile>,
+    target: ExportTarget,
+}
+
+impl ExportWriter {
+    pub fn write(&mut self, content: &str) -> Result<()> {
+        self.out.write_all(content.as_bytes())
+            .with_context(|| format!("Failed to write {}", self.destination()))
+    }
+    
+    pub fn flush(&mut self) -> Result<()> {
+        self.out.flush()
+            .with_context(|| format!("Failed to write {}", self.destination()))
+    }
+    
+    /// Flush and, for a file, move it from `<path>.partial` into place
+    pub fn finish(mut self) -> Result<ExportTarget> {
+        self.flush()?;
+        if let (Some(file), ExportTarget::File(path)) = (self.file.as_mut(), &self.target) {
+            fs::rename(&file.partial, path)
+                .with_context(|| format!("Failed to move {} to {}", file.partial.display(), path.display()))?;
+            file.finished = true;
+        }
+        Ok(self.target.clone())
+    }
+    
+    fn destination(&self) -> String {
+        match &self.file {
+            Some(file) => file.partial.display().to_string(),
+            None => self.target.to_string(),
+        }
+    }
+}
+
+impl Drop for ExportWriter {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.as_ref().filter(|file| !file.finished) {
+            let _ = fs::remove_file(&file.partial);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn dash_means_stdout_and_missing_means_the_default_file() {
+        assert_eq!(ExportTarget::from_arg(Some("-"), || PathBuf::from("pnl.csv")), ExportTarget::Stdout);
+        assert_eq!(ExportTarget::from_arg(Some("out/ledger.csv"), || PathBuf::from("pnl.csv")), ExportTarget::File(PathBuf::from("out/ledger.csv")));
+        assert_eq!(ExportTarget::from_arg(None, || PathBuf::from("pnl.csv")), ExportTarget::File(PathBuf::from("pnl.csv")));
+    }
+    
+    #[test]
+    fn file_writer_only_appears_once_finished() {
+        let dir = std::env::temp_dir().join(format!("definite-export-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("report.csv");
+        let target = ExportTarget::File(path.clone());
+        
+        let mut writer = target.open().unwrap();
+        writer.write("a,b\n").unwrap();
+        writer.flush().unwrap();
+        assert!(!path.exists());
+        assert_eq!(writer.finish().unwrap(), target);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a,b\n");
+        
+        let mut abandoned = ExportTarget::File(dir.join("abandoned.csv")).open().unwrap();
+        abandoned.write("a,b\n").unwrap();
+        drop(abandoned);
+        assert!(!dir.join("abandoned.csv").exists());
+        assert!(!dir.join("abandoned.csv.partial").exists());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
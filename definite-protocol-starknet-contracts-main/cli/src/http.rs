@@ -0,0 +1,82 @@
+//! Bounded HTTP for outbound requests that are not Starknet RPC
+//!
+//! Alert webhooks, explorer APIs and similar integrations should build their
+//! client with `http_client` and read responses with `read_body`, so a slow or
+//! misbehaving endpoint can neither hang the CLI nor exhaust memory.
+
+use anyhow::{Result, Context};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+use crate::config::HttpConfig;
+
+/// A client with the connect and overall request timeouts from `[http]`
+pub fn http_client(config: &HttpConfig) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(config.connect_timeout))
+        .timeout(Duration::from_secs(config.timeout))
+        .user_agent(concat!("definite-cli/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Read a response body, stopping as soon as it exceeds `max_bytes`
+///
+/// A declared `Content-Length` over the limit is rejected before anything is read.
+pub async fn read_body(mut response: reqwest::Response, max_bytes: u64) -> Result<Vec<u8>> {
+    if let Some(length) = response.content_length() {
+        check_size(length, max_bytes)?;
+    }
+    
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.context("Failed to read HTTP response")? {
+        check_size((body.len() + chunk.len()) as u64, max_bytes)?;
+        body.extend_from_slice(&chunk);
+    }
+    
+    Ok(body)
+}
+
+/// GET `url` and decode a JSON response within the configured limits
+pub async fn get_json<T: DeserializeOwned>(config: &HttpConfig, url: &str) -> Result<T> {
+    let response = http_client(config)?
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Request to {} failed", url))?
+        .error_for_status()
+        .with_context(|| format!("Request to {} failed", url))?;
+    
+    let body = read_body(response, config.max_response_bytes).await?;
+    serde_json::from_slice(&body)
+        .with_context(|| format!("Invalid JSON response from {}", url))
+}
+
+fn check_size(size: u64, max_bytes: u64) -> Result<()> {
+    if size > max_bytes {
+        return Err(anyhow::anyhow!(
+            "HTTP response exceeds the {} byte limit (raise http.max_response_bytes to allow it)",
+            max_bytes
+        ));
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn size_limit_is_inclusive() {
+        assert!(check_size(1024, 1024).is_ok());
+        
+        let err = check_size(1025, 1024).unwrap_err();
+        assert!(err.to_string().contains("http.max_response_bytes"));
+    }
+    
+    #[test]
+    fn client_builds_from_default_limits() {
+        assert!(http_client(&HttpConfig::default()).is_ok());
+    }
+}
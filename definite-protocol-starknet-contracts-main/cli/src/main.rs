@@ -2,17 +2,42 @@ use clap::{Parser, Subcommand};
 use owo_colors::OwoColorize;
 use std::process;
 
+mod abi;
+mod abigen;
+#[cfg(test)]
+mod amount_vectors;
+mod audit;
 mod commands;
 mod config;
 mod contracts;
+mod dashboard;
+mod deployment;
+mod doctor;
+mod export;
+mod http;
+mod oplog;
+mod progress;
+mod prompt;
+mod refresh;
+mod retry;
+mod rpc_probe;
+mod shutdown;
+mod spend;
+mod table;
 mod theme;
+mod transaction;
+mod tx_decode;
+mod typed_data;
 mod utils;
+mod validated;
+mod watch;
 
 use commands::{
     UserCommands, ProtocolCommands, ContractCommands,
-    AnalyticsCommands, DevCommands, ConfigCommands,
+    AnalyticsCommands, DevCommands, ConfigCommands, HistoryCommands,
     handle_user_command, handle_protocol_command, handle_contract_command,
-    handle_analytics_command, handle_dev_command, handle_config_command
+    handle_analytics_command, handle_dev_command, handle_config_command,
+    handle_history_command
 };
 use theme::Theme;
 
@@ -35,6 +60,57 @@ struct Cli {
     
     #[arg(long, global = true)]
     network: Option<String>,
+    
+    #[arg(long, global = true, help = "Skip the fee token balance check before transactions")]
+    skip_fee_check: bool,
+    
+    #[arg(long, global = true, value_name = "AMOUNT", help = "Abort any transaction whose estimated fee exceeds this amount of the fee token")]
+    max_fee: Option<String>,
+    
+    #[arg(long, global = true, help = "Re-fetch contract ABIs instead of using the on-disk cache")]
+    refresh_abi: bool,
+    
+    #[arg(long, global = true, help = "Allow amounts above transaction.max_transaction_amount without re-typing them")]
+    confirm_large: bool,
+    
+    #[arg(long, global = true, help = "Price the max fee from the current network gas price times transaction.gas_price_multiplier")]
+    auto_gas: bool,
+    
+    #[arg(long, global = true, help = "Abbreviate large amounts with K/M instead of printing display.decimal_places decimals")]
+    human: bool,
+    
+    #[arg(long, global = true, conflicts_with = "human", help = "Print amounts as exact wei in balance, status and history; JSON output always carries wei")]
+    raw: bool,
+    
+    #[arg(long, global = true, conflicts_with = "no", help = "Answer yes to routine prompts; emergency actions still ask")]
+    yes: bool,
+    
+    #[arg(long, global = true, help = "Answer no to every prompt")]
+    no: bool,
+    
+    #[arg(long, global = true, help = "Run every read, fee estimate and preview, but never send a transaction")]
+    dry_run: bool,
+    
+    #[arg(long, global = true, help = "Append state-changing operations to ~/.definite/history.jsonl (or set transaction.log_operations)")]
+    log_ops: bool,
+    
+    #[arg(long, global = true, help = "Wrap JSON results in an audit envelope with the network, block, account, time and CLI version")]
+    with_metadata: bool,
+    
+    #[arg(long, global = true, value_enum, default_value_t = progress::OutputFormat::Human, help = "Output format; json reports progress as NDJSON stage events on stderr")]
+    output: progress::OutputFormat,
+    
+    #[arg(long, global = true, help = "Do not print the banner (or set display.show_banner = false)")]
+    no_banner: bool,
+    
+    #[arg(long, global = true, value_name = "ADDRESS", help = "Transact as this account instead of the configured one, with --private-key, --keystore or DEFINITE_PRIVATE_KEY")]
+    account: Option<String>,
+    
+    #[arg(long, global = true, value_name = "KEY", conflicts_with = "keystore", help = "Private key for --account; prefer DEFINITE_PRIVATE_KEY, as arguments are visible to other users")]
+    private_key: Option<String>,
+    
+    #[arg(long, global = true, value_name = "FILE", help = "Encrypted keystore holding the key for --account (or the new key for config rotate-key)")]
+    keystore: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -69,6 +145,11 @@ enum Commands {
         #[command(subcommand)]
         action: ConfigCommands,
     },
+    /// Operations recorded on this machine
+    History {
+        #[command(subcommand)]
+        action: HistoryCommands,
+    },
 }
 
 #[tokio::main]
@@ -81,10 +162,13 @@ async fn main() {
         tracing_subscriber::fmt::init();
     }
     
-    // Print banner
-    print_banner();
+    // The banner goes to stderr so piped and JSON output stay clean
+    if show_banner(&cli) {
+        print_banner();
+    }
     
     // Execute command
+    let started = std::time::Instant::now();
     let result = match cli.command {
         Commands::User { ref action } => handle_user_command(action.clone(), &cli).await,
         Commands::Protocol { ref action } => handle_protocol_command(action.clone(), &cli).await,
@@ -92,11 +176,19 @@ async fn main() {
         Commands::Analytics { ref action } => handle_analytics_command(action.clone(), &cli).await,
         Commands::Dev { ref action } => handle_dev_command(action.clone(), &cli).await,
         Commands::Config { ref action } => handle_config_command(action.clone(), &cli).await,
+        Commands::History { ref action } => handle_history_command(action.clone(), &cli).await,
     };
     
+    // Printed even when the command failed part way, as earlier sends were still paid for
+    if let Some(summary) = spend::summarize(started).await {
+        print_summary(&cli, &summary);
+    }
+    
     match result {
         Ok(_) => {
-            println!("{}", "Operation completed successfully".color(theme::SUCCESS));
+            if cli.output == progress::OutputFormat::Human {
+                eprintln!("{}", "Operation completed successfully".color(theme::SUCCESS));
+            }
         }
         Err(e) => {
             eprintln!("{} {}", "Error:".color(theme::ERROR), e);
@@ -105,6 +197,30 @@ async fn main() {
     }
 }
 
+/// The fee footer on stderr, or a `summary` NDJSON event there with `--output json`
+fn print_summary(cli: &Cli, summary: &spend::CommandSummary) {
+    if cli.output == progress::OutputFormat::Json {
+        eprintln!("{}", summary.to_json());
+        return;
+    }
+    
+    let display = config::Config::load(cli.config.as_deref()).map(|config| config.display).unwrap_or_default();
+    summary.print(&utils::AmountFormat::new(&display, cli.human).with_raw(cli.raw));
+}
+
+/// Whether to print the banner: never with `--no-banner` or `--output json`, otherwise as `display.show_banner` says
+///
+/// The config is only read if it exists, so the banner never creates one.
+fn show_banner(cli: &Cli) -> bool {
+    if cli.no_banner || cli.output == progress::OutputFormat::Json {
+        return false;
+    }
+    
+    let configured = config::Config::get_config_path(cli.config.as_deref())
+        .is_ok_and(|path| path.exists());
+    !configured || config::Config::load(cli.config.as_deref()).map_or(true, |config| config.display.show_banner)
+}
+
 fn print_banner() {
     let banner = r#"
     ╔══════════════════════════════════════════════════════════════╗
@@ -121,7 +237,7 @@ fn print_banner() {
     ╚══════════════════════════════════════════════════════════════╝
     "#;
     
-    println!("{}", banner.color(theme::PRIMARY));
-    println!("{}", "Advanced CLI for sophisticated DeFi operations".color(theme::SECONDARY));
-    println!();
+    eprintln!("{}", banner.color(theme::PRIMARY));
+    eprintln!("{}", "Advanced CLI for sophisticated DeFi operations".color(theme::SECONDARY));
+    eprintln!();
 }
@@ -0,0 +1,297 @@
+//! Opt-in local log of state-changing operations, one JSON record per line
+
+use anyhow::{Result, Context};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use starknet::accounts::Call;
+use starknet::core::types::FieldElement;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{Cli, theme};
+use crate::config::Config;
+use crate::transaction::CallSummary;
+
+/// How far an operation got
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    /// Accepted by the node; the outcome was not waited for
+    Submitted,
+    Succeeded,
+    Failed,
+}
+
+impl std::fmt::Display for OperationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationStatus::Submitted => write!(f, "submitted"),
+            OperationStatus::Succeeded => write!(f, "succeeded"),
+            OperationStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// One call of a logged operation; `function` is the entrypoint name when known, otherwise its selector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedCall {
+    pub to: String,
+    pub function: String,
+    pub calldata: Vec<String>,
+}
+
+impl From<&Call> for LoggedCall {
+    fn from(call: &Call) -> Self {
+        LoggedCall {
+            to: format!("{:#x}", call.to),
+            function: format!("{:#x}", call.selector),
+            calldata: call.calldata.iter().map(|felt| format!("{:#x}", felt)).collect(),
+        }
+    }
+}
+
+impl From<&CallSummary> for LoggedCall {
+    fn from(summary: &CallSummary) -> Self {
+        LoggedCall {
+            to: summary.to.clone(),
+            function: summary.function.clone(),
+            calldata: summary.calldata.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationRecord {
+    /// Unix timestamp of when the operation started
+    pub timestamp: u64,
+    pub command: String,
+    pub network: String,
+    pub calls: Vec<LoggedCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_hash: Option<String>,
+    pub status: OperationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl OperationRecord {
+    /// A record for `calls` about to be sent by `command`, e.g. "user deposit"
+    pub fn new<'a, T>(command: &str, config: &Config, calls: impl IntoIterator<Item = &'a T>) -> Self
+    where
+        T: 'a,
+        LoggedCall: From<&'a T>,
+    {
+        OperationRecord {
+            timestamp: chrono::Utc::now().timestamp().max(0) as u64,
+            command: command.to_string(),
+            network: config.network.clone(),
+            calls: calls.into_iter().map(LoggedCall::from).collect(),
+            transaction_hash: None,
+            status: OperationStatus::Submitted,
+            error: None,
+        }
+    }
+    
+    /// The node accepted the transaction
+    pub fn sent(mut self, tx_hash: FieldElement) -> Self {
+        self.transaction_hash = Some(format!("{:#x}", tx_hash));
+        self.status = OperationStatus::Submitted;
+        self
+    }
+    
+    /// The transaction executed successfully
+    pub fn succeeded(mut self) -> Self {
+        self.status = OperationStatus::Succeeded;
+        self
+    }
+    
+    /// Sending failed or the transaction reverted
+    pub fn failed(mut self, err: &anyhow::Error) -> Self {
+        self.status = OperationStatus::Failed;
+        self.error = Some(format!("{:#}", err));
+        self
+    }
+    
+    /// `sent` or `failed` depending on the result of a send
+    pub fn outcome(self, result: &Result<FieldElement>) -> Self {
+        match result {
+            Ok(tx_hash) => self.sent(*tx_hash),
+            Err(e) => self.failed(e),
+        }
+    }
+}
+
+/// Criteria for `history local`; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct RecordFilter {
+    pub command: Option<String>,
+    pub status: Option<OperationStatus>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl RecordFilter {
+    /// `command` matches any command containing it, so "deposit" covers batch deposits too
+    pub fn matches(&self, record: &OperationRecord) -> bool {
+        self.command.as_ref().map_or(true, |command| record.command.contains(command.as_str()))
+            && self.status.map_or(true, |status| record.status == status)
+            && self.since.map_or(true, |since| record.timestamp >= since)
+            && self.until.map_or(true, |until| record.timestamp <= until)
+    }
+}
+
+/// Append-only JSONL file of operation records
+pub struct OperationLog {
+    path: PathBuf,
+}
+
+impl OperationLog {
+    pub fn new(path: impl Into<PathBuf>) -> OperationLog {
+        OperationLog { path: path.into() }
+    }
+    
+    /// The log at `~/.definite/history.jsonl`
+    pub fn open_default() -> Result<OperationLog> {
+        let home = dirs::home_dir()
+            .context("Could not find home directory")?;
+        
+        Ok(OperationLog::new(home.join(".definite").join("history.jsonl")))
+    }
+    
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    
+    pub fn append(&self, record: &OperationRecord) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        
+        let line = serde_json::to_string(record)?;
+        
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(file, "{}", line)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+    
+    /// Every readable record, oldest first, and the number of lines that could not be parsed
+    ///
+    /// A missing file is an empty log.
+    pub fn read(&self) -> Result<(Vec<OperationRecord>, usize)> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", self.path.display())),
+        };
+        
+        let mut records = Vec::new();
+        let mut skipped = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(_) => skipped += 1,
+            }
+        }
+        Ok((records, skipped))
+    }
+}
+
+/// Whether operations should be logged, from `--log-ops` or `transaction.log_operations`
+pub fn enabled(cli: &Cli, config: &Config) -> bool {
+    cli.log_ops || config.transaction.log_operations
+}
+
+/// Log `record` if logging is enabled
+///
+/// The operation has already happened by the time it is logged, so a failure to
+/// write is reported as a warning rather than failing the command.
+pub fn record(cli: &Cli, config: &Config, record: OperationRecord) {
+    if !enabled(cli, config) {
+        return;
+    }
+    
+    if let Err(e) = OperationLog::open_default().and_then(|log| log.append(&record)) {
+        eprintln!("{}", format!("Warning: could not write the operations log: {:#}", e).color(theme::WARNING));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn temp_log() -> OperationLog {
+        OperationLog::new(std::env::temp_dir().join(format!("definite-oplog-{}", uuid::Uuid::new_v4())).join("history.jsonl"))
+    }
+    
+    fn deposit_call() -> Call {
+        Call {
+            to: FieldElement::from(0x1234u32),
+            selector: FieldElement::from(0x99u32),
+            calldata: vec![FieldElement::from(5u32), FieldElement::ZERO],
+        }
+    }
+    
+    #[test]
+    fn records_round_trip_through_the_log() {
+        let log = temp_log();
+        let config = Config::default();
+        let call = deposit_call();
+        
+        let deposit = OperationRecord::new("user deposit", &config, [&call]).sent(FieldElement::from(0xabcu32)).succeeded();
+        let failure = OperationRecord::new("protocol emergency pause", &config, [&call])
+            .outcome(&Err(anyhow::anyhow!("insufficient fee balance")));
+        log.append(&deposit).unwrap();
+        log.append(&failure).unwrap();
+        
+        let (records, skipped) = log.read().unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].command, "user deposit");
+        assert_eq!(records[0].status, OperationStatus::Succeeded);
+        assert_eq!(records[0].transaction_hash.as_deref(), Some("0xabc"));
+        assert_eq!(records[0].calls[0].to, "0x1234");
+        assert_eq!(records[0].calls[0].calldata, vec!["0x5", "0x0"]);
+        assert!(records[0].timestamp > 0);
+        assert_eq!(records[1].status, OperationStatus::Failed);
+        assert_eq!(records[1].error.as_deref(), Some("insufficient fee balance"));
+        
+        fs::remove_dir_all(log.path().parent().unwrap()).ok();
+    }
+    
+    #[test]
+    fn read_skips_corrupt_lines_and_tolerates_a_missing_file() {
+        let log = temp_log();
+        assert!(log.read().unwrap().0.is_empty());
+        
+        log.append(&OperationRecord::new("contract send", &Config::default(), [&deposit_call()])).unwrap();
+        let mut file = OpenOptions::new().append(true).open(log.path()).unwrap();
+        writeln!(file, "{{not json").unwrap();
+        
+        let (records, skipped) = log.read().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(skipped, 1);
+        
+        fs::remove_dir_all(log.path().parent().unwrap()).ok();
+    }
+    
+    #[test]
+    fn filter_matches_command_status_and_time_range() {
+        let config = Config::default();
+        let mut record = OperationRecord::new("user batch-deposit", &config, [&deposit_call()]).sent(FieldElement::ONE);
+        record.timestamp = 1_700_000_000;
+        
+        assert!(RecordFilter::default().matches(&record));
+        assert!(RecordFilter { command: Some("deposit".into()), ..Default::default() }.matches(&record));
+        assert!(!RecordFilter { command: Some("withdraw".into()), ..Default::default() }.matches(&record));
+        assert!(RecordFilter { status: Some(OperationStatus::Submitted), ..Default::default() }.matches(&record));
+        assert!(!RecordFilter { status: Some(OperationStatus::Failed), ..Default::default() }.matches(&record));
+        assert!(RecordFilter { since: Some(1_700_000_000), until: Some(1_700_000_000), ..Default::default() }.matches(&record));
+        assert!(!RecordFilter { since: Some(1_700_000_001), ..Default::default() }.matches(&record));
+    }
+}
@@ -0,0 +1,134 @@
+use indicatif::ProgressBar;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::theme;
+
+/// How command output is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Colored text and progress bars
+    #[default]
+    Human,
+    /// Machine-readable output; progress is reported as NDJSON stage events on stderr
+    Json,
+}
+
+/// One line of NDJSON progress, e.g. `{"stage":"checking_balance","step":2,"of":5}`
+#[derive(Debug, PartialEq, Serialize)]
+struct StageEvent<'a> {
+    stage: &'a str,
+    step: u64,
+    of: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<&'a str>,
+}
+
+/// Progress of a multi-step command: the themed bar for people, stage events for automation
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    step: AtomicU64,
+    steps: u64,
+}
+
+impl Progress {
+    pub fn new(steps: u64, output: OutputFormat) -> Progress {
+        let bar = (output == OutputFormat::Human).then(|| {
+            let bar = ProgressBar::new(steps);
+            bar.set_style(theme::progress_style());
+            bar
+        });
+        
+        Progress { bar, step: AtomicU64::new(0), steps }
+    }
+    
+    /// Move on to the next step
+    pub fn advance(&self, stage: &str, message: &str) {
+        self.step.fetch_add(1, Ordering::SeqCst);
+        self.update(stage, message);
+    }
+    
+    /// Report work within the current step
+    pub fn update(&self, stage: &str, message: &str) {
+        match &self.bar {
+            Some(bar) => {
+                bar.set_message(message.to_string());
+                bar.set_position(self.step.load(Ordering::SeqCst));
+            }
+            None => self.emit(stage, None),
+        }
+    }
+    
+    /// Print a warning without breaking the bar or the event stream
+    pub fn warn(&self, message: &str) {
+        match &self.bar {
+            Some(bar) => bar.println(format!("{}", message.color(theme::WARNING))),
+            None => self.emit("warning", Some(message)),
+        }
+    }
+    
+    /// Keep the spinner moving through a long wait with no step changes
+    pub fn keep_ticking(&self) {
+        if let Some(bar) = &self.bar {
+            bar.enable_steady_tick(std::time::Duration::from_millis(120));
+        }
+    }
+    
+    /// Remove the bar, e.g. before returning an error
+    pub fn clear(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+    
+    /// Complete every step
+    pub fn finish(&self, message: &str) {
+        self.step.store(self.steps, Ordering::SeqCst);
+        match &self.bar {
+            Some(bar) => bar.finish_with_message(message.to_string()),
+            None => self.emit("done", None),
+        }
+    }
+    
+    fn event<'a>(&self, stage: &'a str, warning: Option<&'a str>) -> StageEvent<'a> {
+        StageEvent { stage, step: self.step.load(Ordering::SeqCst), of: self.steps, warning }
+    }
+    
+    fn emit(&self, stage: &str, warning: Option<&str>) {
+        if let Ok(line) = serde_json::to_string(&self.event(stage, warning)) {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn json_events_track_steps() {
+        let progress = Progress::new(5, OutputFormat::Json);
+        progress.advance("connecting", "Connecting to Starknet");
+        progress.advance("checking_balance", "Checking STRK balance");
+        progress.update("checking_fee", "Checking fee");
+        
+        assert_eq!(
+            serde_json::to_string(&progress.event("checking_fee", None)).unwrap(),
+            r#"{"stage":"checking_fee","step":2,"of":5}"#
+        );
+        
+        progress.finish("Done");
+        assert_eq!(progress.event("done", None).step, 5);
+    }
+    
+    #[test]
+    fn warnings_are_tagged_events() {
+        let progress = Progress::new(3, OutputFormat::Json);
+        
+        assert_eq!(
+            serde_json::to_string(&progress.event("warning", Some("low balance"))).unwrap(),
+            r#"{"stage":"warning","step":0,"of":3,"warning":"low balance"}"#
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Yes/no prompts that `--yes`, `--no` and `[prompts]` can answer ahead of time
+//!
+//! Without a terminal (CI, a pipe, a cron job) a prompt would block or fail inside
+//! `dialoguer`, so every prompt first checks for one and otherwise fails with the
+//! flag that answers it.
+
+use anyhow::Result;
+use dialoguer::{Confirm, Input};
+use owo_colors::OwoColorize;
+use std::io::IsTerminal;
+
+use crate::{Cli, theme};
+use crate::config::{NoTtyPolicy, PromptsConfig};
+
+/// Whether prompts can be shown: input comes from a terminal and prompts are drawn on one
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Fail unless a prompt for `what` can be shown, saying what to pass `instead`
+pub fn require_tty(what: &str, instead: &str) -> Result<()> {
+    check_tty(is_interactive(), what, instead)
+}
+
+fn check_tty(interactive: bool, what: &str, instead: &str) -> Result<()> {
+    if interactive {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!("No TTY to ask for {}; {}", what, instead))
+}
+
+/// Answers yes/no prompts, interactively unless `--yes` or `--no` was passed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Prompter {
+    /// `Some(true)` for `--yes`, `Some(false)` for `--no`
+    answer: Option<bool>,
+    default_yes: Option<bool>,
+    interactive: bool,
+    no_tty: NoTtyPolicy,
+}
+
+impl Prompter {
+    pub fn new(cli: &Cli, prompts: &PromptsConfig) -> Self {
+        let answer = match (cli.yes, cli.no) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        };
+        
+        Prompter { answer, default_yes: prompts.default_yes, interactive: is_interactive(), no_tty: prompts.no_tty }
+    }
+    
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+    
+    /// The answer given ahead of time by a flag or, without a TTY, by `prompts.no_tty`
+    fn preset(&self, prompt: &str) -> Result<Option<(bool, &'static str)>> {
+        if let Some(answer) = self.answer {
+            return Ok(Some((answer, if answer { "yes (--yes)" } else { "no (--no)" })));
+        }
+        if self.interactive {
+            return Ok(None);
+        }
+        
+        match self.no_tty {
+            NoTtyPolicy::Yes => Ok(Some((true, "yes (prompts.no_tty)"))),
+            NoTtyPolicy::No => Ok(Some((false, "no (prompts.no_tty)"))),
+            NoTtyPolicy::Error => Err(anyhow::anyhow!("No TTY to ask \"{}\"; pass --yes to confirm or --no to decline", prompt)),
+        }
+    }
+    
+    /// Ask a routine question; `default` applies unless `prompts.default_yes` is set
+    pub fn confirm(&self, prompt: &str, default: bool) -> Result<bool> {
+        if let Some((answer, source)) = self.preset(prompt)? {
+            eprintln!("{} {}", prompt, source.color(theme::MUTED));
+            return Ok(answer);
+        }
+        
+        Ok(Confirm::new()
+            .with_prompt(prompt)
+            .default(self.default_yes.unwrap_or(default))
+            .interact()?)
+    }
+    
+    /// Ask before a critical action
+    ///
+    /// `--no` still declines, but `--yes` and `prompts.default_yes` are ignored:
+    /// the answer must be typed and Enter alone means no. Without a TTY only a
+    /// decline can be given ahead of time.
+    pub fn confirm_critical(&self, prompt: &str) -> Result<bool> {
+        if self.answer == Some(false) || (!self.interactive && self.no_tty == NoTtyPolicy::No && self.answer.is_none()) {
+            let source = if self.answer.is_some() { "no (--no)" } else { "no (prompts.no_tty)" };
+            eprintln!("{} {}", prompt, source.color(theme::MUTED));
+            return Ok(false);
+        }
+        check_tty(self.interactive, "confirmation of a critical action", "it must be answered at a terminal (--no declines)")?;
+        if self.answer == Some(true) {
+            eprintln!("{}", "--yes does not apply to critical actions; confirm explicitly".color(theme::WARNING));
+        }
+        
+        Ok(Confirm::new().with_prompt(prompt).default(false).interact()?)
+    }
+    
+    /// Ask for text with a default, which `--yes` accepts without asking when there is no TTY
+    pub fn text(&self, prompt: &str, default: &str) -> Result<String> {
+        if !self.interactive {
+            if self.answer == Some(true) {
+                eprintln!("{}: {} {}", prompt, default, "(--yes)".color(theme::MUTED));
+                return Ok(default.to_string());
+            }
+            return Err(anyhow::anyhow!("No TTY to ask for {}; pass --yes to accept the default ({})", prompt, default));
+        }
+        
+        Ok(Input::new()
+            .with_prompt(prompt)
+            .default(default.to_string())
+            .interact_text()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn flags_answer_routine_prompts_without_asking() {
+        let yes = Prompter { answer: Some(true), default_yes: Some(false), ..Prompter::default() };
+        assert!(yes.confirm("Proceed with deposit?", false).unwrap());
+        
+        let no = Prompter { answer: Some(false), default_yes: Some(true), ..Prompter::default() };
+        assert!(!no.confirm("Proceed with deposit?", true).unwrap());
+        assert!(!no.confirm_critical("This is a critical emergency action. Confirm?").unwrap());
+    }
+    
+    #[test]
+    fn without_a_tty_prompts_fail_or_follow_the_policy() {
+        // `Prompter::default()` has no TTY, as when stdin is a pipe
+        let piped = Prompter::default();
+        let err = piped.confirm("Proceed with deposit?", true).unwrap_err();
+        assert!(err.to_string().contains("pass --yes to confirm"), "{}", err);
+        assert!(piped.text("Network name", "custom").is_err());
+        assert!(piped.confirm_critical("Confirm pause?").is_err());
+        
+        let yes_policy = Prompter { no_tty: NoTtyPolicy::Yes, ..Prompter::default() };
+        assert!(yes_policy.confirm("Proceed with deposit?", false).unwrap());
+        assert!(yes_policy.confirm_critical("Confirm pause?").is_err());
+        
+        let no_policy = Prompter { no_tty: NoTtyPolicy::No, ..Prompter::default() };
+        assert!(!no_policy.confirm("Proceed with deposit?", true).unwrap());
+        assert!(!no_policy.confirm_critical("Confirm pause?").unwrap());
+        
+        let yes = Prompter { answer: Some(true), ..Prompter::default() };
+        assert_eq!(yes.text("Network name", "custom").unwrap(), "custom");
+        assert!(check_tty(false, "the withdrawal to claim", "pass --id").unwrap_err().to_string().contains("pass --id"));
+        assert!(check_tty(true, "the withdrawal to claim", "pass --id").is_ok());
+    }
+}
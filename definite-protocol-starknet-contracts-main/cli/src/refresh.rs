@@ -0,0 +1,101 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+/// How often each dashboard field is re-read, measured in watch cycles
+///
+/// Fields without an interval are refreshed every cycle, so volatile values stay
+/// live while slow-changing ones (fees, limits, config) can be read every Nth cycle.
+#[derive(Debug, Clone, Default)]
+pub struct RefreshPolicy {
+    intervals: BTreeMap<&'static str, u64>,
+}
+
+impl RefreshPolicy {
+    pub fn new() -> RefreshPolicy {
+        RefreshPolicy::default()
+    }
+    
+    /// Refresh `field` only every `cycles` cycles
+    pub fn every(mut self, field: &'static str, cycles: u64) -> RefreshPolicy {
+        self.intervals.insert(field, cycles.max(1));
+        self
+    }
+    
+    pub fn interval(&self, field: &str) -> u64 {
+        self.intervals.get(field).copied().unwrap_or(1)
+    }
+    
+    /// Whether `field` should be re-read on `cycle` (the first cycle is 0)
+    pub fn is_due(&self, field: &str, cycle: u64) -> bool {
+        cycle % self.interval(field) == 0
+    }
+}
+
+/// A value read under a `RefreshPolicy`, kept between cycles until it is due again
+pub struct Cached<T> {
+    field: &'static str,
+    value: Option<T>,
+}
+
+impl<T> Cached<T> {
+    pub fn new(field: &'static str) -> Cached<T> {
+        Cached { field, value: None }
+    }
+    
+    /// Whether the next `get` on `cycle` will fetch
+    pub fn is_stale(&self, policy: &RefreshPolicy, cycle: u64) -> bool {
+        self.value.is_none() || policy.is_due(self.field, cycle)
+    }
+    
+    /// Return the cached value, running `fetch` first when the field is due or was never read
+    pub async fn get<F, Fut>(&mut self, policy: &RefreshPolicy, cycle: u64, fetch: F) -> Result<&T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if self.is_stale(policy, cycle) {
+            self.value = Some(fetch().await?);
+        }
+        
+        Ok(self.value.as_ref().expect("value was just fetched"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn unlisted_fields_refresh_every_cycle() {
+        let policy = RefreshPolicy::new().every("vault_config", 10);
+        
+        assert!((0..5).all(|cycle| policy.is_due("exchange_rate", cycle)));
+        assert!(policy.is_due("vault_config", 0));
+        assert!(!policy.is_due("vault_config", 9));
+        assert!(policy.is_due("vault_config", 10));
+    }
+    
+    #[tokio::test]
+    async fn cached_value_is_reused_until_due() {
+        let policy = RefreshPolicy::new().every("fees", 3);
+        let mut fees = Cached::new("fees");
+        let reads = &std::cell::Cell::new(0);
+        
+        for cycle in 0..7 {
+            fees.get(&policy, cycle, || async move { reads.set(reads.get() + 1); Ok(cycle) }).await.unwrap();
+        }
+        
+        // Read on cycles 0, 3 and 6
+        assert_eq!(reads.get(), 3);
+        assert_eq!(fees.value, Some(6));
+    }
+    
+    #[tokio::test]
+    async fn first_read_ignores_schedule() {
+        let policy = RefreshPolicy::new().every("fees", 3);
+        let mut fees = Cached::new("fees");
+        
+        assert_eq!(*fees.get(&policy, 2, || async { Ok(42) }).await.unwrap(), 42);
+    }
+}
@@ -0,0 +1,250 @@
+//! Which failures are worth retrying, and retrying reads that hit them
+//!
+//! A read that failed for a transient reason (a timeout, a dropped connection, a
+//! rate limit or a 5xx from the node) can simply be asked again. A write cannot:
+//! a send that timed out may already be in the mempool, and sending it again could
+//! act twice. Writes therefore run exactly once here, whatever the error.
+
+use anyhow::Result;
+use starknet::providers::ProviderError;
+use std::fmt;
+use std::time::Duration;
+
+/// Whether a failure is transient
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Timeout, connection reset, rate limit or 5xx; asking again may succeed
+    Retryable,
+    /// Revert, invalid parameters, insufficient balance and anything unrecognised
+    Fatal,
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorClass::Retryable => write!(f, "retryable"),
+            ErrorClass::Fatal => write!(f, "fatal"),
+        }
+    }
+}
+
+/// Whether an operation only reads state or may change it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Read,
+    Write,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Read => write!(f, "read"),
+            Operation::Write => write!(f, "write"),
+        }
+    }
+}
+
+/// Messages meaning the node looked at the request and refused it
+///
+/// Checked before the transient patterns, so a revert whose reason happens to
+/// mention a timeout is still fatal.
+const FATAL_PATTERNS: &[&str] = &[
+    "revert",
+    "execution error",
+    "insufficient",
+    "invalid",
+    "not found",
+    "unauthorized",
+    "forbidden",
+];
+
+/// Messages meaning the request may never have reached the node, or the node was overloaded
+const RETRYABLE_PATTERNS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connection reset",
+    "connection refused",
+    "connection closed",
+    "broken pipe",
+    "error sending request",
+    "too many requests",
+    "rate limit",
+    "bad gateway",
+    "service unavailable",
+    "gateway timeout",
+    "internal server error",
+];
+
+/// Classify an HTTP status: 429 and 5xx are retryable, everything else is fatal
+pub fn classify_status(status: u16) -> ErrorClass {
+    match status {
+        429 | 500..=599 => ErrorClass::Retryable,
+        _ => ErrorClass::Fatal,
+    }
+}
+
+/// Classify an error from the provider, the HTTP client or anything wrapping them
+///
+/// Typed errors anywhere in the chain decide first. Otherwise the message is
+/// matched, and an unrecognised error is fatal so that nothing is retried by accident.
+pub fn classify(err: &anyhow::Error) -> ErrorClass {
+    for cause in err.chain() {
+        if let Some(provider) = cause.downcast_ref::<ProviderError>() {
+            match provider {
+                ProviderError::RateLimited => return ErrorClass::Retryable,
+                ProviderError::StarknetError(_) => return ErrorClass::Fatal,
+                // Transport failures; their message says what went wrong
+                _ => {}
+            }
+        }
+        if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+            if http.is_timeout() || http.is_connect() {
+                return ErrorClass::Retryable;
+            }
+            if let Some(status) = http.status() {
+                return classify_status(status.as_u16());
+            }
+        }
+    }
+    
+    classify_message(&format!("{:#}", err))
+}
+
+fn classify_message(message: &str) -> ErrorClass {
+    let message = message.to_lowercase();
+    if FATAL_PATTERNS.iter().any(|pattern| message.contains(pattern)) {
+        return ErrorClass::Fatal;
+    }
+    
+    if RETRYABLE_PATTERNS.iter().any(|pattern| message.contains(pattern)) {
+        ErrorClass::Retryable
+    } else {
+        ErrorClass::Fatal
+    }
+}
+
+/// How many times to try a read and how long to wait between attempts
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    /// Delay after the first failure, doubled after each later one
+    pub initial_delay: Duration,
+}
+
+/// Run `op`, retrying a read while its failures are retryable and attempts remain
+///
+/// A write runs once. Every failure is logged with its operation kind and class so
+/// `--verbose` shows why something was or was not retried.
+pub async fn with_retry<T, F, Fut>(operation: Operation, what: &str, policy: RetryPolicy, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut delay = policy.initial_delay;
+    let mut attempt = 1;
+    loop {
+        let err = match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        
+        let class = classify(&err);
+        let retry = operation == Operation::Read && class == ErrorClass::Retryable && attempt < policy.attempts;
+        tracing::warn!(
+            operation = %operation,
+            class = %class,
+            attempt,
+            attempts = policy.attempts,
+            retry,
+            error = %format!("{:#}", err),
+            "{} failed",
+            what
+        );
+        
+        if !retry {
+            if attempt > 1 {
+                return Err(err.context(format!("Gave up on {} after {} attempts", what, attempt)));
+            }
+            return Err(err);
+        }
+        
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    
+    const FAST: RetryPolicy = RetryPolicy { attempts: 3, initial_delay: Duration::from_millis(1) };
+    
+    #[test]
+    fn rate_limits_are_retryable_through_context() {
+        assert_eq!(classify(&anyhow::Error::from(ProviderError::RateLimited)), ErrorClass::Retryable);
+        
+        let wrapped = anyhow::Error::from(ProviderError::RateLimited).context("Failed to read total_assets");
+        assert_eq!(classify(&wrapped), ErrorClass::Retryable);
+    }
+    
+    #[test]
+    fn messages_are_classified_fatal_first() {
+        assert_eq!(classify(&anyhow::anyhow!("operation timed out")), ErrorClass::Retryable);
+        assert_eq!(classify(&anyhow::anyhow!("connection reset by peer")), ErrorClass::Retryable);
+        assert_eq!(classify(&anyhow::anyhow!("HTTP status 503 Service Unavailable")), ErrorClass::Retryable);
+        assert_eq!(classify(&anyhow::anyhow!("429 Too Many Requests")), ErrorClass::Retryable);
+        
+        assert_eq!(classify(&anyhow::anyhow!("Transaction reverted: u256_sub Overflow")), ErrorClass::Fatal);
+        assert_eq!(classify(&anyhow::anyhow!("Invalid params: missing field calldata")), ErrorClass::Fatal);
+        assert_eq!(classify(&anyhow::anyhow!("Insufficient account balance")), ErrorClass::Fatal);
+        assert_eq!(classify(&anyhow::anyhow!("execution reverted after the call timed out")), ErrorClass::Fatal);
+        assert_eq!(classify(&anyhow::anyhow!("something unexpected")), ErrorClass::Fatal);
+    }
+    
+    #[test]
+    fn statuses_retry_only_on_rate_limits_and_server_errors() {
+        assert_eq!(classify_status(429), ErrorClass::Retryable);
+        assert_eq!(classify_status(502), ErrorClass::Retryable);
+        assert_eq!(classify_status(400), ErrorClass::Fatal);
+        assert_eq!(classify_status(404), ErrorClass::Fatal);
+    }
+    
+    #[tokio::test]
+    async fn reads_retry_transient_failures_until_success_or_the_limit() {
+        let calls = Cell::new(0);
+        let value = with_retry(Operation::Read, "chain ID", FAST, || {
+            calls.set(calls.get() + 1);
+            let result = if calls.get() < 3 { Err(anyhow::anyhow!("connection refused")) } else { Ok(calls.get()) };
+            async move { result }
+        }).await.unwrap();
+        assert_eq!(value, 3);
+        
+        let calls = Cell::new(0);
+        let err = with_retry(Operation::Read, "chain ID", FAST, || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(anyhow::anyhow!("node unreachable: connection refused")) }
+        }).await.unwrap_err();
+        assert_eq!(calls.get(), 3);
+        assert!(format!("{:#}", err).contains("after 3 attempts"));
+    }
+    
+    #[tokio::test]
+    async fn fatal_reads_and_all_writes_run_once() {
+        let calls = Cell::new(0);
+        with_retry(Operation::Read, "total_assets", FAST, || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(anyhow::anyhow!("Contract not found")) }
+        }).await.unwrap_err();
+        assert_eq!(calls.get(), 1);
+        
+        let calls = Cell::new(0);
+        let err = with_retry(Operation::Write, "deposit", FAST, || {
+            calls.set(calls.get() + 1);
+            async { Err::<(), _>(anyhow::anyhow!("operation timed out")) }
+        }).await.unwrap_err();
+        assert_eq!(calls.get(), 1);
+        assert_eq!(format!("{:#}", err), "operation timed out");
+    }
+}
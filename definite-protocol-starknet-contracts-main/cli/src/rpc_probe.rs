@@ -0,0 +1,294 @@
+//! Which JSON-RPC methods an endpoint supports, probed by `config probe-rpc` and cached per endpoint
+
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::http::{http_client, read_body};
+
+/// JSON-RPC error code for a method the node does not implement
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A CLI feature and the RPC methods it cannot work without
+pub struct Feature {
+    pub name: &'static str,
+    pub methods: &'static [&'static str],
+    /// What the user loses when a method is missing
+    pub unavailable: &'static str,
+}
+
+pub const FEATURES: &[Feature] = &[
+    Feature {
+        name: "Contract reads",
+        methods: &["starknet_call"],
+        unavailable: "balances, positions and protocol status cannot be read",
+    },
+    Feature {
+        name: "Sending transactions",
+        methods: &["starknet_getNonce", "starknet_estimateFee", "starknet_addInvokeTransaction"],
+        unavailable: "deposits, withdrawals and other transactions cannot be sent",
+    },
+    Feature {
+        name: "Confirmations",
+        methods: &["starknet_getTransactionReceipt"],
+        unavailable: "sent transactions cannot be followed to completion",
+    },
+    Feature {
+        name: "Event history",
+        methods: &["starknet_getEvents"],
+        unavailable: "user history, analytics pnl, contract watch and rebalance history are disabled",
+    },
+    Feature {
+        name: "Date ranges",
+        methods: &["starknet_blockNumber", "starknet_getBlockWithTxHashes"],
+        unavailable: "--since and --until cannot be resolved to blocks",
+    },
+    Feature {
+        name: "Simulation",
+        methods: &["starknet_simulateTransactions"],
+        unavailable: "dev simulate-tx is disabled",
+    },
+    Feature {
+        name: "ABI lookups",
+        methods: &["starknet_getClassHashAt", "starknet_getClass"],
+        unavailable: "contract watch and contract diff cannot decode events or find getters",
+    },
+];
+
+/// Every method probed: those the features need, plus the spec version and chain id
+pub fn probed_methods() -> Vec<&'static str> {
+    let mut methods = vec!["starknet_specVersion", "starknet_chainId"];
+    for feature in FEATURES {
+        for method in feature.methods {
+            if !methods.contains(method) {
+                methods.push(method);
+            }
+        }
+    }
+    methods
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MethodSupport {
+    Supported,
+    Missing,
+    /// The request failed or the reply was not JSON-RPC, so support could not be told
+    Unknown,
+}
+
+impl std::fmt::Display for MethodSupport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MethodSupport::Supported => write!(f, "supported"),
+            MethodSupport::Missing => write!(f, "missing"),
+            MethodSupport::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Classify the reply to a probe
+///
+/// Probes are sent without parameters, so a node that has the method usually answers
+/// with an invalid-params error; only "method not found" means it is missing.
+fn classify(reply: Option<&serde_json::Value>) -> MethodSupport {
+    let Some(reply) = reply else {
+        return MethodSupport::Unknown;
+    };
+    
+    match reply.get("error").and_then(|error| error.get("code")).and_then(|code| code.as_i64()) {
+        Some(METHOD_NOT_FOUND) => MethodSupport::Missing,
+        Some(_) => MethodSupport::Supported,
+        None if reply.get("result").is_some() => MethodSupport::Supported,
+        None => MethodSupport::Unknown,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeReport {
+    /// Unix timestamp of the probe
+    pub probed_at: u64,
+    #[serde(default)]
+    pub spec_version: Option<String>,
+    pub methods: BTreeMap<String, MethodSupport>,
+}
+
+impl ProbeReport {
+    pub fn support(&self, method: &str) -> MethodSupport {
+        self.methods.get(method).copied().unwrap_or(MethodSupport::Unknown)
+    }
+    
+    /// Features with at least one missing method, and the methods they lack
+    pub fn unavailable_features(&self) -> Vec<(&'static Feature, Vec<&'static str>)> {
+        FEATURES
+            .iter()
+            .filter_map(|feature| {
+                let missing: Vec<_> = feature.methods
+                    .iter()
+                    .copied()
+                    .filter(|method| self.support(method) == MethodSupport::Missing)
+                    .collect();
+                (!missing.is_empty()).then_some((feature, missing))
+            })
+            .collect()
+    }
+    
+    /// Methods whose support could not be determined
+    pub fn unknown_methods(&self) -> Vec<&str> {
+        self.methods
+            .iter()
+            .filter(|(_, support)| **support == MethodSupport::Unknown)
+            .map(|(method, _)| method.as_str())
+            .collect()
+    }
+}
+
+/// Send each probe to the configured endpoint, with its `[rpc.headers]`
+pub async fn probe(config: &Config) -> Result<ProbeReport> {
+    let client = http_client(&config.http)?;
+    let url = url::Url::parse(&config.rpc_url).context("Invalid RPC URL")?;
+    
+    let requests = probed_methods().into_iter().enumerate().map(|(id, method)| {
+        let request = config.rpc.headers
+            .iter()
+            .fold(client.post(url.clone()), |request, (name, value)| request.header(name, value))
+            .json(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": [] }));
+        let max_bytes = config.http.max_response_bytes;
+        
+        async move {
+            let reply = match request.send().await {
+                Ok(response) => read_body(response, max_bytes).await
+                    .ok()
+                    .and_then(|body| serde_json::from_slice::<serde_json::Value>(&body).ok()),
+                Err(_) => None,
+            };
+            (method, reply)
+        }
+    });
+    let replies = futures::future::join_all(requests).await;
+    
+    if replies.iter().all(|(_, reply)| reply.is_none()) {
+        return Err(anyhow::anyhow!("No JSON-RPC reply from {}; check rpc_url and your connection", config.rpc_url));
+    }
+    
+    let spec_version = replies
+        .iter()
+        .find(|(method, _)| *method == "starknet_specVersion")
+        .and_then(|(_, reply)| reply.as_ref()?.get("result")?.as_str().map(str::to_string));
+    let methods = replies
+        .iter()
+        .map(|(method, reply)| (method.to_string(), classify(reply.as_ref())))
+        .collect();
+    
+    Ok(ProbeReport {
+        probed_at: chrono::Utc::now().timestamp().max(0) as u64,
+        spec_version,
+        methods,
+    })
+}
+
+/// Probe reports keyed by RPC URL
+pub struct ProbeCache {
+    path: PathBuf,
+}
+
+impl ProbeCache {
+    pub fn new(path: impl Into<PathBuf>) -> ProbeCache {
+        ProbeCache { path: path.into() }
+    }
+    
+    /// The cache at `~/.definite/rpc-capabilities.json`
+    pub fn open_default() -> Result<ProbeCache> {
+        let home = dirs::home_dir()
+            .context("Could not find home directory")?;
+        
+        Ok(ProbeCache::new(home.join(".definite").join("rpc-capabilities.json")))
+    }
+    
+    /// An unreadable cache is treated as empty; it is rewritten by the next probe
+    fn load(&self) -> BTreeMap<String, ProbeReport> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+    
+    pub fn get(&self, endpoint: &str) -> Option<ProbeReport> {
+        self.load().remove(endpoint)
+    }
+    
+    pub fn put(&self, endpoint: &str, report: &ProbeReport) -> Result<()> {
+        let mut reports = self.load();
+        reports.insert(endpoint.to_string(), report.clone());
+        
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&reports)?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn report(missing: &[&str]) -> ProbeReport {
+        ProbeReport {
+            probed_at: 1_700_000_000,
+            spec_version: Some("0.7.1".to_string()),
+            methods: probed_methods()
+                .into_iter()
+                .map(|method| {
+                    let support = if missing.contains(&method) { MethodSupport::Missing } else { MethodSupport::Supported };
+                    (method.to_string(), support)
+                })
+                .collect(),
+        }
+    }
+    
+    #[test]
+    fn only_method_not_found_counts_as_missing() {
+        let missing = json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "Method not found"}});
+        let bad_params = json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "Invalid params"}});
+        let answered = json!({"jsonrpc": "2.0", "id": 1, "result": "0x534e5f5345504f4c4941"});
+        
+        assert_eq!(classify(Some(&missing)), MethodSupport::Missing);
+        assert_eq!(classify(Some(&bad_params)), MethodSupport::Supported);
+        assert_eq!(classify(Some(&answered)), MethodSupport::Supported);
+        assert_eq!(classify(Some(&json!({"message": "rate limited"}))), MethodSupport::Unknown);
+        assert_eq!(classify(None), MethodSupport::Unknown);
+    }
+    
+    #[test]
+    fn missing_methods_name_the_features_they_disable() {
+        let report = report(&["starknet_simulateTransactions", "starknet_getEvents"]);
+        let unavailable = report.unavailable_features();
+        
+        let names: Vec<_> = unavailable.iter().map(|(feature, _)| feature.name).collect();
+        assert_eq!(names, vec!["Event history", "Simulation"]);
+        assert_eq!(unavailable[1].1, vec!["starknet_simulateTransactions"]);
+        assert!(report.unknown_methods().is_empty());
+    }
+    
+    #[test]
+    fn cache_keeps_one_report_per_endpoint() {
+        let dir = std::env::temp_dir().join(format!("definite-probe-{}", uuid::Uuid::new_v4()));
+        let cache = ProbeCache::new(dir.join("rpc-capabilities.json"));
+        assert!(cache.get("https://a.example").is_none());
+        
+        cache.put("https://a.example", &report(&[])).unwrap();
+        cache.put("https://b.example", &report(&["starknet_getEvents"])).unwrap();
+        
+        assert!(cache.get("https://a.example").unwrap().unavailable_features().is_empty());
+        assert_eq!(cache.get("https://b.example").unwrap().support("starknet_getEvents"), MethodSupport::Missing);
+        
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,57 @@
+//! Stopping long-running modes on Ctrl+C or SIGTERM
+
+/// Resolves on the first Ctrl+C (SIGINT) or, on Unix, SIGTERM
+///
+/// Service managers and container runtimes stop processes with SIGTERM, so watch
+/// modes and the dashboard wait on this rather than on Ctrl+C alone. Create it once,
+/// before the loop, and poll the same pinned future each time round: the handlers are
+/// installed on the first poll and a signal that arrives between polls is kept.
+/// A signal whose handler cannot be installed keeps its default behavior.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use std::time::Duration;
+    
+    #[tokio::test]
+    async fn sigterm_resolves_the_shutdown_future() {
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        assert!(shutdown.as_mut().now_or_never().is_none());
+        
+        let status = std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        
+        tokio::time::timeout(Duration::from_secs(5), shutdown).await
+            .expect("SIGTERM should end the wait");
+    }
+}
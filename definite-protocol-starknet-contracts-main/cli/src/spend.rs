@@ -0,0 +1,211 @@
+//! What a command spent: the transactions it sent and the fees their receipts charged
+//!
+//! Each send site notes the hash it got back with [`note_sent`] or [`note_outcome`].
+//! Once the command returns, `main` fetches each receipt and prints the total as a
+//! footer.
+
+use num_bigint::BigUint;
+use owo_colors::OwoColorize;
+use serde_json::{json, Value};
+use starknet::core::types::{FeePayment, FieldElement, MaybePendingTransactionReceipt, PendingTransactionReceipt, PriceUnit, TransactionReceipt};
+use starknet::providers::Provider;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::contracts::utils::felt_to_bigint;
+use crate::theme;
+use crate::utils::{format_duration, get_provider, AmountFormat};
+
+/// Transactions sent so far, with the config of the first send for reaching the same node
+struct Sent {
+    config: Config,
+    hashes: Vec<FieldElement>,
+}
+
+static SENT: Mutex<Option<Sent>> = Mutex::new(None);
+
+/// Note that `tx_hash` was accepted by the node configured in `config`
+pub fn note_sent(config: &Config, tx_hash: FieldElement) {
+    let mut sent = SENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let sent = sent.get_or_insert_with(|| Sent { config: config.clone(), hashes: Vec::new() });
+    if !sent.hashes.contains(&tx_hash) {
+        sent.hashes.push(tx_hash);
+    }
+}
+
+/// Note the hash of a send that succeeded; failed sends never reached the node
+pub fn note_outcome(config: &Config, result: &anyhow::Result<FieldElement>) {
+    if let Ok(tx_hash) = result {
+        note_sent(config, *tx_hash);
+    }
+}
+
+/// Fees charged by a command's transactions, per fee token
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSummary {
+    pub transactions: usize,
+    /// Summed `actual_fee` in each token's smallest unit, keyed by "ETH" or "STRK"
+    pub fees: BTreeMap<&'static str, BigUint>,
+    /// Sent transactions whose receipt could not be read, so they are missing from `fees`
+    pub without_receipt: usize,
+    pub elapsed: Duration,
+}
+
+impl CommandSummary {
+    /// Total the fees of one receipt per transaction, `None` where there was none
+    pub fn new(fees: &[Option<FeePayment>], elapsed: Duration) -> CommandSummary {
+        let mut totals = BTreeMap::new();
+        for fee in fees.iter().flatten() {
+            *totals.entry(fee_token(&fee.unit)).or_insert_with(|| BigUint::from(0u32)) += felt_to_bigint(fee.amount);
+        }
+        
+        CommandSummary {
+            transactions: fees.len(),
+            fees: totals,
+            without_receipt: fees.iter().filter(|fee| fee.is_none()).count(),
+            elapsed,
+        }
+    }
+    
+    /// The `summary` event printed on stderr in `--output json` mode, alongside the progress events; fees are in wei or fri
+    pub fn to_json(&self) -> Value {
+        let fees: BTreeMap<&str, String> = self.fees.iter().map(|(token, amount)| (*token, amount.to_string())).collect();
+        json!({
+            "stage": "summary",
+            "transactions": self.transactions,
+            "fee_paid": fees,
+            "without_receipt": self.without_receipt,
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+        })
+    }
+    
+    /// One line such as "2 transactions, 0.000412 STRK in fees, 14.2s"
+    pub fn line(&self, amounts: &AmountFormat) -> String {
+        let plural = if self.transactions == 1 { "" } else { "s" };
+        let fees = if self.fees.is_empty() {
+            "no fee receipts".to_string()
+        } else {
+            let paid: Vec<String> = self.fees.iter().map(|(token, amount)| format!("{} {}", amounts.format(amount), token)).collect();
+            format!("{} in fees", paid.join(" + "))
+        };
+        let mut line = format!("{} transaction{}, {}, {}", self.transactions, plural, fees, elapsed_text(self.elapsed));
+        if self.without_receipt > 0 && !self.fees.is_empty() {
+            line.push_str(&format!(" ({} without a receipt yet, not counted)", self.without_receipt));
+        }
+        line
+    }
+    
+    pub fn print(&self, amounts: &AmountFormat) {
+        eprintln!("{} {}", "Summary:".color(theme::ACCENT), self.line(amounts).color(theme::SECONDARY));
+    }
+}
+
+/// Tenths of a second under a minute, where `format_duration` would round to "0m"
+fn elapsed_text(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(60) {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else {
+        format_duration(elapsed.as_secs())
+    }
+}
+
+fn fee_token(unit: &PriceUnit) -> &'static str {
+    match unit {
+        PriceUnit::Wei => "ETH",
+        PriceUnit::Fri => "STRK",
+    }
+}
+
+/// The fee a receipt says its transaction was charged
+fn receipt_fee(receipt: MaybePendingTransactionReceipt) -> FeePayment {
+    match receipt {
+        MaybePendingTransactionReceipt::Receipt(receipt) => match receipt {
+            TransactionReceipt::Invoke(receipt) => receipt.actual_fee,
+            TransactionReceipt::L1Handler(receipt) => receipt.actual_fee,
+            TransactionReceipt::Declare(receipt) => receipt.actual_fee,
+            TransactionReceipt::Deploy(receipt) => receipt.actual_fee,
+            TransactionReceipt::DeployAccount(receipt) => receipt.actual_fee,
+        },
+        MaybePendingTransactionReceipt::PendingReceipt(receipt) => match receipt {
+            PendingTransactionReceipt::Invoke(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::L1Handler(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::Declare(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::DeployAccount(receipt) => receipt.actual_fee,
+        },
+    }
+}
+
+/// Poll for the fee of `tx_hash` until `deadline`; reverted transactions are charged too
+async fn fee_paid<P: Provider>(provider: &P, tx_hash: FieldElement, deadline: Instant) -> Option<FeePayment> {
+    loop {
+        if let Ok(receipt) = provider.get_transaction_receipt(tx_hash).await {
+            return Some(receipt_fee(receipt));
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Summarize the transactions sent since `started`, or `None` if the command sent none
+///
+/// Transactions only submitted, such as batch deposits, are waited for up to
+/// `transaction.timeout` so their receipts can be counted.
+pub async fn summarize(started: Instant) -> Option<CommandSummary> {
+    let Sent { config, hashes } = SENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()?;
+    
+    let fees = match get_provider(&config) {
+        Ok(provider) => {
+            let deadline = Instant::now() + Duration::from_secs(config.transaction.timeout);
+            futures::future::join_all(hashes.iter().map(|hash| fee_paid(&provider, *hash, deadline))).await
+        }
+        Err(_) => vec![None; hashes.len()],
+    };
+    
+    Some(CommandSummary::new(&fees, started.elapsed()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn paid(amount: u64, unit: PriceUnit) -> Option<FeePayment> {
+        Some(FeePayment { amount: FieldElement::from(amount), unit })
+    }
+    
+    #[test]
+    fn fees_are_summed_per_token() {
+        let summary = CommandSummary::new(
+            &[paid(400, PriceUnit::Fri), paid(12, PriceUnit::Fri), paid(7, PriceUnit::Wei), None],
+            Duration::from_millis(14_200),
+        );
+        
+        assert_eq!(summary.transactions, 4);
+        assert_eq!(summary.without_receipt, 1);
+        assert_eq!(summary.fees["STRK"], BigUint::from(412u32));
+        assert_eq!(summary.fees["ETH"], BigUint::from(7u32));
+        
+        let json = summary.to_json();
+        assert_eq!(json["stage"], "summary");
+        assert_eq!(json["transactions"], 4);
+        assert_eq!(json["fee_paid"]["STRK"], "412");
+        assert_eq!(json["without_receipt"], 1);
+        assert_eq!(json["elapsed_ms"], 14_200);
+    }
+    
+    #[test]
+    fn footer_line_reads_naturally() {
+        let amounts = AmountFormat::default().with_raw(true);
+        let one = CommandSummary::new(&[paid(412, PriceUnit::Fri)], Duration::from_millis(14_200));
+        assert_eq!(one.line(&amounts), "1 transaction, 412 STRK in fees, 14.2s");
+        
+        let unconfirmed = CommandSummary::new(&[paid(5, PriceUnit::Wei), None], Duration::from_secs(125));
+        assert_eq!(unconfirmed.line(&amounts), "2 transactions, 5 ETH in fees, 2m (1 without a receipt yet, not counted)");
+        
+        let none = CommandSummary::new(&[None], Duration::from_secs(1));
+        assert_eq!(none.line(&amounts), "1 transaction, no fee receipts, 1.0s");
+    }
+}
@@ -0,0 +1,64 @@
+use comfy_table::{presets, Attribute, Cell, Color, ContentArrangement, Table};
+use owo_colors::Rgb;
+
+use crate::config::DisplayConfig;
+use crate::theme;
+
+/// Whether styled output is allowed by the config and the `NO_COLOR` convention
+pub fn colors_enabled(display: &DisplayConfig) -> bool {
+    display.use_colors && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Create an aligned table with themed headers
+pub fn new_table(headers: &[&str], display: &DisplayConfig) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(presets::UTF8_BORDERS_ONLY)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(headers.iter().map(|header| {
+            Cell::new(header)
+                .fg(to_color(theme::SECONDARY))
+                .add_attribute(Attribute::Bold)
+        }));
+    
+    // Without a TTY comfy-table drops all styling, which is exactly the no-color behaviour
+    if !colors_enabled(display) {
+        table.force_no_tty();
+    }
+    
+    table
+}
+
+/// Create a two-column label/value table
+pub fn metrics_table(display: &DisplayConfig) -> Table {
+    new_table(&["Metric", "Value"], display)
+}
+
+/// A cell drawn in one of the theme colors
+pub fn cell(content: impl ToString, color: Rgb) -> Cell {
+    Cell::new(content.to_string()).fg(to_color(color))
+}
+
+fn to_color(color: Rgb) -> Color {
+    Color::Rgb { r: color.0, g: color.1, b: color.2 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn disabled_colors_render_plain_aligned_text() {
+        let display = DisplayConfig { use_colors: false, ..DisplayConfig::default() };
+        let mut table = metrics_table(&display);
+        table.add_row(vec![Cell::new("STRK Balance"), cell("1,234.567890", theme::PRIMARY)]);
+        table.add_row(vec![Cell::new("ETH"), cell("0.1", theme::SECONDARY)]);
+        
+        let rendered = table.to_string();
+        assert!(!rendered.contains('\u{1b}'));
+        
+        let lines: Vec<&str> = rendered.lines().collect();
+        let widths: Vec<usize> = lines.iter().map(|line| line.chars().count()).collect();
+        assert!(widths.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+}
@@ -0,0 +1,1284 @@
+use anyhow::{Result, Context};
+use dialoguer::Input;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use starknet::accounts::{Account, Call, ConnectedAccount};
+use starknet::core::types::{BlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1, ExecutionResult, FieldElement, MaybePendingBlockWithTxHashes, ResourcePrice, SimulatedTransaction};
+use starknet::providers::Provider;
+use starknet::core::types::TransactionFinalityStatus;
+use owo_colors::OwoColorize;
+use std::fs;
+
+use crate::config::{Config, ConfirmVia, ETH_TOKEN_ADDRESS};
+use crate::contracts::selectors;
+use crate::contracts::token::TokenContract;
+use crate::prompt::{self, Prompter};
+use crate::theme;
+use crate::utils::{format_duration, parse_amount, parse_amount_smart, validate_address, AmountFormat, RoundingMode};
+
+/// Human-readable description of a call included in a signed transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallSummary {
+    pub to: String,
+    pub function: String,
+    pub calldata: Vec<String>,
+}
+
+/// A signed invoke transaction that can be broadcast later, possibly from another machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub transaction_hash: String,
+    pub chain_id: String,
+    pub sender_address: String,
+    pub nonce: String,
+    pub max_fee: String,
+    pub calls: Vec<CallSummary>,
+    pub transaction: BroadcastedInvokeTransactionV1,
+}
+
+impl SignedTransaction {
+    /// Load a signed transaction blob from disk
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read transaction file {}", path))?;
+        
+        serde_json::from_str(&content)
+            .context("Failed to parse signed transaction")
+    }
+    
+    /// Serialize the blob as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .context("Failed to serialize signed transaction")
+    }
+}
+
+/// A call in a multisig proposal, using the field names of starknet.js `Call`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedCall {
+    pub contract_address: String,
+    pub entrypoint: String,
+    pub selector: String,
+    pub calldata: Vec<String>,
+}
+
+/// Unsigned transaction proposal for a multisig to import, review and sign
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionProposal {
+    pub summary: String,
+    pub chain_id: String,
+    pub calls: Vec<ProposedCall>,
+}
+
+impl TransactionProposal {
+    pub fn new(summary: &str, chain_id: &str, calls: &[CallSummary]) -> Result<Self> {
+        let calls = calls
+            .iter()
+            .map(|call| {
+                Ok(ProposedCall {
+                    contract_address: call.to.clone(),
+                    entrypoint: call.function.clone(),
+                    selector: format!("{:#x}", selectors::selector(&call.function)?),
+                    calldata: call.calldata.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(TransactionProposal {
+            summary: summary.to_string(),
+            chain_id: chain_id.to_string(),
+            calls,
+        })
+    }
+    
+    /// Serialize the proposal as pretty-printed JSON, fields in declaration order
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .context("Failed to serialize transaction proposal")
+    }
+    
+    /// Write the proposal to disk as pretty-printed JSON
+    pub fn save(&self, path: &str) -> Result<()> {
+        let content = self.to_json()?;
+        
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write proposal to {}", path))
+    }
+}
+
+/// Build a call to `function` on `to` together with its display summary
+pub fn build_call(to: FieldElement, function: &str, calldata: Vec<FieldElement>) -> Result<(Call, CallSummary)> {
+    let summary = CallSummary {
+        to: format!("{:#x}", to),
+        function: function.to_string(),
+        calldata: calldata.iter().map(|felt| format!("{:#x}", felt)).collect(),
+    };
+    
+    let call = Call {
+        to,
+        selector: selectors::selector(function)?,
+        calldata,
+    };
+    
+    Ok((call, summary))
+}
+
+/// Estimate a max fee for `calls` with a 10% safety margin
+pub async fn estimate_max_fee<A>(account: &A, calls: Vec<Call>) -> Result<FieldElement>
+where
+    A: ConnectedAccount + Sync,
+    <A as Account>::SignError: 'static,
+{
+    let estimate = account.execute(calls).estimate_fee().await
+        .context("Failed to estimate transaction fee")?;
+    
+    let fee = crate::contracts::utils::felt_to_bigint(estimate.overall_fee);
+    crate::contracts::utils::bigint_to_felt(&(fee * 11u32 / 10u32))
+}
+
+/// How a transaction's max fee is priced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GasPricing {
+    /// The node's fee estimate plus a 10% margin
+    Estimate,
+    /// Estimated gas at the current network gas price times `multiplier`, capped at
+    /// `transaction.max_fee_per_gas`
+    Auto { multiplier: f64, ceiling: u128 },
+}
+
+impl GasPricing {
+    /// Pricing for `--auto-gas`, or the plain estimate without it
+    pub fn from_cli(auto_gas: bool, config: &Config) -> Result<GasPricing> {
+        if !auto_gas {
+            return Ok(GasPricing::Estimate);
+        }
+        
+        let ceiling = config.transaction.max_fee_per_gas.parse()
+            .with_context(|| format!("Invalid transaction.max_fee_per_gas '{}'", config.transaction.max_fee_per_gas))?;
+        Ok(GasPricing::Auto { multiplier: config.transaction.gas_price_multiplier, ceiling })
+    }
+}
+
+/// Buffer the network gas price, staying at or above it and at or below `ceiling`
+///
+/// A network price already over the ceiling is refused rather than underpriced.
+pub fn auto_gas_price(network: u128, multiplier: f64, ceiling: u128) -> Result<u128> {
+    if network > ceiling {
+        return Err(anyhow::anyhow!(
+            "network gas price {} is above transaction.max_fee_per_gas {}; raise the ceiling or retry later",
+            network,
+            ceiling
+        ));
+    }
+    
+    let buffered = (network as f64 * multiplier.max(1.0)).ceil();
+    Ok((buffered.min(u128::MAX as f64) as u128).clamp(network, ceiling))
+}
+
+/// Current L1 gas and L1 data gas prices from the pending block, in the fee token's smallest unit
+async fn network_gas_prices<P: Provider>(provider: &P, config: &Config) -> Result<(u128, u128)> {
+    let block = provider.get_block_with_tx_hashes(BlockId::Tag(BlockTag::Pending)).await
+        .context("Failed to read the pending block gas price")?;
+    let (gas, data_gas) = match block {
+        MaybePendingBlockWithTxHashes::Block(block) => (block.l1_gas_price, block.l1_data_gas_price),
+        MaybePendingBlockWithTxHashes::PendingBlock(block) => (block.l1_gas_price, block.l1_data_gas_price),
+    };
+    
+    let in_fee_token = |price: ResourcePrice| {
+        let price = if uses_v3(config) { price.price_in_fri } else { price.price_in_wei };
+        crate::contracts::utils::felt_to_bigint(price).to_u128()
+            .context("Gas price does not fit in 128 bits")
+    };
+    Ok((in_fee_token(gas)?, in_fee_token(data_gas)?))
+}
+
+/// Whether `transaction.fee_token` asks for STRK fees, which take a v3 transaction priced by resource bounds
+///
+/// The CLI can only send v1 transactions paid in ETH, so a v3 fee is priced as a
+/// preview and refused by [`FeeEstimate::sendable`].
+pub fn uses_v3(config: &Config) -> bool {
+    config.transaction.fee_token.eq_ignore_ascii_case("STRK")
+}
+
+/// Why a v3 fee cannot be signed
+pub const V3_NOT_SENDABLE: &str = "transaction.fee_token = \"STRK\" needs a v3 transaction, which this CLI cannot send yet; the STRK fee shown is a preview. Set transaction.fee_token to ETH to send";
+
+/// Gas units of one resource and the price per unit, in fri, for a v3 fee
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceBound {
+    pub amount: u64,
+    pub price_per_unit: u128,
+}
+
+impl ResourceBound {
+    pub fn cost(&self) -> BigUint {
+        BigUint::from(self.amount) * BigUint::from(self.price_per_unit)
+    }
+}
+
+/// The fee a send signs with, in the shape of its transaction version
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeeEstimate {
+    /// v1, paid in ETH: one max fee in wei
+    V1 { max_fee: FieldElement },
+    /// v3, paid in STRK: the estimated amount and price of each resource, and the max fee in fri
+    ///
+    /// Only a preview: v3 transactions cannot be sent yet.
+    V3 {
+        l1_gas: ResourceBound,
+        l1_data_gas: ResourceBound,
+        l2_gas: ResourceBound,
+        max_fee: FieldElement,
+    },
+}
+
+impl FeeEstimate {
+    /// Price a v1 fee in wei from the estimated L1 gas and L1 data gas
+    pub fn v1(l1_gas: ResourceBound, l1_data_gas: ResourceBound) -> Result<FeeEstimate> {
+        let max_fee = crate::contracts::utils::bigint_to_felt(&(l1_gas.cost() + l1_data_gas.cost()))?;
+        Ok(FeeEstimate::V1 { max_fee })
+    }
+    
+    /// Price a v3 fee from the estimated resources
+    ///
+    /// The max fee is the estimated total plus a 10% margin, or under `--auto-gas`
+    /// the same total with `auto_l1_price` in place of the network L1 gas price.
+    pub fn v3(l1_gas: ResourceBound, l1_data_gas: ResourceBound, l2_gas: ResourceBound, auto_l1_price: Option<u128>) -> Result<FeeEstimate> {
+        let max_fee = match auto_l1_price {
+            Some(price) => ResourceBound { price_per_unit: price, ..l1_gas }.cost() + l1_data_gas.cost() + l2_gas.cost(),
+            None => (l1_gas.cost() + l1_data_gas.cost() + l2_gas.cost()) * 11u32 / 10u32,
+        };
+        Ok(FeeEstimate::V3 { l1_gas, l1_data_gas, l2_gas, max_fee: crate::contracts::utils::bigint_to_felt(&max_fee)? })
+    }
+    
+    /// The most the transaction may be charged, in the fee token's smallest unit
+    pub fn max_fee(&self) -> FieldElement {
+        match self {
+            FeeEstimate::V1 { max_fee } | FeeEstimate::V3 { max_fee, .. } => *max_fee,
+        }
+    }
+    
+    /// The max fee to sign with, refusing a v3 fee that only previews a STRK transaction
+    pub fn sendable(&self) -> Result<FieldElement> {
+        match self {
+            FeeEstimate::V1 { max_fee } => Ok(*max_fee),
+            FeeEstimate::V3 { .. } => Err(anyhow::anyhow!(V3_NOT_SENDABLE)),
+        }
+    }
+    
+    /// The same estimate signed with a different max fee, such as one lowered to the `--max-fee` cap
+    pub fn with_max_fee(self, capped: FieldElement) -> FeeEstimate {
+        match self {
+            FeeEstimate::V1 { .. } => FeeEstimate::V1 { max_fee: capped },
+            FeeEstimate::V3 { l1_gas, l1_data_gas, l2_gas, .. } => FeeEstimate::V3 { l1_gas, l1_data_gas, l2_gas, max_fee: capped },
+        }
+    }
+    
+    pub fn fee_token(&self) -> &'static str {
+        match self {
+            FeeEstimate::V1 { .. } => "ETH",
+            FeeEstimate::V3 { .. } => "STRK",
+        }
+    }
+    
+    /// Label and value of each line in a transaction summary
+    pub fn summary_lines(&self, amounts: &AmountFormat) -> Vec<(String, String)> {
+        let max_fee = format!("{} {}", amounts.format(&crate::contracts::utils::felt_to_bigint(self.max_fee())), self.fee_token());
+        let FeeEstimate::V3 { l1_gas, l1_data_gas, l2_gas, .. } = self else {
+            return vec![("Max Fee".to_string(), max_fee)];
+        };
+        
+        let resource = |bound: &ResourceBound| format!("{} @ {} fri", bound.amount, bound.price_per_unit);
+        let estimated = l1_gas.cost() + l1_data_gas.cost() + l2_gas.cost();
+        vec![
+            ("L1 Gas".to_string(), resource(l1_gas)),
+            ("L1 Data Gas".to_string(), resource(l1_data_gas)),
+            ("L2 Gas".to_string(), resource(l2_gas)),
+            ("Estimated Fee".to_string(), format!("{} STRK", amounts.format(&estimated))),
+            ("Max Fee".to_string(), max_fee),
+            ("Fee Preview".to_string(), "v3 STRK fees cannot be sent yet; set transaction.fee_token to ETH to send".to_string()),
+        ]
+    }
+    
+    /// Print the fee lines of a transaction summary
+    pub fn print(&self, amounts: &AmountFormat) {
+        for (label, value) in self.summary_lines(amounts) {
+            println!("  {}: {}", label, value.color(theme::SECONDARY));
+        }
+    }
+}
+
+/// Fail with a clear next step when the signing account has no contract deployed
+///
+/// Without this, the first fee estimate fails with an opaque validation error.
+pub async fn ensure_account_deployed<A>(account: &A) -> Result<()>
+where
+    A: ConnectedAccount + Sync,
+{
+    let address = account.address();
+    match account.provider().get_class_hash_at(BlockId::Tag(BlockTag::Pending), address).await {
+        Ok(_) => Ok(()),
+        Err(err) if crate::contracts::utils::is_contract_not_found(&err) => Err(anyhow::anyhow!(undeployed_account_message(address))),
+        Err(err) => Err(err).context("Failed to check that the account is deployed"),
+    }
+}
+
+fn undeployed_account_message(address: FieldElement) -> String {
+    format!(
+        "Account {:#x} is not deployed. Deploy and fund it from your wallet, then re-run the command.",
+        address
+    )
+}
+
+/// Fee for `calls` under `pricing`, as v3 resource bounds when fees are paid in STRK
+pub async fn price_max_fee<A>(account: &A, config: &Config, calls: Vec<Call>, pricing: GasPricing) -> Result<FeeEstimate>
+where
+    A: ConnectedAccount + Sync,
+    <A as Account>::SignError: 'static,
+{
+    ensure_account_deployed(account).await?;
+    
+    if !uses_v3(config) && pricing == GasPricing::Estimate {
+        return Ok(FeeEstimate::V1 { max_fee: estimate_max_fee(account, calls).await? });
+    }
+    
+    let estimate = account.execute(calls).estimate_fee().await
+        .context("Failed to estimate transaction fee")?;
+    let (network, data_price) = network_gas_prices(account.provider(), config).await?;
+    let units = |felt: FieldElement| crate::contracts::utils::felt_to_bigint(felt).to_u64()
+        .context("Estimated gas does not fit in 64 bits");
+    let auto_price = match pricing {
+        GasPricing::Estimate => None,
+        GasPricing::Auto { multiplier, ceiling } => {
+            let price = auto_gas_price(network, multiplier, ceiling)?;
+            eprintln!("Auto gas price: {} (network {} x {}, ceiling {})", price, network, multiplier, ceiling);
+            Some(price)
+        }
+    };
+    
+    if !uses_v3(config) {
+        let l1_gas = ResourceBound { amount: units(estimate.gas_consumed)?, price_per_unit: auto_price.unwrap_or(network) };
+        let l1_data_gas = ResourceBound { amount: units(estimate.data_gas_consumed)?, price_per_unit: data_price };
+        return FeeEstimate::v1(l1_gas, l1_data_gas);
+    }
+    
+    FeeEstimate::v3(
+        ResourceBound { amount: units(estimate.gas_consumed)?, price_per_unit: network },
+        ResourceBound { amount: units(estimate.data_gas_consumed)?, price_per_unit: data_price },
+        ResourceBound::default(),
+        auto_price,
+    )
+}
+
+/// Largest whole-number max fee taken as fee token units rather than a wei value
+///
+/// `--max-fee` was once given in wei, so a bare integer above this is refused
+/// instead of being read as millions of tokens.
+const MAX_BARE_FEE_TOKENS: u64 = 1_000_000;
+
+/// The hard fee ceiling from `--max-fee`, falling back to `transaction.max_fee`
+pub fn fee_cap(cli_max_fee: Option<&str>, config: &Config) -> Result<Option<FieldElement>> {
+    cli_max_fee.or(config.transaction.max_fee.as_deref()).map(parse_max_fee).transpose()
+}
+
+/// Parse a max fee given in fee token units into wei
+///
+/// Shared by `--max-fee` and `config set transaction.max_fee`, so a value the
+/// config accepts is never refused at send time.
+pub fn parse_max_fee(amount: &str) -> Result<FieldElement> {
+    let bare = amount.trim();
+    if bare.chars().all(|c| c.is_ascii_digit()) && bare.parse::<u64>().map_or(true, |tokens| tokens > MAX_BARE_FEE_TOKENS) {
+        return Err(anyhow::anyhow!(
+            "Max fee '{}' is in fee token units, not wei, and would allow over {} tokens; pass a token amount such as 0.01, or --max-fee-wei to contract build-tx",
+            amount,
+            MAX_BARE_FEE_TOKENS
+        ));
+    }
+    let wei = parse_amount(amount, RoundingMode::Down)
+        .with_context(|| format!("Invalid max fee '{}'", amount))?;
+    crate::contracts::utils::bigint_to_felt(&wei)
+}
+
+/// The configured `transaction.max_transaction_amount` if `amount` exceeds it
+pub fn exceeded_spending_limit(amount: &BigUint, config: &Config) -> Result<Option<BigUint>> {
+    let Some(limit) = config.transaction.max_transaction_amount.as_deref() else {
+        return Ok(None);
+    };
+    
+    let limit = parse_amount_smart(limit, RoundingMode::Down)
+        .with_context(|| format!("Invalid transaction.max_transaction_amount '{}'", limit))?;
+    Ok((*amount > limit).then_some(limit))
+}
+
+/// Printed by every command that sends when the global `--dry-run` flag is set
+pub const DRY_RUN_BANNER: &str = "DRY RUN — no transaction sent";
+
+/// Announce a dry run where the command would otherwise ask to confirm and send
+pub fn print_dry_run_banner() {
+    println!("{}", DRY_RUN_BANNER.style(theme::warning_style()));
+}
+
+/// Guard against fat-fingered amounts: above the spending limit the user must pass
+/// `--confirm-large` or type the amount again
+pub fn confirm_large_amount(amount: &BigUint, config: &Config, confirm_large: bool) -> Result<()> {
+    let Some(limit) = exceeded_spending_limit(amount, config)? else {
+        return Ok(());
+    };
+    
+    let amounts = AmountFormat::from(&config.display);
+    let notice = format!(
+        "{} STRK exceeds the max_transaction_amount limit of {} STRK",
+        amounts.format(amount),
+        amounts.format(&limit)
+    );
+    if confirm_large {
+        eprintln!("{} (allowed by --confirm-large)", notice);
+        return Ok(());
+    }
+    
+    eprintln!("{}", notice);
+    prompt::require_tty("confirmation of a large amount", "pass --confirm-large to send it")?;
+    let exact = crate::utils::wei_to_decimal(amount)
+        .map(|value| value.normalize().to_string())
+        .unwrap_or_else(|_| amounts.format(amount));
+    let typed: String = Input::new()
+        .with_prompt(format!("Type the amount ({}) to confirm, or pass --confirm-large", exact))
+        .allow_empty(true)
+        .interact_text()?;
+    
+    match parse_amount_smart(&typed, RoundingMode::Down) {
+        Ok(value) if value == *amount => Ok(()),
+        _ => Err(anyhow::anyhow!("Confirmation did not match {} STRK; transaction cancelled", exact)),
+    }
+}
+
+/// Environment variable holding the token that `--confirm-token` must match
+pub const CONFIRM_TOKEN_ENV: &str = "DEFINITE_CONFIRM_TOKEN";
+
+/// The confirmation an action needs on top of its yes/no prompt
+///
+/// The default is a routine prompt that `--yes` can answer; emergency actions
+/// built with `new` are critical and always need an explicit answer.
+#[derive(Debug, Clone, Default)]
+pub struct Confirmation {
+    /// Re-type a one-time code after the prompt
+    code: bool,
+    /// Check `--confirm-token` before the prompt
+    needs_token: bool,
+    token: Option<String>,
+    critical: bool,
+}
+
+impl Confirmation {
+    /// Require the configured step plus any `--confirm-via` adds; neither replaces the other
+    pub fn new(configured: ConfirmVia, requested: Option<ConfirmVia>, token: Option<String>) -> Self {
+        let requires = |via| configured == via || requested == Some(via);
+        Confirmation {
+            code: requires(ConfirmVia::Code),
+            needs_token: requires(ConfirmVia::Token),
+            token,
+            critical: true,
+        }
+    }
+    
+    /// Ask `prompt`, then the second factor if one is required
+    ///
+    /// Returns `false` when the prompt is declined; a missing or wrong token or code is an error.
+    pub fn confirm(&self, prompt: &str, prompter: &Prompter) -> Result<bool> {
+        // A bad token fails before anything is asked, so scripts cannot get past it
+        if self.needs_token {
+            check_confirm_token(std::env::var(CONFIRM_TOKEN_ENV).ok().as_deref(), self.token.as_deref())?;
+        }
+        
+        let confirmed = if self.critical {
+            prompter.confirm_critical(prompt)?
+        } else {
+            prompter.confirm(prompt, false)?
+        };
+        if !confirmed {
+            return Ok(false);
+        }
+        
+        if self.code {
+            prompt::require_tty("a confirmation code", "emergency actions need an interactive terminal")?;
+            let code = uuid::Uuid::new_v4().simple().to_string()[..6].to_uppercase();
+            let typed: String = Input::new()
+                .with_prompt(format!("Type {} to confirm", code))
+                .allow_empty(true)
+                .interact_text()?;
+            if !typed.trim().eq_ignore_ascii_case(&code) {
+                return Err(anyhow::anyhow!("Confirmation code did not match; action cancelled"));
+            }
+        }
+        
+        Ok(true)
+    }
+}
+
+/// Check `--confirm-token` against the value of `DEFINITE_CONFIRM_TOKEN`
+fn check_confirm_token(expected: Option<&str>, given: Option<&str>) -> Result<()> {
+    let expected = expected.filter(|token| !token.is_empty()).ok_or_else(|| anyhow::anyhow!(
+        "This action is confirmed by token, but {} is not set",
+        CONFIRM_TOKEN_ENV
+    ))?;
+    
+    match given {
+        Some(given) if given == expected => Ok(()),
+        Some(_) => Err(anyhow::anyhow!("--confirm-token does not match {}; action cancelled", CONFIRM_TOKEN_ENV)),
+        None => Err(anyhow::anyhow!("This action requires --confirm-token")),
+    }
+}
+
+/// Clamp an estimated max fee to the ceiling, refusing when even the estimate exceeds it
+///
+/// `max_fee` carries the 10% margin from `estimate_max_fee`; the cap is only
+/// breached when the fee without that margin is above it.
+pub fn apply_fee_cap(max_fee: FieldElement, cap: Option<FieldElement>, amounts: &AmountFormat) -> Result<FieldElement> {
+    let Some(cap) = cap else {
+        return Ok(max_fee);
+    };
+    
+    let with_margin = crate::contracts::utils::felt_to_bigint(max_fee);
+    let estimate = &with_margin * 10u32 / 11u32;
+    let cap_wei = crate::contracts::utils::felt_to_bigint(cap);
+    
+    if estimate > cap_wei {
+        return Err(anyhow::anyhow!(
+            "estimated fee {} exceeds the --max-fee cap of {}; not sending",
+            amounts.format(&estimate),
+            amounts.format(&cap_wei)
+        ));
+    }
+    
+    Ok(if with_margin > cap_wei { cap } else { max_fee })
+}
+
+/// Estimate, cap and (unless `skip_balance_check`) balance-check the fee for `calls`
+///
+/// The result carries the max fee every send path should sign with.
+pub async fn prepare_max_fee<A>(
+    account: &A,
+    config: &Config,
+    calls: Vec<Call>,
+    cap: Option<FieldElement>,
+    pricing: GasPricing,
+    skip_balance_check: bool,
+) -> Result<FeeEstimate>
+where
+    A: ConnectedAccount + Sync,
+    <A as Account>::SignError: 'static,
+{
+    let fee = if skip_balance_check {
+        price_max_fee(account, config, calls, pricing).await?
+    } else {
+        check_fee_balance(account, config, calls, pricing).await?
+    };
+    
+    let capped = apply_fee_cap(fee.max_fee(), cap, &AmountFormat::from(&config.display))?;
+    Ok(fee.with_max_fee(capped))
+}
+
+/// Send `calls` with a capped max fee and return the transaction hash
+pub async fn send_capped<A>(
+    account: &A,
+    config: &Config,
+    calls: Vec<Call>,
+    cap: Option<FieldElement>,
+    pricing: GasPricing,
+    skip_balance_check: bool,
+) -> Result<FieldElement>
+where
+    A: ConnectedAccount + Sync,
+    <A as Account>::SignError: 'static,
+{
+    let fee = prepare_max_fee(account, config, calls.clone(), cap, pricing, skip_balance_check).await?;
+    let result = account.execute(calls).max_fee(fee.sendable()?).send().await
+        .context("Failed to send transaction")?;
+    
+    Ok(result.transaction_hash)
+}
+
+/// Error text nodes use when a transaction's nonce was already taken
+///
+/// Matched case-insensitively against the whole error chain, covering the RPC
+/// spec's `InvalidTransactionNonce` and the messages devnet and pathfinder return.
+const NONCE_CONFLICT_PATTERNS: &[&str] = &[
+    "invalidtransactionnonce",
+    "invalid transaction nonce",
+    "nonce too low",
+    "nonce is too old",
+];
+
+/// Total attempts for a send that keeps hitting nonce conflicts
+pub const NONCE_RETRY_ATTEMPTS: u32 = 3;
+
+/// Whether `err` is a nonce conflict that resending with a fresh nonce can fix
+///
+/// Reverts and every other failure are not retryable.
+pub fn is_nonce_conflict(err: &anyhow::Error) -> bool {
+    let text = format!("{:?}", err).to_lowercase();
+    NONCE_CONFLICT_PATTERNS.iter().any(|pattern| text.contains(pattern))
+}
+
+/// Run `send` until it succeeds, fails for another reason, or uses up `attempts`
+///
+/// `send` must build a fresh execution each time so the nonce is re-fetched from
+/// the pending block. `on_retry` is told the failed attempt number and its error
+/// before waiting `delay` times that number.
+pub async fn retry_on_nonce_conflict<T, F, Fut>(
+    attempts: u32,
+    delay: std::time::Duration,
+    mut send: F,
+    mut on_retry: impl FnMut(u32, &anyhow::Error),
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match send().await {
+            Err(err) if attempt < attempts && is_nonce_conflict(&err) => {
+                on_retry(attempt, &err);
+                tokio::time::sleep(delay * attempt).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a transaction at `nonce` can still be replaced, given the account's nonce
+/// in the latest and pending blocks
+///
+/// A nonce below the pending nonce is already taken by a transaction the sequencer
+/// has accepted. A nonce above it is allowed, but the replacement waits behind the
+/// earlier nonces, so the returned note says so.
+pub fn check_replaceable_nonce(nonce: FieldElement, latest: FieldElement, pending: FieldElement) -> Result<Option<String>> {
+    if nonce < latest {
+        return Err(anyhow::anyhow!("Nonce {:#x} was already used by a transaction in an accepted block; there is nothing to cancel", nonce));
+    }
+    if nonce < pending {
+        return Err(anyhow::anyhow!("Nonce {:#x} is used by a transaction in the pending block; it will be included and can no longer be replaced", nonce));
+    }
+    if nonce > pending {
+        return Ok(Some(format!(
+            "Nonces {:#x} to {:#x} are still outstanding; the replacement is only included after them",
+            pending,
+            nonce - FieldElement::ONE
+        )));
+    }
+    Ok(None)
+}
+
+/// Max fee for a replacement: `bump_percent` above the stuck transaction's max fee or
+/// the fresh estimate, whichever is higher
+pub fn replacement_fee(estimate: &BigUint, stuck: Option<&BigUint>, bump_percent: u32) -> BigUint {
+    let base = stuck.filter(|stuck| *stuck > estimate).unwrap_or(estimate);
+    base * (100u32 + bump_percent) / 100u32
+}
+
+/// Why a node refused a transaction at an occupied nonce
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplacementRejection {
+    /// The node keeps the first transaction at a nonce and never replaces it
+    Unsupported,
+    /// The node replaces by fee, but the new fee was not high enough
+    Underpriced,
+}
+
+/// Classify a broadcast error for a replacement, or `None` if it is unrelated to replacing
+pub fn replacement_rejection(err: &anyhow::Error) -> Option<ReplacementRejection> {
+    let text = format!("{:?}", err).to_lowercase();
+    if text.contains("underpriced") || text.contains("replacement fee") || text.contains("insufficient max fee") {
+        Some(ReplacementRejection::Underpriced)
+    } else if is_nonce_conflict(err) || text.contains("duplicate") || text.contains("already exists") {
+        Some(ReplacementRejection::Unsupported)
+    } else {
+        None
+    }
+}
+
+/// Token that pays for the v1 transactions the CLI sends, whatever `transaction.fee_token` previews
+pub fn fee_payment_token() -> Result<FieldElement> {
+    validate_address(ETH_TOKEN_ADDRESS)
+}
+
+/// Estimate the fee for `calls` and abort before any prompt if the ETH balance cannot cover it
+///
+/// A v3 preview is returned unchecked, as it can never be sent.
+pub async fn check_fee_balance<A>(account: &A, config: &Config, calls: Vec<Call>, pricing: GasPricing) -> Result<FeeEstimate>
+where
+    A: ConnectedAccount + Sync,
+    <A as Account>::SignError: 'static,
+{
+    let fee = price_max_fee(account, config, calls, pricing).await?;
+    let FeeEstimate::V1 { max_fee } = fee else {
+        return Ok(fee);
+    };
+    
+    let fee_token = TokenContract::with_address(account, fee_payment_token()?);
+    let balance = fee_token.balance_of(account.address()).await
+        .context("Failed to read the ETH balance for fees")?;
+    let needed = crate::contracts::utils::felt_to_bigint(max_fee);
+    
+    if balance < needed {
+        let amounts = AmountFormat::from(&config.display);
+        return Err(anyhow::anyhow!(
+            "insufficient fee balance: have {} ETH, need ~{} ETH",
+            amounts.format(&balance),
+            amounts.format(&needed)
+        ));
+    }
+    
+    Ok(fee)
+}
+
+/// Run `calls` through the node's simulation endpoint without broadcasting
+///
+/// Fee charging is skipped so an unfunded account can still trial a call;
+/// the returned fee estimate is unaffected.
+pub async fn simulate<A>(account: &A, calls: Vec<Call>, skip_validate: bool) -> Result<SimulatedTransaction>
+where
+    A: ConnectedAccount + Sync,
+    <A as Account>::SignError: 'static,
+{
+    account.execute(calls).simulate(skip_validate, true).await
+        .context("Failed to simulate transaction")
+}
+
+/// Sign `calls` with an explicit nonce and max fee without contacting the network
+pub async fn sign_calls<A>(
+    account: &A,
+    calls: Vec<Call>,
+    summaries: Vec<CallSummary>,
+    nonce: FieldElement,
+    max_fee: FieldElement,
+) -> Result<SignedTransaction>
+where
+    A: ConnectedAccount + Sync,
+{
+    let prepared = account
+        .execute(calls)
+        .nonce(nonce)
+        .max_fee(max_fee)
+        .prepared()?;
+    
+    let transaction_hash = prepared.transaction_hash(false);
+    let request = prepared
+        .get_invoke_request(false)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to sign transaction: {}", e))?;
+    
+    let transaction = match request {
+        BroadcastedInvokeTransaction::V1(tx) => tx,
+        _ => return Err(anyhow::anyhow!("Unexpected transaction version from signer")),
+    };
+    
+    Ok(SignedTransaction {
+        transaction_hash: format!("{:#x}", transaction_hash),
+        chain_id: format!("{:#x}", account.chain_id()),
+        sender_address: format!("{:#x}", account.address()),
+        nonce: format!("{:#x}", nonce),
+        max_fee: format!("{:#x}", max_fee),
+        calls: summaries,
+        transaction,
+    })
+}
+
+/// Submit a previously signed transaction and return the hash reported by the node
+pub async fn broadcast<P: Provider>(provider: &P, signed: &SignedTransaction) -> Result<FieldElement> {
+    let result = provider
+        .add_invoke_transaction(BroadcastedInvokeTransaction::V1(signed.transaction.clone()))
+        .await
+        .context("Failed to broadcast transaction")?;
+    
+    Ok(result.transaction_hash)
+}
+
+/// A contract panic the CLI knows how to explain
+#[derive(Debug, PartialEq)]
+pub struct KnownError {
+    /// The Cairo short string passed to `assert`
+    pub reason: &'static str,
+    pub message: &'static str,
+    pub hint: &'static str,
+}
+
+/// Panics raised by the vault and the tokens it moves
+pub const KNOWN_ERRORS: &[KnownError] = &[
+    KnownError {
+        reason: "Vault: paused",
+        message: "The vault is paused",
+        hint: "Deposits and withdrawals resume once the operators lift the pause; check `definite protocol status`",
+    },
+    KnownError {
+        reason: "Vault: below min deposit",
+        message: "The amount is below the vault's minimum deposit",
+        hint: "Increase the amount; `definite protocol status --detailed` shows the minimum",
+    },
+    KnownError {
+        reason: "Vault: max TVL exceeded",
+        message: "The deposit would exceed the vault's deposit limit",
+        hint: "Deposit a smaller amount or wait for capacity to free up",
+    },
+    KnownError {
+        reason: "Vault: zero amount",
+        message: "The amount is zero",
+        hint: "Pass a positive amount",
+    },
+    KnownError {
+        reason: "Vault: insufficient shares",
+        message: "The vault has no shares to redeem",
+        hint: "Check your hSTRK balance with `definite user balance`",
+    },
+    KnownError {
+        reason: "Vault: reentrancy",
+        message: "The vault rejected a reentrant call",
+        hint: "Retry the transaction on its own rather than batched with other vault calls",
+    },
+    KnownError {
+        reason: "ERC20: insufficient balance",
+        message: "The token balance is too low",
+        hint: "Check balances with `definite user balance`",
+    },
+    KnownError {
+        reason: "ERC20: insufficient allowance",
+        message: "The vault is not approved to move enough STRK",
+        hint: "Approve the vault for at least the deposit amount and retry",
+    },
+];
+
+/// Cairo short strings found in a revert reason, whether quoted or as raw hex felts
+pub fn revert_short_strings(reason: &str) -> Vec<String> {
+    let mut strings: Vec<String> = reason
+        .split('\'')
+        .skip(1)
+        .step_by(2)
+        .map(str::to_string)
+        .collect();
+    
+    for token in reason.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let Some(hex) = token.strip_prefix("0x") else { continue };
+        let Ok(felt) = FieldElement::from_hex_be(hex) else { continue };
+        if let Ok(decoded) = starknet::core::utils::parse_cairo_short_string(&felt) {
+            if !decoded.is_empty() && decoded.bytes().all(|b| b.is_ascii_graphic() || b == b' ') && !strings.contains(&decoded) {
+                strings.push(decoded);
+            }
+        }
+    }
+    
+    strings
+}
+
+/// Look up the first known error named in a revert reason
+pub fn explain_revert(reason: &str) -> Option<&'static KnownError> {
+    let strings = revert_short_strings(reason);
+    KNOWN_ERRORS.iter().find(|known| strings.iter().any(|s| s == known.reason))
+}
+
+/// Rewrite an error whose chain carries a known contract panic into a message with a hint
+pub fn explain_error(err: anyhow::Error) -> anyhow::Error {
+    match explain_revert(&format!("{:#}", err)) {
+        Some(known) => anyhow::anyhow!("{} ('{}')\nHint: {}", known.message, known.reason, known.hint),
+        None => err,
+    }
+}
+
+/// Poll for the receipt of `tx_hash` and fail with the decoded revert reason if it reverted
+pub async fn wait_for_execution<P: Provider>(provider: &P, tx_hash: FieldElement, timeout_secs: u64) -> Result<()> {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
+    
+    loop {
+        if let Ok(receipt) = provider.get_transaction_receipt(tx_hash).await {
+            return match receipt.execution_result() {
+                ExecutionResult::Succeeded => Ok(()),
+                ExecutionResult::Reverted { reason } => Err(explain_error(anyhow::anyhow!(
+                    "Transaction {:#x} reverted: {}",
+                    tx_hash,
+                    reason
+                ))),
+            };
+        }
+        
+        if std::time::Instant::now() >= deadline {
+            return Err(anyhow::anyhow!(
+                "Timed out after {}s waiting for transaction {:#x}",
+                timeout_secs,
+                tx_hash
+            ));
+        }
+        
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+}
+
+/// How settled a transaction must be before it counts as confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Finality {
+    /// Accepted on L2: fast, but a reorg can still undo it
+    #[default]
+    L2,
+    /// Accepted on L1, which can take hours
+    L1,
+}
+
+impl Finality {
+    /// Whether an executed transaction is final enough, given whether it is accepted on L1 yet
+    pub fn is_reached(self, accepted_on_l1: bool) -> bool {
+        match self {
+            Finality::L2 => true,
+            Finality::L1 => accepted_on_l1,
+        }
+    }
+}
+
+/// Longest `wait_for_l1_acceptance` waits before giving up
+pub const L1_ACCEPTANCE_TIMEOUT_SECS: u64 = 12 * 60 * 60;
+
+/// Pause between receipt polls while waiting for L1 acceptance
+const L1_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Poll until an executed transaction is accepted on L1, calling `on_poll` with the time waited so far
+///
+/// Meant to follow `wait_for_execution`; it does not check for reverts.
+pub async fn wait_for_l1_acceptance<P: Provider>(
+    provider: &P,
+    tx_hash: FieldElement,
+    timeout_secs: u64,
+    mut on_poll: impl FnMut(std::time::Duration),
+) -> Result<()> {
+    let started = std::time::Instant::now();
+    
+    loop {
+        if let Ok(receipt) = provider.get_transaction_receipt(tx_hash).await {
+            let accepted_on_l1 = matches!(receipt.finality_status(), TransactionFinalityStatus::AcceptedOnL1);
+            if Finality::L1.is_reached(accepted_on_l1) {
+                return Ok(());
+            }
+        }
+        
+        let waited = started.elapsed();
+        if waited >= std::time::Duration::from_secs(timeout_secs) {
+            return Err(anyhow::anyhow!(
+                "Timed out after {} waiting for transaction {:#x} to be accepted on L1 (it is executed on L2)",
+                format_duration(waited.as_secs()),
+                tx_hash
+            ));
+        }
+        
+        on_poll(waited);
+        tokio::time::sleep(L1_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn l1_finality_waits_for_l1_acceptance() {
+        assert!(Finality::L2.is_reached(false));
+        assert!(Finality::L2.is_reached(true));
+        assert!(!Finality::L1.is_reached(false));
+        assert!(Finality::L1.is_reached(true));
+        assert_eq!(Finality::default(), Finality::L2);
+    }
+    
+    #[test]
+    fn confirm_via_flag_adds_to_the_configured_step() {
+        let steps = |confirmation: Confirmation| (confirmation.code, confirmation.needs_token);
+        assert_eq!(steps(Confirmation::new(ConfirmVia::Code, None, None)), (true, false));
+        assert_eq!(steps(Confirmation::new(ConfirmVia::Code, Some(ConfirmVia::Prompt), None)), (true, false));
+        assert_eq!(steps(Confirmation::new(ConfirmVia::Prompt, Some(ConfirmVia::Token), None)), (false, true));
+        assert_eq!(steps(Confirmation::new(ConfirmVia::Code, Some(ConfirmVia::Token), None)), (true, true));
+        assert_eq!(steps(Confirmation::new(ConfirmVia::Token, Some(ConfirmVia::Code), None)), (true, true));
+    }
+    
+    #[test]
+    fn confirm_token_must_match_the_environment() {
+        assert!(check_confirm_token(Some("s3cret"), Some("s3cret")).is_ok());
+        assert!(check_confirm_token(Some("s3cret"), Some("guess")).is_err());
+        assert!(check_confirm_token(Some("s3cret"), None).is_err());
+        
+        // An unset or empty variable must not let an empty token through
+        assert!(check_confirm_token(None, Some("")).is_err());
+        assert!(check_confirm_token(Some(""), Some("")).is_err());
+    }
+    
+    #[tokio::test]
+    async fn nonce_conflict_is_retried_until_success() {
+        let sends = std::cell::Cell::new(0);
+        let mut retries = Vec::new();
+        
+        let result = retry_on_nonce_conflict(
+            NONCE_RETRY_ATTEMPTS,
+            std::time::Duration::ZERO,
+            || {
+                sends.set(sends.get() + 1);
+                let attempt = sends.get();
+                async move {
+                    match attempt {
+                        1 => Err(anyhow::anyhow!("StarknetError: InvalidTransactionNonce")).context("Failed to send transaction"),
+                        _ => Ok(FieldElement::from(0xabcu32)),
+                    }
+                }
+            },
+            |attempt, _| retries.push(attempt),
+        ).await;
+        
+        assert_eq!(result.unwrap(), FieldElement::from(0xabcu32));
+        assert_eq!(sends.get(), 2);
+        assert_eq!(retries, vec![1]);
+    }
+    
+    #[tokio::test]
+    async fn reverts_and_exhausted_retries_are_not_retried_further() {
+        let sends = std::cell::Cell::new(0);
+        let revert = retry_on_nonce_conflict(
+            NONCE_RETRY_ATTEMPTS,
+            std::time::Duration::ZERO,
+            || {
+                sends.set(sends.get() + 1);
+                async { Err::<(), _>(anyhow::anyhow!("Execution reverted: Vault: paused")) }
+            },
+            |_, _| {},
+        ).await;
+        assert!(revert.is_err());
+        assert_eq!(sends.get(), 1);
+        
+        sends.set(0);
+        let conflict = retry_on_nonce_conflict(
+            NONCE_RETRY_ATTEMPTS,
+            std::time::Duration::ZERO,
+            || {
+                sends.set(sends.get() + 1);
+                async { Err::<(), _>(anyhow::anyhow!("Invalid transaction nonce of contract at address 0x1")) }
+            },
+            |_, _| {},
+        ).await;
+        assert!(conflict.is_err());
+        assert_eq!(sends.get(), NONCE_RETRY_ATTEMPTS);
+    }
+    
+    #[test]
+    fn only_open_nonces_can_be_replaced() {
+        let felt = |n: u32| FieldElement::from(n);
+        
+        assert!(check_replaceable_nonce(felt(4), felt(5), felt(5)).is_err());
+        assert!(check_replaceable_nonce(felt(5), felt(5), felt(6)).is_err());
+        assert_eq!(check_replaceable_nonce(felt(5), felt(5), felt(5)).unwrap(), None);
+        assert!(check_replaceable_nonce(felt(7), felt(5), felt(5)).unwrap().unwrap().contains("0x5 to 0x6"));
+    }
+    
+    #[test]
+    fn replacement_outbids_the_higher_of_stuck_and_estimated_fees() {
+        let wei = |n: u32| BigUint::from(n);
+        
+        assert_eq!(replacement_fee(&wei(100), None, 50), wei(150));
+        assert_eq!(replacement_fee(&wei(100), Some(&wei(300)), 50), wei(450));
+        assert_eq!(replacement_fee(&wei(100), Some(&wei(40)), 10), wei(110));
+    }
+    
+    #[test]
+    fn replacement_rejections_are_told_apart() {
+        let rejection = |message: &str| replacement_rejection(&anyhow::anyhow!("{}", message.to_string()).context("Failed to broadcast transaction"));
+        
+        assert_eq!(rejection("StarknetError: InvalidTransactionNonce"), Some(ReplacementRejection::Unsupported));
+        assert_eq!(rejection("DuplicateTx: transaction already exists in the mempool"), Some(ReplacementRejection::Unsupported));
+        assert_eq!(rejection("replacement transaction underpriced"), Some(ReplacementRejection::Underpriced));
+        assert_eq!(rejection("Execution reverted: Vault: paused"), None);
+    }
+    
+    #[test]
+    fn undeployed_account_message_names_the_address() {
+        let message = undeployed_account_message(FieldElement::from(0xabcu32));
+        assert!(message.starts_with("Account 0xabc is not deployed."));
+    }
+    
+    #[test]
+    fn proposal_uses_starknet_js_call_fields() {
+        let (_, summary) = build_call(FieldElement::from(0x1234u32), "emergency_pause", vec![FieldElement::from(7u32)]).unwrap();
+        let proposal = TransactionProposal::new("Pause the vault", "0x534e5f5345504f4c4941", &[summary]).unwrap();
+        
+        let json = serde_json::to_value(&proposal).unwrap();
+        let call = &json["calls"][0];
+        assert_eq!(call["contractAddress"], "0x1234");
+        assert_eq!(call["entrypoint"], "emergency_pause");
+        assert_eq!(call["calldata"][0], "0x7");
+        assert_eq!(
+            call["selector"],
+            format!("{:#x}", starknet::core::utils::get_selector_from_name("emergency_pause").unwrap())
+        );
+        assert_eq!(json["summary"], "Pause the vault");
+    }
+    
+    #[test]
+    fn proposal_json_is_byte_stable() {
+        let build = || {
+            let (_, summary) = build_call(FieldElement::from(0x1234u32), "collect_management_fees", vec![]).unwrap();
+            TransactionProposal::new("Collect fees", "0x534e5f5345504f4c4941", &[summary]).unwrap().to_json().unwrap()
+        };
+        
+        let expected = format!(
+            r#"{{
+  "summary": "Collect fees",
+  "chain_id": "0x534e5f5345504f4c4941",
+  "calls": [
+    {{
+      "contractAddress": "0x1234",
+      "entrypoint": "collect_management_fees",
+      "selector": "{:#x}",
+      "calldata": []
+    }}
+  ]
+}}"#,
+            starknet::core::utils::get_selector_from_name("collect_management_fees").unwrap()
+        );
+        
+        assert_eq!(build(), expected);
+        assert_eq!(build(), build());
+    }
+    
+    #[test]
+    fn apply_fee_cap_clamps_margin_and_rejects_overruns() {
+        let wei = |amount: u32| FieldElement::from(amount);
+        
+        // 1000 estimated, 1100 with margin
+        assert_eq!(apply_fee_cap(wei(1100), None, &AmountFormat::default()).unwrap(), wei(1100));
+        assert_eq!(apply_fee_cap(wei(1100), Some(wei(2000)), &AmountFormat::default()).unwrap(), wei(1100));
+        assert_eq!(apply_fee_cap(wei(1100), Some(wei(1050)), &AmountFormat::default()).unwrap(), wei(1050));
+        assert_eq!(apply_fee_cap(wei(1100), Some(wei(1000)), &AmountFormat::default()).unwrap(), wei(1000));
+        assert!(apply_fee_cap(wei(1100), Some(wei(999)), &AmountFormat::default()).is_err());
+    }
+    
+    #[test]
+    fn fee_cap_prefers_flag_over_config() {
+        let mut config = Config::default();
+        assert_eq!(fee_cap(None, &config).unwrap(), None);
+        
+        config.transaction.max_fee = Some("0.01".to_string());
+        let from_config = fee_cap(None, &config).unwrap().unwrap();
+        let from_flag = fee_cap(Some("0.02"), &config).unwrap().unwrap();
+        
+        assert_eq!(crate::contracts::utils::felt_to_bigint(from_config), parse_amount("0.01", RoundingMode::Down).unwrap());
+        assert_eq!(crate::contracts::utils::felt_to_bigint(from_flag), parse_amount("0.02", RoundingMode::Down).unwrap());
+        assert!(fee_cap(Some("lots"), &config).is_err());
+    }
+    
+    #[test]
+    fn fee_cap_refuses_bare_integers_that_look_like_wei() {
+        let config = Config::default();
+        assert!(fee_cap(Some("2"), &config).unwrap().is_some());
+        assert!(fee_cap(Some("1000000000000000"), &config).unwrap_err().to_string().contains("not wei"));
+        
+        // The config refuses what fee_cap would refuse later
+        let mut config = Config::default();
+        assert!(config.set_value("transaction.max_fee", "1000000000000000").is_err());
+        config.set_value("transaction.max_fee", "0.01").unwrap();
+        assert!(fee_cap(None, &config).unwrap().is_some());
+    }
+    
+    #[test]
+    fn spending_limit_only_trips_above_threshold() {
+        let mut config = Config::default();
+        let amount = parse_amount("1500", RoundingMode::Down).unwrap();
+        assert_eq!(exceeded_spending_limit(&amount, &config).unwrap(), None);
+        
+        config.transaction.max_transaction_amount = Some("1.5K".to_string());
+        assert_eq!(exceeded_spending_limit(&amount, &config).unwrap(), None);
+        
+        config.transaction.max_transaction_amount = Some("1K".to_string());
+        assert_eq!(exceeded_spending_limit(&amount, &config).unwrap(), Some(parse_amount("1000", RoundingMode::Down).unwrap()));
+        assert!(confirm_large_amount(&amount, &config, true).is_ok());
+    }
+    
+    #[test]
+    fn auto_gas_price_buffers_within_ceiling() {
+        assert_eq!(auto_gas_price(100, 1.5, 1_000).unwrap(), 150);
+        // Clamped to the ceiling, never below the network price
+        assert_eq!(auto_gas_price(800, 1.5, 1_000).unwrap(), 1_000);
+        assert_eq!(auto_gas_price(100, 0.5, 1_000).unwrap(), 100);
+        assert!(auto_gas_price(1_001, 1.5, 1_000).is_err());
+    }
+    
+    #[test]
+    fn v3_fee_totals_resource_bounds() {
+        let l1_gas = ResourceBound { amount: 1_000, price_per_unit: 30 };
+        let l1_data_gas = ResourceBound { amount: 200, price_per_unit: 5 };
+        
+        // 31_000 fri estimated, plus a 10% margin
+        let estimated = FeeEstimate::v3(l1_gas, l1_data_gas, ResourceBound::default(), None).unwrap();
+        assert_eq!(estimated.max_fee(), FieldElement::from(34_100u32));
+        assert_eq!(estimated.fee_token(), "STRK");
+        
+        // Auto gas replaces the L1 price instead of adding a margin
+        let auto = FeeEstimate::v3(l1_gas, l1_data_gas, ResourceBound::default(), Some(45)).unwrap();
+        assert_eq!(auto.max_fee(), FieldElement::from(46_000u32));
+        
+        let capped = estimated.with_max_fee(FieldElement::from(32_000u32));
+        assert_eq!(capped.max_fee(), FieldElement::from(32_000u32));
+        let labels: Vec<String> = capped.summary_lines(&AmountFormat::default()).into_iter().map(|(label, _)| label).collect();
+        assert_eq!(labels, ["L1 Gas", "L1 Data Gas", "L2 Gas", "Estimated Fee", "Max Fee", "Fee Preview"]);
+        assert_eq!(capped.summary_lines(&AmountFormat::default())[0].1, "1000 @ 30 fri");
+        assert!(capped.sendable().is_err());
+    }
+    
+    #[test]
+    fn v1_fee_is_a_single_eth_max_fee() {
+        let fee = FeeEstimate::V1 { max_fee: FieldElement::from(1_100u32) };
+        let lines = fee.summary_lines(&AmountFormat::default());
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].0, "Max Fee");
+        assert!(lines[0].1.ends_with(" ETH"));
+        assert_eq!(fee.sendable().unwrap(), FieldElement::from(1_100u32));
+        
+        // Auto gas prices both the L1 gas and the L1 data gas
+        let auto = FeeEstimate::v1(
+            ResourceBound { amount: 1_000, price_per_unit: 45 },
+            ResourceBound { amount: 200, price_per_unit: 5 },
+        ).unwrap();
+        assert_eq!(auto.max_fee(), FieldElement::from(46_000u32));
+        
+        let mut config = Config::default();
+        assert!(!uses_v3(&config));
+        config.transaction.fee_token = "strk".to_string();
+        assert!(uses_v3(&config));
+    }
+    
+    #[test]
+    fn gas_pricing_reads_ceiling_and_multiplier() {
+        let mut config = Config::default();
+        assert_eq!(GasPricing::from_cli(false, &config).unwrap(), GasPricing::Estimate);
+        
+        config.set_value("transaction.gas_price_multiplier", "1.2").unwrap();
+        assert_eq!(
+            GasPricing::from_cli(true, &config).unwrap(),
+            GasPricing::Auto { multiplier: 1.2, ceiling: 1_000_000_000 }
+        );
+        assert!(config.set_value("transaction.gas_price_multiplier", "0.9").is_err());
+    }
+    
+    #[test]
+    fn explain_revert_matches_quoted_and_hex_reasons() {
+        let quoted = "Execution failed. Failure reason: 0x5661756c743a20706175736564 ('Vault: paused').";
+        assert_eq!(explain_revert(quoted).map(|known| known.reason), Some("Vault: paused"));
+        
+        let hex_only = "Error in the called contract: Execution failed. Failure reason: 0x5661756c743a20706175736564.";
+        assert_eq!(explain_revert(hex_only).map(|known| known.reason), Some("Vault: paused"));
+        
+        assert_eq!(explain_revert("Failure reason: 'Something else'."), None);
+    }
+    
+    #[test]
+    fn explain_error_adds_hint_and_keeps_unknown_errors() {
+        let known = explain_error(anyhow::anyhow!("Failure reason: 'Vault: max TVL exceeded'."));
+        assert!(known.to_string().contains("deposit limit"));
+        assert!(known.to_string().contains("Hint:"));
+        
+        let unknown = explain_error(anyhow::anyhow!("connection refused"));
+        assert_eq!(unknown.to_string(), "connection refused");
+    }
+}
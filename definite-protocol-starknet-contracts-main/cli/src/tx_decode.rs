@@ -0,0 +1,171 @@
+//! Splitting a transaction's calldata back into the calls it made, for `contract decode-tx`
+
+use anyhow::Result;
+use starknet::core::types::{FieldElement, InvokeTransaction, Transaction};
+use starknet::core::utils::get_selector_from_name;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Entry points the CLI itself calls, used to name a selector when the target's ABI is unavailable
+const KNOWN_FUNCTIONS: &[&str] = &[
+    "transfer",
+    "transferFrom",
+    "transfer_from",
+    "approve",
+    "increase_allowance",
+    "decrease_allowance",
+    "deposit",
+    "withdraw",
+    "redeem",
+    "claim_withdrawal",
+    "mint",
+    "emergency_pause",
+    "resume_operations",
+    "emergency_withdraw",
+    "collect_management_fee",
+    "collect_management_fees",
+    "collect_performance_fee",
+    "execute_rebalancing",
+    "close_positions",
+    "emergency_close_all",
+    "emergency_close_all_options",
+    "set_protocol_vault",
+    "update_price",
+    "deployContract",
+    "upgrade",
+    "__execute__",
+    "__validate__",
+];
+
+/// Name of a well-known entry point with this selector
+pub fn known_function(selector: FieldElement) -> Option<&'static str> {
+    static BY_SELECTOR: OnceLock<HashMap<FieldElement, &'static str>> = OnceLock::new();
+    BY_SELECTOR
+        .get_or_init(|| {
+            KNOWN_FUNCTIONS
+                .iter()
+                .filter_map(|name| Some((get_selector_from_name(name).ok()?, *name)))
+                .collect()
+        })
+        .get(&selector)
+        .copied()
+}
+
+/// One call made by a transaction, with its calldata still raw
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawCall {
+    pub to: FieldElement,
+    pub selector: FieldElement,
+    pub calldata: Vec<FieldElement>,
+}
+
+/// The sending account, if any, and the calls a transaction made
+pub fn transaction_calls(transaction: &Transaction) -> Result<(Option<FieldElement>, Vec<RawCall>)> {
+    match transaction {
+        Transaction::Invoke(InvokeTransaction::V0(tx)) => Ok((None, vec![RawCall {
+            to: tx.contract_address,
+            selector: tx.entry_point_selector,
+            calldata: tx.calldata.clone(),
+        }])),
+        Transaction::Invoke(InvokeTransaction::V1(tx)) => Ok((Some(tx.sender_address), split_execute_calldata(&tx.calldata)?)),
+        Transaction::Invoke(InvokeTransaction::V3(tx)) => Ok((Some(tx.sender_address), split_execute_calldata(&tx.calldata)?)),
+        Transaction::L1Handler(tx) => Ok((None, vec![RawCall {
+            to: tx.contract_address,
+            selector: tx.entry_point_selector,
+            calldata: tx.calldata.clone(),
+        }])),
+        _ => Err(anyhow::anyhow!("Only invoke and L1 handler transactions make calls; declare and deploy transactions have no calldata to decode")),
+    }
+}
+
+/// Split an account's `__execute__` calldata into its calls
+///
+/// Cairo 1 accounts encode each call inline as `to, selector, len, data...`. Cairo 0
+/// accounts list `to, selector, offset, len` headers first and then one shared data
+/// array. The inline layout is tried first; a layout is only accepted if it accounts
+/// for every felt.
+pub fn split_execute_calldata(calldata: &[FieldElement]) -> Result<Vec<RawCall>> {
+    split_inline(calldata)
+        .or_else(|| split_legacy(calldata))
+        .ok_or_else(|| anyhow::anyhow!("Calldata does not follow either account multicall layout; the account may use a custom __execute__"))
+}
+
+fn felt_to_len(felt: FieldElement) -> Option<usize> {
+    u32::try_from(felt).ok().map(|len| len as usize)
+}
+
+fn split_inline(calldata: &[FieldElement]) -> Option<Vec<RawCall>> {
+    let count = felt_to_len(*calldata.first()?)?;
+    let mut offset = 1;
+    let mut calls = Vec::new();
+    
+    for _ in 0..count {
+        let header = calldata.get(offset..offset + 3)?;
+        let len = felt_to_len(header[2])?;
+        let data = calldata.get(offset + 3..offset + 3 + len)?;
+        calls.push(RawCall { to: header[0], selector: header[1], calldata: data.to_vec() });
+        offset += 3 + len;
+    }
+    
+    (offset == calldata.len()).then_some(calls)
+}
+
+fn split_legacy(calldata: &[FieldElement]) -> Option<Vec<RawCall>> {
+    let count = felt_to_len(*calldata.first()?)?;
+    let headers_end = 1 + count.checked_mul(4)?;
+    let data_len = felt_to_len(*calldata.get(headers_end)?)?;
+    let data = calldata.get(headers_end + 1..)?;
+    if data.len() != data_len {
+        return None;
+    }
+    
+    calldata[1..headers_end]
+        .chunks(4)
+        .map(|header| {
+            let (start, len) = (felt_to_len(header[2])?, felt_to_len(header[3])?);
+            Some(RawCall { to: header[0], selector: header[1], calldata: data.get(start..start + len)?.to_vec() })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn felts(values: &[u64]) -> Vec<FieldElement> {
+        values.iter().map(|value| FieldElement::from(*value)).collect()
+    }
+    
+    #[test]
+    fn inline_multicall_is_split_per_call() {
+        // approve(0xb, 5, 0) then deposit(5, 0, 0xc)
+        let calldata = felts(&[2, 0xa, 0x1, 3, 0xb, 5, 0, 0xb, 0x2, 3, 5, 0, 0xc]);
+        let calls = split_execute_calldata(&calldata).unwrap();
+        
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], RawCall { to: FieldElement::from(0xau32), selector: FieldElement::ONE, calldata: felts(&[0xb, 5, 0]) });
+        assert_eq!(calls[1].calldata, felts(&[5, 0, 0xc]));
+    }
+    
+    #[test]
+    fn legacy_multicall_reads_the_shared_data_array() {
+        // Two calls over one data array [7, 8, 9]: the first takes [7], the second [8, 9]
+        let calldata = felts(&[2, 0xa, 0x1, 0, 1, 0xb, 0x2, 1, 2, 3, 7, 8, 9]);
+        let calls = split_execute_calldata(&calldata).unwrap();
+        
+        assert_eq!(calls[0].calldata, felts(&[7]));
+        assert_eq!(calls[1], RawCall { to: FieldElement::from(0xbu32), selector: FieldElement::TWO, calldata: felts(&[8, 9]) });
+    }
+    
+    #[test]
+    fn calldata_in_neither_layout_is_rejected() {
+        assert!(split_execute_calldata(&felts(&[1, 0xa, 0x1, 5, 1])).is_err());
+        assert!(split_execute_calldata(&[]).is_err());
+    }
+    
+    #[test]
+    fn known_functions_name_protocol_selectors() {
+        assert_eq!(known_function(get_selector_from_name("deposit").unwrap()), Some("deposit"));
+        assert_eq!(known_function(FieldElement::from(0x1234u32)), None);
+    }
+}
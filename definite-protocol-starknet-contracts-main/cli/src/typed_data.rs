@@ -0,0 +1,249 @@
+//! SNIP-12 typed data hashing, for signing messages off chain
+//!
+//! Implements revision 0, the encoding wallets use for `StarkNetDomain` typed data:
+//! a struct hashes to `pedersen_array([type_hash, ...encoded members])`, and the
+//! message signed is `pedersen_array(["StarkNet Message", domain_hash, account, message_hash])`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use starknet::core::crypto::compute_hash_on_elements;
+use starknet::core::types::FieldElement;
+use starknet::core::utils::{cairo_short_string_to_felt, starknet_keccak};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Type name of the domain every revision 0 message is bound to
+const DOMAIN_TYPE: &str = "StarkNetDomain";
+
+/// Domain name used when signing a plain text message
+pub const MESSAGE_DOMAIN_NAME: &str = "Definite Protocol";
+
+/// One member of a struct type
+#[derive(Debug, Clone, Deserialize)]
+pub struct Member {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// A typed data document as wallets and dapps exchange it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<Member>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+impl TypedData {
+    pub fn from_json(content: &str) -> Result<TypedData> {
+        let typed_data: TypedData = serde_json::from_str(content)
+            .context("Invalid typed data: expected types, primaryType, domain and message")?;
+        if !typed_data.types.contains_key(DOMAIN_TYPE) {
+            return Err(anyhow::anyhow!("Typed data does not define {}; only revision 0 typed data is supported", DOMAIN_TYPE));
+        }
+        if !typed_data.types.contains_key(&typed_data.primary_type) {
+            return Err(anyhow::anyhow!("primaryType '{}' is not defined in types", typed_data.primary_type));
+        }
+        Ok(typed_data)
+    }
+    
+    /// Typed data wrapping a plain text message of at most 31 characters
+    ///
+    /// The domain carries the chain ID so a signature made for one network is not
+    /// valid on another.
+    pub fn plain_message(message: &str, chain_id: FieldElement) -> Result<TypedData> {
+        cairo_short_string_to_felt(message)
+            .map_err(|e| anyhow::anyhow!("Message '{}' cannot be signed as a short string: {} (use --typed-data for longer messages)", message, e))?;
+        
+        let member = |name: &str| Member { name: name.to_string(), kind: "felt".to_string() };
+        let types = BTreeMap::from([
+            (DOMAIN_TYPE.to_string(), vec![member("name"), member("version"), member("chainId")]),
+            ("Message".to_string(), vec![member("message")]),
+        ]);
+        
+        Ok(TypedData {
+            types,
+            primary_type: "Message".to_string(),
+            domain: serde_json::json!({
+                "name": MESSAGE_DOMAIN_NAME,
+                "version": "1",
+                "chainId": format!("{:#x}", chain_id),
+            }),
+            message: serde_json::json!({ "message": message }),
+        })
+    }
+    
+    /// `Name(member:type,...)` followed by every struct it references, sorted by name
+    pub fn encode_type(&self, name: &str) -> Result<String> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(name, &mut dependencies)?;
+        dependencies.remove(name);
+        
+        let encoded = std::iter::once(name)
+            .chain(dependencies.iter().map(String::as_str))
+            .map(|name| {
+                let fields: Vec<String> = self.types[name].iter().map(|m| format!("{}:{}", m.name, m.kind)).collect();
+                format!("{}({})", name, fields.join(","))
+            })
+            .collect();
+        Ok(encoded)
+    }
+    
+    fn collect_dependencies(&self, name: &str, found: &mut BTreeSet<String>) -> Result<()> {
+        if found.contains(name) {
+            return Ok(());
+        }
+        let members = self.types.get(name)
+            .with_context(|| format!("Type '{}' is not defined", name))?;
+        found.insert(name.to_string());
+        
+        for member in members {
+            let kind = member.kind.trim_end_matches('*');
+            if self.types.contains_key(kind) {
+                self.collect_dependencies(kind, found)?;
+            }
+        }
+        Ok(())
+    }
+    
+    pub fn type_hash(&self, name: &str) -> Result<FieldElement> {
+        Ok(starknet_keccak(self.encode_type(name)?.as_bytes()))
+    }
+    
+    /// Hash of `value` as an instance of struct type `name`
+    pub fn struct_hash(&self, name: &str, value: &Value) -> Result<FieldElement> {
+        let members = self.types.get(name)
+            .with_context(|| format!("Type '{}' is not defined", name))?;
+        let object = value.as_object()
+            .with_context(|| format!("Expected an object for {}", name))?;
+        
+        let mut elements = vec![self.type_hash(name)?];
+        for member in members {
+            let field = object.get(&member.name)
+                .with_context(|| format!("{} is missing member '{}'", name, member.name))?;
+            let encoded = self.encode_value(&member.kind, field)
+                .with_context(|| format!("Invalid value for {}.{}", name, member.name))?;
+            elements.push(encoded);
+        }
+        
+        Ok(compute_hash_on_elements(&elements))
+    }
+    
+    fn encode_value(&self, kind: &str, value: &Value) -> Result<FieldElement> {
+        if let Some(element) = kind.strip_suffix('*') {
+            let items = value.as_array().context("Expected an array")?;
+            let encoded = items.iter().map(|item| self.encode_value(element, item)).collect::<Result<Vec<_>>>()?;
+            return Ok(compute_hash_on_elements(&encoded));
+        }
+        if self.types.contains_key(kind) {
+            return self.struct_hash(kind, value);
+        }
+        
+        match kind {
+            "felt" | "string" | "shortstring" => encode_felt(value),
+            "bool" => match value {
+                Value::Bool(flag) => Ok(if *flag { FieldElement::ONE } else { FieldElement::ZERO }),
+                _ => encode_felt(value),
+            },
+            _ => Err(anyhow::anyhow!("Unsupported type '{}'", kind)),
+        }
+    }
+    
+    /// The hash an account signs for this message
+    pub fn message_hash(&self, account: FieldElement) -> Result<FieldElement> {
+        let prefix = cairo_short_string_to_felt("StarkNet Message").expect("prefix is a valid short string");
+        let domain_hash = self.struct_hash(DOMAIN_TYPE, &self.domain).context("Invalid typed data domain")?;
+        let message_hash = self.struct_hash(&self.primary_type, &self.message).context("Invalid typed data message")?;
+        
+        Ok(compute_hash_on_elements(&[prefix, domain_hash, account, message_hash]))
+    }
+}
+
+/// A felt from a JSON number, a hex or decimal string, or any other string as a short string
+fn encode_felt(value: &Value) -> Result<FieldElement> {
+    match value {
+        Value::Number(number) => FieldElement::from_dec_str(&number.to_string())
+            .with_context(|| format!("Invalid felt {}", number)),
+        Value::String(text) => {
+            if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                FieldElement::from_hex_be(hex).with_context(|| format!("Invalid hex felt '{}'", text))
+            } else if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+                FieldElement::from_dec_str(text).with_context(|| format!("Invalid felt '{}'", text))
+            } else {
+                cairo_short_string_to_felt(text).map_err(|e| anyhow::anyhow!("Invalid short string '{}': {}", text, e))
+            }
+        }
+        Value::Bool(flag) => Ok(if *flag { FieldElement::ONE } else { FieldElement::ZERO }),
+        _ => Err(anyhow::anyhow!("Expected a number or string, got {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// The mail example from the SNIP-12 reference implementation
+    const MAIL: &str = r#"{
+        "types": {
+            "StarkNetDomain": [
+                { "name": "name", "type": "felt" },
+                { "name": "version", "type": "felt" },
+                { "name": "chainId", "type": "felt" }
+            ],
+            "Person": [
+                { "name": "name", "type": "felt" },
+                { "name": "wallet", "type": "felt" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "felt" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": { "name": "StarkNet Mail", "version": "1", "chainId": 1 },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+    
+    #[test]
+    fn mail_example_matches_the_reference_hashes() {
+        let typed_data = TypedData::from_json(MAIL).unwrap();
+        
+        assert_eq!(typed_data.encode_type("Mail").unwrap(), "Mail(from:Person,to:Person,contents:felt)Person(name:felt,wallet:felt)");
+        assert_eq!(
+            typed_data.type_hash("Mail").unwrap(),
+            FieldElement::from_hex_be("0x13d89452df9512bf750f539ba3001b945576243288137ddb6c788457d4b2f79").unwrap()
+        );
+        
+        let account = FieldElement::from_hex_be("0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826").unwrap();
+        assert_eq!(
+            typed_data.message_hash(account).unwrap(),
+            FieldElement::from_hex_be("0x6fcff244f63e38b9d88b9e3378d44757710d1b244282b435cb472053c8d78d0").unwrap()
+        );
+    }
+    
+    #[test]
+    fn plain_messages_are_bound_to_the_chain() {
+        let account = FieldElement::from(0xabcu32);
+        let mainnet = TypedData::plain_message("login", cairo_short_string_to_felt("SN_MAIN").unwrap()).unwrap();
+        let sepolia = TypedData::plain_message("login", cairo_short_string_to_felt("SN_SEPOLIA").unwrap()).unwrap();
+        
+        assert_ne!(mainnet.message_hash(account).unwrap(), sepolia.message_hash(account).unwrap());
+        assert!(TypedData::plain_message(&"x".repeat(32), FieldElement::ONE).is_err());
+    }
+    
+    #[test]
+    fn missing_members_and_unknown_primary_types_are_rejected() {
+        let mut typed_data = TypedData::from_json(MAIL).unwrap();
+        typed_data.message.as_object_mut().unwrap().remove("contents");
+        assert!(typed_data.message_hash(FieldElement::ONE).is_err());
+        
+        assert!(TypedData::from_json(&MAIL.replace(r#""primaryType": "Mail""#, r#""primaryType": "Letter""#)).is_err());
+    }
+}
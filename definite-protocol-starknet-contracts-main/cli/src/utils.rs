@@ -1,30 +1,184 @@
 use anyhow::{Result, Context};
-use starknet::core::types::FieldElement;
+use starknet::core::types::{BlockId, BlockTag, FieldElement};
 use starknet::accounts::{Account, SingleOwnerAccount};
 use starknet::providers::jsonrpc::{HttpTransport, JsonRpcClient};
 use starknet::signers::{LocalWallet, SigningKey};
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
 use std::str::FromStr;
+use std::time::Duration;
 
-use crate::{Cli, config::Config};
+use crate::{Cli, config::{Config, DisplayConfig}};
+use crate::contracts::account::AccountContract;
+use crate::retry::{with_retry, Operation, RetryPolicy};
 
-/// Parse amount string to BigUint (assumes 18 decimals)
-pub fn parse_amount(amount_str: &str) -> Result<BigUint> {
-    let amount_f64 = amount_str.parse::<f64>()
-        .context("Invalid amount format")?;
-    
-    if amount_f64 < 0.0 {
+/// Direction to round amounts that carry more precision than 18 decimals
+///
+/// Round down what the user gives (deposits, shares to burn, fee caps) so the CLI
+/// never sends more than was typed, and round up what the user requires (minimum
+/// outputs, STRK targets) so a check is never satisfied by a fraction of a wei less.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Down,
+    Up,
+}
+
+/// Decimals of STRK, hSTRK and every amount the CLI calls wei
+pub const WEI_DECIMALS: u8 = 18;
+
+/// Parse a plain decimal amount into wei (18 decimals)
+///
+/// Digits are converted exactly; precision beyond 18 decimals is rounded per `rounding`.
+pub fn parse_amount(amount_str: &str, rounding: RoundingMode) -> Result<BigUint> {
+    parse_units(amount_str, WEI_DECIMALS, rounding)
+}
+
+/// Parse a plain decimal amount of a token with `decimals` decimals into its base units
+pub fn parse_units(amount_str: &str, decimals: u8, rounding: RoundingMode) -> Result<BigUint> {
+    let amount = amount_str.trim();
+    if amount.starts_with('-') {
         return Err(anyhow::anyhow!("Amount cannot be negative"));
     }
     
-    // Convert to wei (18 decimals)
-    let amount_wei = (amount_f64 * 1e18) as u128;
-    Ok(BigUint::from(amount_wei))
+    let units = decimal_to_units(amount, decimals as usize, rounding)
+        .with_context(|| format!("Invalid amount format '{}'", amount_str))?;
+    within_u256(units, amount)
+}
+
+/// Parse a user-typed token amount such as `1.5K`, `2M` or `100strk` into wei (18 decimals)
+///
+/// `K`, `M` and `B` multiply by a thousand, million and billion; a trailing `STRK`,
+/// `hSTRK` or `ETH` symbol is ignored. Digits are converted exactly rather than via
+/// `f64`, with precision beyond 18 decimals rounded per `rounding`. Anything else,
+/// such as `1.2.3K`, `1e3` or `1,000`, is rejected as ambiguous.
+pub fn parse_amount_smart(input: &str, rounding: RoundingMode) -> Result<BigUint> {
+    parse_units_smart(input, WEI_DECIMALS, rounding)
+}
+
+/// [`parse_amount_smart`] for a token with `decimals` decimals
+pub fn parse_units_smart(input: &str, decimals: u8, rounding: RoundingMode) -> Result<BigUint> {
+    let lower = input.trim().to_ascii_lowercase();
+    let without_symbol = ["hstrk", "strk", "eth"]
+        .iter()
+        .find_map(|symbol| lower.strip_suffix(symbol))
+        .unwrap_or(&lower)
+        .trim_end();
+    
+    let (number, exponent) = match without_symbol.chars().last() {
+        Some('k') => (&without_symbol[..without_symbol.len() - 1], 3),
+        Some('m') => (&without_symbol[..without_symbol.len() - 1], 6),
+        Some('b') => (&without_symbol[..without_symbol.len() - 1], 9),
+        _ => (without_symbol, 0),
+    };
+    
+    let units = decimal_to_units(number, decimals as usize + exponent, rounding).map_err(|_| anyhow::anyhow!(
+        "Invalid amount '{}' (expected a number with an optional K/M/B suffix, e.g. 1.5K)",
+        input
+    ))?;
+    within_u256(units, input)
+}
+
+/// Amounts are sent as `u256`, so anything larger is rejected rather than truncated on chain
+fn within_u256(units: BigUint, input: &str) -> Result<BigUint> {
+    if units.bits() > 256 {
+        return Err(anyhow::anyhow!("Amount '{}' is larger than a u256 can hold", input.trim()));
+    }
+    Ok(units)
+}
+
+/// Convert a decimal number to base units with `decimals` decimals, without going through `f64`
+fn decimal_to_units(number: &str, decimals: usize, rounding: RoundingMode) -> Result<BigUint> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let digits_only = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !digits_only(whole) || !digits_only(fraction) {
+        return Err(anyhow::anyhow!("'{}' is not a decimal number", number));
+    }
+    
+    let (kept, dropped) = fraction.split_at(fraction.len().min(decimals));
+    let padded = format!("{}{}{}", whole, kept, "0".repeat(decimals - kept.len()));
+    let units = BigUint::from_str(&padded).unwrap_or_default();
+    
+    let has_remainder = dropped.bytes().any(|b| b != b'0');
+    Ok(match rounding {
+        RoundingMode::Up if has_remainder => units + 1u32,
+        _ => units,
+    })
+}
+
+/// Exact decimal form of `amount` base units of a token with `decimals` decimals
+///
+/// Trailing fractional zeros are dropped, so `parse_units` reads the result back to the same amount.
+pub fn format_units(amount: &BigUint, decimals: u8) -> String {
+    let digits = format!("{:0>width$}", amount.to_string(), width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+/// How wei amounts are rendered: `display.decimal_places` digits, abbreviated with `--human`, or exact wei with `--raw`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountFormat {
+    decimals: usize,
+    human: bool,
+    raw: bool,
+}
+
+impl AmountFormat {
+    pub fn new(display: &DisplayConfig, human: bool) -> Self {
+        AmountFormat {
+            decimals: (display.decimal_places as usize).min(18),
+            human,
+            raw: false,
+        }
+    }
+    
+    /// Print wei verbatim instead, for commands that honor `--raw`
+    pub fn with_raw(self, raw: bool) -> Self {
+        AmountFormat { raw, ..self }
+    }
+    
+    /// Format a wei amount (18 decimals) for display
+    pub fn format(&self, amount: &BigUint) -> String {
+        if self.raw {
+            return amount.to_string();
+        }
+        if self.human {
+            return format_abbreviated(amount);
+        }
+        
+        // Round half up at the configured precision without going through `f64`
+        let unit = BigUint::from(10u32).pow(18);
+        let scale = BigUint::from(10u32).pow(self.decimals as u32);
+        let scaled = (amount * &scale + &unit / 2u32) / &unit;
+        
+        let whole = &scaled / &scale;
+        if self.decimals == 0 {
+            return whole.to_string();
+        }
+        format!("{}.{:0>width$}", whole, (&scaled % &scale).to_string(), width = self.decimals)
+    }
+}
+
+impl Default for AmountFormat {
+    fn default() -> Self {
+        AmountFormat::new(&DisplayConfig::default(), false)
+    }
 }
 
-/// Format BigUint amount to human readable string
-pub fn format_amount(amount: BigUint) -> String {
+impl From<&DisplayConfig> for AmountFormat {
+    fn from(display: &DisplayConfig) -> Self {
+        AmountFormat::new(display, false)
+    }
+}
+
+/// Abbreviate large amounts with K/M, using more decimals the smaller the amount
+fn format_abbreviated(amount: &BigUint) -> String {
     let amount_f64 = amount.to_f64().unwrap_or(0.0) / 1e18;
     
     if amount_f64 >= 1_000_000.0 {
@@ -38,15 +192,56 @@ pub fn format_amount(amount: BigUint) -> String {
     }
 }
 
+/// Most decimals a percentage is printed with; anything smaller shows as `<0.00000001%`
+const MAX_PERCENT_DECIMALS: u32 = 8;
+
 /// Format percentage with appropriate precision
-pub fn format_percentage(value: f64) -> String {
-    if value >= 100.0 {
-        format!("{:.1}%", value)
-    } else if value >= 10.0 {
-        format!("{:.2}%", value)
+///
+/// Precision follows the magnitude, so negative values are formatted like positive
+/// ones. A nonzero value too small for three decimals gets as many as it needs to
+/// show a significant digit, rather than collapsing to `0.000%`.
+pub fn format_percentage(value: Decimal) -> String {
+    percentage(value, false)
+}
+
+/// Like `format_percentage`, but nonzero values always carry `+` or `-`, for changes and PnL
+pub fn format_signed_percentage(value: Decimal) -> String {
+    percentage(value, true)
+}
+
+fn percentage(value: Decimal, with_sign: bool) -> String {
+    let magnitude = value.abs();
+    let decimals = if magnitude >= Decimal::ONE_HUNDRED {
+        1
+    } else if magnitude >= Decimal::TEN {
+        2
+    } else if magnitude.is_zero() || !magnitude.round_dp(3).is_zero() {
+        3
     } else {
-        format!("{:.3}%", value)
-    }
+        match (4..=MAX_PERCENT_DECIMALS).find(|decimals| !magnitude.round_dp(*decimals).is_zero()) {
+            Some(first_digit) => (first_digit + 1).min(MAX_PERCENT_DECIMALS),
+            None if value.is_sign_negative() => return format!(">-{:.*}%", MAX_PERCENT_DECIMALS as usize, Decimal::new(1, MAX_PERCENT_DECIMALS)),
+            None => return format!("<{:.*}%", MAX_PERCENT_DECIMALS as usize, Decimal::new(1, MAX_PERCENT_DECIMALS)),
+        }
+    };
+    
+    let rounded = magnitude.round_dp(decimals);
+    let sign = match (rounded.is_zero(), value.is_sign_negative()) {
+        (true, _) => "",
+        (false, true) => "-",
+        (false, false) if with_sign => "+",
+        (false, false) => "",
+    };
+    format!("{}{:.*}%", sign, decimals as usize, rounded)
+}
+
+/// Convert a wei amount (18 decimals) to a token-denominated Decimal
+pub fn wei_to_decimal(amount: &BigUint) -> Result<Decimal> {
+    let wei = amount.to_i128()
+        .context("Amount too large for decimal conversion")?;
+    
+    Decimal::try_from_i128_with_scale(wei, 18)
+        .context("Amount too large for decimal conversion")
 }
 
 /// Format duration in human readable format
@@ -64,48 +259,365 @@ pub fn format_duration(seconds: u64) -> String {
     }
 }
 
-/// Get configured Starknet account
+/// Get a JSON-RPC provider for the configured network, sending any `[rpc.headers]`
+pub fn get_provider(config: &Config) -> Result<JsonRpcClient<HttpTransport>> {
+    let rpc_url = url::Url::parse(&config.rpc_url)
+        .context("Invalid RPC URL")?;
+    
+    let transport = config.rpc.headers.iter().fold(HttpTransport::new(rpc_url), |transport, (name, value)| {
+        transport.with_header(name.clone(), value.clone())
+    });
+    
+    Ok(JsonRpcClient::new(transport))
+}
+
+/// Get configured Starknet account, or the `--account` override
+///
+/// An overriding key is checked against the account contract's public key before
+/// the account is handed out, so a mistyped address or key fails before any send.
 pub async fn get_account(cli: &Cli) -> Result<SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>> {
-    use starknet::providers::Provider;
+    connect_checked(cli, &account_config(cli)?).await
+}
+
+/// Connect the account in a config from [`account_config`], checking the key of an `--account` override
+pub async fn connect_checked(cli: &Cli, config: &Config) -> Result<SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>> {
+    let account = connect_account(config).await?;
+    if cli.account.is_some() {
+        check_key_controls_account(&account, local_signing_key(config)?.verifying_key().scalar()).await?;
+    }
+    Ok(account)
+}
 
-    let config = Config::load(cli.config.as_deref())?;
+/// Environment variable holding the key for `--account` when neither `--private-key` nor `--keystore` is given
+pub const ACCOUNT_KEY_ENV: &str = "DEFINITE_PRIVATE_KEY";
 
-    // Create provider
-    let rpc_url = url::Url::parse(&config.rpc_url)
-        .context("Invalid RPC URL")?;
-    let provider = JsonRpcClient::new(HttpTransport::new(rpc_url));
+/// Environment variable holding the `--keystore` password, read instead of prompting
+pub const KEYSTORE_PASSWORD_ENV: &str = "DEFINITE_KEYSTORE_PASSWORD";
 
-    // Create signer from private key
-    let signing_key = SigningKey::from_secret_scalar(
-        FieldElement::from_hex_be(&config.private_key)?
-    );
-    let signer = LocalWallet::from(signing_key);
+/// Where the key for an `--account` override comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeySource {
+    PrivateKey(String),
+    Keystore(String),
+}
+
+/// The address and key source of an `--account` override, or `None` without one
+///
+/// `env_key` is the value of [`ACCOUNT_KEY_ENV`], used when no key flag is given.
+pub fn account_override(
+    account: Option<&str>,
+    private_key: Option<&str>,
+    keystore: Option<&str>,
+    env_key: Option<String>,
+) -> Result<Option<(FieldElement, KeySource)>> {
+    let Some(account) = account else {
+        if private_key.is_some() || keystore.is_some() {
+            return Err(anyhow::anyhow!("--private-key and --keystore need --account; the configured account uses the configured key"));
+        }
+        return Ok(None);
+    };
+    
+    let address = validate_address(account).with_context(|| format!("Invalid --account '{}'", account))?;
+    let source = match (private_key, keystore) {
+        (Some(key), _) => KeySource::PrivateKey(key.to_string()),
+        (None, Some(path)) => KeySource::Keystore(path.to_string()),
+        (None, None) => match env_key.filter(|key| !key.trim().is_empty()) {
+            Some(key) => KeySource::PrivateKey(key),
+            None => return Err(anyhow::anyhow!("--account needs a key: pass --private-key or --keystore, or set {}", ACCOUNT_KEY_ENV)),
+        },
+    };
+    Ok(Some((address, source)))
+}
+
+/// Load the config, with the account address and key replaced by any `--account` override
+pub fn account_config(cli: &Cli) -> Result<Config> {
+    let mut config = Config::load(cli.config.as_deref())?;
+    let env_key = std::env::var(ACCOUNT_KEY_ENV).ok();
+    let Some((address, source)) = account_override(cli.account.as_deref(), cli.private_key.as_deref(), cli.keystore.as_deref(), env_key)? else {
+        return Ok(config);
+    };
+    
+    let signing_key = match source {
+        KeySource::PrivateKey(key) => {
+            let scalar = FieldElement::from_hex_be(key.trim())
+                .context("--private-key must be a hex felt")?;
+            SigningKey::from_secret_scalar(scalar)
+        }
+        KeySource::Keystore(path) => keystore_key(&path)?,
+    };
+    
+    config.account_address = felt_to_hex(address);
+    config.private_key = felt_to_hex(signing_key.secret_scalar());
+    Ok(config)
+}
+
+/// Decrypt the `--keystore` key, asking for the password at most once per run
+fn keystore_key(path: &str) -> Result<SigningKey> {
+    static DECRYPTED: std::sync::OnceLock<FieldElement> = std::sync::OnceLock::new();
+    if let Some(scalar) = DECRYPTED.get() {
+        return Ok(SigningKey::from_secret_scalar(*scalar));
+    }
+    
+    let password = match std::env::var(KEYSTORE_PASSWORD_ENV) {
+        Ok(password) => password,
+        Err(_) => {
+            crate::prompt::require_tty("the keystore password", &format!("set {}", KEYSTORE_PASSWORD_ENV))?;
+            dialoguer::Password::new()
+                .with_prompt(format!("Password for {}", path))
+                .interact()?
+        }
+    };
+    let key = SigningKey::from_keystore(path, &password)
+        .map_err(|e| anyhow::anyhow!("Failed to decrypt keystore {}: {}", path, e))?;
+    let _ = DECRYPTED.set(key.secret_scalar());
+    Ok(key)
+}
+
+/// Fail unless the account contract checks signatures against `public_key`
+pub async fn check_key_controls_account(account: &SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>, public_key: FieldElement) -> Result<()> {
+    let expected = AccountContract::with_address(account, account.address()).public_key().await
+        .with_context(|| format!("Failed to read the public key of account {:#x}; is it deployed?", account.address()))?;
+    
+    if expected != public_key {
+        return Err(anyhow::anyhow!(
+            "Key does not control account {:#x}: it derives public key {:#x}, but the account expects {:#x}",
+            account.address(),
+            public_key,
+            expected
+        ));
+    }
+    Ok(())
+}
+
+/// Retries for fetching the chain ID when the config does not set it
+const CHAIN_ID_RETRY: RetryPolicy = RetryPolicy { attempts: 3, initial_delay: Duration::from_millis(500) };
 
-    // Fetch chain ID from provider (enterprise-grade approach)
-    let chain_id = provider.chain_id().await
-        .context("Failed to fetch chain ID from provider. Please verify RPC URL is accessible.")?;
+/// Connect the configured account to the RPC node in `config`
+///
+/// The chain ID comes from `chain_id` in the config when it is set, so building the
+/// account needs no network access; it is only fetched from the node when unset.
+pub async fn connect_account(config: &Config) -> Result<SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>> {
+    use starknet::providers::Provider;
+    
+    let provider = get_provider(config)?;
+    
+    let chain_id = match configured_chain_id(config)? {
+        Some(chain_id) => chain_id,
+        None => with_retry(Operation::Read, "chain ID", CHAIN_ID_RETRY, || async { provider.chain_id().await.map_err(anyhow::Error::from) }).await
+            .context("Failed to fetch chain ID from provider. Please verify RPC URL is accessible, or set chain_id in the config.")?,
+    };
+    
+    build_account(config, provider, chain_id)
+}
+
+/// Get configured Starknet account without contacting the RPC node
+///
+/// The chain ID is taken from the config instead of the provider, so this is
+/// suitable for signing on a machine with no network access.
+///
+/// An `--account` override is used as given; with no network access, its key
+/// cannot be checked against the account contract.
+pub fn get_offline_account(cli: &Cli) -> Result<SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>> {
+    let config = account_config(cli)?;
+    let provider = get_provider(&config)?;
+    let chain_id = configured_chain_id(&config)?
+        .context("chain_id must be set in the config to sign offline")?;
+    
+    build_account(&config, provider, chain_id)
+}
 
+/// The chain ID set in the config, or `None` when it is left empty to be fetched from the node
+pub fn configured_chain_id(config: &Config) -> Result<Option<FieldElement>> {
+    let chain_id = config.chain_id.trim();
+    if chain_id.is_empty() {
+        return Ok(None);
+    }
+    
+    parse_chain_id(chain_id)
+        .with_context(|| format!("Invalid chain_id '{}' in the config", chain_id))
+        .map(Some)
+}
+
+fn build_account(
+    config: &Config,
+    provider: JsonRpcClient<HttpTransport>,
+    chain_id: FieldElement,
+) -> Result<SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>> {
+    let signer = local_wallet(config)?;
+    
     // Create account
-    let account = SingleOwnerAccount::new(
+    let mut account = SingleOwnerAccount::new(
         provider,
         signer,
         FieldElement::from_hex_be(&config.account_address)?,
         chain_id,
         starknet::accounts::ExecutionEncoding::New,
     );
-
+    // Nonces and fee estimates must see transactions this process has just sent
+    account.set_block_id(BlockId::Tag(BlockTag::Pending));
+    
     Ok(account)
 }
 
+/// Signer for the configured private key, for signing without building an account
+pub fn local_wallet(config: &Config) -> Result<LocalWallet> {
+    Ok(LocalWallet::from(local_signing_key(config)?))
+}
+
+fn local_signing_key(config: &Config) -> Result<SigningKey> {
+    if config.private_key.is_empty() {
+        return Err(anyhow::anyhow!("No private key configured; run `config init` or `config rotate-key`"));
+    }
+    Ok(SigningKey::from_secret_scalar(
+        FieldElement::from_hex_be(&config.private_key)?
+    ))
+}
+
+/// Parse a chain ID given either as hex (`0x534e5f4d41494e`) or as a short string (`SN_MAIN`)
+pub fn parse_chain_id(chain_id: &str) -> Result<FieldElement> {
+    if chain_id.starts_with("0x") {
+        FieldElement::from_hex_be(chain_id)
+            .context("Invalid chain ID")
+    } else {
+        starknet::core::utils::cairo_short_string_to_felt(chain_id)
+            .context("Invalid chain ID")
+    }
+}
+
+/// Parse a felt given as hex (`0x...`) or decimal
+pub fn parse_felt(value: &str) -> Result<FieldElement> {
+    let parsed = if value.starts_with("0x") {
+        FieldElement::from_hex_be(value).ok()
+    } else {
+        FieldElement::from_dec_str(value).ok()
+    };
+    
+    parsed.with_context(|| format!("Invalid felt value '{}'", value))
+}
+
+/// Parse a contract call argument: decimal (`1000`), hex (`0xabc`) or a quoted
+/// short string (`'hello'`, encoded as an ASCII felt of at most 31 characters)
+pub fn parse_felt_arg(arg: &str) -> Result<FieldElement> {
+    let arg = arg.trim();
+    
+    if let Some(text) = arg.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+        return starknet::core::utils::cairo_short_string_to_felt(text)
+            .map_err(|e| anyhow::anyhow!("Invalid short string '{}': {}", text, e));
+    }
+    
+    let (digits, radix) = match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (arg, 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(anyhow::anyhow!(
+            "Invalid argument '{}' (expected a decimal, 0x-prefixed hex or 'short string' felt)",
+            arg
+        ));
+    }
+    
+    // The digits are valid, so a parse failure can only mean the value is too large
+    let parsed = if radix == 16 {
+        FieldElement::from_hex_be(digits)
+    } else {
+        FieldElement::from_dec_str(digits)
+    };
+    parsed.map_err(|_| anyhow::anyhow!("Argument '{}' exceeds the field modulus (2^251 + 17 * 2^192 + 1)", arg))
+}
+
+/// Parse calldata arguments given as decimal, hex or short-string felts
+pub fn parse_calldata(args: &[String]) -> Result<Vec<FieldElement>> {
+    args.iter().map(|arg| parse_felt_arg(arg)).collect()
+}
+
+/// Parse a JSON array of felts, e.g. calldata copied from a block explorer
+///
+/// Entries may be hex or decimal strings, or plain non-negative integers.
+pub fn parse_calldata_json(json: &str) -> Result<Vec<FieldElement>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(json)
+        .context("Calldata must be a JSON array of felts")?;
+    
+    values.iter().enumerate().map(|(index, value)| {
+        let felt = match value {
+            serde_json::Value::String(s) => parse_felt(s.trim()),
+            serde_json::Value::Number(n) if n.is_u64() => parse_felt(&n.to_string()),
+            other => Err(anyhow::anyhow!("Expected a hex or decimal felt, found {}", other)),
+        };
+        felt.with_context(|| format!("Invalid calldata entry at index {}", index))
+    }).collect()
+}
+
+/// Read calldata from a JSON file of felts
+pub fn load_calldata_file(path: &str) -> Result<Vec<FieldElement>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read calldata file {}", path))?;
+    parse_calldata_json(&json)
+        .with_context(|| format!("Invalid calldata file {}", path))
+}
+
 /// Validate Starknet address format
 pub fn validate_address(address: &str) -> Result<FieldElement> {
-    if address.starts_with("0x") {
-        FieldElement::from_hex_be(address)
-            .context("Invalid address format")
-    } else {
-        FieldElement::from_hex_be(&format!("0x{}", address))
-            .context("Invalid address format")
+    let hex = address.strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .unwrap_or(address);
+    
+    FieldElement::from_hex_be(&format!("0x{}", hex))
+        .context("Invalid address format")
+}
+
+/// Canonical form of an address: lowercase, `0x`-prefixed and zero-padded to 64 hex digits
+///
+/// Use this whenever an address is stored, displayed or compared as text, so that
+/// `0x49D3`, `49d3` and `0x0049d3` are treated as one address.
+pub fn normalize_address(address: FieldElement) -> String {
+    format!("0x{:064x}", address)
+}
+
+/// Shorten an address to `0x` plus `chars` hex digits from each end of its
+/// zero-padded form, like `0x04ca…4aa4`
+///
+/// Addresses too short to gain anything are returned in full.
+pub fn shorten_address(address: FieldElement, chars: usize) -> String {
+    let full = normalize_address(address);
+    let digits = &full[2..];
+    if chars == 0 || chars * 2 + 1 >= digits.len() {
+        return full;
     }
+    
+    format!("0x{}…{}", &digits[..chars], &digits[digits.len() - chars..])
+}
+
+/// Parse a hex address in any padding or case and return its canonical form
+pub fn canonical_address(address: &str) -> Result<String> {
+    validate_address(address.trim()).map(normalize_address)
+}
+
+/// Canonical form of a configured address for display, or the raw text if it does not parse
+pub fn display_address(address: &str) -> String {
+    canonical_address(address).unwrap_or_else(|_| address.to_string())
+}
+
+/// Resolve the address a read-only command should target
+///
+/// Address book names take precedence over hex parsing so that a short name
+/// like `cafe` is never silently read as an address. With no argument the
+/// account's own address is used.
+pub fn resolve_target_address<A: Account>(
+    arg: Option<String>,
+    account: &A,
+    config: &Config,
+) -> Result<FieldElement> {
+    let Some(arg) = arg else {
+        return Ok(account.address());
+    };
+    
+    if let Some(address) = config.address_book.get(&arg) {
+        return validate_address(address)
+            .with_context(|| format!("Invalid address for address book entry '{}'", arg));
+    }
+    
+    validate_address(&arg)
+        .with_context(|| format!("'{}' is neither a valid address nor an address book entry", arg))
 }
 
 /// Convert hex string to FieldElement
@@ -125,14 +637,117 @@ pub fn felt_to_hex(felt: FieldElement) -> String {
     format!("0x{:064x}", felt)
 }
 
+/// Interpret a felt as a signed Cairo integer
+///
+/// Negative values are encoded modulo the field prime, so anything above P/2 is
+/// `felt - P`. Magnitudes beyond the `i128` range saturate.
+pub fn felt_to_signed_i128(felt: FieldElement) -> i128 {
+    let value = crate::contracts::utils::felt_to_bigint(felt);
+    let negated = crate::contracts::utils::felt_to_bigint(FieldElement::ZERO - felt);
+    
+    if negated < value {
+        negated.to_i128().map(|magnitude| -magnitude).unwrap_or(i128::MIN)
+    } else {
+        value.to_i128().unwrap_or(i128::MAX)
+    }
+}
+
 /// Calculate APY from daily rate
-pub fn calculate_apy(daily_rate: f64) -> f64 {
-    (1.0 + daily_rate).powf(365.0) - 1.0
+pub fn calculate_apy(daily_rate: Decimal) -> Decimal {
+    compound_growth(daily_rate, 365)
 }
 
 /// Calculate daily rate from APY
-pub fn calculate_daily_rate(apy: f64) -> f64 {
-    (1.0 + apy).powf(1.0 / 365.0) - 1.0
+pub fn calculate_daily_rate(apy: Decimal) -> Decimal {
+    nth_root(Decimal::ONE + apy, 365) - Decimal::ONE
+}
+
+/// Total growth from compounding `rate` over `periods`, i.e. `(1 + rate)^periods - 1`
+///
+/// Saturates at `Decimal::MAX` instead of overflowing.
+pub fn compound_growth(rate: Decimal, periods: u32) -> Decimal {
+    powu(Decimal::ONE + rate, periods) - Decimal::ONE
+}
+
+/// How often accrued yield is reinvested in a projection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Compounding {
+    /// Reinvest every day
+    #[default]
+    Daily,
+    /// Reinvest every 30 days; yield within a month accrues simply
+    Monthly,
+    /// Never reinvest (simple interest)
+    None,
+}
+
+impl std::fmt::Display for Compounding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compounding::Daily => write!(f, "Daily"),
+            Compounding::Monthly => write!(f, "Monthly"),
+            Compounding::None => write!(f, "None (simple interest)"),
+        }
+    }
+}
+
+/// Days per month used by `Compounding::Monthly`
+const DAYS_PER_MONTH: u32 = 30;
+
+/// Total growth over `days` at `daily_rate`, reinvesting yield per `compounding`
+pub fn projected_growth(daily_rate: Decimal, days: u32, compounding: Compounding) -> Decimal {
+    match compounding {
+        Compounding::Daily => compound_growth(daily_rate, days),
+        Compounding::Monthly => {
+            let monthly_rate = daily_rate * Decimal::from(DAYS_PER_MONTH);
+            let partial_month = Decimal::ONE + daily_rate * Decimal::from(days % DAYS_PER_MONTH);
+            (compound_growth(monthly_rate, days / DAYS_PER_MONTH) + Decimal::ONE)
+                .checked_mul(partial_month)
+                .unwrap_or(Decimal::MAX)
+                - Decimal::ONE
+        }
+        Compounding::None => daily_rate * Decimal::from(days),
+    }
+}
+
+/// Integer power by repeated squaring, saturating at `Decimal::MAX`
+fn powu(base: Decimal, exponent: u32) -> Decimal {
+    let mut result = Decimal::ONE;
+    let mut square = base;
+    let mut remaining = exponent;
+    
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result = result.checked_mul(square).unwrap_or(Decimal::MAX);
+        }
+        remaining >>= 1;
+        if remaining > 0 {
+            square = square.checked_mul(square).unwrap_or(Decimal::MAX);
+        }
+    }
+    
+    result
+}
+
+/// Positive n-th root by Newton's method, starting from 1 since rates are near it
+fn nth_root(value: Decimal, n: u32) -> Decimal {
+    if value <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    
+    let degree = Decimal::from(n);
+    let mut x = Decimal::ONE;
+    
+    for _ in 0..200 {
+        let x_pow = powu(x, n - 1);
+        let next = x - (x_pow * x - value) / (degree * x_pow);
+        if next == x {
+            break;
+        }
+        x = next;
+    }
+    
+    x
 }
 
 /// Format timestamp to human readable date
@@ -146,6 +761,36 @@ pub fn format_timestamp(timestamp: u64) -> String {
     dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
 }
 
+/// Parse a UTC date back into a unix timestamp, the reverse of `format_timestamp`
+///
+/// Accepts `2024-05-01`, RFC 3339 (`2024-05-01T12:00:00Z`) and the
+/// `2024-05-01 12:00:00 UTC` form printed by the CLI. A bare date means the
+/// start of that day, or its last second when `end_of_day` is set.
+pub fn parse_date(input: &str, end_of_day: bool) -> Result<u64> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+    
+    let input = input.trim();
+    let timestamp = if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        let time = if end_of_day { date.and_hms_opt(23, 59, 59) } else { date.and_hms_opt(0, 0, 0) };
+        time.map(|t| t.and_utc().timestamp())
+    } else if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        Some(dt.timestamp())
+    } else {
+        NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S UTC")
+            .ok()
+            .map(|dt| dt.and_utc().timestamp())
+    };
+    
+    match timestamp {
+        Some(ts) if ts >= 0 => Ok(ts as u64),
+        Some(_) => Err(anyhow::anyhow!("Date '{}' is before 1970", input)),
+        None => Err(anyhow::anyhow!(
+            "Invalid date '{}' (expected YYYY-MM-DD, YYYY-MM-DDTHH:MM:SSZ or YYYY-MM-DD HH:MM:SS UTC)",
+            input
+        )),
+    }
+}
+
 /// Calculate time until timestamp
 pub fn time_until(timestamp: u64) -> String {
     use chrono::{DateTime, Utc, TimeZone};
@@ -159,21 +804,65 @@ pub fn time_until(timestamp: u64) -> String {
     format_duration(diff)
 }
 
-/// Validate and parse slippage basis points
-pub fn parse_slippage_bps(slippage_str: &str) -> Result<u16> {
+/// A rate in basis points (1 bps = 0.01%)
+///
+/// Contracts store fees, slippage and thresholds in bps; convert through this
+/// type rather than dividing by 100 or 10000 by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize)]
+#[serde(transparent)]
+pub struct Bps(pub u32);
+
+impl Bps {
+    /// Parse a percentage such as `1.5` (= 150 bps)
+    pub fn from_percent(percent: f64) -> Result<Bps> {
+        if !percent.is_finite() || percent < 0.0 {
+            return Err(anyhow::anyhow!("Invalid percentage: {}", percent));
+        }
+        
+        Ok(Bps((percent * 100.0).round().min(u32::MAX as f64) as u32))
+    }
+    
+    /// Read a bps value returned by a contract, saturating oversized values
+    pub fn from_biguint(value: &BigUint) -> Bps {
+        Bps(value.to_u32().unwrap_or(u32::MAX))
+    }
+    
+    pub fn to_percent(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+    
+    pub fn to_ratio(self) -> f64 {
+        self.0 as f64 / 10_000.0
+    }
+    
+    /// Exact ratio, e.g. for APY arithmetic in `Decimal`
+    pub fn to_decimal_ratio(self) -> Decimal {
+        Decimal::new(self.0 as i64, 4)
+    }
+}
+
+impl From<u16> for Bps {
+    fn from(bps: u16) -> Bps {
+        Bps(bps as u32)
+    }
+}
+
+impl std::fmt::Display for Bps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2}%", self.to_percent())
+    }
+}
+
+/// Validate and parse a slippage percentage into basis points
+pub fn parse_slippage_bps(slippage_str: &str) -> Result<Bps> {
     let slippage = slippage_str.parse::<f64>()
         .context("Invalid slippage format")?;
     
-    if slippage < 0.0 || slippage > 100.0 {
+    if !(0.0..=100.0).contains(&slippage) {
         return Err(anyhow::anyhow!("Slippage must be between 0% and 100%"));
     }
     
-    Ok((slippage * 100.0) as u16)
-}
-
-/// Format slippage basis points to percentage
-pub fn format_slippage_bps(bps: u16) -> String {
-    format!("{:.2}%", bps as f64 / 100.0)
+    Bps::from_percent(slippage)
 }
 
 /// Calculate price impact
@@ -231,3 +920,402 @@ pub fn pretty_print_json(json_str: &str) -> Result<String> {
     serde_json::to_string_pretty(&value)
         .context("Failed to format JSON")
 }
+
+/// Render a series as a one-line block-character chart scaled to its own min and max
+pub fn sparkline(values: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    
+    values
+        .iter()
+        .map(|value| {
+            if range > 0.0 {
+                BARS[(((value - min) / range) * 7.0).round() as usize]
+            } else {
+                BARS[3]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet::accounts::ExecutionEncoding;
+    
+    #[test]
+    fn parse_felt_arg_accepts_decimal_hex_and_short_strings() {
+        assert_eq!(parse_felt_arg("1000").unwrap(), FieldElement::from(1000u32));
+        assert_eq!(parse_felt_arg("0xabc").unwrap(), FieldElement::from(0xabcu32));
+        assert_eq!(parse_felt_arg("'hello'").unwrap(), FieldElement::from(0x68656c6c6fu64));
+        assert_eq!(parse_felt_arg("''").unwrap(), FieldElement::ZERO);
+    }
+    
+    #[test]
+    fn parse_felt_arg_rejects_overflow_and_garbage() {
+        // The field modulus itself is one past the largest felt
+        let modulus = "0x800000000000011000000000000000000000000000000000000000000000001";
+        let err = parse_felt_arg(modulus).unwrap_err();
+        assert!(err.to_string().contains("exceeds the field modulus"));
+        assert!(parse_felt_arg(&"9".repeat(80)).unwrap_err().to_string().contains("exceeds the field modulus"));
+        
+        for input in ["hello", "0x", "12ab", "'unterminated", "'this short string is far too long to fit'"] {
+            assert!(parse_felt_arg(input).is_err(), "{} should be rejected", input);
+        }
+    }
+    
+    #[test]
+    fn calldata_json_accepts_hex_decimal_and_numbers() {
+        let calldata = parse_calldata_json(r#"["0x1f", "42", 7]"#).unwrap();
+        assert_eq!(calldata, vec![
+            FieldElement::from(31u64),
+            FieldElement::from(42u64),
+            FieldElement::from(7u64),
+        ]);
+    }
+    
+    #[test]
+    fn calldata_json_reports_bad_entry_index() {
+        let err = parse_calldata_json(r#"["0x1", "nope"]"#).unwrap_err();
+        assert!(format!("{:#}", err).contains("index 1"));
+        assert!(parse_calldata_json(r#"{"calldata": []}"#).is_err());
+        assert!(parse_calldata_json("[-1]").is_err());
+    }
+    
+    #[tokio::test]
+    async fn configured_chain_id_needs_no_node() {
+        let mut config = Config::default();
+        config.rpc_url = "http://127.0.0.1:9".to_string();
+        config.private_key = "0x1".to_string();
+        config.chain_id = "SN_MAIN".to_string();
+        
+        let account = connect_account(&config).await.unwrap();
+        assert_eq!(account.chain_id(), starknet::core::utils::cairo_short_string_to_felt("SN_MAIN").unwrap());
+        
+        config.chain_id = " ".to_string();
+        assert_eq!(configured_chain_id(&config).unwrap(), None);
+        config.chain_id = "0xnot-hex".to_string();
+        assert!(configured_chain_id(&config).is_err());
+    }
+    
+    fn test_account() -> SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet> {
+        let provider = JsonRpcClient::new(HttpTransport::new(
+            url::Url::parse("http://localhost:5050").unwrap(),
+        ));
+        let signer = LocalWallet::from(SigningKey::from_secret_scalar(FieldElement::ONE));
+        
+        SingleOwnerAccount::new(
+            provider,
+            signer,
+            FieldElement::from_hex_be("0xabc").unwrap(),
+            FieldElement::ZERO,
+            ExecutionEncoding::New,
+        )
+    }
+    
+    #[test]
+    fn short_padded_and_uppercase_addresses_normalize_alike() {
+        let canonical = "0x0000000000000000000000000000000000000000000000000000000000049d36";
+        for input in ["0x49d36", "49d36", "0x0000049d36", "0x49D36", "0X49D36", canonical] {
+            assert_eq!(canonical_address(input).unwrap(), canonical, "{}", input);
+        }
+        
+        assert_eq!(normalize_address(FieldElement::from(0x49d36u32)), canonical);
+        assert!(canonical_address("0xnothex").is_err());
+        assert_eq!(display_address("bogus"), "bogus");
+    }
+    
+    #[test]
+    fn shortened_addresses_keep_both_ends() {
+        let strk = FieldElement::from_hex_be("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d").unwrap();
+        assert_eq!(shorten_address(strk, 4), "0x0471…938d");
+        assert_eq!(shorten_address(strk, 6), "0x04718f…7c938d");
+        
+        // Leading zeros of the padded form are kept, so short addresses stay recognisable
+        assert_eq!(shorten_address(FieldElement::from(0x49d36u32), 4), "0x0000…9d36");
+        
+        // Nothing is gained by eliding fewer than one digit
+        assert_eq!(shorten_address(strk, 32), normalize_address(strk));
+        assert_eq!(shorten_address(strk, 0), normalize_address(strk));
+    }
+    
+    #[test]
+    fn parse_date_reverses_format_timestamp() {
+        let ts = 1_714_567_890;
+        assert_eq!(parse_date(&format_timestamp(ts), false).unwrap(), ts);
+        assert_eq!(parse_date("2024-05-01T12:51:30Z", false).unwrap(), ts);
+        
+        assert_eq!(parse_date("2024-05-01", false).unwrap(), 1_714_521_600);
+        assert_eq!(parse_date("2024-05-01", true).unwrap(), 1_714_521_600 + 86_399);
+        assert!(parse_date("05/01/2024", false).is_err());
+    }
+    
+    #[test]
+    fn resolve_target_address_defaults_to_account() {
+        let account = test_account();
+        let resolved = resolve_target_address(None, &account, &Config::default()).unwrap();
+        assert_eq!(resolved, account.address());
+    }
+    
+    #[test]
+    fn resolve_target_address_parses_hex() {
+        let account = test_account();
+        let config = Config::default();
+        
+        let prefixed = resolve_target_address(Some("0x123".to_string()), &account, &config).unwrap();
+        let bare = resolve_target_address(Some("123".to_string()), &account, &config).unwrap();
+        
+        assert_eq!(prefixed, FieldElement::from_hex_be("0x123").unwrap());
+        assert_eq!(bare, prefixed);
+    }
+    
+    #[test]
+    fn resolve_target_address_prefers_address_book() {
+        let account = test_account();
+        let mut config = Config::default();
+        config.address_book.insert("treasury".to_string(), "0x456".to_string());
+        config.address_book.insert("cafe".to_string(), "0x789".to_string());
+        
+        let named = resolve_target_address(Some("treasury".to_string()), &account, &config).unwrap();
+        let shadowed = resolve_target_address(Some("cafe".to_string()), &account, &config).unwrap();
+        
+        assert_eq!(named, FieldElement::from_hex_be("0x456").unwrap());
+        assert_eq!(shadowed, FieldElement::from_hex_be("0x789").unwrap());
+    }
+    
+    #[test]
+    fn resolve_target_address_rejects_unknown_names() {
+        let account = test_account();
+        let result = resolve_target_address(Some("nobody".to_string()), &account, &Config::default());
+        assert!(result.is_err());
+    }
+    
+    #[test]
+    fn resolve_target_address_rejects_invalid_book_entries() {
+        let account = test_account();
+        let mut config = Config::default();
+        config.address_book.insert("broken".to_string(), "not-hex".to_string());
+        
+        let result = resolve_target_address(Some("broken".to_string()), &account, &config);
+        assert!(result.is_err());
+    }
+    
+    #[test]
+    fn calculate_apy_matches_exact_compounding() {
+        // (1.0005)^365 - 1 computed with 60-digit arithmetic
+        let exact = Decimal::from_str("0.2001594106777108885744222921").unwrap();
+        let apy = calculate_apy(Decimal::from_str("0.0005").unwrap());
+        
+        let decimal_error = (apy - exact).abs();
+        let f64_error = (Decimal::try_from((1.0f64 + 0.0005).powf(365.0) - 1.0).unwrap() - exact).abs();
+        
+        assert!(decimal_error < Decimal::from_str("0.000000000000000000001").unwrap());
+        assert!(decimal_error < f64_error);
+    }
+    
+    #[test]
+    fn calculate_daily_rate_matches_exact_root() {
+        // 1.15^(1/365) - 1 computed with 60-digit arithmetic
+        let exact = Decimal::from_str("0.0003829827503389582995676255").unwrap();
+        let daily = calculate_daily_rate(Decimal::from_str("0.15").unwrap());
+        
+        let decimal_error = (daily - exact).abs();
+        let f64_error = (Decimal::try_from(1.15f64.powf(1.0 / 365.0) - 1.0).unwrap() - exact).abs();
+        
+        assert!(decimal_error < Decimal::from_str("0.000000000000000000001").unwrap());
+        assert!(decimal_error < f64_error);
+    }
+    
+    #[test]
+    fn apy_and_daily_rate_round_trip() {
+        let daily = Decimal::from_str("0.00042").unwrap();
+        let round_trip = calculate_daily_rate(calculate_apy(daily));
+        assert!((round_trip - daily).abs() < Decimal::from_str("0.000000000000000000001").unwrap());
+    }
+    
+    #[test]
+    fn projected_growth_orders_compounding_modes() {
+        let daily = calculate_daily_rate(Decimal::from_str("0.15").unwrap());
+        let year = |mode| projected_growth(daily, 365, mode);
+        
+        // Daily reinvestment recovers the APY exactly; less frequent reinvestment earns less
+        assert!((year(Compounding::Daily) - Decimal::from_str("0.15").unwrap()).abs() < Decimal::from_str("0.000000000001").unwrap());
+        assert!(year(Compounding::Monthly) < year(Compounding::Daily));
+        assert!(year(Compounding::None) < year(Compounding::Monthly));
+        assert_eq!(year(Compounding::None), daily * Decimal::from(365));
+        
+        // Within the first month monthly compounding is still simple interest
+        let first_month = projected_growth(daily, 20, Compounding::Monthly) - projected_growth(daily, 20, Compounding::None);
+        assert!(first_month.abs() < Decimal::from_str("0.000000000000000000001").unwrap());
+    }
+    
+    #[test]
+    fn format_percentage_scales_precision() {
+        assert_eq!(format_percentage(Decimal::from_str("156.789").unwrap()), "156.8%");
+        assert_eq!(format_percentage(Decimal::from_str("15.6789").unwrap()), "15.68%");
+        assert_eq!(format_percentage(Decimal::from_str("1.5").unwrap()), "1.500%");
+    }
+    
+    #[test]
+    fn format_percentage_keeps_the_sign_of_negatives() {
+        assert_eq!(format_percentage(Decimal::from_str("-156.789").unwrap()), "-156.8%");
+        assert_eq!(format_percentage(Decimal::from_str("-15.6789").unwrap()), "-15.68%");
+        assert_eq!(format_percentage(Decimal::from_str("-0.003").unwrap()), "-0.003%");
+        assert_eq!(format_signed_percentage(Decimal::from_str("2.34").unwrap()), "+2.340%");
+        assert_eq!(format_signed_percentage(Decimal::from_str("-2.34").unwrap()), "-2.340%");
+        assert_eq!(format_signed_percentage(Decimal::ZERO), "0.000%");
+    }
+    
+    #[test]
+    fn format_percentage_does_not_collapse_near_zero_values() {
+        assert_eq!(format_percentage(Decimal::from_str("0.0004").unwrap()), "0.00040%");
+        assert_eq!(format_percentage(Decimal::from_str("-0.000042").unwrap()), "-0.000042%");
+        assert_eq!(format_signed_percentage(Decimal::from_str("0.00000123").unwrap()), "+0.0000012%");
+        assert_eq!(format_percentage(Decimal::from_str("0.000000001").unwrap()), "<0.00000001%");
+        assert_eq!(format_percentage(Decimal::from_str("-0.000000001").unwrap()), ">-0.00000001%");
+        assert_eq!(format_percentage(Decimal::from_str("-0.0000").unwrap()), "0.000%");
+    }
+    
+    #[test]
+    fn decimal_places_sets_amount_precision() {
+        let balance = parse_amount("1234.56789", RoundingMode::Down).unwrap();
+        let mut display = DisplayConfig::default();
+        assert_eq!(AmountFormat::from(&display).format(&balance), "1234.567890");
+        
+        display.decimal_places = 3;
+        assert_eq!(AmountFormat::from(&display).format(&balance), "1234.568");
+        
+        display.decimal_places = 0;
+        assert_eq!(AmountFormat::from(&display).format(&balance), "1235");
+    }
+    
+    #[test]
+    fn human_amounts_abbreviate_by_magnitude() {
+        let format = AmountFormat::new(&DisplayConfig::default(), true);
+        
+        assert_eq!(format.format(&parse_amount("2500000", RoundingMode::Down).unwrap()), "2.50M");
+        assert_eq!(format.format(&parse_amount("1234.5", RoundingMode::Down).unwrap()), "1.23K");
+        assert_eq!(format.format(&parse_amount("0.5", RoundingMode::Down).unwrap()), "0.50000000");
+    }
+    
+    #[test]
+    fn raw_amounts_are_exact_wei() {
+        let amount = parse_amount("1234.567890123456789", RoundingMode::Down).unwrap();
+        
+        assert_eq!(AmountFormat::default().with_raw(true).format(&amount), "1234567890123456789000");
+        assert_eq!(AmountFormat::new(&DisplayConfig::default(), true).with_raw(true).format(&amount), "1234567890123456789000");
+        assert_eq!(AmountFormat::default().with_raw(false).format(&amount), "1234.567890");
+    }
+    
+    #[test]
+    fn parse_amount_smart_applies_multipliers() {
+        let parse = |input| parse_amount_smart(input, RoundingMode::Down).unwrap();
+        
+        assert_eq!(parse("1.5K"), BigUint::from(1_500u32) * BigUint::from(10u64.pow(18)));
+        assert_eq!(parse("0.001M"), BigUint::from(1_000u32) * BigUint::from(10u64.pow(18)));
+        assert_eq!(parse("2b"), BigUint::from(2_000_000_000u64) * BigUint::from(10u64.pow(18)));
+        assert_eq!(parse("0.1"), BigUint::from(100_000_000_000_000_000u64));
+    }
+    
+    #[test]
+    fn parse_amount_smart_ignores_token_symbol() {
+        let hundred = BigUint::from(100u32) * BigUint::from(10u64.pow(18));
+        let parse = |input| parse_amount_smart(input, RoundingMode::Down).unwrap();
+        
+        assert_eq!(parse("100strk"), hundred);
+        assert_eq!(parse("100 STRK"), hundred);
+        assert_eq!(parse("0.1K hSTRK"), hundred);
+    }
+    
+    #[test]
+    fn parse_amount_smart_rejects_ambiguous_input() {
+        for input in ["1.2.3K", "", "K", "1KM", "-1", "1e3", "1,000", "strk"] {
+            assert!(parse_amount_smart(input, RoundingMode::Down).is_err(), "{} should be rejected", input);
+        }
+    }
+    
+    #[test]
+    fn sub_wei_precision_rounds_by_mode() {
+        // 1.9 wei: f64 truncation would give 1 in both directions
+        let input = "0.0000000000000000019";
+        
+        assert_eq!(parse_amount(input, RoundingMode::Down).unwrap(), BigUint::from(1u32));
+        assert_eq!(parse_amount(input, RoundingMode::Up).unwrap(), BigUint::from(2u32));
+        assert_eq!(parse_amount_smart(input, RoundingMode::Down).unwrap(), BigUint::from(1u32));
+        assert_eq!(parse_amount_smart(input, RoundingMode::Up).unwrap(), BigUint::from(2u32));
+    }
+    
+    #[test]
+    fn exact_amounts_do_not_round_up() {
+        assert_eq!(parse_amount("1.500000000000000000000", RoundingMode::Up).unwrap(), BigUint::from(1_500_000_000_000_000_000u64));
+        assert_eq!(parse_amount_smart("0.0000000000000000000001K", RoundingMode::Up).unwrap(), BigUint::from(1u32));
+        assert!(parse_amount("-1", RoundingMode::Down).is_err());
+    }
+    
+    #[test]
+    fn felt_to_signed_i128_decodes_small_values() {
+        assert_eq!(felt_to_signed_i128(FieldElement::from(42u32)), 42);
+        assert_eq!(felt_to_signed_i128(FieldElement::ZERO), 0);
+        assert_eq!(felt_to_signed_i128(FieldElement::ZERO - FieldElement::from(42u32)), -42);
+        assert_eq!(felt_to_signed_i128(FieldElement::ZERO - FieldElement::ONE), -1);
+    }
+    
+    #[test]
+    fn felt_to_signed_i128_saturates_out_of_range() {
+        let huge = FieldElement::from_hex_be("0x100000000000000000000000000000000").unwrap();
+        
+        assert_eq!(felt_to_signed_i128(huge), i128::MAX);
+        assert_eq!(felt_to_signed_i128(FieldElement::ZERO - huge), i128::MIN);
+    }
+    
+    #[test]
+    fn sparkline_scales_between_min_and_max() {
+        assert_eq!(sparkline(&[1.0, 1.5, 2.0]), "▁▅█");
+        assert_eq!(sparkline(&[2.0, 2.0]), "▄▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+    
+    #[test]
+    fn bps_converts_between_percent_and_ratio() {
+        let bps = Bps::from_percent(1.5).unwrap();
+        
+        assert_eq!(bps, Bps(150));
+        assert_eq!(bps.to_percent(), 1.5);
+        assert_eq!(bps.to_ratio(), 0.015);
+        assert_eq!(bps.to_string(), "1.50%");
+        assert_eq!(Bps(10_000).to_string(), "100.00%");
+        assert_eq!(Bps::from_biguint(&BigUint::from(2000u32)).to_ratio(), 0.2);
+    }
+    
+    #[test]
+    fn bps_rejects_negative_percent() {
+        assert!(Bps::from_percent(-0.01).is_err());
+        assert!(Bps::from_percent(f64::NAN).is_err());
+    }
+    
+    #[test]
+    fn parse_slippage_bps_rounds_instead_of_truncating() {
+        // 0.29 * 100.0 is 28.999... in f64
+        assert_eq!(parse_slippage_bps("0.29").unwrap(), Bps(29));
+        assert!(parse_slippage_bps("100.5").is_err());
+    }
+    
+    #[test]
+    fn account_override_needs_an_address_and_one_key() {
+        assert_eq!(account_override(None, None, None, Some("0x1".to_string())).unwrap(), None);
+        assert!(account_override(None, Some("0x1"), None, None).is_err());
+        assert!(account_override(None, None, Some("key.json"), None).is_err());
+        
+        let (address, source) = account_override(Some("0xabc"), Some("0x1"), None, Some("0x2".to_string())).unwrap().unwrap();
+        assert_eq!(address, FieldElement::from(0xabcu32));
+        assert_eq!(source, KeySource::PrivateKey("0x1".to_string()));
+        assert_eq!(account_override(Some("0xabc"), None, Some("key.json"), None).unwrap().unwrap().1, KeySource::Keystore("key.json".to_string()));
+        assert_eq!(account_override(Some("0xabc"), None, None, Some("0x2".to_string())).unwrap().unwrap().1, KeySource::PrivateKey("0x2".to_string()));
+        
+        assert!(account_override(Some("0xabc"), None, None, Some(" ".to_string())).is_err());
+        assert!(account_override(Some("not-an-address"), Some("0x1"), None, None).is_err());
+    }
+}
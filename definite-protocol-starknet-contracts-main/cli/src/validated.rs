@@ -0,0 +1,153 @@
+//! Command-line argument types that are validated by clap at parse time
+//!
+//! Invalid amounts and addresses are rejected with the same message wherever they
+//! appear, before any config is loaded or RPC call is made.
+
+use num_bigint::BigUint;
+use starknet::core::types::FieldElement;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utils::{parse_amount_smart, parse_date, parse_units_smart, validate_address, RoundingMode, WEI_DECIMALS};
+
+/// A token amount such as `1.5`, `2K` or `100strk`, parsed exactly to wei
+///
+/// Both roundings are kept so each command can pick the conservative one for
+/// its direction (down for what is spent, up for what must be received).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Amount {
+    input: String,
+    down: BigUint,
+    up: BigUint,
+}
+
+impl Amount {
+    /// The amount in wei, with precision beyond 18 decimals rounded per `rounding`
+    pub fn wei(&self, rounding: RoundingMode) -> BigUint {
+        match rounding {
+            RoundingMode::Down => self.down.clone(),
+            RoundingMode::Up => self.up.clone(),
+        }
+    }
+    
+    /// The amount in base units of a token with `decimals` decimals
+    pub fn units(&self, decimals: u8, rounding: RoundingMode) -> anyhow::Result<BigUint> {
+        if decimals == WEI_DECIMALS {
+            return Ok(self.wei(rounding));
+        }
+        parse_units_smart(&self.input, decimals, rounding)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let down = parse_amount_smart(s, RoundingMode::Down).map_err(|e| e.to_string())?;
+        let up = parse_amount_smart(s, RoundingMode::Up).map_err(|e| e.to_string())?;
+        
+        Ok(Amount { input: s.trim().to_string(), down, up })
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.input)
+    }
+}
+
+/// A Starknet contract or account address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address(pub FieldElement);
+
+impl FromStr for Address {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        validate_address(s.trim())
+            .map(Address)
+            .map_err(|_| format!(
+                "'{}' is not a valid Starknet address (expected up to 64 hex digits, e.g. 0x49d36570)",
+                s
+            ))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+/// Value parser for arguments that take an address or an `[address_book]` name
+///
+/// Names are resolved once the config is loaded, so only input that is clearly
+/// meant as an address (a `0x` prefix) is checked here.
+pub fn address_or_name(s: &str) -> Result<String, String> {
+    let trimmed = s.trim();
+    if trimmed.is_empty() {
+        return Err("expected an address or an address book name".to_string());
+    }
+    if trimmed.starts_with("0x") {
+        Address::from_str(trimmed)?;
+    }
+    
+    Ok(trimmed.to_string())
+}
+
+/// Value parser for `--since`: a date or time, with a bare date meaning midnight UTC
+pub fn since_date(s: &str) -> Result<u64, String> {
+    parse_date(s, false).map_err(|e| e.to_string())
+}
+
+/// Value parser for `--until`: a date or time, with a bare date covering the whole day
+pub fn until_date(s: &str) -> Result<u64, String> {
+    parse_date(s, true).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn amount_keeps_both_roundings() {
+        let amount: Amount = "0.0000000000000000019".parse().unwrap();
+        assert_eq!(amount.wei(RoundingMode::Down), BigUint::from(1u32));
+        assert_eq!(amount.wei(RoundingMode::Up), BigUint::from(2u32));
+        
+        let thousand: Amount = "1.5K".parse().unwrap();
+        assert_eq!(thousand.wei(RoundingMode::Down), BigUint::from(1500u32) * BigUint::from(10u64.pow(18)));
+        assert_eq!(thousand.to_string(), "1.5K");
+    }
+    
+    #[test]
+    fn amount_rejects_ambiguous_input() {
+        for bad in ["", "-1", "1,000", "1e3", "abc"] {
+            assert!(bad.parse::<Amount>().is_err(), "{} should be rejected", bad);
+        }
+    }
+    
+    #[test]
+    fn address_parses_with_or_without_prefix() {
+        let address: Address = "0x1234".parse().unwrap();
+        assert_eq!(address, "1234".parse().unwrap());
+        assert_eq!(address.to_string(), "0x1234");
+        
+        let err = "0xnothex".parse::<Address>().unwrap_err();
+        assert!(err.contains("not a valid Starknet address"));
+    }
+    
+    #[test]
+    fn address_or_name_only_checks_hex_input() {
+        assert_eq!(address_or_name("treasury").unwrap(), "treasury");
+        assert_eq!(address_or_name("0x1234").unwrap(), "0x1234");
+        assert!(address_or_name("0xtreasury").is_err());
+        assert!(address_or_name("  ").is_err());
+    }
+    
+    #[test]
+    fn bare_dates_bound_the_whole_day() {
+        assert_eq!(until_date("2024-05-01").unwrap() - since_date("2024-05-01").unwrap(), 86_399);
+        assert!(since_date("yesterday").unwrap_err().contains("YYYY-MM-DD"));
+    }
+}
@@ -0,0 +1,116 @@
+//! Re-running read-only commands on an interval for `--watch`
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::progress::OutputFormat;
+use crate::shutdown::shutdown_signal;
+use crate::theme;
+
+/// Clear the screen and move the cursor to the top left
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[1;1H";
+
+/// Run `render` once, or every `interval` seconds until Ctrl+C or SIGTERM when `interval` is set
+///
+/// `render` receives the cycle number, starting at 0, so it can be paired with a
+/// `RefreshPolicy`. With human output each cycle redraws a cleared screen; with
+/// JSON output cycles are appended undecorated so they can be piped into a log.
+/// An error from `render` ends the watch.
+pub async fn run_watched<F, Fut>(interval: Option<u64>, output: OutputFormat, mut render: F) -> Result<()>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let Some(interval) = interval else {
+        return render(0).await;
+    };
+    if interval == 0 {
+        return Err(anyhow::anyhow!("Watch interval must be at least 1 second"));
+    }
+    let human = output == OutputFormat::Human;
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    
+    for cycle in 0.. {
+        if human {
+            print!("{}", CLEAR_SCREEN);
+        }
+        render(cycle).await?;
+        
+        if human {
+            println!();
+            println!("{}", format!("Refreshing every {} seconds... (Ctrl+C to exit)", interval).color(theme::MUTED));
+        }
+        
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = tokio::time::sleep(Duration::from_secs(interval)) => {}
+        }
+    }
+    
+    if human {
+        println!("{}", "Stopped watching".color(theme::MUTED));
+    }
+    Ok(())
+}
+
+/// Title for a watchable command, marked live while watching
+pub fn title(base: &str, interval: Option<u64>) -> String {
+    match interval {
+        Some(_) => format!("{} (Live)", base),
+        None => base.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn renders_once_without_an_interval() {
+        let mut cycles = Vec::new();
+        run_watched(None, OutputFormat::Json, |cycle| {
+            cycles.push(cycle);
+            async { Ok::<_, anyhow::Error>(()) }
+        }).await.unwrap();
+        
+        assert_eq!(cycles, vec![0]);
+    }
+    
+    #[tokio::test]
+    async fn render_error_ends_the_watch() {
+        let mut calls = 0;
+        let result = run_watched(Some(1), OutputFormat::Json, |cycle| {
+            calls += 1;
+            async move {
+                match cycle {
+                    0 => Ok(()),
+                    _ => Err(anyhow::anyhow!("RPC unavailable")),
+                }
+            }
+        }).await;
+        
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+    }
+    
+    #[tokio::test]
+    async fn zero_interval_is_refused_before_rendering() {
+        let mut calls = 0;
+        let result = run_watched(Some(0), OutputFormat::Json, |_| {
+            calls += 1;
+            async { Ok(()) }
+        }).await;
+        
+        assert!(result.is_err());
+        assert_eq!(calls, 0);
+    }
+    
+    #[test]
+    fn title_marks_live_views() {
+        assert_eq!(title("Net Delta Monitor", Some(5)), "Net Delta Monitor (Live)");
+        assert_eq!(title("Net Delta Monitor", None), "Net Delta Monitor");
+    }
+}